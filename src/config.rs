@@ -30,10 +30,24 @@ fn default_diff_compare_method() -> String {
     "content".to_string()
 }
 
+fn default_diff_exclude_presets() -> Vec<DiffExcludePreset> {
+    vec![DiffExcludePreset {
+        name: "Build output".to_string(),
+        patterns: [".git", "target", "node_modules", "dist", "build", "__pycache__", ".venv"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    }]
+}
+
 fn default_encrypt_split_size() -> u64 {
     1800
 }
 
+fn default_encrypt_key() -> String {
+    crate::enc::keys::MACHINE_KEY.to_string()
+}
+
 fn default_telegram_polling_time() -> u64 {
     3000
 }
@@ -48,6 +62,14 @@ impl Default for PanelSettings {
     }
 }
 
+/// Named set of exclude patterns for folder compare (gitignore glob syntax)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffExcludePreset {
+    pub name: String,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
 /// Theme settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThemeSettings {
@@ -92,6 +114,15 @@ pub struct Settings {
     /// DIFF compare method: "content", "modified_time", "content_and_time"
     #[serde(default = "default_diff_compare_method")]
     pub diff_compare_method: String,
+    /// Honour .gitignore / .ignore files in folder compare by default
+    #[serde(default)]
+    pub diff_use_gitignore: bool,
+    /// Exclude presets offered in the folder compare exclude dialog
+    #[serde(default = "default_diff_exclude_presets")]
+    pub diff_exclude_presets: Vec<DiffExcludePreset>,
+    /// Name of the exclude preset applied when folder compare opens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_default_exclude_preset: Option<String>,
     /// Remote server profiles for SSH/SFTP connections
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub remote_profiles: Vec<RemoteProfile>,
@@ -101,6 +132,12 @@ pub struct Settings {
     /// Encryption split size in MB (0 = no split)
     #[serde(default = "default_encrypt_split_size")]
     pub encrypt_split_size: u64,
+    /// Compress files with zstd before encrypting them
+    #[serde(default)]
+    pub encrypt_compress: bool,
+    /// Last key picked for encryption: machine, passphrase or a keyring name
+    #[serde(default = "default_encrypt_key")]
+    pub encrypt_key: String,
     /// Telegram API polling interval in milliseconds (minimum 2500, default 3000)
     #[serde(default = "default_telegram_polling_time")]
     pub telegram_polling_time: u64,
@@ -142,9 +179,14 @@ impl Default for Settings {
             panels: vec![PanelSettings::default(), PanelSettings::default()],
             active_panel_index: 0,
             diff_compare_method: default_diff_compare_method(),
+            diff_use_gitignore: false,
+            diff_exclude_presets: default_diff_exclude_presets(),
+            diff_default_exclude_preset: None,
             remote_profiles: Vec::new(),
             keybindings: KeybindingsConfig::default(),
             encrypt_split_size: default_encrypt_split_size(),
+            encrypt_compress: false,
+            encrypt_key: default_encrypt_key(),
             telegram_polling_time: default_telegram_polling_time(),
         }
     }
@@ -271,6 +313,15 @@ impl Settings {
         Ok(())
     }
 
+    /// Patterns of the default exclude preset (empty if unset or not found)
+    pub fn default_diff_exclude_patterns(&self) -> Vec<String> {
+        self.diff_default_exclude_preset
+            .as_ref()
+            .and_then(|name| self.diff_exclude_presets.iter().find(|p| &p.name == name))
+            .map(|p| p.patterns.clone())
+            .unwrap_or_default()
+    }
+
     /// Resolves a path setting to a valid directory
     /// Security: Only accepts absolute paths and canonicalizes to resolve symlinks
     pub fn resolve_path<F>(&self, path_opt: &Option<String>, fallback: F) -> PathBuf
@@ -349,6 +400,18 @@ mod tests {
         assert_eq!(settings.panels[0].sort_by, "name");
     }
 
+    #[test]
+    fn test_default_diff_exclude_patterns() {
+        let mut settings = Settings::default();
+        assert!(settings.default_diff_exclude_patterns().is_empty());
+
+        settings.diff_default_exclude_preset = Some("Build output".to_string());
+        assert!(settings.default_diff_exclude_patterns().contains(&"target".to_string()));
+
+        settings.diff_default_exclude_preset = Some("Missing".to_string());
+        assert!(settings.default_diff_exclude_patterns().is_empty());
+    }
+
     #[test]
     fn test_ensure_config_exists() {
         Settings::ensure_config_exists();
@@ -1,20 +1,60 @@
+//! Chunk format.
+//!
+//! Every chunk starts with a header: magic, version, then per version
+//!
+//! - v2: salt, CBC IV, filename. The body is AES-256-CBC with PKCS7 padding
+//!   and no MAC. Only decrypted.
+//! - v3: flags, salt, nonce prefix, segment size, key info (with
+//!   `FLAG_KEY_INFO`), filename. The body is cut into `segment_size`
+//!   plaintext segments, each sealed with AES-256-GCM. A segment's nonce is
+//!   the prefix, its index and a last-segment flag, and the whole header is
+//!   its associated data, so changing the header, a segment, their order or
+//!   cutting the chunk short fails authentication.
+//!
+//! Key info is a kind byte, then for a key file its name (u8 length) and
+//! fingerprint, for a passphrase the scrypt cost, for recipients their count
+//! (u8) and stanzas.
+
 use std::io::{Read, Write};
 
 use aes::Aes256;
-use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+#[cfg(test)]
+use cbc::cipher::BlockEncryptMut;
 use hmac::Hmac;
 use rand::RngCore;
 use sha2::Sha512;
 
 use super::error::CokacencError;
+use super::keys::{valid_key_name, KeyInfo, FINGERPRINT_LEN, MACHINE_KEY};
+use super::recipients::{Stanza, MAX_RECIPIENTS};
 
 pub const MAGIC: &[u8; 8] = b"COKACENC";
-pub const VERSION: u32 = 2;
+/// Version written by `write_header`
+pub const VERSION: u32 = 3;
+/// Unauthenticated AES-256-CBC chunks, still decrypted
+pub const LEGACY_VERSION: u32 = 2;
+/// Plaintext bytes per v3 segment
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+const MAX_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+const TAG_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
 const MAX_FILENAME_LEN: usize = 4096;
 const AES_BLOCK: usize = 16;
 const KEY_LEN: usize = 32;
 const PBKDF2_ITERATIONS: u32 = 100_000;
-
+/// Header flag: key info follows the segment size
+pub const FLAG_KEY_INFO: u16 = 0x0001;
+const KEY_KIND_FILE: u8 = 0;
+const KEY_KIND_PASSPHRASE: u8 = 1;
+const KEY_KIND_RECIPIENTS: u8 = 2;
+/// Accepted scrypt costs; the upper bound caps memory at 1 GiB
+const MIN_LOG_N: u8 = 10;
+const MAX_LOG_N: u8 = 20;
+
+#[cfg(test)]
 type Aes256CbcEnc = cbc::Encryptor<Aes256>;
 type Aes256CbcDec = cbc::Decryptor<Aes256>;
 
@@ -38,35 +78,133 @@ pub fn derive_key(password: &[u8], salt: &[u8; 16]) -> [u8; KEY_LEN] {
     key
 }
 
+/// Derive a 32-byte AES key from a passphrase + salt via scrypt (N = 2^log_n,
+/// r = 8, p = 1).
+pub fn derive_passphrase_key(passphrase: &[u8], salt: &[u8; 16], log_n: u8) -> Result<[u8; KEY_LEN], CokacencError> {
+    let params = scrypt::Params::new(log_n, 8, 1, KEY_LEN)
+        .map_err(|e| CokacencError::Other(format!("Invalid scrypt parameters: {}", e)))?;
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(passphrase, salt, &params, &mut key)
+        .map_err(|e| CokacencError::Other(format!("scrypt: {}", e)))?;
+    Ok(key)
+}
+
 pub fn generate_salt() -> [u8; 16] {
     let mut salt = [0u8; 16];
     rand::thread_rng().fill_bytes(&mut salt);
     salt
 }
 
+#[cfg(test)]
 pub fn generate_iv() -> [u8; 16] {
     let mut iv = [0u8; 16];
     rand::thread_rng().fill_bytes(&mut iv);
     iv
 }
 
-/// Write the chunk header: magic + version + salt + iv + filename.
+pub fn generate_nonce_prefix() -> [u8; NONCE_PREFIX_LEN] {
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    rand::thread_rng().fill_bytes(&mut prefix);
+    prefix
+}
+
+/// A parsed chunk header
+#[derive(Debug, Clone)]
+pub struct ChunkHeader {
+    pub version: u32,
+    /// Format extensions; only `FLAG_KEY_INFO` is known
+    pub flags: u16,
+    pub salt: [u8; 16],
+    /// CBC IV (v2 only)
+    pub iv: [u8; 16],
+    /// Leading bytes of every segment nonce (v3 only)
+    pub nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    /// Plaintext bytes per segment (v3 only)
+    pub segment_size: usize,
+    /// The key the chunk needs; `Legacy` for v2 and v3 without key info
+    pub key: KeyInfo,
+    pub filename: String,
+    /// The header bytes as stored; v3 segments authenticate them
+    pub raw: Vec<u8>,
+}
+
+fn check_filename_len(len: usize) -> Result<(), CokacencError> {
+    if len > MAX_FILENAME_LEN {
+        return Err(CokacencError::Other(format!(
+            "Filename too long: {} bytes (max {})",
+            len,
+            MAX_FILENAME_LEN,
+        )));
+    }
+    Ok(())
+}
+
+/// Write a v3 chunk header: magic + version + flags + salt + nonce prefix +
+/// segment size + key info + filename. Returns the written bytes for
+/// `SegmentEncryptor`.
 pub fn write_header(
+    w: &mut dyn Write,
+    salt: &[u8; 16],
+    nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    key: &KeyInfo,
+    filename: &str,
+) -> Result<Vec<u8>, CokacencError> {
+    let name_bytes = filename.as_bytes();
+    check_filename_len(name_bytes.len())?;
+    let flags = if *key == KeyInfo::Legacy { 0 } else { FLAG_KEY_INFO };
+    let mut header = Vec::with_capacity(80 + name_bytes.len());
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(salt);
+    header.extend_from_slice(nonce_prefix);
+    header.extend_from_slice(&(SEGMENT_SIZE as u32).to_le_bytes());
+    match key {
+        KeyInfo::Legacy => {}
+        KeyInfo::KeyFile { name, fingerprint } => {
+            let key_name = name.as_bytes();
+            if key_name.len() > u8::MAX as usize {
+                return Err(CokacencError::Other(format!("Key name too long: {}", name)));
+            }
+            header.push(KEY_KIND_FILE);
+            header.push(key_name.len() as u8);
+            header.extend_from_slice(key_name);
+            header.extend_from_slice(fingerprint);
+        }
+        KeyInfo::Passphrase { log_n } => {
+            header.push(KEY_KIND_PASSPHRASE);
+            header.push(*log_n);
+        }
+        KeyInfo::Recipients { stanzas } => {
+            if stanzas.is_empty() || stanzas.len() > MAX_RECIPIENTS {
+                return Err(CokacencError::Other(format!("Invalid recipient count: {}", stanzas.len())));
+            }
+            header.push(KEY_KIND_RECIPIENTS);
+            header.push(stanzas.len() as u8);
+            for stanza in stanzas {
+                header.extend_from_slice(&stanza.ephemeral);
+                header.extend_from_slice(&stanza.wrapped);
+            }
+        }
+    }
+    header.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
+    header.extend_from_slice(name_bytes);
+    w.write_all(&header)?;
+    Ok(header)
+}
+
+/// Write a v2 chunk header: magic + version + salt + iv + filename.
+#[cfg(test)]
+pub fn write_legacy_header(
     w: &mut dyn Write,
     salt: &[u8; 16],
     iv: &[u8; 16],
     filename: &str,
 ) -> Result<(), CokacencError> {
     let name_bytes = filename.as_bytes();
-    if name_bytes.len() > MAX_FILENAME_LEN {
-        return Err(CokacencError::Other(format!(
-            "Filename too long: {} bytes (max {})",
-            name_bytes.len(),
-            MAX_FILENAME_LEN,
-        )));
-    }
+    check_filename_len(name_bytes.len())?;
     w.write_all(MAGIC)?;
-    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&LEGACY_VERSION.to_le_bytes())?;
     w.write_all(salt)?;
     w.write_all(iv)?;
     w.write_all(&(name_bytes.len() as u16).to_le_bytes())?;
@@ -74,30 +212,102 @@ pub fn write_header(
     Ok(())
 }
 
-/// Read and validate the chunk header. Returns (salt, iv, filename).
-pub fn read_header(r: &mut dyn Read) -> Result<([u8; 16], [u8; 16], String), CokacencError> {
-    let mut magic = [0u8; 8];
-    r.read_exact(&mut magic)?;
+/// Read `N` bytes, keeping a copy in `raw`
+fn read_field<const N: usize>(r: &mut dyn Read, raw: &mut Vec<u8>) -> Result<[u8; N], CokacencError> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    raw.extend_from_slice(&buf);
+    Ok(buf)
+}
+
+fn read_key_info(r: &mut dyn Read, raw: &mut Vec<u8>) -> Result<KeyInfo, CokacencError> {
+    let [kind] = read_field(r, raw)?;
+    match kind {
+        KEY_KIND_FILE => {
+            let [name_len] = read_field(r, raw)?;
+            let mut name = vec![0u8; name_len as usize];
+            r.read_exact(&mut name)?;
+            raw.extend_from_slice(&name);
+            let name = String::from_utf8(name)
+                .map_err(|e| CokacencError::Other(format!("Invalid key name UTF-8: {}", e)))?;
+            // The name picks a file in the keyring, so it must not escape it
+            if name != MACHINE_KEY && !valid_key_name(&name) {
+                return Err(CokacencError::Other(format!("Invalid key name in header: {:?}", name)));
+            }
+            let fingerprint: [u8; FINGERPRINT_LEN] = read_field(r, raw)?;
+            Ok(KeyInfo::KeyFile { name, fingerprint })
+        }
+        KEY_KIND_PASSPHRASE => {
+            let [log_n] = read_field(r, raw)?;
+            if !(MIN_LOG_N..=MAX_LOG_N).contains(&log_n) {
+                return Err(CokacencError::Other(format!("Invalid passphrase cost in header: {}", log_n)));
+            }
+            Ok(KeyInfo::Passphrase { log_n })
+        }
+        KEY_KIND_RECIPIENTS => {
+            let [count] = read_field(r, raw)?;
+            if count == 0 || count as usize > MAX_RECIPIENTS {
+                return Err(CokacencError::Other(format!("Invalid recipient count in header: {}", count)));
+            }
+            let mut stanzas = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let ephemeral = read_field(r, raw)?;
+                let wrapped = read_field(r, raw)?;
+                stanzas.push(Stanza { ephemeral, wrapped });
+            }
+            Ok(KeyInfo::Recipients { stanzas })
+        }
+        _ => Err(CokacencError::Other(format!("Unknown key kind in header: {}", kind))),
+    }
+}
+
+/// Read and validate a v2 or v3 chunk header.
+pub fn read_header(r: &mut dyn Read) -> Result<ChunkHeader, CokacencError> {
+    let mut raw = Vec::new();
+    let magic: [u8; 8] = read_field(r, &mut raw)?;
     if &magic != MAGIC {
         return Err(CokacencError::InvalidMagic);
     }
 
-    let mut ver_bytes = [0u8; 4];
-    r.read_exact(&mut ver_bytes)?;
-    let ver = u32::from_le_bytes(ver_bytes);
-    if ver != VERSION {
-        return Err(CokacencError::UnsupportedVersion(ver));
+    let version = u32::from_le_bytes(read_field(r, &mut raw)?);
+    let mut header = ChunkHeader {
+        version,
+        flags: 0,
+        salt: [0u8; 16],
+        iv: [0u8; 16],
+        nonce_prefix: [0u8; NONCE_PREFIX_LEN],
+        segment_size: 0,
+        key: KeyInfo::Legacy,
+        filename: String::new(),
+        raw: Vec::new(),
+    };
+    match version {
+        LEGACY_VERSION => {
+            header.salt = read_field(r, &mut raw)?;
+            header.iv = read_field(r, &mut raw)?;
+        }
+        VERSION => {
+            header.flags = u16::from_le_bytes(read_field(r, &mut raw)?);
+            if header.flags & !FLAG_KEY_INFO != 0 {
+                return Err(CokacencError::UnsupportedFlags(header.flags));
+            }
+            header.salt = read_field(r, &mut raw)?;
+            header.nonce_prefix = read_field(r, &mut raw)?;
+            header.segment_size = u32::from_le_bytes(read_field(r, &mut raw)?) as usize;
+            if header.segment_size == 0 || header.segment_size > MAX_SEGMENT_SIZE {
+                return Err(CokacencError::Other(format!(
+                    "Invalid segment size in header: {}",
+                    header.segment_size,
+                )));
+            }
+            if header.flags & FLAG_KEY_INFO != 0 {
+                header.key = read_key_info(r, &mut raw)?;
+            }
+        }
+        _ => return Err(CokacencError::UnsupportedVersion(version)),
     }
 
-    let mut salt = [0u8; 16];
-    r.read_exact(&mut salt)?;
-
-    let mut iv = [0u8; 16];
-    r.read_exact(&mut iv)?;
-
-    let mut name_len_bytes = [0u8; 2];
-    r.read_exact(&mut name_len_bytes)?;
-    let name_len = u16::from_le_bytes(name_len_bytes) as usize;
+    let name_len = u16::from_le_bytes(read_field(r, &mut raw)?) as usize;
     if name_len > MAX_FILENAME_LEN {
         return Err(CokacencError::Other(format!(
             "Filename length in header too long: {} bytes (max {})",
@@ -107,19 +317,153 @@ pub fn read_header(r: &mut dyn Read) -> Result<([u8; 16], [u8; 16], String), Cok
     }
     let mut name_buf = vec![0u8; name_len];
     r.read_exact(&mut name_buf)?;
-    let filename = String::from_utf8(name_buf)
+    raw.extend_from_slice(&name_buf);
+    header.filename = String::from_utf8(name_buf)
         .map_err(|e| CokacencError::Other(format!("Invalid filename UTF-8: {}", e)))?;
+    header.raw = raw;
+
+    Ok(header)
+}
+
+/// Nonce of segment `index`: prefix + big-endian index + last-segment flag
+fn segment_nonce(prefix: &[u8; NONCE_PREFIX_LEN], index: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&index.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+/// Streaming v3 encryptor: seals every `SEGMENT_SIZE` bytes of plaintext.
+pub struct SegmentEncryptor {
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    /// The chunk header, authenticated with every segment
+    aad: Vec<u8>,
+    index: u32,
+    buf: Vec<u8>,     // plaintext not sealed yet
+    out_buf: Vec<u8>, // reusable output buffer
+}
+
+impl SegmentEncryptor {
+    pub fn new(key: &[u8; KEY_LEN], nonce_prefix: &[u8; NONCE_PREFIX_LEN], header: &[u8]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+            nonce_prefix: *nonce_prefix,
+            aad: header.to_vec(),
+            index: 0,
+            buf: Vec::with_capacity(SEGMENT_SIZE * 2),
+            out_buf: Vec::new(),
+        }
+    }
+
+    /// Feed plaintext data; returns the sealed segments (may be empty). A full
+    /// segment is held back until more data arrives: the last one is sealed
+    /// differently.
+    pub fn update(&mut self, data: &[u8]) -> Result<&[u8], CokacencError> {
+        self.out_buf.clear();
+        self.buf.extend_from_slice(data);
+        let mut start = 0;
+        while self.buf.len() - start > SEGMENT_SIZE {
+            let sealed = self.seal(start, SEGMENT_SIZE, false)?;
+            self.out_buf.extend_from_slice(&sealed);
+            start += SEGMENT_SIZE;
+        }
+        self.buf.drain(..start);
+        Ok(&self.out_buf)
+    }
 
-    Ok((salt, iv, filename))
+    /// Finalize: seal the remaining plaintext as the last segment.
+    pub fn finalize(mut self) -> Result<Vec<u8>, CokacencError> {
+        let len = self.buf.len();
+        self.seal(0, len, true)
+    }
+
+    fn seal(&mut self, start: usize, len: usize, last: bool) -> Result<Vec<u8>, CokacencError> {
+        let nonce = segment_nonce(&self.nonce_prefix, self.index, last);
+        self.index = self.index.checked_add(1)
+            .ok_or_else(|| CokacencError::Other("Too many segments in chunk".to_string()))?;
+        let payload = Payload { msg: &self.buf[start..start + len], aad: &self.aad };
+        self.cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| CokacencError::Other("Encryption failed".to_string()))
+    }
 }
 
-/// Streaming chunk encryptor that processes data block-by-block.
+/// Read until `buf` is full or the reader is exhausted; returns the bytes read
+fn read_full(r: &mut dyn Read, buf: &mut [u8]) -> Result<usize, CokacencError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Decrypt the v3 segments following `header`, writing plaintext to writer.
+/// Each segment is written once it is authenticated, so on error the output
+/// so far must be discarded.
+pub fn decrypt_segments_streaming(
+    r: &mut dyn Read,
+    w: &mut dyn Write,
+    key: &[u8; KEY_LEN],
+    header: &ChunkHeader,
+) -> Result<(), CokacencError> {
+    let cipher = Aes256Gcm::new(key.into());
+    let sealed_len = header.segment_size + TAG_LEN;
+    let mut current = vec![0u8; sealed_len];
+    let mut next = vec![0u8; sealed_len];
+    let mut current_len = read_full(r, &mut current)?;
+    let mut index: u32 = 0;
+
+    loop {
+        // Only a full segment can be followed by another
+        let next_len = if current_len == sealed_len { read_full(r, &mut next)? } else { 0 };
+        let last = next_len == 0;
+
+        let nonce = segment_nonce(&header.nonce_prefix, index, last);
+        let payload = Payload { msg: &current[..current_len], aad: &header.raw };
+        let plain = cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| CokacencError::AuthenticationFailed)?;
+        w.write_all(&plain)?;
+
+        if last {
+            return Ok(());
+        }
+        index = index.checked_add(1).ok_or(CokacencError::AuthenticationFailed)?;
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    }
+}
+
+/// Decrypt the body of a chunk, whichever version `header` says wrote it.
+pub fn decrypt_chunk(
+    r: &mut dyn Read,
+    w: &mut dyn Write,
+    key: &[u8; KEY_LEN],
+    header: &ChunkHeader,
+) -> Result<(), CokacencError> {
+    if header.version == LEGACY_VERSION {
+        decrypt_chunk_streaming(r, w, key, &header.iv)
+    } else {
+        decrypt_segments_streaming(r, w, key, header)
+    }
+}
+
+/// Streaming v2 chunk encryptor that processes data block-by-block.
+#[cfg(test)]
 pub struct ChunkEncryptor {
     encryptor: Aes256CbcEnc,
     buf: Vec<u8>,     // partial block buffer
     out_buf: Vec<u8>, // reusable output buffer
 }
 
+#[cfg(test)]
 impl ChunkEncryptor {
     pub fn new(key: &[u8; KEY_LEN], iv: &[u8; 16]) -> Self {
         Self {
@@ -170,7 +514,7 @@ impl ChunkEncryptor {
     }
 }
 
-/// Decrypt a chunk from reader, writing plaintext to writer.
+/// Decrypt a v2 chunk from reader, writing plaintext to writer.
 /// Uses 1-block look-ahead to handle PKCS7 unpadding on the final block.
 pub fn decrypt_chunk_streaming(
     r: &mut dyn Read,
@@ -236,3 +580,133 @@ fn to_blocks_mut(data: &mut [u8]) -> &mut [aes::Block] {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_LEN] = [7u8; KEY_LEN];
+
+    /// A v3 chunk holding `data`
+    fn seal(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let prefix = generate_nonce_prefix();
+        let key = KeyInfo::KeyFile { name: "alice".to_string(), fingerprint: [3u8; FINGERPRINT_LEN] };
+        let header = write_header(&mut out, &generate_salt(), &prefix, &key, "photo.jpg").unwrap();
+        let mut enc = SegmentEncryptor::new(&KEY, &prefix, &header);
+        // Odd-sized writes cross segment boundaries
+        for piece in data.chunks(1000) {
+            let sealed = enc.update(piece).unwrap().to_vec();
+            out.extend_from_slice(&sealed);
+        }
+        out.extend_from_slice(&enc.finalize().unwrap());
+        out
+    }
+
+    fn open(chunk: &[u8]) -> Result<Vec<u8>, CokacencError> {
+        let mut r = chunk;
+        let header = read_header(&mut r)?;
+        let mut plain = Vec::new();
+        decrypt_chunk(&mut r, &mut plain, &KEY, &header)?;
+        Ok(plain)
+    }
+
+    fn header_len(chunk: &[u8]) -> usize {
+        let mut r = chunk;
+        read_header(&mut r).unwrap().raw.len()
+    }
+
+    #[test]
+    fn test_v3_roundtrip() {
+        for len in [0, 1, SEGMENT_SIZE, SEGMENT_SIZE * 2, SEGMENT_SIZE * 2 + 5] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 253) as u8).collect();
+            let chunk = seal(&data);
+            let segments = len.div_ceil(SEGMENT_SIZE).max(1);
+            assert_eq!(chunk.len(), header_len(&chunk) + len + segments * TAG_LEN);
+            assert_eq!(open(&chunk).unwrap(), data, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_v3_rejects_tampering() {
+        let data: Vec<u8> = (0..SEGMENT_SIZE * 2 + 100).map(|i| (i % 251) as u8).collect();
+        let chunk = seal(&data);
+        let body = header_len(&chunk);
+        let sealed = SEGMENT_SIZE + TAG_LEN;
+        let auth_failed = |c: &[u8]| matches!(open(c), Err(CokacencError::AuthenticationFailed));
+
+        // A flipped bit in a segment
+        let mut bad = chunk.clone();
+        bad[body + SEGMENT_SIZE + 40] ^= 1;
+        assert!(auth_failed(&bad));
+
+        // The filename in the header
+        let mut bad = chunk.clone();
+        bad[body - 1] ^= 1;
+        assert!(auth_failed(&bad));
+
+        // Cut at a segment boundary or inside the last segment
+        assert!(auth_failed(&chunk[..body + sealed * 2]));
+        assert!(auth_failed(&chunk[..chunk.len() - 1]));
+
+        // Segments swapped
+        let mut bad = chunk[..body].to_vec();
+        bad.extend_from_slice(&chunk[body + sealed..body + sealed * 2]);
+        bad.extend_from_slice(&chunk[body..body + sealed]);
+        bad.extend_from_slice(&chunk[body + sealed * 2..]);
+        assert!(auth_failed(&bad));
+
+        // Another key
+        let mut r = &chunk[..];
+        let header = read_header(&mut r).unwrap();
+        let mut plain = Vec::new();
+        let result = decrypt_chunk(&mut r, &mut plain, &[8u8; KEY_LEN], &header);
+        assert!(matches!(result, Err(CokacencError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn test_header_versions() {
+        let v3 = seal(b"x");
+        let mut r = &v3[..];
+        let header = read_header(&mut r).unwrap();
+        assert_eq!(header.flags, FLAG_KEY_INFO);
+        assert_eq!(header.key.label(), "alice (0303030303030303)");
+
+        let mut v3 = v3;
+        v3[13] = 0x80; // flags
+        assert!(matches!(open(&v3), Err(CokacencError::UnsupportedFlags(0x8001))));
+
+        let mut v4 = seal(b"x");
+        v4[8] = 4;
+        assert!(matches!(open(&v4), Err(CokacencError::UnsupportedVersion(4))));
+    }
+
+    #[test]
+    fn test_header_rejects_path_key_name() {
+        for name in ["../../outside", "a/b", ""] {
+            let mut chunk = Vec::new();
+            let key = KeyInfo::KeyFile { name: name.to_string(), fingerprint: [3u8; FINGERPRINT_LEN] };
+            write_header(&mut chunk, &generate_salt(), &generate_nonce_prefix(), &key, "x").unwrap();
+            let mut r = &chunk[..];
+            assert!(read_header(&mut r).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_v2_still_decrypts() {
+        let data = b"written by an older version".repeat(10);
+        let salt = generate_salt();
+        let iv = generate_iv();
+        let mut chunk = Vec::new();
+        write_legacy_header(&mut chunk, &salt, &iv, "old.txt").unwrap();
+        let mut enc = ChunkEncryptor::new(&KEY, &iv);
+        let body = enc.update(&data).to_vec();
+        chunk.extend_from_slice(&body);
+        chunk.extend_from_slice(&enc.finalize());
+
+        let mut r = &chunk[..];
+        let header = read_header(&mut r).unwrap();
+        assert_eq!((header.version, header.filename.as_str()), (LEGACY_VERSION, "old.txt"));
+        assert_eq!(open(&chunk).unwrap(), data);
+    }
+}
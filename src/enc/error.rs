@@ -11,9 +11,15 @@ pub enum CokacencError {
     #[error("Unsupported version: {0}")]
     UnsupportedVersion(u32),
 
+    #[error("Unsupported header flags: {0:#06x}")]
+    UnsupportedFlags(u16),
+
     #[error("Invalid PKCS7 padding")]
     InvalidPadding,
 
+    #[error("Authentication failed: the chunk is corrupted, truncated or was modified")]
+    AuthenticationFailed,
+
     #[error("MD5 mismatch: expected {expected}, got {actual}")]
     Md5Mismatch { expected: String, actual: String },
 
@@ -26,6 +32,12 @@ pub enum CokacencError {
     #[error("Key file is empty")]
     EmptyKeyFile,
 
+    #[error("Key not found in keyring: {0}")]
+    KeyNotFound(String),
+
+    #[error("A passphrase is required")]
+    PassphraseRequired,
+
     #[error("Sequence index {0} exceeds maximum (456975)")]
     SeqOverflow(usize),
 
@@ -0,0 +1,430 @@
+//! Key sources.
+//!
+//! A group is encrypted either with a key file — the machine key
+//! (`~/.cokacdir/credential/cokacenc.key`, created on first use) or a named
+//! key in the keyring directory (`~/.cokacdir/credential/keys/<name>.key`) —
+//! with a passphrase, or for public-key recipients (see `recipients`). The v3
+//! header records which (`KeyInfo`), so decryption finds the key by itself and
+//! only needs to ask for a passphrase when a group was encrypted with one.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use super::crypto::{self, load_key_file, ChunkHeader};
+use super::error::CokacencError;
+use super::recipients::{self, PublicKey, Stanza};
+
+/// Name of the machine key in headers and in the key picker
+pub const MACHINE_KEY: &str = "machine";
+/// Key picker entry for a passphrase
+pub const PASSPHRASE: &str = "passphrase";
+const MACHINE_KEY_FILE: &str = "cokacenc.key";
+const KEYRING_DIR: &str = "keys";
+const KEY_EXT: &str = ".key";
+const MAX_NAME_LEN: usize = 24;
+pub const FINGERPRINT_LEN: usize = 8;
+/// Shortest passphrase accepted for encryption
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+/// scrypt cost (log2 N) for new passphrase groups; with r = 8 that is 128 MiB
+pub const PASSPHRASE_LOG_N: u8 = 17;
+
+/// Which key a chunk needs, as recorded in its header
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeyInfo {
+    /// Written before key sources existed: the machine key
+    Legacy,
+    /// A key file, by keyring name and fingerprint
+    KeyFile { name: String, fingerprint: [u8; FINGERPRINT_LEN] },
+    /// A passphrase stretched with scrypt at cost `2^log_n`
+    Passphrase { log_n: u8 },
+    /// A random file key sealed for each recipient
+    Recipients { stanzas: Vec<Stanza> },
+}
+
+impl KeyInfo {
+    /// Short description for messages
+    pub fn label(&self) -> String {
+        match self {
+            KeyInfo::Legacy => MACHINE_KEY.to_string(),
+            KeyInfo::KeyFile { name, fingerprint } => format!("{} ({})", name, hex::encode(fingerprint)),
+            KeyInfo::Passphrase { .. } => PASSPHRASE.to_string(),
+            KeyInfo::Recipients { stanzas } => format!("{} recipient(s)", stanzas.len()),
+        }
+    }
+}
+
+/// Key material with the header info it is written under
+#[derive(Clone)]
+pub struct Key {
+    info: KeyInfo,
+    secret: Vec<u8>,
+}
+
+impl Key {
+    /// A key file's contents, known under `name`
+    pub fn from_bytes(name: &str, secret: Vec<u8>) -> Self {
+        let info = KeyInfo::KeyFile { name: name.to_string(), fingerprint: fingerprint(&secret) };
+        Self { info, secret }
+    }
+
+    pub fn from_file(name: &str, path: &Path) -> Result<Self, CokacencError> {
+        Ok(Self::from_bytes(name, load_key_file(path)?))
+    }
+
+    pub fn passphrase(passphrase: &str) -> Result<Self, CokacencError> {
+        Self::passphrase_with_cost(passphrase, PASSPHRASE_LOG_N)
+    }
+
+    pub(crate) fn passphrase_with_cost(passphrase: &str, log_n: u8) -> Result<Self, CokacencError> {
+        if passphrase.is_empty() {
+            return Err(CokacencError::Other("Passphrase is empty".to_string()));
+        }
+        Ok(Self { info: KeyInfo::Passphrase { log_n }, secret: passphrase.as_bytes().to_vec() })
+    }
+
+    /// A new random file key, sealed for every recipient
+    pub fn for_recipients(recipients: &[PublicKey]) -> Result<Self, CokacencError> {
+        if recipients.is_empty() || recipients.len() > recipients::MAX_RECIPIENTS {
+            return Err(CokacencError::Other(format!("Use 1 to {} recipients", recipients::MAX_RECIPIENTS)));
+        }
+        let mut file_key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut file_key);
+        let stanzas = recipients.iter()
+            .map(|r| recipients::wrap(&file_key, r))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { info: KeyInfo::Recipients { stanzas }, secret: file_key.to_vec() })
+    }
+
+    pub fn info(&self) -> &KeyInfo {
+        &self.info
+    }
+
+    pub(crate) fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// AES key for a group with this salt
+    pub fn derive(&self, salt: &[u8; 16]) -> Result<[u8; 32], CokacencError> {
+        match self.info {
+            KeyInfo::Passphrase { log_n } => crypto::derive_passphrase_key(&self.secret, salt, log_n),
+            KeyInfo::Recipients { .. } => Ok(recipients::payload_key(&self.secret, salt)),
+            _ => Ok(crypto::derive_key(&self.secret, salt)),
+        }
+    }
+}
+
+/// Identifies a key file without revealing it: truncated SHA-256 with a
+/// domain prefix
+pub fn fingerprint(secret: &[u8]) -> [u8; FINGERPRINT_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"cokacenc key id\0");
+    hasher.update(secret);
+    let digest = hasher.finalize();
+    let mut fp = [0u8; FINGERPRINT_LEN];
+    fp.copy_from_slice(&digest[..FINGERPRINT_LEN]);
+    fp
+}
+
+/// `~/.cokacdir/credential`
+pub fn credential_dir() -> Result<PathBuf, CokacencError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        CokacencError::Other("Cannot determine home directory".to_string())
+    })?;
+    Ok(home.join(".cokacdir").join("credential"))
+}
+
+pub fn machine_key_path(credential_dir: &Path) -> PathBuf {
+    credential_dir.join(MACHINE_KEY_FILE)
+}
+
+fn named_key_path(credential_dir: &Path, name: &str) -> PathBuf {
+    credential_dir.join(KEYRING_DIR).join(format!("{}{}", name, KEY_EXT))
+}
+
+/// Keyring names are used as filename prefixes: ASCII alphanumerics only,
+/// and not one of the reserved picker entries.
+pub fn valid_key_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_alphanumeric())
+        && !name.eq_ignore_ascii_case(MACHINE_KEY)
+        && !name.eq_ignore_ascii_case(PASSPHRASE)
+        && !name.eq_ignore_ascii_case(super::naming::PASSPHRASE_PREFIX)
+        && !name.eq_ignore_ascii_case(super::naming::RECIPIENTS_PREFIX)
+}
+
+/// Names of the keys in the keyring directory, sorted
+pub fn list_keys(credential_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(credential_dir.join(KEYRING_DIR)) {
+        Ok(rd) => rd.filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.strip_suffix(KEY_EXT).filter(|n| valid_key_name(n)).map(str::to_string)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), CokacencError> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a file readable by the owner only, in a private directory
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), CokacencError> {
+    if let Some(parent) = path.parent() {
+        create_private_dir(parent)?;
+    }
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Create a random key file at `path` unless it exists: 4096 random bytes,
+/// base64, readable by the owner only.
+pub fn ensure_key_file(path: &Path) -> Result<(), CokacencError> {
+    if !path.exists() {
+        let mut raw = vec![0u8; 4096];
+        rand::thread_rng().fill_bytes(&mut raw);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&raw);
+        write_private_file(path, encoded.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// What the user picked to encrypt with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    Machine,
+    /// A keyring key; created on first use
+    Named(String),
+    Passphrase(String),
+    /// Public keys by name, see `recipients`
+    Recipients(Vec<String>),
+}
+
+impl KeySource {
+    /// Parse key picker input: `machine`, `passphrase`, a keyring name or
+    /// `@name,@name...` recipients. The passphrase itself is asked for
+    /// separately.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.starts_with('@') {
+            let mut names: Vec<String> = Vec::new();
+            for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                let name = part.strip_prefix('@').unwrap_or(part);
+                if !valid_key_name(name) {
+                    return Err(format!("Invalid recipient '{}'", part));
+                }
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+            Ok(KeySource::Recipients(names))
+        } else if spec.is_empty() || spec.eq_ignore_ascii_case(MACHINE_KEY) {
+            Ok(KeySource::Machine)
+        } else if spec.eq_ignore_ascii_case(PASSPHRASE) {
+            Ok(KeySource::Passphrase(String::new()))
+        } else if valid_key_name(spec) {
+            Ok(KeySource::Named(spec.to_string()))
+        } else {
+            Err(format!("Invalid key name '{}': use up to {} letters and digits", spec, MAX_NAME_LEN))
+        }
+    }
+
+    /// The key picker entry for this source
+    pub fn spec(&self) -> String {
+        match self {
+            KeySource::Machine => MACHINE_KEY.to_string(),
+            KeySource::Named(name) => name.clone(),
+            KeySource::Passphrase(_) => PASSPHRASE.to_string(),
+            KeySource::Recipients(names) => names.iter()
+                .map(|n| format!("@{}", n))
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    /// Load the key, creating the machine key or a new keyring key if needed
+    pub fn load(&self, credential_dir: &Path) -> Result<Key, CokacencError> {
+        match self {
+            KeySource::Machine => {
+                let path = machine_key_path(credential_dir);
+                ensure_key_file(&path)?;
+                Key::from_file(MACHINE_KEY, &path)
+            }
+            KeySource::Named(name) => {
+                create_private_dir(credential_dir)?;
+                let path = named_key_path(credential_dir, name);
+                ensure_key_file(&path)?;
+                Key::from_file(name, &path)
+            }
+            KeySource::Passphrase(passphrase) => Key::passphrase(passphrase),
+            KeySource::Recipients(names) => {
+                Key::for_recipients(&recipients::resolve(credential_dir, names)?)
+            }
+        }
+    }
+}
+
+/// Key picker entries: the machine key, a passphrase, the keyring, then
+/// the recipients
+pub fn key_choices(credential_dir: &Path) -> Vec<String> {
+    let mut choices = vec![MACHINE_KEY.to_string(), PASSPHRASE.to_string()];
+    choices.extend(list_keys(credential_dir));
+    choices.extend(recipient_choices(credential_dir));
+    choices
+}
+
+/// Key picker entries for recipients: `@name`
+pub fn recipient_choices(credential_dir: &Path) -> Vec<String> {
+    recipients::list_recipients(credential_dir)
+        .into_iter()
+        .map(|(name, _)| format!("@{}", name))
+        .collect()
+}
+
+/// Finds the key each chunk needs and caches the derived AES keys, so a
+/// group sharing one salt is only stretched once.
+#[derive(Clone)]
+pub struct KeyResolver {
+    credential_dir: PathBuf,
+    passphrase: Option<String>,
+    derived: HashMap<(KeyInfo, [u8; 16]), [u8; 32]>,
+}
+
+impl KeyResolver {
+    pub fn new(credential_dir: PathBuf, passphrase: Option<String>) -> Self {
+        Self { credential_dir, passphrase, derived: HashMap::new() }
+    }
+
+    /// The key `info` names: the named key file if its fingerprint matches,
+    /// otherwise any keyring key (or the machine key) with that fingerprint.
+    pub fn key(&self, info: &KeyInfo) -> Result<Key, CokacencError> {
+        match info {
+            KeyInfo::Legacy => {
+                let path = machine_key_path(&self.credential_dir);
+                if !path.exists() {
+                    return Err(CokacencError::KeyNotFound(MACHINE_KEY.to_string()));
+                }
+                Key::from_file(MACHINE_KEY, &path)
+            }
+            KeyInfo::Passphrase { log_n } => match &self.passphrase {
+                Some(passphrase) => Key::passphrase_with_cost(passphrase, *log_n),
+                None => Err(CokacencError::PassphraseRequired),
+            },
+            KeyInfo::Recipients { stanzas } => {
+                let identities = recipients::load_identities(&self.credential_dir);
+                stanzas.iter()
+                    .find_map(|stanza| identities.iter().find_map(|id| recipients::unwrap(stanza, id)))
+                    .map(|file_key| Key { info: info.clone(), secret: file_key.to_vec() })
+                    .ok_or_else(|| CokacencError::KeyNotFound(format!("no identity for {}", info.label())))
+            }
+            KeyInfo::KeyFile { name, fingerprint } => {
+                let named = if name == MACHINE_KEY {
+                    machine_key_path(&self.credential_dir)
+                } else {
+                    named_key_path(&self.credential_dir, name)
+                };
+                let candidates = std::iter::once((name.clone(), named))
+                    .chain(std::iter::once((
+                        MACHINE_KEY.to_string(),
+                        machine_key_path(&self.credential_dir),
+                    )))
+                    .chain(list_keys(&self.credential_dir).into_iter().map(|n| {
+                        let path = named_key_path(&self.credential_dir, &n);
+                        (n, path)
+                    }));
+                for (candidate, path) in candidates {
+                    if let Ok(key) = Key::from_file(&candidate, &path) {
+                        if matches!(&key.info, KeyInfo::KeyFile { fingerprint: fp, .. } if fp == fingerprint) {
+                            return Ok(key);
+                        }
+                    }
+                }
+                Err(CokacencError::KeyNotFound(info.label()))
+            }
+        }
+    }
+
+    /// AES key of the chunk with this header
+    pub fn chunk_key(&mut self, header: &ChunkHeader) -> Result<[u8; 32], CokacencError> {
+        let id = (header.key.clone(), header.salt);
+        if let Some(key) = self.derived.get(&id) {
+            return Ok(*key);
+        }
+        let key = self.key(&header.key)?.derive(&header.salt)?;
+        self.derived.insert(id, key);
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_source_parse() {
+        assert_eq!(KeySource::parse(""), Ok(KeySource::Machine));
+        assert_eq!(KeySource::parse("Machine"), Ok(KeySource::Machine));
+        assert_eq!(KeySource::parse("passphrase"), Ok(KeySource::Passphrase(String::new())));
+        assert_eq!(KeySource::parse(" alice "), Ok(KeySource::Named("alice".to_string())));
+        let team = KeySource::parse("@alice, @bob,@alice").unwrap();
+        assert_eq!(team, KeySource::Recipients(vec!["alice".to_string(), "bob".to_string()]));
+        assert_eq!(team.spec(), "@alice,@bob");
+        assert!(KeySource::parse("@alice,@../x").is_err());
+        assert!(KeySource::parse("../etc").is_err());
+        assert!(KeySource::parse("pass").is_err());
+        assert!(KeySource::parse(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_keyring_lookup() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cred = tmp.path();
+
+        let alice = KeySource::Named("alice".to_string()).load(cred).unwrap();
+        let machine = KeySource::Machine.load(cred).unwrap();
+        assert_eq!(key_choices(cred), vec!["machine", "passphrase", "alice"]);
+        // Loading again reuses the file
+        let again = KeySource::Named("alice".to_string()).load(cred).unwrap();
+        assert_eq!(again.info(), alice.info());
+        assert_ne!(alice.info(), machine.info());
+
+        let resolver = KeyResolver::new(cred.to_path_buf(), None);
+        assert_eq!(resolver.key(alice.info()).unwrap().secret(), alice.secret());
+        assert_eq!(resolver.key(&KeyInfo::Legacy).unwrap().secret(), machine.secret());
+
+        // A colleague's key imported under another name is found by fingerprint
+        let KeyInfo::KeyFile { fingerprint, .. } = alice.info().clone() else { unreachable!() };
+        fs::rename(named_key_path(cred, "alice"), named_key_path(cred, "bob")).unwrap();
+        let info = KeyInfo::KeyFile { name: "alice".to_string(), fingerprint };
+        assert_eq!(resolver.key(&info).unwrap().secret(), alice.secret());
+
+        fs::remove_file(named_key_path(cred, "bob")).unwrap();
+        assert!(matches!(resolver.key(&info), Err(CokacencError::KeyNotFound(_))));
+        assert!(matches!(
+            resolver.key(&KeyInfo::Passphrase { log_n: 10 }),
+            Err(CokacencError::PassphraseRequired)
+        ));
+    }
+}
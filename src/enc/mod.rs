@@ -1,6 +1,9 @@
 pub mod crypto;
 pub mod error;
+pub mod keys;
 pub mod naming;
+pub mod recipients;
+pub mod vault;
 
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Read, Write};
@@ -9,23 +12,37 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 
-use base64::Engine;
 use md5::{Digest, Md5};
-use rand::RngCore;
 use serde::{Serialize, Deserialize};
 
 use crypto::{
-    derive_key, generate_iv, generate_salt, load_key_file, write_header, ChunkEncryptor,
-    decrypt_chunk_streaming, read_header,
+    decrypt_chunk, ChunkHeader, generate_nonce_prefix, generate_salt, read_header, write_header, SegmentEncryptor,
 };
 use error::CokacencError;
+use keys::{Key, KeyInfo, KeyResolver};
 use crate::services::file_ops::ProgressMessage;
 
 const READ_BUF_SIZE: usize = 64 * 1024; // 64KB
 
+/// zstd level for compressed groups
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Extensions of formats that are compressed already, where zstd only costs time
+const PRECOMPRESSED_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "heic", "avif",
+    "mp4", "m4v", "mkv", "mov", "avi", "webm", "mp3", "m4a", "aac", "ogg", "opus", "flac",
+    "zip", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "jar", "apk", "docx", "xlsx", "pptx",
+];
+
+/// Whether a file is worth compressing, judged by its extension
+fn is_compressible(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    !ext.is_some_and(|e| PRECOMPRESSED_EXTENSIONS.contains(&e.as_str()))
+}
+
 // ─── Chunk metadata (embedded inside each encrypted chunk) ─────────────
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ChunkMetadata {
     #[serde(rename = "v")]
     version: u32,
@@ -49,6 +66,10 @@ struct ChunkMetadata {
     chunk_offset: u64,
     #[serde(rename = "len")]
     chunk_data_size: u64,
+    /// The group's data is one zstd stream, split across its chunks; offset
+    /// and len count stream bytes while size and md5 are the original file's
+    #[serde(rename = "zstd", default, skip_serializing_if = "std::ops::Not::not")]
+    compressed: bool,
 }
 
 // ─── File info gathered in first pass ──────────────────────────────────
@@ -58,9 +79,11 @@ struct FileInfo {
     md5: String,
     modified: i64,
     permissions: u32,
+    /// Size of the zstd stream, when the file is to be stored compressed
+    stream_size: Option<u64>,
 }
 
-fn gather_file_info(path: &Path, use_md5: bool) -> Result<FileInfo, CokacencError> {
+fn gather_file_info(path: &Path, use_md5: bool, compress: bool) -> Result<FileInfo, CokacencError> {
     let metadata = fs::metadata(path)?;
     let size = metadata.len();
 
@@ -78,23 +101,39 @@ fn gather_file_info(path: &Path, use_md5: bool) -> Result<FileInfo, CokacencErro
     #[cfg(not(unix))]
     let permissions = 0u32;
 
-    let md5 = if use_md5 {
-        // Compute MD5 (first pass)
+    // Compute MD5 and the compressed size (first pass)
+    let compress = compress && is_compressible(path);
+    let mut hasher = use_md5.then(Md5::new);
+    let mut sink = std::io::sink();
+    let mut encoder = if compress {
+        let counter = CountingWriter { inner: &mut sink, len: 0 };
+        Some(zstd::stream::write::Encoder::new(counter, COMPRESSION_LEVEL)?)
+    } else {
+        None
+    };
+    if use_md5 || compress {
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
-        let mut hasher = Md5::new();
         let mut buf = [0u8; READ_BUF_SIZE];
         loop {
             let n = reader.read(&mut buf)?;
             if n == 0 { break; }
-            hasher.update(&buf[..n]);
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&buf[..n]);
+            }
+            if let Some(encoder) = encoder.as_mut() {
+                encoder.write_all(&buf[..n])?;
+            }
         }
-        format!("{:032x}", hasher.finalize())
-    } else {
-        String::new()
+    }
+    let md5 = hasher.map(|h| format!("{:032x}", h.finalize())).unwrap_or_default();
+    // Stored as is when zstd does not make it smaller
+    let stream_size = match encoder {
+        Some(encoder) => Some(encoder.finish()?.len).filter(|&len| len < size),
+        None => None,
     };
 
-    Ok(FileInfo { size, md5, modified, permissions })
+    Ok(FileInfo { size, md5, modified, permissions, stream_size })
 }
 
 // ─── MetadataSplitWriter (extracts metadata from decrypted stream) ─────
@@ -206,42 +245,57 @@ impl<W: Write> Write for TeeWriter<'_, W> {
     }
 }
 
-// ─── Key management ────────────────────────────────────────────────────
+// ─── DataWriter (file data from a group's data stream) ───────────────
 
-/// Ensure the encryption key file exists at ~/.cokacdir/credential/cokacenc.key.
-/// Creates the directory and key file if they don't exist.
-/// Returns the path to the key file.
-pub fn ensure_key() -> Result<PathBuf, CokacencError> {
-    let home = dirs::home_dir().ok_or_else(|| {
-        CokacencError::Other("Cannot determine home directory".to_string())
-    })?;
-    let cred_dir = home.join(".cokacdir").join("credential");
-
-    if !cred_dir.exists() {
-        fs::create_dir_all(&cred_dir)?;
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(&cred_dir, fs::Permissions::from_mode(0o700))?;
+/// Writer that turns the data of a group's chunks back into the original
+/// file, decompressing it for compressed groups. One zstd stream spans all
+/// chunks of a group, so a DataWriter lives for the whole group.
+enum DataWriter<W: Write> {
+    Stored(W),
+    Zstd(zstd::stream::write::Decoder<'static, W>),
+}
+
+impl<W: Write> DataWriter<W> {
+    fn new(inner: W, compressed: bool) -> Result<Self, CokacencError> {
+        if compressed {
+            Ok(DataWriter::Zstd(zstd::stream::write::Decoder::new(inner)?))
+        } else {
+            Ok(DataWriter::Stored(inner))
         }
     }
+}
 
-    let key_path = cred_dir.join("cokacenc.key");
-
-    if !key_path.exists() {
-        let mut raw = vec![0u8; 4096];
-        rand::thread_rng().fill_bytes(&mut raw);
-        let encoded = base64::engine::general_purpose::STANDARD.encode(&raw);
+impl<W: Write> Write for DataWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            DataWriter::Stored(w) => w.write(buf),
+            DataWriter::Zstd(w) => w.write(buf),
+        }
+    }
 
-        fs::write(&key_path, encoded.as_bytes())?;
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            DataWriter::Stored(w) => w.flush(),
+            DataWriter::Zstd(w) => w.flush(),
         }
     }
+}
+
+// ─── Key management ────────────────────────────────────────────────────
 
-    Ok(key_path)
+/// The distinct keys the groups in `dir` need, read from their first chunks
+pub fn required_keys(dir: &Path) -> Result<Vec<KeyInfo>, CokacencError> {
+    let mut needed = Vec::new();
+    for chunks in naming::group_enc_files(dir)?.values() {
+        let Some(first) = chunks.first() else { continue };
+        let mut reader = BufReader::new(File::open(&first.path)?);
+        if let Ok(header) = read_header(&mut reader) {
+            if !needed.contains(&header.key) {
+                needed.push(header.key);
+            }
+        }
+    }
+    Ok(needed)
 }
 
 // ─── Pack (encrypt) ────────────────────────────────────────────────────
@@ -249,23 +303,17 @@ pub fn ensure_key() -> Result<PathBuf, CokacencError> {
 /// Pack (encrypt) all eligible files in a directory with progress reporting.
 /// Uses 2-pass: first pass computes MD5+metadata, second pass encrypts.
 /// Each chunk embeds full metadata. After encryption, original files are deleted.
+/// With `compress`, files are zstd-compressed inside the encrypted stream
+/// unless their type is compressed already.
 pub fn pack_directory_with_progress(
     dir: &Path,
-    key_path: &Path,
+    key: &Key,
     tx: Sender<ProgressMessage>,
     cancel_flag: Arc<AtomicBool>,
     split_size_mb: u64,
     use_md5: bool,
+    compress: bool,
 ) {
-    let password = match load_key_file(key_path) {
-        Ok(p) => p,
-        Err(e) => {
-            let _ = tx.send(ProgressMessage::Error(String::new(), format!("Key file error: {}", e)));
-            let _ = tx.send(ProgressMessage::Completed(0, 1));
-            return;
-        }
-    };
-
     let split_size = if split_size_mb == 0 { u64::MAX } else { split_size_mb * 1024 * 1024 };
 
     let mut entries: Vec<_> = match fs::read_dir(dir) {
@@ -309,7 +357,7 @@ pub fn pack_directory_with_progress(
 
         let _ = tx.send(ProgressMessage::FileStarted(name.clone()));
 
-        match pack_file(&path, &name, dir, &password, split_size, use_md5) {
+        match pack_file(&path, &name, dir, key, split_size, use_md5, compress) {
             Ok(_) => {
                 // Delete original after successful encryption
                 if let Err(e) = fs::remove_file(&path) {
@@ -333,50 +381,67 @@ pub fn pack_directory_with_progress(
     let _ = tx.send(ProgressMessage::Completed(success_count, failure_count));
 }
 
+/// A group ID not used in `dir` yet
+fn new_group_id(dir: &Path) -> String {
+    loop {
+        let id = naming::generate_group_id();
+        if !naming::group_id_exists(dir, &id) {
+            break id;
+        }
+    }
+}
+
 /// Pack a single file using 2-pass approach.
 /// Pass 1: gather file info (MD5, size, mtime, permissions).
 /// Pass 2: encrypt with metadata embedded in each chunk.
+/// The chunks of a group share one salt, so the key is derived once.
+/// A compressed file is split by its zstd stream, so `split_size` bounds
+/// the chunk files rather than the original.
 fn pack_file(
     file_path: &Path,
     original_name: &str,
     out_dir: &Path,
-    password: &[u8],
+    key: &Key,
     split_size: u64,
     use_md5: bool,
+    compress: bool,
 ) -> Result<(), CokacencError> {
     // ── Pass 1: gather info ──
-    let info = gather_file_info(file_path, use_md5)?;
-
-    let group_id = loop {
-        let id = naming::generate_group_id();
-        if !naming::group_id_exists(out_dir, &id) {
-            break id;
-        }
-    };
-    let kp = naming::key_prefix(password);
-    let total_chunks = if info.size == 0 {
+    let info = gather_file_info(file_path, use_md5, compress)?;
+    let stream_size = info.stream_size.unwrap_or(info.size);
+
+    let group_id = new_group_id(out_dir);
+    let kp = naming::key_prefix(key);
+    let salt = generate_salt();
+    let aes_key = key.derive(&salt)?;
+    let total_chunks = if stream_size == 0 {
         1
     } else {
-        ((info.size + split_size - 1) / split_size) as usize
+        stream_size.div_ceil(split_size) as usize
     };
 
     // ── Pass 2: encrypt ──
     let file = File::open(file_path)?;
-    let mut reader = BufReader::new(file);
+    let mut reader: Box<dyn Read> = if info.stream_size.is_some() {
+        Box::new(zstd::stream::read::Encoder::new(file, COMPRESSION_LEVEL)?)
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let changed = || CokacencError::Other(format!("{} changed during encryption", original_name));
     let mut read_buf = [0u8; READ_BUF_SIZE];
     let mut created_chunks: Vec<PathBuf> = Vec::new();
 
     let result = (|| -> Result<(), CokacencError> {
         for chunk_idx in 0..total_chunks {
             let chunk_offset = chunk_idx as u64 * split_size;
-            let chunk_data_size = if info.size == 0 {
+            let chunk_data_size = if stream_size == 0 {
                 0
             } else {
-                split_size.min(info.size - chunk_offset)
+                split_size.min(stream_size - chunk_offset)
             };
 
             let metadata = ChunkMetadata {
-                version: 2,
+                version: crypto::VERSION,
                 group_id: group_id.clone(),
                 filename: original_name.to_string(),
                 file_size: info.size,
@@ -387,6 +452,7 @@ fn pack_file(
                 chunk_index: chunk_idx,
                 chunk_offset,
                 chunk_data_size,
+                compressed: info.stream_size.is_some(),
             };
 
             let chunk_path = naming::chunk_filename(out_dir, &kp, &group_id, chunk_idx)?;
@@ -394,21 +460,19 @@ fn pack_file(
             created_chunks.push(chunk_path);
             let mut writer = BufWriter::new(chunk_file);
 
-            let salt = generate_salt();
-            let iv = generate_iv();
-            let key = derive_key(password, &salt);
-            write_header(&mut writer, &salt, &iv, original_name)?;
+            let nonce_prefix = generate_nonce_prefix();
+            let header = write_header(&mut writer, &salt, &nonce_prefix, key.info(), original_name)?;
 
-            let mut enc = ChunkEncryptor::new(&key, &iv);
+            let mut enc = SegmentEncryptor::new(&aes_key, &nonce_prefix, &header);
 
             // Write metadata length + metadata into encrypted stream
             let meta_bytes = serde_json::to_vec(&metadata)
                 .map_err(|e| CokacencError::Other(format!("JSON serialize: {}", e)))?;
             let meta_len_bytes = (meta_bytes.len() as u32).to_le_bytes();
 
-            let encrypted = enc.update(&meta_len_bytes);
+            let encrypted = enc.update(&meta_len_bytes)?;
             writer.write_all(encrypted)?;
-            let encrypted = enc.update(&meta_bytes);
+            let encrypted = enc.update(&meta_bytes)?;
             writer.write_all(encrypted)?;
 
             // Write file data portion
@@ -416,17 +480,23 @@ fn pack_file(
             while remaining > 0 {
                 let to_read = (READ_BUF_SIZE as u64).min(remaining) as usize;
                 let n = reader.read(&mut read_buf[..to_read])?;
-                if n == 0 { break; }
-                let encrypted = enc.update(&read_buf[..n]);
+                if n == 0 {
+                    return Err(changed());
+                }
+                let encrypted = enc.update(&read_buf[..n])?;
                 writer.write_all(encrypted)?;
                 remaining -= n as u64;
             }
 
-            let final_block = enc.finalize();
-            writer.write_all(&final_block)?;
+            let final_segment = enc.finalize()?;
+            writer.write_all(&final_segment)?;
             writer.flush()?;
         }
 
+        // Pass 1 sized the chunks; the data must end where it said
+        if reader.read(&mut read_buf)? != 0 {
+            return Err(changed());
+        }
         Ok(())
     })();
 
@@ -444,21 +514,29 @@ fn pack_file(
 
 /// Unpack (decrypt) all .cokacenc file groups in a directory with progress reporting.
 /// Metadata is extracted from each chunk. After decryption, .cokacenc files are deleted.
+/// Each group's key is looked up from its header by `keys`.
 pub fn unpack_directory_with_progress(
     dir: &Path,
-    key_path: &Path,
+    mut keys: KeyResolver,
     tx: Sender<ProgressMessage>,
     cancel_flag: Arc<AtomicBool>,
 ) {
-    let password = match load_key_file(key_path) {
-        Ok(p) => p,
-        Err(e) => {
-            let _ = tx.send(ProgressMessage::Error(String::new(), format!("Key file error: {}", e)));
-            let _ = tx.send(ProgressMessage::Completed(0, 1));
-            return;
+    for_each_group(dir, &tx, &cancel_flag, |chunks, tx| {
+        let original_name = unpack_file_group(dir, chunks, &mut keys, tx)?;
+        // Delete .cokacenc files after successful decryption
+        for chunk_info in chunks {
+            let _ = fs::remove_file(&chunk_info.path);
         }
-    };
+        Ok(original_name)
+    });
+}
 
+/// Run `process` on every .cokacenc group in `dir`, reporting each group's
+/// result (the original filename or an error) and the totals through `tx`
+fn for_each_group<F>(dir: &Path, tx: &Sender<ProgressMessage>, cancel_flag: &AtomicBool, mut process: F)
+where
+    F: FnMut(&[naming::EncFileInfo], &Sender<ProgressMessage>) -> Result<String, CokacencError>,
+{
     let groups = match naming::group_enc_files(dir) {
         Ok(g) => g,
         Err(e) => {
@@ -486,12 +564,8 @@ pub fn unpack_directory_with_progress(
 
         let _ = tx.send(ProgressMessage::FileStarted(format!("{}...", &group_id[..8.min(group_id.len())])));
 
-        match unpack_file_group(dir, chunks, &password, &tx) {
+        match process(chunks, tx) {
             Ok(original_name) => {
-                // Delete .cokacenc files after successful decryption
-                for chunk_info in chunks {
-                    let _ = fs::remove_file(&chunk_info.path);
-                }
                 success_count += 1;
                 let _ = tx.send(ProgressMessage::FileCompleted(original_name));
             }
@@ -507,108 +581,136 @@ pub fn unpack_directory_with_progress(
     let _ = tx.send(ProgressMessage::Completed(success_count, failure_count));
 }
 
-/// Decrypt and merge a group of chunk files into the original file.
-/// Returns the original filename on success.
-fn unpack_file_group(
-    dir: &Path,
-    chunks: &[naming::EncFileInfo],
-    password: &[u8],
-    tx: &Sender<ProgressMessage>,
-) -> Result<String, CokacencError> {
+/// A group's chunk files must be numbered 0, 1, 2, ... without gaps
+fn check_sequence(chunks: &[naming::EncFileInfo]) -> Result<(), CokacencError> {
     if chunks.is_empty() {
         return Err(CokacencError::NoEncFiles("empty group".to_string()));
     }
-
-    // Validate sequence continuity
     for (i, chunk) in chunks.iter().enumerate() {
         if chunk.seq_index != i {
             let expected_label = naming::seq_label(i)?;
             return Err(CokacencError::MissingChunk { expected: expected_label });
         }
     }
+    Ok(())
+}
+
+/// Validate the metadata of chunk `index` of a group of `total` chunks
+fn check_chunk_metadata(meta: &ChunkMetadata, index: usize, group_id: &str, total: usize) -> Result<(), CokacencError> {
+    if meta.chunk_index != index {
+        return Err(CokacencError::MetadataParse(
+            format!("Chunk index mismatch: expected {}, got {}", index, meta.chunk_index),
+        ));
+    }
+    // A chunk of another group, or a group missing its last chunks
+    if meta.group_id != group_id || meta.total_chunks != total {
+        return Err(CokacencError::MetadataParse(format!(
+            "Chunk {} belongs to group {} of {} chunks, found {} of {} chunks",
+            index, meta.group_id, meta.total_chunks, group_id, total,
+        )));
+    }
+    Ok(())
+}
+
+/// Decrypt and merge a group of chunk files into the original file.
+/// Returns the original filename on success.
+fn unpack_file_group(
+    dir: &Path,
+    chunks: &[naming::EncFileInfo],
+    keys: &mut KeyResolver,
+    tx: &Sender<ProgressMessage>,
+) -> Result<String, CokacencError> {
+    check_sequence(chunks)?;
 
     let group_id = &chunks[0].group_id;
     let temp_path = dir.join(format!(".{}.unpacking", group_id));
 
-    let out_file = File::create(&temp_path)?;
-    let mut file_writer = BufWriter::new(out_file);
-    let mut md5_hasher = Md5::new();
-
     let mut original_name = String::new();
-    let mut expected_md5 = String::new();
-    let mut file_size = 0u64;
     let mut modified = 0i64;
     let mut permissions: u32 = 0;
 
-    for (i, chunk_info) in chunks.iter().enumerate() {
-        let enc_file = File::open(&chunk_info.path)?;
-        let mut reader = BufReader::new(enc_file);
-
-        let (salt, iv, _header_filename) = read_header(&mut reader)?;
-        let key = derive_key(password, &salt);
-
-        // Decrypt through MetadataSplitWriter -> TeeWriter(file, md5)
-        let meta_bytes;
-        {
-            let mut tee = TeeWriter {
-                file: &mut file_writer,
-                hasher: &mut md5_hasher,
-            };
-            let mut split = MetadataSplitWriter::new(&mut tee);
-            decrypt_chunk_streaming(&mut reader, &mut split, &key, &iv)?;
-            meta_bytes = split.take_metadata_bytes()?;
-        }
+    // Nothing of a group that fails any check may be left behind
+    let result = (|| -> Result<(), CokacencError> {
+        let compressed = read_first_metadata(chunks, keys)?.0.compressed;
+        let out_file = File::create(&temp_path)?;
+        let mut file_writer = BufWriter::new(out_file);
+        let mut md5_hasher = Md5::new();
+        let mut expected_md5 = String::new();
+        let mut file_size = 0u64;
+        let tee = TeeWriter {
+            file: &mut file_writer,
+            hasher: &mut md5_hasher,
+        };
+        let mut data = DataWriter::new(tee, compressed)?;
+
+        for (i, chunk_info) in chunks.iter().enumerate() {
+            let enc_file = File::open(&chunk_info.path)?;
+            let mut reader = BufReader::new(enc_file);
+
+            let header = read_header(&mut reader)?;
+            let key = keys.chunk_key(&header)?;
+
+            // Decrypt through MetadataSplitWriter -> DataWriter -> TeeWriter(file, md5)
+            let meta_bytes;
+            {
+                let mut split = MetadataSplitWriter::new(&mut data);
+                decrypt_chunk(&mut reader, &mut split, &key, &header)?;
+                meta_bytes = split.take_metadata_bytes()?;
+            }
 
-        let meta: ChunkMetadata = serde_json::from_slice(&meta_bytes)
-            .map_err(|e| CokacencError::MetadataParse(e.to_string()))?;
+            let meta: ChunkMetadata = serde_json::from_slice(&meta_bytes)
+                .map_err(|e| CokacencError::MetadataParse(e.to_string()))?;
 
-        // Validate chunk metadata
-        if meta.chunk_index != i {
-            let _ = fs::remove_file(&temp_path);
-            return Err(CokacencError::MetadataParse(
-                format!("Chunk index mismatch: expected {}, got {}", i, meta.chunk_index),
-            ));
-        }
+            check_chunk_metadata(&meta, i, group_id, chunks.len())?;
 
-        if i == 0 {
-            original_name = meta.filename.clone();
-            expected_md5 = meta.file_md5.clone();
-            file_size = meta.file_size;
-            modified = meta.modified;
-            permissions = meta.permissions;
-            // Update progress with real filename
-            let _ = tx.send(ProgressMessage::FileStarted(original_name.clone()));
-        } else {
-            // Cross-check metadata consistency across chunks
-            if meta.filename != original_name || (!expected_md5.is_empty() && meta.file_md5 != expected_md5) {
-                let _ = fs::remove_file(&temp_path);
-                return Err(CokacencError::MetadataParse(
-                    "Inconsistent metadata across chunks".to_string(),
-                ));
+            if i == 0 {
+                original_name = meta.filename.clone();
+                expected_md5 = meta.file_md5.clone();
+                file_size = meta.file_size;
+                modified = meta.modified;
+                permissions = meta.permissions;
+                // Update progress with real filename
+                let _ = tx.send(ProgressMessage::FileStarted(original_name.clone()));
+            } else {
+                // Cross-check metadata consistency across chunks
+                if meta.filename != original_name
+                    || meta.compressed != compressed
+                    || (!expected_md5.is_empty() && meta.file_md5 != expected_md5)
+                {
+                    return Err(CokacencError::MetadataParse(
+                        "Inconsistent metadata across chunks".to_string(),
+                    ));
+                }
             }
         }
-    }
 
-    file_writer.flush()?;
-    drop(file_writer);
+        data.flush()?;
+        drop(data);
+        file_writer.flush()?;
+        drop(file_writer);
+
+        // Verify MD5 (skip if MD5 was not computed during encryption)
+        let md5_hex = format!("{:032x}", md5_hasher.finalize());
+        if !expected_md5.is_empty() && md5_hex != expected_md5 {
+            return Err(CokacencError::Md5Mismatch {
+                expected: expected_md5,
+                actual: md5_hex,
+            });
+        }
 
-    // Verify MD5 (skip if MD5 was not computed during encryption)
-    let md5_hex = format!("{:032x}", md5_hasher.finalize());
-    if !expected_md5.is_empty() && md5_hex != expected_md5 {
-        let _ = fs::remove_file(&temp_path);
-        return Err(CokacencError::Md5Mismatch {
-            expected: expected_md5,
-            actual: md5_hex,
-        });
-    }
+        // Verify file size
+        let actual_size = fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+        if actual_size != file_size {
+            return Err(CokacencError::Other(
+                format!("Size mismatch: expected {}, got {}", file_size, actual_size),
+            ));
+        }
+        Ok(())
+    })();
 
-    // Verify file size
-    let actual_size = fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
-    if actual_size != file_size {
+    if let Err(e) = result {
         let _ = fs::remove_file(&temp_path);
-        return Err(CokacencError::Other(
-            format!("Size mismatch: expected {}, got {}", file_size, actual_size),
-        ));
+        return Err(e);
     }
 
     // Rename to original filename (sanitize to prevent path traversal)
@@ -650,3 +752,788 @@ fn unpack_file_group(
 
     Ok(safe_name.to_string())
 }
+
+// ─── Verify / list ─────────────────────────────────────────────────────
+
+/// What a group's metadata says about the original file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInfo {
+    pub filename: String,
+    pub size: u64,
+    pub modified: i64,
+    /// Chunks the group was written as
+    pub chunks: usize,
+    pub key: KeyInfo,
+}
+
+/// One .cokacenc group found in a directory
+#[derive(Debug)]
+pub struct GroupListing {
+    pub group_id: String,
+    /// Chunk files present on disk
+    pub chunks_found: usize,
+    pub info: Result<GroupInfo, CokacencError>,
+}
+
+/// Writer that refuses file data, to stop decrypting once the metadata is read
+struct MetadataOnly;
+
+impl Write for MetadataOnly {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("metadata read"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer that counts the data it passes on
+struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    len: u64,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// List the .cokacenc groups in `dir` with the metadata of their first chunk.
+/// Only the start of each first chunk is decrypted, to memory. Each group is
+/// sent on `listing_tx` as soon as it is read, so the listing is complete
+/// once the progress reports `Completed`.
+pub fn list_groups_with_progress(
+    dir: &Path,
+    mut keys: KeyResolver,
+    tx: Sender<ProgressMessage>,
+    listing_tx: Sender<GroupListing>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    for_each_group(dir, &tx, &cancel_flag, |chunks, _| {
+        let info = read_group_info(chunks, &mut keys);
+        let name = match &info {
+            Ok(info) => Ok(info.filename.clone()),
+            Err(e) => Err(CokacencError::Other(e.to_string())),
+        };
+        let _ = listing_tx.send(GroupListing {
+            group_id: chunks[0].group_id.clone(),
+            chunks_found: chunks.len(),
+            info,
+        });
+        name
+    });
+}
+
+/// Metadata of a group, from its first chunk
+fn read_group_info(chunks: &[naming::EncFileInfo], keys: &mut KeyResolver) -> Result<GroupInfo, CokacencError> {
+    let (meta, header) = read_first_metadata(chunks, keys)?;
+    Ok(GroupInfo {
+        filename: meta.filename,
+        size: meta.file_size,
+        modified: meta.modified,
+        chunks: meta.total_chunks,
+        key: header.key,
+    })
+}
+
+/// Header and metadata of a group's first chunk, decrypting no file data
+fn read_first_metadata(
+    chunks: &[naming::EncFileInfo],
+    keys: &mut KeyResolver,
+) -> Result<(ChunkMetadata, ChunkHeader), CokacencError> {
+    let first = chunks.first().ok_or_else(|| CokacencError::NoEncFiles("empty group".to_string()))?;
+    if first.seq_index != 0 {
+        return Err(CokacencError::MissingChunk { expected: naming::seq_label(0)? });
+    }
+    let mut reader = BufReader::new(File::open(&first.path)?);
+    let header = read_header(&mut reader)?;
+    let key = keys.chunk_key(&header)?;
+
+    let mut stop = MetadataOnly;
+    let mut split = MetadataSplitWriter::new(&mut stop);
+    let decrypted = decrypt_chunk(&mut reader, &mut split, &key, &header);
+    // Decryption stops at the first file data; only a chunk without a
+    // complete metadata block is an error
+    let meta_bytes = match split.take_metadata_bytes() {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(decrypted.err().unwrap_or(e)),
+    };
+    let meta: ChunkMetadata = serde_json::from_slice(&meta_bytes)
+        .map_err(|e| CokacencError::MetadataParse(e.to_string()))?;
+    check_chunk_metadata(&meta, 0, &first.group_id, meta.total_chunks)?;
+    Ok((meta, header))
+}
+
+/// Verify all .cokacenc file groups in a directory with progress reporting.
+/// Every chunk is decrypted to memory and checked; nothing is written or deleted.
+pub fn verify_directory_with_progress(
+    dir: &Path,
+    mut keys: KeyResolver,
+    tx: Sender<ProgressMessage>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    for_each_group(dir, &tx, &cancel_flag, |chunks, _| Ok(verify_file_group(chunks, &mut keys)?.filename));
+}
+
+/// Check that a group decrypts with a known key, has all its chunks in order
+/// and matches the size and MD5 of the original file
+pub fn verify_file_group(chunks: &[naming::EncFileInfo], keys: &mut KeyResolver) -> Result<GroupInfo, CokacencError> {
+    read_file_group(chunks, keys, &mut std::io::sink())
+}
+
+/// Decrypt a group to memory, for viewing. Groups larger than `max_size` are
+/// refused before anything is decrypted.
+pub fn decrypt_group_to_memory(
+    chunks: &[naming::EncFileInfo],
+    keys: &mut KeyResolver,
+    max_size: u64,
+) -> Result<(GroupInfo, Vec<u8>), CokacencError> {
+    let info = read_group_info(chunks, keys)?;
+    if info.size > max_size {
+        return Err(CokacencError::Other(format!(
+            "File too large to open in memory ({:.1} MB)",
+            info.size as f64 / 1024.0 / 1024.0,
+        )));
+    }
+    let mut data = Vec::with_capacity(info.size as usize);
+    let info = read_file_group(chunks, keys, &mut data)?;
+    Ok((info, data))
+}
+
+/// Decrypt a group into `out`, checking it like `verify_file_group`
+fn read_file_group<W: Write>(
+    chunks: &[naming::EncFileInfo],
+    keys: &mut KeyResolver,
+    out: &mut W,
+) -> Result<GroupInfo, CokacencError> {
+    check_sequence(chunks)?;
+
+    let group_id = &chunks[0].group_id;
+    let compressed = read_first_metadata(chunks, keys)?.0.compressed;
+    let mut md5_hasher = Md5::new();
+    let mut info: Option<GroupInfo> = None;
+    let mut expected_md5 = String::new();
+    let mut offset = 0u64;
+
+    // MetadataSplitWriter -> CountingWriter(stream) -> DataWriter -> CountingWriter(file) -> TeeWriter(out, md5)
+    let mut tee = TeeWriter {
+        file: out,
+        hasher: &mut md5_hasher,
+    };
+    let mut plain = CountingWriter { inner: &mut tee, len: 0 };
+    let mut data = DataWriter::new(&mut plain, compressed)?;
+
+    for (i, chunk_info) in chunks.iter().enumerate() {
+        let mut reader = BufReader::new(File::open(&chunk_info.path)?);
+        let header = read_header(&mut reader)?;
+        let key = keys.chunk_key(&header)?;
+
+        let mut counter = CountingWriter { inner: &mut data, len: 0 };
+        let meta_bytes;
+        {
+            let mut split = MetadataSplitWriter::new(&mut counter);
+            decrypt_chunk(&mut reader, &mut split, &key, &header)?;
+            meta_bytes = split.take_metadata_bytes()?;
+        }
+
+        let meta: ChunkMetadata = serde_json::from_slice(&meta_bytes)
+            .map_err(|e| CokacencError::MetadataParse(e.to_string()))?;
+        check_chunk_metadata(&meta, i, group_id, chunks.len())?;
+        if meta.chunk_offset != offset || meta.chunk_data_size != counter.len {
+            return Err(CokacencError::Other(format!(
+                "Size mismatch in chunk {}: expected {} bytes at {}, got {} at {}",
+                i, meta.chunk_data_size, meta.chunk_offset, counter.len, offset,
+            )));
+        }
+        offset += counter.len;
+
+        match &info {
+            None => {
+                expected_md5 = meta.file_md5.clone();
+                info = Some(GroupInfo {
+                    filename: meta.filename,
+                    size: meta.file_size,
+                    modified: meta.modified,
+                    chunks: meta.total_chunks,
+                    key: header.key,
+                });
+            }
+            Some(first) => {
+                if meta.filename != first.filename || meta.file_md5 != expected_md5 || meta.compressed != compressed {
+                    return Err(CokacencError::MetadataParse(
+                        "Inconsistent metadata across chunks".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    data.flush()?;
+    drop(data);
+    let info = info.ok_or_else(|| CokacencError::NoEncFiles(group_id.clone()))?;
+    if plain.len != info.size {
+        return Err(CokacencError::Other(
+            format!("Size mismatch: expected {}, got {}", info.size, plain.len),
+        ));
+    }
+    // MD5 is empty when it was not computed during encryption
+    let md5_hex = format!("{:032x}", md5_hasher.finalize());
+    if !expected_md5.is_empty() && md5_hex != expected_md5 {
+        return Err(CokacencError::Md5Mismatch {
+            expected: expected_md5,
+            actual: md5_hex,
+        });
+    }
+    Ok(info)
+}
+
+/// Decrypt the given groups into `target_dir`, keeping their chunks. A file
+/// that already exists in `target_dir` is not overwritten.
+pub fn extract_groups_with_progress(
+    groups: &[Vec<naming::EncFileInfo>],
+    target_dir: &Path,
+    mut keys: KeyResolver,
+    tx: Sender<ProgressMessage>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    let total_groups = groups.len();
+    let _ = tx.send(ProgressMessage::TotalProgress(0, total_groups, 0, 0));
+
+    let mut success_count = 0;
+    let mut failure_count = 0;
+
+    for (i, chunks) in groups.iter().enumerate() {
+        if cancel_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        let group_id = chunks.first().map(|c| c.group_id.clone()).unwrap_or_default();
+        let _ = tx.send(ProgressMessage::FileStarted(format!("{}...", &group_id[..8.min(group_id.len())])));
+
+        let result = read_group_info(chunks, &mut keys).and_then(|info| {
+            let existing = Path::new(&info.filename).file_name().map(|n| target_dir.join(n));
+            if existing.is_some_and(|p| p.exists()) {
+                return Err(CokacencError::Other(format!("{} already exists", info.filename)));
+            }
+            unpack_file_group(target_dir, chunks, &mut keys, &tx)
+        });
+        match result {
+            Ok(original_name) => {
+                success_count += 1;
+                let _ = tx.send(ProgressMessage::FileCompleted(original_name));
+            }
+            Err(e) => {
+                failure_count += 1;
+                let _ = tx.send(ProgressMessage::Error(group_id, e.to_string()));
+            }
+        }
+
+        let _ = tx.send(ProgressMessage::TotalProgress(i + 1, total_groups, 0, 0));
+    }
+
+    let _ = tx.send(ProgressMessage::Completed(success_count, failure_count));
+}
+
+// ─── Re-key ────────────────────────────────────────────────────────────
+
+/// Writer that re-encrypts a decrypted chunk for another key. The metadata is
+/// rewritten for the new group and everything goes straight into the new
+/// chunk, so no plaintext reaches the disk.
+struct RekeyWriter<'a, W: Write, D: Write> {
+    head: Vec<u8>, // meta_len + metadata, until complete
+    meta: Option<ChunkMetadata>,
+    group_id: &'a str,
+    enc: SegmentEncryptor,
+    out: &'a mut W,
+    data: &'a mut D, // checks the data on its way through
+    data_len: u64,
+}
+
+impl<W: Write, D: Write> RekeyWriter<'_, W, D> {
+    /// Once the buffered metadata is complete, write the rewritten copy and
+    /// return the data that followed it
+    fn take_head(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        if self.head.len() < 4 {
+            return Ok(None);
+        }
+        let meta_len = u32::from_le_bytes([self.head[0], self.head[1], self.head[2], self.head[3]]) as usize;
+        if self.head.len() < 4 + meta_len {
+            return Ok(None);
+        }
+        let rest = self.head.split_off(4 + meta_len);
+        let meta: ChunkMetadata = serde_json::from_slice(&self.head[4..])
+            .map_err(|e| std::io::Error::other(CokacencError::MetadataParse(e.to_string())))?;
+
+        let mut rewritten = meta.clone();
+        rewritten.version = crypto::VERSION;
+        rewritten.group_id = self.group_id.to_string();
+        let meta_bytes = serde_json::to_vec(&rewritten).map_err(std::io::Error::other)?;
+        let sealed = self.enc.update(&(meta_bytes.len() as u32).to_le_bytes()).map_err(std::io::Error::other)?;
+        self.out.write_all(sealed)?;
+        let sealed = self.enc.update(&meta_bytes).map_err(std::io::Error::other)?;
+        self.out.write_all(sealed)?;
+
+        self.meta = Some(meta);
+        Ok(Some(rest))
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.data.write_all(data)?;
+        self.data_len += data.len() as u64;
+        let sealed = self.enc.update(data).map_err(std::io::Error::other)?;
+        self.out.write_all(sealed)
+    }
+
+    /// Seal the last segment; returns the original metadata
+    fn finish(self) -> Result<ChunkMetadata, CokacencError> {
+        let meta = self.meta.ok_or_else(|| {
+            CokacencError::MetadataParse("Incomplete metadata in chunk".to_string())
+        })?;
+        if self.data_len != meta.chunk_data_size {
+            return Err(CokacencError::Other(format!(
+                "Size mismatch in chunk {}: expected {}, got {}",
+                meta.chunk_index, meta.chunk_data_size, self.data_len,
+            )));
+        }
+        self.out.write_all(&self.enc.finalize()?)?;
+        Ok(meta)
+    }
+}
+
+impl<W: Write, D: Write> Write for RekeyWriter<'_, W, D> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.meta.is_some() {
+            self.write_data(buf)?;
+        } else {
+            self.head.extend_from_slice(buf);
+            if let Some(rest) = self.take_head()? {
+                self.write_data(&rest)?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Re-encrypt all .cokacenc file groups in a directory for `new_key` with
+/// progress reporting. Each group gets new chunk files; the old ones are
+/// deleted only once the whole group was re-encrypted and verified.
+pub fn rekey_directory_with_progress(
+    dir: &Path,
+    mut keys: KeyResolver,
+    new_key: &Key,
+    tx: Sender<ProgressMessage>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    for_each_group(dir, &tx, &cancel_flag, |chunks, _| rekey_file_group(dir, chunks, &mut keys, new_key));
+}
+
+/// Re-encrypt a group of chunk files under a new group ID, chunk by chunk.
+/// Returns the original filename on success.
+fn rekey_file_group(
+    dir: &Path,
+    chunks: &[naming::EncFileInfo],
+    keys: &mut KeyResolver,
+    new_key: &Key,
+) -> Result<String, CokacencError> {
+    check_sequence(chunks)?;
+
+    let group_id = &chunks[0].group_id;
+    let new_group_id = new_group_id(dir);
+    let kp = naming::key_prefix(new_key);
+    let salt = generate_salt();
+    let aes_key = new_key.derive(&salt)?;
+    let mut created_chunks: Vec<PathBuf> = Vec::new();
+    let mut original_name = String::new();
+
+    let result = (|| -> Result<(), CokacencError> {
+        let compressed = read_first_metadata(chunks, keys)?.0.compressed;
+        let mut md5_hasher = Md5::new();
+        let mut expected_md5 = String::new();
+        let mut sink = std::io::sink();
+        let tee = TeeWriter {
+            file: &mut sink,
+            hasher: &mut md5_hasher,
+        };
+        let mut data = DataWriter::new(tee, compressed)?;
+
+        for (i, chunk_info) in chunks.iter().enumerate() {
+            let mut reader = BufReader::new(File::open(&chunk_info.path)?);
+            let header = read_header(&mut reader)?;
+            let old_key = keys.chunk_key(&header)?;
+
+            let chunk_path = naming::chunk_filename(dir, &kp, &new_group_id, i)?;
+            let chunk_file = File::create(&chunk_path)?;
+            created_chunks.push(chunk_path);
+            let mut writer = BufWriter::new(chunk_file);
+
+            let nonce_prefix = generate_nonce_prefix();
+            let new_header = write_header(&mut writer, &salt, &nonce_prefix, new_key.info(), &header.filename)?;
+            let mut rekey = RekeyWriter {
+                head: Vec::new(),
+                meta: None,
+                group_id: &new_group_id,
+                enc: SegmentEncryptor::new(&aes_key, &nonce_prefix, &new_header),
+                out: &mut writer,
+                data: &mut data,
+                data_len: 0,
+            };
+            decrypt_chunk(&mut reader, &mut rekey, &old_key, &header)?;
+            let meta = rekey.finish()?;
+            writer.flush()?;
+
+            check_chunk_metadata(&meta, i, group_id, chunks.len())?;
+            if i == 0 {
+                original_name = meta.filename;
+                expected_md5 = meta.file_md5;
+            } else if meta.filename != original_name
+                || meta.compressed != compressed
+                || (!expected_md5.is_empty() && meta.file_md5 != expected_md5)
+            {
+                return Err(CokacencError::MetadataParse(
+                    "Inconsistent metadata across chunks".to_string(),
+                ));
+            }
+        }
+        data.flush()?;
+        drop(data);
+
+        // Unauthenticated v2 chunks are only trusted when their MD5 matches
+        let md5_hex = format!("{:032x}", md5_hasher.finalize());
+        if !expected_md5.is_empty() && md5_hex != expected_md5 {
+            return Err(CokacencError::Md5Mismatch {
+                expected: expected_md5,
+                actual: md5_hex,
+            });
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for path in &created_chunks {
+            let _ = fs::remove_file(path);
+        }
+        return Err(e);
+    }
+
+    for chunk_info in chunks {
+        let _ = fs::remove_file(&chunk_info.path);
+    }
+    Ok(original_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    const PASSWORD: &[u8] = b"test-password";
+
+    /// A credential directory holding `PASSWORD` as the machine key
+    fn credentials() -> tempfile::TempDir {
+        let cred = tempfile::TempDir::new().unwrap();
+        fs::write(keys::machine_key_path(cred.path()), PASSWORD).unwrap();
+        cred
+    }
+
+    fn machine_key() -> Key {
+        Key::from_bytes(keys::MACHINE_KEY, PASSWORD.to_vec())
+    }
+
+    /// Encrypt `data` as `name` in `dir`, split into chunks of `split_size`
+    fn pack(dir: &Path, name: &str, data: &[u8], split_size: u64) -> Vec<naming::EncFileInfo> {
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        pack_file(&path, name, dir, &machine_key(), split_size, true, false).unwrap();
+        fs::remove_file(&path).unwrap();
+        naming::group_enc_files(dir).unwrap().into_values().next().unwrap()
+    }
+
+    fn unpack(
+        dir: &Path,
+        chunks: &[naming::EncFileInfo],
+        cred: &Path,
+        passphrase: Option<&str>,
+    ) -> Result<String, CokacencError> {
+        let (tx, _rx) = mpsc::channel();
+        let mut keys = KeyResolver::new(cred.to_path_buf(), passphrase.map(str::to_string));
+        unpack_file_group(dir, chunks, &mut keys, &tx)
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_pack_unpack_chunks() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cred = credentials();
+        let data: Vec<u8> = (0..150_000u32).map(|i| (i % 241) as u8).collect();
+        let chunks = pack(tmp.path(), "data.bin", &data, 100_000);
+        assert_eq!(chunks.len(), 2);
+
+        assert_eq!(unpack(tmp.path(), &chunks, cred.path(), None).unwrap(), "data.bin");
+        assert_eq!(fs::read(tmp.path().join("data.bin")).unwrap(), data);
+    }
+
+    #[test]
+    fn test_unpack_refuses_damaged_groups() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = credentials();
+        let data = vec![42u8; 150_000];
+        let chunks = pack(dir, "data.bin", &data, 100_000);
+
+        // Last chunk missing
+        assert!(unpack(dir, &chunks[..1], cred.path(), None).is_err());
+
+        // A modified byte in the second chunk
+        let mut bytes = fs::read(&chunks[1].path).unwrap();
+        let at = bytes.len() / 2;
+        bytes[at] ^= 0x80;
+        fs::write(&chunks[1].path, &bytes).unwrap();
+        assert!(matches!(unpack(dir, &chunks, cred.path(), None), Err(CokacencError::AuthenticationFailed)));
+
+        // Nothing decrypted is left behind and the chunks are kept
+        let names = file_names(dir);
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|n| n.ends_with(naming::EXT)));
+    }
+
+    #[test]
+    fn test_pack_unpack_compressed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = credentials();
+        let data: Vec<u8> = (0..40_000u32)
+            .flat_map(|i| format!("2024-05-01 12:{:02}:{:02} INFO request {} served in {} ms\n", i / 60 % 60, i % 60, i, i % 97).into_bytes())
+            .collect();
+        let path = dir.join("app.log");
+        fs::write(&path, &data).unwrap();
+        pack_file(&path, "app.log", dir, &machine_key(), 50_000, true, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The split size applies to the compressed stream
+        let chunks = naming::group_enc_files(dir).unwrap().into_values().next().unwrap();
+        let packed: u64 = chunks.iter().map(|c| fs::metadata(&c.path).unwrap().len()).sum();
+        assert!(chunks.len() > 1);
+        assert!(packed * 5 < data.len() as u64);
+        assert!(chunks.iter().all(|c| fs::metadata(&c.path).unwrap().len() < 51_000));
+
+        let mut keys = KeyResolver::new(cred.path().to_path_buf(), None);
+        assert_eq!(verify_file_group(&chunks, &mut keys).unwrap().size, data.len() as u64);
+        let (_, bytes) = decrypt_group_to_memory(&chunks, &mut keys, u64::MAX).unwrap();
+        assert_eq!(bytes, data);
+
+        // Re-keying keeps the group compressed
+        let new_key = Key::passphrase_with_cost("correct horse", 10).unwrap();
+        rekey_file_group(dir, &chunks, &mut keys, &new_key).unwrap();
+        let chunks = naming::group_enc_files(dir).unwrap().into_values().next().unwrap();
+        let rekeyed: u64 = chunks.iter().map(|c| fs::metadata(&c.path).unwrap().len()).sum();
+        assert!(rekeyed * 5 < data.len() as u64);
+        assert_eq!(unpack(dir, &chunks, cred.path(), Some("correct horse")).unwrap(), "app.log");
+        assert_eq!(fs::read(dir.join("app.log")).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compression_skips_compressed_types() {
+        assert!(is_compressible(Path::new("app.log")));
+        assert!(is_compressible(Path::new("Makefile")));
+        assert!(!is_compressible(Path::new("photo.JPG")));
+        assert!(!is_compressible(Path::new("clip.mp4")));
+        assert!(!is_compressible(Path::new("backup.zip")));
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = credentials();
+        let data = vec![7u8; 150_000];
+        let path = dir.join("backup.zip");
+        fs::write(&path, &data).unwrap();
+        pack_file(&path, "backup.zip", dir, &machine_key(), 100_000, false, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Stored as is: two chunks of the original size
+        let chunks = naming::group_enc_files(dir).unwrap().into_values().next().unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(unpack(dir, &chunks, cred.path(), None).unwrap(), "backup.zip");
+        assert_eq!(fs::read(dir.join("backup.zip")).unwrap(), data);
+    }
+
+    #[test]
+    fn test_rekey_to_passphrase() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = credentials();
+        let data: Vec<u8> = (0..150_000u32).map(|i| (i % 239) as u8).collect();
+        let old_chunks = pack(dir, "data.bin", &data, 100_000);
+        assert_eq!(required_keys(dir).unwrap(), vec![machine_key().info().clone()]);
+
+        let new_key = Key::passphrase_with_cost("correct horse", 10).unwrap();
+        let mut keys = KeyResolver::new(cred.path().to_path_buf(), None);
+        assert_eq!(rekey_file_group(dir, &old_chunks, &mut keys, &new_key).unwrap(), "data.bin");
+
+        // Only the new chunks are left, named after the key they need
+        let names = file_names(dir);
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|n| n.starts_with("pass_") && n.ends_with(naming::EXT)));
+        assert!(old_chunks.iter().all(|c| !c.path.exists()));
+        assert_eq!(required_keys(dir).unwrap(), vec![KeyInfo::Passphrase { log_n: 10 }]);
+
+        let chunks = naming::group_enc_files(dir).unwrap().into_values().next().unwrap();
+        assert!(matches!(unpack(dir, &chunks, cred.path(), None), Err(CokacencError::PassphraseRequired)));
+        assert!(matches!(
+            unpack(dir, &chunks, cred.path(), Some("wrong horse")),
+            Err(CokacencError::AuthenticationFailed)
+        ));
+        assert_eq!(unpack(dir, &chunks, cred.path(), Some("correct horse")).unwrap(), "data.bin");
+        assert_eq!(fs::read(dir.join("data.bin")).unwrap(), data);
+    }
+
+    #[test]
+    fn test_pack_unpack_recipients() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let mine = tempfile::TempDir::new().unwrap();
+        let bobs = tempfile::TempDir::new().unwrap();
+        recipients::generate_identity(mine.path(), "me").unwrap();
+        let bob = recipients::generate_identity(bobs.path(), "bob").unwrap();
+        let bob_file = dir.join("bob.pub");
+        fs::write(&bob_file, bob.encode()).unwrap();
+        recipients::import_key(mine.path(), &bob_file, None).unwrap();
+        fs::remove_file(&bob_file).unwrap();
+
+        let names = vec!["me".to_string(), "bob".to_string()];
+        let key = Key::for_recipients(&recipients::resolve(mine.path(), &names).unwrap()).unwrap();
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i % 233) as u8).collect();
+        let path = dir.join("data.bin");
+        fs::write(&path, &data).unwrap();
+        pack_file(&path, "data.bin", dir, &key, u64::MAX, true, false).unwrap();
+        fs::remove_file(&path).unwrap();
+        let chunks = naming::group_enc_files(dir).unwrap().into_values().next().unwrap();
+        assert!(chunks[0].path.file_name().unwrap().to_string_lossy().starts_with("rcpt_"));
+
+        // Either private key opens the group; a keyring without one does not
+        for cred in [mine.path(), bobs.path()] {
+            assert_eq!(unpack(dir, &chunks, cred, None).unwrap(), "data.bin");
+            assert_eq!(fs::read(dir.join("data.bin")).unwrap(), data);
+            fs::remove_file(dir.join("data.bin")).unwrap();
+        }
+        let empty = tempfile::TempDir::new().unwrap();
+        assert!(matches!(unpack(dir, &chunks, empty.path(), None), Err(CokacencError::KeyNotFound(_))));
+    }
+
+    fn list(dir: &Path, keys: &KeyResolver) -> Vec<GroupListing> {
+        let (tx, _rx) = mpsc::channel();
+        let (listing_tx, listing_rx) = mpsc::channel();
+        list_groups_with_progress(dir, keys.clone(), tx, listing_tx, Arc::new(AtomicBool::new(false)));
+        listing_rx.try_iter().collect()
+    }
+
+    #[test]
+    fn test_verify_and_list_groups() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = credentials();
+        let data: Vec<u8> = (0..250_000u32).map(|i| (i % 251) as u8).collect();
+        let chunks = pack(dir, "data.bin", &data, 100_000);
+        let before = file_names(dir);
+
+        let mut keys = KeyResolver::new(cred.path().to_path_buf(), None);
+        let info = verify_file_group(&chunks, &mut keys).unwrap();
+        assert_eq!((info.filename.as_str(), info.size, info.chunks), ("data.bin", 250_000, 3));
+        assert_eq!(info.key, machine_key().info().clone());
+
+        let listing = list(dir, &keys);
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].chunks_found, 3);
+        assert_eq!(listing[0].info.as_ref().unwrap(), &info);
+        // Neither decrypts to disk nor removes anything
+        assert_eq!(file_names(dir), before);
+
+        // A keyring without the key
+        let empty = tempfile::TempDir::new().unwrap();
+        let mut other = KeyResolver::new(empty.path().to_path_buf(), None);
+        assert!(matches!(verify_file_group(&chunks, &mut other), Err(CokacencError::KeyNotFound(_))));
+        assert!(matches!(list(dir, &other)[0].info, Err(CokacencError::KeyNotFound(_))));
+
+        // Missing middle chunk, missing last chunk, swapped chunks
+        assert!(matches!(
+            verify_file_group(&[chunks[0].clone(), chunks[2].clone()], &mut keys),
+            Err(CokacencError::MissingChunk { .. })
+        ));
+        assert!(matches!(verify_file_group(&chunks[..2], &mut keys), Err(CokacencError::MetadataParse(_))));
+        let mut swapped = chunks.clone();
+        swapped.swap(1, 2);
+        swapped[1].seq_index = 1;
+        swapped[2].seq_index = 2;
+        assert!(verify_file_group(&swapped, &mut keys).is_err());
+
+        // A flipped byte in the last chunk
+        let mut bytes = fs::read(&chunks[2].path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&chunks[2].path, bytes).unwrap();
+        assert!(matches!(verify_file_group(&chunks, &mut keys), Err(CokacencError::AuthenticationFailed)));
+        // The listing only reads the first chunk
+        assert!(list(dir, &keys)[0].info.is_ok());
+    }
+
+    #[test]
+    fn test_extract_keeps_chunks() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let out = tempfile::TempDir::new().unwrap();
+        let cred = credentials();
+        let data = vec![42u8; 5000];
+        let chunks = pack(tmp.path(), "data.bin", &data, u64::MAX);
+        let before = file_names(tmp.path());
+
+        let extract = || {
+            let (tx, rx) = mpsc::channel();
+            let keys = KeyResolver::new(cred.path().to_path_buf(), None);
+            extract_groups_with_progress(std::slice::from_ref(&chunks), out.path(), keys, tx, Arc::new(AtomicBool::new(false)));
+            rx.try_iter().find_map(|m| match m {
+                ProgressMessage::Completed(ok, failed) => Some((ok, failed)),
+                _ => None,
+            })
+        };
+        assert_eq!(extract(), Some((1, 0)));
+        assert_eq!(fs::read(out.path().join("data.bin")).unwrap(), data);
+        assert_eq!(file_names(tmp.path()), before);
+        // An existing file is left alone
+        assert_eq!(extract(), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_rekey_keeps_group_on_failure() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let data = vec![7u8; 1000];
+        let chunks = pack(dir, "data.bin", &data, u64::MAX);
+        let before = file_names(dir);
+
+        // The machine key is not in this keyring
+        let empty = tempfile::TempDir::new().unwrap();
+        let mut keys = KeyResolver::new(empty.path().to_path_buf(), None);
+        let new_key = Key::from_bytes("alice", b"another key".to_vec());
+        assert!(matches!(
+            rekey_file_group(dir, &chunks, &mut keys, &new_key),
+            Err(CokacencError::KeyNotFound(_))
+        ));
+        assert_eq!(file_names(dir), before);
+    }
+}
@@ -4,8 +4,13 @@ use std::path::{Path, PathBuf};
 use rand::RngCore;
 
 use super::error::CokacencError;
+use super::keys::{Key, KeyInfo, MACHINE_KEY};
 
 pub const EXT: &str = ".cokacenc";
+/// Filename prefix of passphrase groups
+pub const PASSPHRASE_PREFIX: &str = "pass";
+/// Filename prefix of groups encrypted for public-key recipients
+pub const RECIPIENTS_PREFIX: &str = "rcpt";
 
 /// Generate a random group ID (8 bytes -> 16 hex characters).
 pub fn generate_group_id() -> String {
@@ -42,8 +47,19 @@ fn parse_seq_label(s: &str) -> Option<usize> {
     Some(a * 26 * 26 * 26 + b * 26 * 26 + c * 26 + d)
 }
 
-/// Extract key prefix from password: take first 6 bytes, filter ASCII alphanumeric.
-pub fn key_prefix(password: &[u8]) -> String {
+/// Filename prefix naming the key a group needs: the keyring name, `pass`
+/// for a passphrase, `rcpt` for recipients, or for the machine key its first 6 bytes filtered to
+/// ASCII alphanumerics, as before key sources existed.
+pub fn key_prefix(key: &Key) -> String {
+    match key.info() {
+        KeyInfo::KeyFile { name, .. } if name != MACHINE_KEY => name.clone(),
+        KeyInfo::Passphrase { .. } => PASSPHRASE_PREFIX.to_string(),
+        KeyInfo::Recipients { .. } => RECIPIENTS_PREFIX.to_string(),
+        _ => machine_key_prefix(key.secret()),
+    }
+}
+
+fn machine_key_prefix(password: &[u8]) -> String {
     let len = password.len().min(6);
     password[..len]
         .iter()
@@ -185,18 +201,22 @@ mod tests {
 
     #[test]
     fn test_key_prefix() {
+        let machine = |secret: &[u8]| key_prefix(&Key::from_bytes(MACHINE_KEY, secret.to_vec()));
         // Mixed alphanumeric and special chars
-        assert_eq!(key_prefix(b"Ab3+/Z"), "Ab3Z");
+        assert_eq!(machine(b"Ab3+/Z"), "Ab3Z");
         // All alphanumeric
-        assert_eq!(key_prefix(b"Hello9"), "Hello9");
+        assert_eq!(machine(b"Hello9"), "Hello9");
         // No alphanumeric in first 6 bytes
-        assert_eq!(key_prefix(b"!@#$%^"), "");
+        assert_eq!(machine(b"!@#$%^"), "");
         // Shorter than 6 bytes
-        assert_eq!(key_prefix(b"aB"), "aB");
+        assert_eq!(machine(b"aB"), "aB");
         // Longer than 6 bytes - only first 6 considered
-        assert_eq!(key_prefix(b"abcdefghij"), "abcdef");
+        assert_eq!(machine(b"abcdefghij"), "abcdef");
         // Empty password
-        assert_eq!(key_prefix(b""), "");
+        assert_eq!(machine(b""), "");
+        // Keyring keys and passphrases are named, whatever the secret
+        assert_eq!(key_prefix(&Key::from_bytes("alice", b"Hello9".to_vec())), "alice");
+        assert_eq!(key_prefix(&Key::passphrase("Hello9").unwrap()), "pass");
     }
 
     #[test]
@@ -0,0 +1,317 @@
+//! Public-key recipients (X25519, age-style).
+//!
+//! A group encrypted for recipients gets a random file key. For every
+//! recipient the header carries a stanza: an ephemeral X25519 public key and
+//! the file key sealed with AES-256-GCM under a key derived with HKDF-SHA256
+//! from the shared secret. Any one matching identity (private key) opens its
+//! stanza, so teammates decrypt with their own key and nothing is shared.
+//!
+//! Identities live in `~/.cokacdir/credential/identities/<name>.key`, other
+//! people's public keys in `~/.cokacdir/credential/recipients/<name>.pub`.
+
+use std::fs;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use hkdf::Hkdf;
+use rand::RngCore;
+use sha2::Sha256;
+
+use super::error::CokacencError;
+use super::keys::{self, valid_key_name};
+
+pub const PUBLIC_PREFIX: &str = "cokacenc-pub-";
+pub const SECRET_PREFIX: &str = "cokacenc-secret-";
+/// Sealed file key: key + GCM tag
+const WRAPPED_LEN: usize = 32 + 16;
+pub const MAX_RECIPIENTS: usize = 64;
+const IDENTITY_DIR: &str = "identities";
+const RECIPIENT_DIR: &str = "recipients";
+const WRAP_INFO: &[u8] = b"cokacenc x25519 v1";
+const PAYLOAD_INFO: &[u8] = b"cokacenc payload v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey(pub [u8; 32]);
+
+impl PublicKey {
+    pub fn parse(text: &str) -> Result<Self, CokacencError> {
+        parse_encoded(text, PUBLIC_PREFIX).map(PublicKey)
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}{}", PUBLIC_PREFIX, hex::encode(self.0))
+    }
+
+    /// Short form for listings
+    pub fn fingerprint(&self) -> String {
+        hex::encode(keys::fingerprint(&self.0))
+    }
+}
+
+/// An X25519 private key
+pub struct Identity {
+    secret: [u8; 32],
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self { secret }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CokacencError> {
+        parse_encoded(text, SECRET_PREFIX).map(|secret| Self { secret })
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}{}", SECRET_PREFIX, hex::encode(self.secret))
+    }
+
+    pub fn public(&self) -> PublicKey {
+        PublicKey(MontgomeryPoint::mul_base_clamped(self.secret).to_bytes())
+    }
+}
+
+fn parse_encoded(text: &str, prefix: &str) -> Result<[u8; 32], CokacencError> {
+    let invalid = || CokacencError::Other(format!("Not a {}... key", prefix));
+    let hex_part = text.trim().strip_prefix(prefix).ok_or_else(invalid)?;
+    let bytes = hex::decode(hex_part).map_err(|_| invalid())?;
+    bytes.try_into().map_err(|_| invalid())
+}
+
+/// The file key sealed for one recipient
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stanza {
+    pub ephemeral: [u8; 32],
+    pub wrapped: [u8; WRAPPED_LEN],
+}
+
+fn wrap_key(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &PublicKey) -> [u8; 32] {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral);
+    salt[32..].copy_from_slice(&recipient.0);
+    let mut key = [0u8; 32];
+    // Only fails for outputs longer than 255 hashes
+    let _ = Hkdf::<Sha256>::new(Some(&salt), shared).expand(WRAP_INFO, &mut key);
+    key
+}
+
+/// Seal `file_key` for `recipient` under a fresh ephemeral key
+pub fn wrap(file_key: &[u8; 32], recipient: &PublicKey) -> Result<Stanza, CokacencError> {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let ephemeral = MontgomeryPoint::mul_base_clamped(secret).to_bytes();
+    let shared = MontgomeryPoint(recipient.0).mul_clamped(secret).to_bytes();
+    // A low-order public key would make the shared secret predictable
+    if shared == [0u8; 32] {
+        return Err(CokacencError::Other("Invalid recipient public key".to_string()));
+    }
+    let key = wrap_key(&shared, &ephemeral, recipient);
+    // Every wrap key is used once, so a fixed nonce is fine
+    let sealed = Aes256Gcm::new(&key.into())
+        .encrypt(Nonce::from_slice(&[0u8; 12]), file_key.as_slice())
+        .map_err(|_| CokacencError::Other("Encryption failed".to_string()))?;
+    let mut wrapped = [0u8; WRAPPED_LEN];
+    wrapped.copy_from_slice(&sealed);
+    Ok(Stanza { ephemeral, wrapped })
+}
+
+/// The file key, if `stanza` was sealed for `identity`
+pub fn unwrap(stanza: &Stanza, identity: &Identity) -> Option<[u8; 32]> {
+    let shared = MontgomeryPoint(stanza.ephemeral).mul_clamped(identity.secret).to_bytes();
+    if shared == [0u8; 32] {
+        return None;
+    }
+    let key = wrap_key(&shared, &stanza.ephemeral, &identity.public());
+    let plain = Aes256Gcm::new(&key.into())
+        .decrypt(Nonce::from_slice(&[0u8; 12]), stanza.wrapped.as_slice())
+        .ok()?;
+    plain.try_into().ok()
+}
+
+/// AES key of a group from its file key and salt. The file key is random, so
+/// no stretching is needed.
+pub fn payload_key(file_key: &[u8], salt: &[u8; 16]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    let _ = Hkdf::<Sha256>::new(Some(salt), file_key).expand(PAYLOAD_INFO, &mut key);
+    key
+}
+
+fn identity_path(credential_dir: &Path, name: &str) -> std::path::PathBuf {
+    credential_dir.join(IDENTITY_DIR).join(format!("{}.key", name))
+}
+
+fn recipient_path(credential_dir: &Path, name: &str) -> std::path::PathBuf {
+    credential_dir.join(RECIPIENT_DIR).join(format!("{}.pub", name))
+}
+
+/// Names of the files with `ext` in `dir`, sorted
+fn list_names(dir: &Path, ext: &str) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(rd) => rd.filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.strip_suffix(ext).filter(|n| valid_key_name(n)).map(str::to_string)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+fn check_name(name: &str) -> Result<(), CokacencError> {
+    if valid_key_name(name) {
+        Ok(())
+    } else {
+        Err(CokacencError::Other(format!("Invalid key name '{}': use letters and digits", name)))
+    }
+}
+
+/// The public key known as `name`: one of our identities or an imported recipient
+pub fn public_key(credential_dir: &Path, name: &str) -> Result<PublicKey, CokacencError> {
+    let identity = identity_path(credential_dir, name);
+    if identity.exists() {
+        return Ok(Identity::parse(&fs::read_to_string(identity)?)?.public());
+    }
+    let recipient = recipient_path(credential_dir, name);
+    if recipient.exists() {
+        return PublicKey::parse(&fs::read_to_string(recipient)?);
+    }
+    Err(CokacencError::KeyNotFound(format!("recipient '{}'", name)))
+}
+
+/// Create the identity `name`; returns its public key to hand out
+pub fn generate_identity(credential_dir: &Path, name: &str) -> Result<PublicKey, CokacencError> {
+    check_name(name)?;
+    if public_key(credential_dir, name).is_ok() {
+        return Err(CokacencError::Other(format!("Key '{}' already exists", name)));
+    }
+    let identity = Identity::generate();
+    keys::write_private_file(&identity_path(credential_dir, name), identity.encode().as_bytes())?;
+    Ok(identity.public())
+}
+
+/// Import a public key (as a recipient) or an identity from a file written by
+/// `--export-key` or another installation. Returns the name it was stored
+/// under and whether it was an identity.
+pub fn import_key(credential_dir: &Path, path: &Path, name: Option<&str>) -> Result<(String, bool), CokacencError> {
+    let text = fs::read_to_string(path)?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+    check_name(&name)?;
+
+    let (public, identity) = match Identity::parse(&text) {
+        Ok(identity) => (identity.public(), Some(identity)),
+        Err(_) => (PublicKey::parse(&text)?, None),
+    };
+    // Importing the same key again is fine; replacing another one is not
+    match public_key(credential_dir, &name) {
+        Ok(existing) if existing != public => {
+            return Err(CokacencError::Other(format!("Another key is already named '{}'", name)));
+        }
+        Ok(_) if identity.is_none() => return Ok((name, false)),
+        _ => {}
+    }
+    match identity {
+        Some(identity) => {
+            keys::write_private_file(&identity_path(credential_dir, &name), identity.encode().as_bytes())?;
+            Ok((name, true))
+        }
+        None => {
+            let path = recipient_path(credential_dir, &name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, format!("{}\n", public.encode()))?;
+            Ok((name, false))
+        }
+    }
+}
+
+/// Every public key we can encrypt to, by name: our identities and the
+/// imported recipients
+pub fn list_recipients(credential_dir: &Path) -> Vec<(String, PublicKey)> {
+    let mut names = list_names(&credential_dir.join(IDENTITY_DIR), ".key");
+    names.extend(list_names(&credential_dir.join(RECIPIENT_DIR), ".pub"));
+    names.sort();
+    names.dedup();
+    names.into_iter()
+        .filter_map(|name| public_key(credential_dir, &name).ok().map(|key| (name, key)))
+        .collect()
+}
+
+/// Names of our identities, sorted
+pub fn list_identities(credential_dir: &Path) -> Vec<String> {
+    list_names(&credential_dir.join(IDENTITY_DIR), ".key")
+}
+
+pub fn load_identities(credential_dir: &Path) -> Vec<Identity> {
+    list_identities(credential_dir)
+        .iter()
+        .filter_map(|name| fs::read_to_string(identity_path(credential_dir, name)).ok())
+        .filter_map(|text| Identity::parse(&text).ok())
+        .collect()
+}
+
+/// Public keys of the named recipients
+pub fn resolve(credential_dir: &Path, names: &[String]) -> Result<Vec<PublicKey>, CokacencError> {
+    if names.is_empty() || names.len() > MAX_RECIPIENTS {
+        return Err(CokacencError::Other(format!("Use 1 to {} recipients", MAX_RECIPIENTS)));
+    }
+    names.iter().map(|name| public_key(credential_dir, name)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_unwrap() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+        let eve = Identity::generate();
+        let file_key = [9u8; 32];
+
+        let stanzas: Vec<Stanza> = [alice.public(), bob.public()]
+            .iter()
+            .map(|r| wrap(&file_key, r).unwrap())
+            .collect();
+        assert_eq!(unwrap(&stanzas[0], &alice), Some(file_key));
+        assert_eq!(unwrap(&stanzas[1], &bob), Some(file_key));
+        assert_eq!(unwrap(&stanzas[0], &bob), None);
+        assert!(stanzas.iter().all(|s| unwrap(s, &eve).is_none()));
+
+        // A low-order point is refused
+        assert!(wrap(&file_key, &PublicKey([0u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_key_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cred = tmp.path();
+        let me = generate_identity(cred, "me").unwrap();
+        assert!(generate_identity(cred, "me").is_err());
+        assert_eq!(PublicKey::parse(&me.encode()).unwrap(), me);
+
+        // A teammate's exported public key
+        let teammate = Identity::generate();
+        let file = tmp.path().join("bob.pub");
+        fs::write(&file, format!("{}\n", teammate.public().encode())).unwrap();
+        assert_eq!(import_key(cred, &file, None).unwrap(), ("bob".to_string(), false));
+        assert_eq!(import_key(cred, &file, None).unwrap(), ("bob".to_string(), false));
+        fs::write(&file, Identity::generate().public().encode()).unwrap();
+        assert!(import_key(cred, &file, None).is_err());
+
+        let names: Vec<String> = list_recipients(cred).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["bob", "me"]);
+        assert_eq!(resolve(cred, &["bob".to_string()]).unwrap(), vec![teammate.public()]);
+        assert!(resolve(cred, &["carol".to_string()]).is_err());
+        assert_eq!(load_identities(cred).len(), 1);
+    }
+}
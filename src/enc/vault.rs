@@ -0,0 +1,227 @@
+//! Vault view: the .cokacenc groups of a directory shown as their original
+//! files.
+//!
+//! Name, size and mtime come from the metadata at the start of each group's
+//! first chunk. It is read once per group on a worker thread and cached,
+//! together with the derived keys, so refreshing the panel does not decrypt
+//! again.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use super::crypto::read_header;
+use super::error::CokacencError;
+use super::keys::KeyResolver;
+use super::naming::{self, EncFileInfo};
+use super::{decrypt_group_to_memory, read_group_info, GroupInfo};
+use crate::services::file_ops::ProgressMessage;
+
+/// A group as listed in the vault view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultEntry {
+    pub group_id: String,
+    /// Original filename
+    pub name: String,
+    pub size: u64,
+    pub modified: i64,
+}
+
+pub struct Vault {
+    keys: KeyResolver,
+    groups: BTreeMap<String, Vec<EncFileInfo>>,
+    /// Metadata of the groups read so far; `None` when it could not be read
+    infos: HashMap<String, Option<GroupInfo>>,
+}
+
+/// Metadata read by `read_infos_with_progress`, for `Vault::merge`
+pub struct VaultRead {
+    keys: KeyResolver,
+    infos: Vec<(String, Option<GroupInfo>)>,
+}
+
+impl std::fmt::Debug for Vault {
+    // The resolver holds key material; only show what is listed
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vault").field("groups", &self.groups.len()).finish()
+    }
+}
+
+impl Vault {
+    pub fn new(keys: KeyResolver) -> Self {
+        Self { keys, groups: BTreeMap::new(), infos: HashMap::new() }
+    }
+
+    /// Rescan `dir`, one entry per group, decrypting nothing. A group whose
+    /// metadata is not read yet or cannot be read (no key, damaged first
+    /// chunk) is listed with the name in its header and the size and mtime
+    /// of its chunk files.
+    pub fn scan(&mut self, dir: &Path) -> Result<Vec<VaultEntry>, CokacencError> {
+        self.groups = naming::group_enc_files(dir)?;
+        Ok(self.groups.iter().map(|(group_id, chunks)| match self.infos.get(group_id) {
+            Some(Some(info)) => VaultEntry {
+                group_id: group_id.clone(),
+                name: info.filename.clone(),
+                size: info.size,
+                modified: info.modified,
+            },
+            _ => fallback_entry(group_id, chunks),
+        }).collect())
+    }
+
+    /// Groups of the last scan whose metadata has not been read yet
+    pub fn unread(&self) -> Vec<(String, Vec<EncFileInfo>)> {
+        self.groups.iter()
+            .filter(|(group_id, _)| !self.infos.contains_key(*group_id))
+            .map(|(group_id, chunks)| (group_id.clone(), chunks.clone()))
+            .collect()
+    }
+
+    /// Take in metadata read on a worker thread, and the keys it derived
+    pub fn merge(&mut self, read: VaultRead) {
+        self.keys = read.keys;
+        self.infos.extend(read.infos);
+    }
+
+    /// Chunks of a group found by the last scan
+    pub fn chunks(&self, group_id: &str) -> Option<&[EncFileInfo]> {
+        self.groups.get(group_id).map(Vec::as_slice)
+    }
+
+    /// Decrypt a group to memory, see `decrypt_group_to_memory`
+    pub fn open(&mut self, group_id: &str, max_size: u64) -> Result<(GroupInfo, Vec<u8>), CokacencError> {
+        let chunks = self.groups.get(group_id)
+            .ok_or_else(|| CokacencError::NoEncFiles(group_id.to_string()))?;
+        decrypt_group_to_memory(chunks, &mut self.keys, max_size)
+    }
+
+    /// The keys the vault was opened with, for work on another thread
+    pub fn keys(&self) -> KeyResolver {
+        self.keys.clone()
+    }
+}
+
+/// Read the metadata of `groups` with a copy of the vault's keys. The result
+/// is sent on `result_tx` before the final progress message. Groups not
+/// reached before a cancel count as unreadable, so they are not read again.
+pub fn read_infos_with_progress(
+    groups: Vec<(String, Vec<EncFileInfo>)>,
+    mut keys: KeyResolver,
+    tx: Sender<ProgressMessage>,
+    result_tx: Sender<VaultRead>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    let total = groups.len();
+    let _ = tx.send(ProgressMessage::TotalProgress(0, total, 0, 0));
+
+    let mut infos = Vec::with_capacity(total);
+    let mut read = 0;
+    for (i, (group_id, chunks)) in groups.into_iter().enumerate() {
+        if cancel_flag.load(Ordering::Relaxed) {
+            infos.push((group_id, None));
+            continue;
+        }
+        let _ = tx.send(ProgressMessage::FileStarted(format!("{}...", &group_id[..8.min(group_id.len())])));
+        let info = read_group_info(&chunks, &mut keys).ok();
+        if let Some(ref info) = info {
+            read += 1;
+            let _ = tx.send(ProgressMessage::FileCompleted(info.filename.clone()));
+        }
+        let _ = tx.send(ProgressMessage::TotalProgress(i + 1, total, 0, 0));
+        infos.push((group_id, info));
+    }
+
+    let _ = result_tx.send(VaultRead { keys, infos });
+    let _ = tx.send(ProgressMessage::Completed(read, total - read));
+}
+
+fn fallback_entry(group_id: &str, chunks: &[EncFileInfo]) -> VaultEntry {
+    let name = chunks.first()
+        .and_then(|c| File::open(&c.path).ok())
+        .and_then(|f| read_header(&mut BufReader::new(f)).ok())
+        .map(|h| h.filename)
+        .unwrap_or_else(|| group_id.to_string());
+    let metas: Vec<fs::Metadata> = chunks.iter().filter_map(|c| fs::metadata(&c.path).ok()).collect();
+    let modified = metas.first()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    VaultEntry {
+        group_id: group_id.to_string(),
+        name,
+        size: metas.iter().map(|m| m.len()).sum(),
+        modified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enc::keys::{self, Key};
+    use crate::enc::pack_file;
+    use std::sync::mpsc;
+
+    fn read_unread(vault: &mut Vault, cancel: bool) {
+        let (tx, _rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        read_infos_with_progress(vault.unread(), vault.keys(), tx, result_tx, Arc::new(AtomicBool::new(cancel)));
+        vault.merge(result_rx.recv().unwrap());
+    }
+
+    #[test]
+    fn test_scan_and_open() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let cred = tempfile::TempDir::new().unwrap();
+        fs::write(keys::machine_key_path(cred.path()), b"vault-password").unwrap();
+        let key = Key::from_bytes(keys::MACHINE_KEY, b"vault-password".to_vec());
+
+        let data: Vec<u8> = (0..120_000u32).map(|i| (i % 199) as u8).collect();
+        let path = dir.join("notes.txt");
+        fs::write(&path, &data).unwrap();
+        pack_file(&path, "notes.txt", dir, &key, 50_000, true, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut vault = Vault::new(KeyResolver::new(cred.path().to_path_buf(), None));
+        // Scanning decrypts nothing: until the metadata is read the chunks' size is shown
+        let entries = vault.scan(dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].size > 120_000);
+        read_unread(&mut vault, false);
+        assert!(vault.unread().is_empty());
+        let entries = vault.scan(dir).unwrap();
+        assert_eq!((entries[0].name.as_str(), entries[0].size), ("notes.txt", 120_000));
+        assert_eq!(vault.chunks(&entries[0].group_id).unwrap().len(), 3);
+
+        let (info, opened) = vault.open(&entries[0].group_id, 1 << 20).unwrap();
+        assert_eq!(info.filename, "notes.txt");
+        assert_eq!(opened, data);
+        assert!(vault.open(&entries[0].group_id, 1000).is_err());
+        // Viewing writes nothing next to the chunks
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 3);
+
+        // Without the key only the header name is known
+        let empty = tempfile::TempDir::new().unwrap();
+        let mut locked = Vault::new(KeyResolver::new(empty.path().to_path_buf(), None));
+        locked.scan(dir).unwrap();
+        read_unread(&mut locked, false);
+        // A group that could not be read is not read again
+        assert!(locked.unread().is_empty());
+        let entries = locked.scan(dir).unwrap();
+        assert_eq!(entries[0].name, "notes.txt");
+        assert!(entries[0].size > 120_000);
+        assert!(matches!(locked.open(&entries[0].group_id, 1 << 20), Err(CokacencError::KeyNotFound(_))));
+
+        // A cancelled read leaves the header name and does not start over
+        let mut cancelled = Vault::new(KeyResolver::new(cred.path().to_path_buf(), None));
+        cancelled.scan(dir).unwrap();
+        read_unread(&mut cancelled, true);
+        assert!(cancelled.unread().is_empty());
+        assert!(cancelled.scan(dir).unwrap()[0].size > 120_000);
+    }
+}
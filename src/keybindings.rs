@@ -293,11 +293,17 @@ pub enum PanelAction {
     AIScreen,
     Settings,
     GitScreen,
+    GitFileHistory,
     ToggleBookmark,
     SetHandler,
     EncryptAll,
     DecryptAll,
+    RekeyAll,
+    VerifyEncrypted,
+    ListEncrypted,
+    ToggleVault,
     RemoveDuplicates,
+    FindSimilarImages,
     #[cfg(target_os = "macos")]
     OpenInFinder,
     #[cfg(target_os = "macos")]
@@ -369,13 +375,19 @@ pub fn default_panel_keybindings() -> HashMap<PanelAction, Vec<String>> {
 
     // Git / Diff
     m.insert(PanelAction::GitScreen, vec!["//Git screen".into(), "g".into()]);
+    m.insert(PanelAction::GitFileHistory, vec!["//Git history of current file".into(), "shift+g".into()]);
     m.insert(PanelAction::GitLogDiff, vec!["//Git log diff".into(), "7".into()]);
     m.insert(PanelAction::StartDiff, vec!["//Start diff".into(), "8".into()]);
 
     // Encryption
     m.insert(PanelAction::EncryptAll, vec!["//Encrypt all files in directory".into(), "shift+e".into()]);
     m.insert(PanelAction::DecryptAll, vec!["//Decrypt all .cokacenc files".into(), "shift+d".into()]);
+    m.insert(PanelAction::RekeyAll, vec!["//Re-encrypt all .cokacenc files for another key".into(), "shift+k".into()]);
+    m.insert(PanelAction::VerifyEncrypted, vec!["//Verify all .cokacenc files without decrypting to disk".into(), "shift+c".into()]);
+    m.insert(PanelAction::ListEncrypted, vec!["//List the files in the .cokacenc groups".into(), "shift+l".into()]);
+    m.insert(PanelAction::ToggleVault, vec!["//Vault view: show .cokacenc files as their originals".into(), "shift+w".into()]);
     m.insert(PanelAction::RemoveDuplicates, vec!["//Remove duplicate files".into(), "shift+x".into()]);
+    m.insert(PanelAction::FindSimilarImages, vec!["//Find similar images".into(), "shift+i".into()]);
 
     // macOS only
     #[cfg(target_os = "macos")]
@@ -574,6 +586,8 @@ pub enum DiffFileViewAction {
     GoEnd,
     NextChange,
     PrevChange,
+    ToggleHex,
+    Export,
 }
 
 pub fn default_diff_file_view_keybindings() -> HashMap<DiffFileViewAction, Vec<String>> {
@@ -587,6 +601,8 @@ pub fn default_diff_file_view_keybindings() -> HashMap<DiffFileViewAction, Vec<S
     m.insert(DiffFileViewAction::GoEnd, vec!["//Go to end".into(), "end".into()]);
     m.insert(DiffFileViewAction::NextChange, vec!["//Next change".into(), "n".into()]);
     m.insert(DiffFileViewAction::PrevChange, vec!["//Previous change".into(), "shift+n".into(), "p".into()]);
+    m.insert(DiffFileViewAction::ToggleHex, vec!["//Toggle hex view".into(), "h".into()]);
+    m.insert(DiffFileViewAction::Export, vec!["//Export as unified patch".into(), "x".into()]);
     m
 }
 
@@ -611,6 +627,11 @@ pub enum DiffScreenAction {
     SortByType,
     ExpandAll,
     CollapseAll,
+    EditExcludes,
+    ToggleGitignore,
+    SyncToRight,
+    SyncToLeft,
+    Export,
     Open,
     Close,
 }
@@ -642,7 +663,14 @@ pub fn default_diff_screen_keybindings() -> HashMap<DiffScreenAction, Vec<String
     m.insert(DiffScreenAction::ExpandAll, vec!["//Expand all".into(), "e".into()]);
     m.insert(DiffScreenAction::CollapseAll, vec!["//Collapse all".into(), "c".into()]);
 
+    // Excludes
+    m.insert(DiffScreenAction::EditExcludes, vec!["//Edit exclude patterns".into(), "i".into()]);
+    m.insert(DiffScreenAction::ToggleGitignore, vec!["//Toggle .gitignore handling".into(), "g".into()]);
+
     // Actions
+    m.insert(DiffScreenAction::SyncToRight, vec!["//Copy to right side".into(), ">".into()]);
+    m.insert(DiffScreenAction::SyncToLeft, vec!["//Copy to left side".into(), "<".into()]);
+    m.insert(DiffScreenAction::Export, vec!["//Export patch / JSON / CSV report".into(), "x".into()]);
     m.insert(DiffScreenAction::Open, vec!["//View file diff / toggle dir".into(), "enter".into()]);
     m.insert(DiffScreenAction::Close, vec!["//Return to file panel".into(), "esc".into()]);
 
@@ -671,6 +699,9 @@ pub enum ViewerAction {
     ToggleWrap,
     ToggleHex,
     GotoLine,
+    ToggleBlame,
+    BlameShowCommit,
+    BlameParent,
 }
 
 pub fn default_viewer_keybindings() -> HashMap<ViewerAction, Vec<String>> {
@@ -692,6 +723,9 @@ pub fn default_viewer_keybindings() -> HashMap<ViewerAction, Vec<String>> {
     m.insert(ViewerAction::ToggleWrap, vec!["//Toggle word wrap".into(), "w".into()]);
     m.insert(ViewerAction::ToggleHex, vec!["//Toggle hex mode".into(), "h".into(), "shift+h".into()]);
     m.insert(ViewerAction::GotoLine, vec!["//Go to line".into(), "ctrl+g".into(), ":".into()]);
+    m.insert(ViewerAction::ToggleBlame, vec!["//Toggle git blame".into(), "a".into()]);
+    m.insert(ViewerAction::BlameShowCommit, vec!["//Blame: show commit in git screen".into(), "enter".into()]);
+    m.insert(ViewerAction::BlameParent, vec!["//Blame: re-blame at parent commit".into(), "p".into()]);
     m
 }
 
@@ -836,12 +870,14 @@ pub fn default_ai_screen_keybindings() -> HashMap<AIScreenAction, Vec<String>> {
 pub enum GotoAction {
     BookmarkDelete,
     BookmarkEdit,
+    ImportSshConfig,
 }
 
 pub fn default_goto_keybindings() -> HashMap<GotoAction, Vec<String>> {
     let mut m = HashMap::new();
     m.insert(GotoAction::BookmarkDelete, vec!["//Delete bookmark or profile".into(), "ctrl+d".into()]);
     m.insert(GotoAction::BookmarkEdit, vec!["//Edit remote profile".into(), "ctrl+e".into()]);
+    m.insert(GotoAction::ImportSshConfig, vec!["//Import hosts from ~/.ssh/config".into(), "ctrl+s".into()]);
     m
 }
 
@@ -43,6 +43,27 @@ fn print_help() {
     println!("    --ccserver <TOKEN>...   Start Telegram bot server(s)");
     println!("    --sendfile <PATH> --chat <ID> --key <HASH>");
     println!("                            Send file via Telegram bot (internal use, HASH = token hash)");
+    println!("    --diff <LEFT> <RIGHT> [--format json|csv|patch] [--compare <METHOD>] [--output <FILE>]");
+    println!("           [--exclude <GLOB>]... [--gitignore]");
+    println!("                            Compare two folders without the UI and print a report");
+    println!("                            (METHOD: content, modified_time, content_and_time;");
+    println!("                            exit code 0 = identical, 1 = different, 2 = error)");
+    println!("    --keygen <NAME>         Create an X25519 identity for public-key encryption and print");
+    println!("                            its public key");
+    println!("    --export-key <NAME> [--output <FILE>]");
+    println!("                            Print or save the public key of an identity or recipient");
+    println!("    --import-key <FILE> [--name <NAME>]");
+    println!("                            Import a teammate's public key as a recipient, or an identity");
+    println!("    --list-keys             List keyring keys, identities and recipients");
+    println!("    --encrypt <DIR> [--split <MB>] [--md5] [--compress] [--key <KEY>]");
+    println!("                            Encrypt all files in DIR to .cokacenc (KEY: machine, passphrase,");
+    println!("                            a keyring name or @recipient,...; default machine; --compress");
+    println!("                            applies zstd before encryption, --split limits the output size)");
+    println!("    --decrypt <DIR>         Decrypt all .cokacenc files in DIR");
+    println!("    --verify <DIR>          Check all .cokacenc files in DIR without writing anything");
+    println!("                            (progress on stderr; exit code 0 = all done, 1 = some failed,");
+    println!("                            2 = error; passphrases are read from COKACDIR_PASSPHRASE)");
+    println!("    --import-ssh-config     Add a remote profile for each Host alias in ~/.ssh/config");
     println!("    --currenttime            Print current server time");
     println!("    --cron <PROMPT> --at <TIME> --chat <ID> --key <HASH> [--once] [--session <SID>]");
     println!("                            Register a scheduled task");
@@ -72,6 +93,76 @@ fn handle_base64(encoded: &str) {
     }
 }
 
+fn handle_diff(
+    left: &str,
+    right: &str,
+    format: &str,
+    compare: Option<&str>,
+    output: Option<&str>,
+    excludes: crate::ui::diff_exclude::DiffExcludeConfig,
+) -> i32 {
+    use crate::ui::diff_export::{self, ExportFormat};
+    use crate::ui::diff_screen::{parse_compare_method, DiffState};
+
+    let format = match ExportFormat::parse(format) {
+        Some(f) => f,
+        None => {
+            eprintln!("Error: unknown --format '{}' (expected json, csv or patch)", format);
+            return 2;
+        }
+    };
+    let left = std::path::PathBuf::from(left);
+    let right = std::path::PathBuf::from(right);
+    for p in [&left, &right] {
+        if !p.is_dir() {
+            eprintln!("Error: not a directory: {}", p.display());
+            return 2;
+        }
+    }
+
+    let compare_method = match compare {
+        Some(m) => parse_compare_method(m),
+        None => parse_compare_method(&crate::config::Settings::load().diff_compare_method),
+    };
+    let mut state = DiffState::new(
+        left.clone(),
+        right.clone(),
+        compare_method,
+        crate::ui::app::SortBy::Name,
+        crate::ui::app::SortOrder::Asc,
+    );
+    state.excludes = excludes;
+    state.build_diff_list();
+
+    let content = match diff_export::render(format, &state) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 2;
+        }
+    };
+
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &content) {
+                eprintln!("Error: cannot write {}: {}", path, e);
+                return 2;
+            }
+        }
+        None => {
+            use std::io::Write;
+            // Ignore write errors (e.g. closed pipe when piped into `head`)
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(content.as_bytes());
+            if format == ExportFormat::Json {
+                let _ = stdout.write_all(b"\n");
+            }
+        }
+    }
+
+    if state.has_differences() { 1 } else { 0 }
+}
+
 fn handle_sendfile(path: &str, chat_id: i64, hash_key: &str) {
     use md5::{Md5, Digest};
 
@@ -530,6 +621,281 @@ fn handle_prompt(prompt: &str) {
     }
 }
 
+/// Key management for public-key encryption: `args` starts with the command
+fn handle_key_command(args: &[String]) -> i32 {
+    use crate::enc::{keys, recipients};
+
+    let mut positional: Option<String> = None;
+    let mut output: Option<String> = None;
+    let mut name: Option<String> = None;
+    let mut j = 1;
+    while j < args.len() {
+        match args[j].as_str() {
+            "--output" | "--name" if j + 1 < args.len() => {
+                let value = args[j + 1].clone();
+                if args[j] == "--output" {
+                    output = Some(value);
+                } else {
+                    name = Some(value);
+                }
+                j += 2;
+            }
+            arg if !arg.starts_with("--") && positional.is_none() => {
+                positional = Some(arg.to_string());
+                j += 1;
+            }
+            arg => {
+                eprintln!("Error: unexpected argument for {}: {}", args[0], arg);
+                return 2;
+            }
+        }
+    }
+
+    let cred = match keys::credential_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 2;
+        }
+    };
+    let result = match (args[0].as_str(), positional) {
+        ("--list-keys", None) => {
+            for key in keys::list_keys(&cred) {
+                println!("key        {}", key);
+            }
+            let identities = recipients::list_identities(&cred);
+            for (name, public) in recipients::list_recipients(&cred) {
+                let kind = if identities.contains(&name) { "identity" } else { "recipient" };
+                println!("{:<10} {} {}", kind, name, public.fingerprint());
+            }
+            Ok(())
+        }
+        ("--keygen", Some(name)) => recipients::generate_identity(&cred, &name).map(|public| {
+            eprintln!("Created identity '{}'. Share this public key:", name);
+            println!("{}", public.encode());
+        }),
+        ("--export-key", Some(name)) => recipients::public_key(&cred, &name).and_then(|public| {
+            match output {
+                Some(path) => Ok(std::fs::write(path, format!("{}\n", public.encode()))?),
+                None => {
+                    println!("{}", public.encode());
+                    Ok(())
+                }
+            }
+        }),
+        ("--import-key", Some(file)) => {
+            recipients::import_key(&cred, std::path::Path::new(&file), name.as_deref()).map(|(name, identity)| {
+                let kind = if identity { "identity" } else { "recipient" };
+                eprintln!("Imported {} '{}'", kind, name);
+            })
+        }
+        (command, _) => {
+            eprintln!("Error: {} needs exactly one argument (see --help)", command);
+            return 2;
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            2
+        }
+    }
+}
+
+/// `--import-ssh-config`: add a remote profile for every Host alias in
+/// ~/.ssh/config that has none yet
+fn handle_import_ssh_config() -> i32 {
+    let mut settings = config::Settings::load();
+    let added = services::ssh_config::import_profiles(&mut settings.remote_profiles);
+    if added.is_empty() {
+        eprintln!("No new hosts in ~/.ssh/config");
+        return 0;
+    }
+    if let Err(e) = settings.save() {
+        eprintln!("Error: failed to save settings: {}", e);
+        return 2;
+    }
+    for name in &added {
+        println!("{}", name);
+    }
+    eprintln!("Imported {} remote profile(s)", added.len());
+    0
+}
+
+/// Headless `--encrypt`, `--decrypt` and `--verify` (`args` starts with the
+/// command). Runs the same directory operations as the TUI and reports their
+/// progress on stderr. Exit code 0 = every file succeeded, 1 = some failed,
+/// 2 = usage, key or directory error.
+fn handle_crypto_command(args: &[String]) -> i32 {
+    use crate::enc::keys::{self, KeyInfo, KeyResolver, KeySource};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{mpsc, Arc};
+
+    let command = args[0].as_str();
+    let encrypt = command == "--encrypt";
+    let mut dir: Option<String> = None;
+    let mut split_mb: u64 = 0;
+    let mut use_md5 = false;
+    let mut compress = false;
+    let mut key_spec = keys::MACHINE_KEY.to_string();
+    let mut j = 1;
+    while j < args.len() {
+        match args[j].as_str() {
+            "--split" if encrypt && j + 1 < args.len() => {
+                split_mb = match args[j + 1].parse() {
+                    Ok(mb) => mb,
+                    Err(_) => {
+                        eprintln!("Error: --split needs a size in MB (0 = no split)");
+                        return 2;
+                    }
+                };
+                j += 2;
+            }
+            "--key" if encrypt && j + 1 < args.len() => {
+                key_spec = args[j + 1].clone();
+                j += 2;
+            }
+            "--md5" if encrypt => {
+                use_md5 = true;
+                j += 1;
+            }
+            "--compress" if encrypt => {
+                compress = true;
+                j += 1;
+            }
+            arg if !arg.starts_with("--") && dir.is_none() => {
+                dir = Some(arg.to_string());
+                j += 1;
+            }
+            arg => {
+                eprintln!("Error: unexpected argument for {}: {}", command, arg);
+                return 2;
+            }
+        }
+    }
+    let Some(dir) = dir.map(std::path::PathBuf::from) else {
+        eprintln!("Error: {} requires a directory (see --help)", command);
+        return 2;
+    };
+    if !dir.is_dir() {
+        eprintln!("Error: not a directory: {}", dir.display());
+        return 2;
+    }
+    let cred = match keys::credential_dir() {
+        Ok(cred) => cred,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 2;
+        }
+    };
+    let passphrase = std::env::var("COKACDIR_PASSPHRASE").ok().filter(|p| !p.is_empty());
+
+    let (tx, rx) = mpsc::channel();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let worker = if encrypt {
+        let source = match KeySource::parse(&key_spec) {
+            Ok(KeySource::Passphrase(_)) => match passphrase {
+                // Same rule as a new passphrase typed in the UI
+                Some(p) if p.chars().count() < keys::MIN_PASSPHRASE_LEN => {
+                    eprintln!("Error: COKACDIR_PASSPHRASE must have at least {} characters", keys::MIN_PASSPHRASE_LEN);
+                    return 2;
+                }
+                Some(p) => KeySource::Passphrase(p),
+                None => {
+                    eprintln!("Error: set COKACDIR_PASSPHRASE to encrypt with a passphrase");
+                    return 2;
+                }
+            },
+            // A typo must not silently create a new key
+            Ok(KeySource::Named(name)) if !keys::list_keys(&cred).contains(&name) => {
+                eprintln!("Error: no key '{}' in the keyring", name);
+                return 2;
+            }
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return 2;
+            }
+        };
+        let key = match source.load(&cred) {
+            Ok(key) => key,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return 2;
+            }
+        };
+        std::thread::spawn(move || {
+            crate::enc::pack_directory_with_progress(&dir, &key, tx, cancel_flag, split_mb, use_md5, compress);
+        })
+    } else {
+        let needs_passphrase = crate::enc::required_keys(&dir)
+            .map(|keys| keys.iter().any(|k| matches!(k, KeyInfo::Passphrase { .. })))
+            .unwrap_or(false);
+        if needs_passphrase && passphrase.is_none() {
+            eprintln!("Error: some files were encrypted with a passphrase; set COKACDIR_PASSPHRASE");
+            return 2;
+        }
+        let resolver = KeyResolver::new(cred, passphrase);
+        let verify = command == "--verify";
+        std::thread::spawn(move || {
+            if verify {
+                crate::enc::verify_directory_with_progress(&dir, resolver, tx, cancel_flag);
+            } else {
+                crate::enc::unpack_directory_with_progress(&dir, resolver, tx, cancel_flag);
+            }
+        })
+    };
+
+    let result = report_crypto_progress(&rx);
+    let _ = worker.join();
+    let verb = match command {
+        "--encrypt" => "Encrypted",
+        "--decrypt" => "Decrypted",
+        _ => "Verified",
+    };
+    match result {
+        Some((0, 0)) => {
+            eprintln!("Nothing to do");
+            0
+        }
+        Some((ok, 0)) => {
+            eprintln!("{} {} file(s)", verb, ok);
+            0
+        }
+        Some((ok, failed)) => {
+            eprintln!("{} {} file(s), {} failed", verb, ok, failed);
+            1
+        }
+        None => {
+            eprintln!("Error: the operation stopped unexpectedly");
+            2
+        }
+    }
+}
+
+/// Print the progress of a directory operation on stderr until it completes;
+/// returns its success and failure counts (None if it never completed)
+fn report_crypto_progress(
+    rx: &std::sync::mpsc::Receiver<crate::services::file_ops::ProgressMessage>,
+) -> Option<(usize, usize)> {
+    use crate::services::file_ops::ProgressMessage;
+
+    let mut position = String::new();
+    while let Ok(message) = rx.recv() {
+        match message {
+            ProgressMessage::TotalProgress(done, total, _, _) => {
+                position = format!("[{}/{}]", (done + 1).min(total), total);
+            }
+            ProgressMessage::FileCompleted(name) => eprintln!("{} ok      {}", position, name),
+            ProgressMessage::Error(name, error) => eprintln!("{} FAILED  {}: {}", position, name, error),
+            ProgressMessage::Completed(ok, failed) => return Some((ok, failed)),
+            _ => {}
+        }
+    }
+    None
+}
+
 /// Normalize consecutive empty lines to maximum of one
 fn normalize_consecutive_empty_lines(text: &str) -> String {
     let lines: Vec<&str> = text.lines().collect();
@@ -791,6 +1157,59 @@ fn main() -> io::Result<()> {
                 }
                 return Ok(());
             }
+            "--diff" => {
+                // Parse: --diff LEFT RIGHT [--format FMT] [--compare METHOD] [--output FILE]
+                let mut paths: Vec<String> = Vec::new();
+                let mut format = "json".to_string();
+                let mut compare: Option<String> = None;
+                let mut output: Option<String> = None;
+                let mut excludes = ui::diff_exclude::DiffExcludeConfig::default();
+                let mut j = i + 1;
+                while j < args.len() {
+                    match args[j].as_str() {
+                        "--format" | "--compare" | "--output" | "--exclude" if j + 1 < args.len() => {
+                            let value = args[j + 1].clone();
+                            match args[j].as_str() {
+                                "--format" => format = value,
+                                "--compare" => compare = Some(value),
+                                "--exclude" => excludes.patterns.extend(ui::diff_exclude::parse_patterns(&value)),
+                                _ => output = Some(value),
+                            }
+                            j += 2;
+                        }
+                        "--gitignore" => {
+                            excludes.use_ignore_files = true;
+                            j += 1;
+                        }
+                        arg if !arg.starts_with("--") && paths.len() < 2 => {
+                            paths.push(args[j].clone());
+                            j += 1;
+                        }
+                        arg => {
+                            eprintln!("Error: unexpected argument for --diff: {}", arg);
+                            std::process::exit(2);
+                        }
+                    }
+                }
+                if paths.len() != 2 {
+                    eprintln!("Error: --diff requires two directory arguments");
+                    eprintln!("Usage: cokacdir --diff <LEFT> <RIGHT> [--format json|csv|patch]");
+                    std::process::exit(2);
+                }
+                let code = handle_diff(&paths[0], &paths[1], &format, compare.as_deref(), output.as_deref(), excludes);
+                std::process::exit(code);
+            }
+            "--keygen" | "--export-key" | "--import-key" | "--list-keys" => {
+                let code = handle_key_command(&args[i..]);
+                std::process::exit(code);
+            }
+            "--encrypt" | "--decrypt" | "--verify" => {
+                let code = handle_crypto_command(&args[i..]);
+                std::process::exit(code);
+            }
+            "--import-ssh-config" => {
+                std::process::exit(handle_import_ssh_config());
+            }
             "--design" => {
                 design_mode = true;
             }
@@ -1018,7 +1437,7 @@ fn run_app<B: ratatui::backend::Backend>(
         let is_diff_comparing = app.current_screen == Screen::DiffScreen
             && app.diff_state.as_ref().map(|s| s.is_comparing).unwrap_or(false);
         let is_dedup_active = app.current_screen == Screen::DedupScreen
-            && app.dedup_screen_state.as_ref().map(|s| !s.is_complete).unwrap_or(false);
+            && app.dedup_screen_state.as_ref().map(|s| s.is_running()).unwrap_or(false);
         let is_progress_active = app.file_operation_progress
             .as_ref()
             .map(|p| p.is_active)
@@ -1075,10 +1494,16 @@ fn run_app<B: ratatui::backend::Backend>(
         if app.current_screen == Screen::DiffScreen {
             if let Some(ref mut state) = app.diff_state {
                 let just_completed = state.poll();
-                if just_completed && !state.has_differences() {
-                    app.diff_state = None;
-                    app.current_screen = Screen::FilePanel;
-                    app.show_message("No differences found");
+                if just_completed {
+                    if let Some(error) = state.error.take() {
+                        app.diff_state = None;
+                        app.current_screen = Screen::FilePanel;
+                        app.show_message(&format!("Diff failed: {}", error));
+                    } else if !state.has_differences() {
+                        app.diff_state = None;
+                        app.current_screen = Screen::FilePanel;
+                        app.show_message("No differences found");
+                    }
                 }
             }
         }
@@ -1086,6 +1511,17 @@ fn run_app<B: ratatui::backend::Backend>(
         // Poll for remote spinner completion
         app.poll_remote_spinner();
 
+        // Poll for background git status refreshes of the panels
+        app.poll_git_status();
+
+        // Read the metadata of new vault entries in the background
+        app.poll_vault();
+
+        // Poll for git screen fetch / pull / push progress
+        if let Some(ref mut state) = app.git_screen_state {
+            state.poll_remote_op();
+        }
+
         // Check for theme file changes (hot-reload, only in design mode)
         if app.design_mode && app.theme_watch_state.check_for_changes() {
             app.reload_theme();
@@ -1127,6 +1563,9 @@ fn run_app<B: ratatui::backend::Backend>(
                             crate::services::file_ops::FileOperationType::Download => "Downloaded",
                             crate::services::file_ops::FileOperationType::Encrypt => "Encrypted",
                             crate::services::file_ops::FileOperationType::Decrypt => "Decrypted",
+                            crate::services::file_ops::FileOperationType::Rekey => "Re-keyed",
+                            crate::services::file_ops::FileOperationType::Verify => "Verified",
+                            crate::services::file_ops::FileOperationType::List => "Read",
                         };
                         let total = result.success_count + result.failure_count;
                         if result.failure_count == 0 {
@@ -1201,6 +1640,7 @@ fn run_app<B: ratatui::backend::Backend>(
                                     selected_button: 1,
                                     selection: None,
                                     use_md5: false,
+                                    compress: false,
                                 });
                             } else {
                                 app.image_viewer_state = Some(
@@ -1211,6 +1651,10 @@ fn run_app<B: ratatui::backend::Backend>(
                         }
                     }
                 }
+            } else if app.pending_enc_list.is_some() {
+                app.finish_enc_list(progress_message);
+            } else if app.pending_vault_read.is_some() {
+                app.finish_vault_read();
             } else {
                 if let Some(msg) = progress_message {
                     app.show_message(&msg);
@@ -1237,6 +1681,13 @@ fn run_app<B: ratatui::backend::Backend>(
                 } else {
                     app.refresh_panels();
                 }
+                // Compare again after a diff sync so the tree shows the new state
+                if app.pending_diff_resync {
+                    app.pending_diff_resync = false;
+                    if let Some(ref mut state) = app.diff_state {
+                        state.start_comparison();
+                    }
+                }
                 app.file_operation_progress = None;
                 app.dialog = None;
             }
@@ -1324,10 +1775,18 @@ fn run_app<B: ratatui::backend::Backend>(
                             }
                         }
                         Screen::DiffScreen => {
-                            ui::diff_screen::handle_input(app, key.code, key.modifiers);
+                            if app.dialog.is_some() {
+                                ui::dialogs::handle_dialog_input(app, key.code, key.modifiers);
+                            } else {
+                                ui::diff_screen::handle_input(app, key.code, key.modifiers);
+                            }
                         }
                         Screen::DiffFileView => {
-                            ui::diff_file_view::handle_input(app, key.code, key.modifiers);
+                            if app.dialog.is_some() {
+                                ui::dialogs::handle_dialog_input(app, key.code, key.modifiers);
+                            } else {
+                                ui::diff_file_view::handle_input(app, key.code, key.modifiers);
+                            }
                         }
                         Screen::GitScreen => {
                             ui::git_screen::handle_input(app, key.code, key.modifiers);
@@ -1340,6 +1799,10 @@ fn run_app<B: ratatui::backend::Backend>(
                                     app.refresh_panels();
                                 }
                             }
+                            let preview = app.dedup_screen_state.as_mut().and_then(|s| s.preview.take());
+                            if let Some((images, index)) = preview {
+                                app.open_dedup_preview(images, index);
+                            }
                         }
                     }
                 }
@@ -1365,7 +1828,7 @@ fn run_app<B: ratatui::backend::Backend>(
                         Screen::FileEditor => {
                             ui::file_editor::handle_paste(app, &text);
                         }
-                        Screen::ImageViewer => {
+                        Screen::ImageViewer | Screen::DiffScreen | Screen::DiffFileView => {
                             if app.dialog.is_some() {
                                 ui::dialogs::handle_paste(app, &text);
                             }
@@ -1471,6 +1934,7 @@ fn handle_panel_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) ->
             PanelAction::GoHomeDir => app.goto_home(),
             PanelAction::Refresh => app.refresh_panels(),
             PanelAction::GitLogDiff => app.show_git_log_diff_dialog(),
+            PanelAction::GitFileHistory => app.show_git_file_history(),
             PanelAction::StartDiff => app.start_diff(),
             PanelAction::ClosePanel => app.close_panel(),
             PanelAction::AIScreen => app.show_ai_screen(),
@@ -1480,7 +1944,12 @@ fn handle_panel_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) ->
             PanelAction::SetHandler => app.show_handler_dialog(),
             PanelAction::EncryptAll => app.show_encrypt_dialog(),
             PanelAction::DecryptAll => app.show_decrypt_dialog(),
+            PanelAction::RekeyAll => app.show_rekey_dialog(),
+            PanelAction::VerifyEncrypted => app.verify_encrypted(),
+            PanelAction::ListEncrypted => app.list_encrypted(),
+            PanelAction::ToggleVault => app.toggle_vault(),
             PanelAction::RemoveDuplicates => app.show_dedup_screen(),
+            PanelAction::FindSimilarImages => app.show_similar_images_screen(),
             #[cfg(target_os = "macos")]
             PanelAction::OpenInFinder => app.open_in_finder(),
             #[cfg(target_os = "macos")]
@@ -1,15 +1,31 @@
+//! Duplicate file detection and removal.
+//!
+//! Files below the roots of a `DedupScope` are compared in tiers: same size,
+//! then a hash of the first and last `PARTIAL_BYTES`, then a SHA-256 of the
+//! whole file. Only files that match on every tier end up in a
+//! `DuplicateGroup`. `run_dedup` stops there and hands the groups over for
+//! review; nothing is touched until `run_apply` is called with the reviewed
+//! groups and an action.
+//!
+//! `similar_images` builds the same groups from images that only look alike.
+
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
+use std::time::SystemTime;
 
-use md5::{Digest, Md5};
+use sha2::{Digest, Sha256};
 
 const READ_BUF_SIZE: usize = 64 * 1024; // 64KB
 
+/// Bytes hashed from each end of a file by the partial hash tier
+const PARTIAL_BYTES: u64 = 16 * 1024;
+
 // Marker files: if any of these exist INSIDE a directory, skip that entire directory
 // (matches removeduplicated.js lines 47-50)
 const DIR_MARKER_FILES: &[&str] = &[
@@ -35,30 +51,311 @@ const SKIP_FILE_NAMES: &[&str] = &[
 #[derive(Debug, Clone, PartialEq)]
 pub enum DedupPhase {
     Scanning,
+    PartialHashing,
     Hashing,
-    Deleting,
+    Review,
+    Applying,
     Complete,
 }
 
+/// What happens to every duplicate except the survivor of its group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupAction {
+    Delete,
+    Hardlink,
+    /// Copy-on-write clone (Linux FICLONE; btrfs, XFS, ...)
+    Reflink,
+    Symlink,
+}
+
+impl DedupAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            DedupAction::Delete => "Delete",
+            DedupAction::Hardlink => "Hardlink",
+            DedupAction::Reflink => "Reflink",
+            DedupAction::Symlink => "Symlink",
+        }
+    }
+
+    /// Tag of the log line written for each processed file
+    pub fn log_tag(self) -> &'static str {
+        match self {
+            DedupAction::Delete => "REMOVE",
+            DedupAction::Hardlink => "HARDLINK",
+            DedupAction::Reflink => "REFLINK",
+            DedupAction::Symlink => "SYMLINK",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DedupAction::Delete => DedupAction::Hardlink,
+            DedupAction::Hardlink => DedupAction::Reflink,
+            DedupAction::Reflink => DedupAction::Symlink,
+            DedupAction::Symlink => DedupAction::Delete,
+        }
+    }
+}
+
+/// Which file of a group survives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepRule {
+    Oldest,
+    Newest,
+    ShortestPath,
+    /// Shortest path inside the directory; groups without one fall back to `Oldest`
+    PreferDir(PathBuf),
+    /// Most pixels, then the bigger file; files that are not images lose
+    LargestImage,
+}
+
+impl KeepRule {
+    pub fn label(&self) -> String {
+        match self {
+            KeepRule::Oldest => "oldest".to_string(),
+            KeepRule::Newest => "newest".to_string(),
+            KeepRule::ShortestPath => "shortest path".to_string(),
+            KeepRule::PreferDir(dir) => format!("in {}", dir.display()),
+            KeepRule::LargestImage => "largest image".to_string(),
+        }
+    }
+}
+
+/// Directories to search for duplicates. With `references`, only files below
+/// `roots` that also exist below a reference are reported; files below a
+/// reference are never changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupScope {
+    pub roots: Vec<PathBuf>,
+    pub references: Vec<PathBuf>,
+}
+
+impl DedupScope {
+    pub fn single(path: PathBuf) -> Self {
+        Self { roots: vec![path], references: Vec::new() }
+    }
+
+    /// Parse "root; root => reference; reference". Relative paths and `~` are
+    /// resolved against `base`; every path must be an existing directory.
+    pub fn parse(input: &str, base: &Path) -> Result<Self, String> {
+        let (roots, references) = match input.split_once("=>") {
+            Some((roots, references)) => (roots, Some(references)),
+            None => (input, None),
+        };
+        let parse_list = |list: &str| -> Result<Vec<PathBuf>, String> {
+            let mut paths: Vec<PathBuf> = Vec::new();
+            for part in list.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+                let path = match part.strip_prefix('~') {
+                    Some(rest) => match dirs::home_dir() {
+                        Some(home) => home.join(rest.trim_start_matches('/')),
+                        None => PathBuf::from(part),
+                    },
+                    None => base.join(part),
+                };
+                if !path.is_dir() {
+                    return Err(format!("Not a directory: {}", part));
+                }
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+            Ok(paths)
+        };
+
+        let scope = Self {
+            roots: parse_list(roots)?,
+            references: match references {
+                Some(list) => parse_list(list)?,
+                None => Vec::new(),
+            },
+        };
+        if scope.roots.is_empty() {
+            return Err("No directory to search".to_string());
+        }
+        if references.is_some() && scope.references.is_empty() {
+            return Err("No directory after '=>'".to_string());
+        }
+        Ok(scope)
+    }
+
+    /// Inverse of `parse`
+    pub fn to_input(&self) -> String {
+        let join = |paths: &[PathBuf]| paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join("; ");
+        if self.references.is_empty() {
+            join(&self.roots)
+        } else {
+            format!("{} => {}", join(&self.roots), join(&self.references))
+        }
+    }
+
+    /// Roots followed by references; `DupFile::root` indexes this
+    pub fn all_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.roots.iter().chain(self.references.iter())
+    }
+
+    pub fn is_reference(&self, root: usize) -> bool {
+        root >= self.roots.len()
+    }
+
+    /// Short name per directory of `all_dirs`: its last component, numbered
+    /// when names repeat, "ref:" in front for references
+    pub fn labels(&self) -> Vec<String> {
+        let names: Vec<String> = self
+            .all_dirs()
+            .map(|d| {
+                d.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| d.display().to_string())
+            })
+            .collect();
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut label = if names.iter().filter(|n| *n == name).count() > 1 {
+                    format!("{}#{}", name, i + 1)
+                } else {
+                    name.clone()
+                };
+                if self.is_reference(i) {
+                    label.insert_str(0, "ref:");
+                }
+                label
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DupFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Width and height, for images found by `similar_images`
+    pub dimensions: Option<(u32, u32)>,
+    /// Index into `DedupScope::all_dirs`
+    pub root: usize,
+    /// Below a reference directory: never changed
+    pub reference: bool,
+}
+
+/// Files with identical (or, from `similar_images`, similar looking) content
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// SHA-256 of the content, or the hex perceptual hash of the first image
+    pub hash: String,
+    /// Size of each file; the largest one for similar images
+    pub size: u64,
+    pub files: Vec<DupFile>,
+    /// Index of the survivor in `files`
+    pub keep: usize,
+    /// Left alone by `run_apply`
+    pub excluded: bool,
+}
+
+impl DuplicateGroup {
+    /// Choose the survivor; a copy in a reference directory always wins
+    pub fn apply_rule(&mut self, rule: &KeepRule) {
+        let references: Vec<DupFile> = self.files.iter().filter(|f| f.reference).cloned().collect();
+        self.keep = if references.is_empty() {
+            choose_survivor(&self.files, rule)
+        } else {
+            let chosen = &references[choose_survivor(&references, rule)];
+            self.files.iter().position(|f| f.path == chosen.path).unwrap_or(0)
+        };
+    }
+
+    /// Files `run_apply` acts on: all but the survivor and reference copies
+    pub fn to_process(&self) -> impl Iterator<Item = &DupFile> {
+        let keep = self.keep;
+        self.files.iter().enumerate().filter(move |(i, f)| *i != keep && !f.reference).map(|(_, f)| f)
+    }
+
+    /// Space freed by processing this group
+    pub fn reclaimable(&self) -> u64 {
+        self.to_process().map(|f| f.size).sum()
+    }
+}
+
+/// Index of the file that should survive under `rule`. Ties go to the
+/// lexically smallest path so the choice is stable.
+pub fn choose_survivor(files: &[DupFile], rule: &KeepRule) -> usize {
+    let indices = 0..files.len();
+    let chosen = match rule {
+        // Files without a modification time lose under both age rules
+        KeepRule::Oldest => indices.min_by_key(|&i| (files[i].modified.is_none(), files[i].modified, &files[i].path)),
+        KeepRule::Newest => indices.min_by_key(|&i| {
+            (files[i].modified.is_none(), std::cmp::Reverse(files[i].modified), &files[i].path)
+        }),
+        KeepRule::ShortestPath => indices.min_by_key(|&i| (files[i].path.as_os_str().len(), &files[i].path)),
+        KeepRule::LargestImage => indices.min_by_key(|&i| {
+            let pixels = files[i].dimensions.map(|(w, h)| w as u64 * h as u64);
+            (std::cmp::Reverse(pixels), std::cmp::Reverse(files[i].size), &files[i].path)
+        }),
+        KeepRule::PreferDir(dir) => {
+            let inside = indices.filter(|&i| files[i].path.starts_with(dir));
+            match inside.min_by_key(|&i| (files[i].path.as_os_str().len(), &files[i].path)) {
+                Some(i) => Some(i),
+                None => return choose_survivor(files, &KeepRule::Oldest),
+            }
+        }
+    };
+    chosen.unwrap_or(0)
+}
+
 pub enum DedupMessage {
     Phase(DedupPhase),
     Scanning(String),
     Hashing(String, u8),
-    Deleting(String),
+    Applying(String),
     Log(String),
     Stats { scanned: usize, duplicates: usize, freed: u64 },
     Error(String),
+    /// Scan result, sent instead of `Complete` when duplicates were found;
+    /// also the similar image groups after a regroup
+    Groups(Vec<DuplicateGroup>),
+    /// Hashed images, sent before their `Groups` when similar ones were found
+    Images(Vec<crate::services::similar_images::ImageHashes>),
     Complete,
 }
 
 #[derive(Debug)]
-struct FileEntry {
-    path: PathBuf,
-    size: u64,
+pub(crate) struct FileEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub root: usize,
+    /// (device, inode), to keep hard links to the same data out of the groups
+    pub inode: (u64, u64),
+}
+
+/// Walk every directory of `scope`, grouping the files by size. Returns the
+/// groups and the number of files seen, or `None` when cancelled.
+pub(crate) fn scan_scope(
+    scope: &DedupScope,
+    tx: &Sender<DedupMessage>,
+    cancel_flag: &Arc<AtomicBool>,
+) -> Option<(HashMap<u64, Vec<FileEntry>>, usize)> {
+    let mut size_map: HashMap<u64, Vec<FileEntry>> = HashMap::new();
+    let mut scanned: usize = 0;
+
+    // References first: a file reachable from both sides (nested
+    // directories) then counts as a reference and is left alone
+    let (references, roots): (Vec<_>, Vec<_>) = scope.all_dirs().enumerate().partition(|(i, _)| scope.is_reference(*i));
+    for (root, dir) in references.into_iter().chain(roots) {
+        scan_directory(dir, root, tx, cancel_flag, &mut size_map, &mut scanned);
+    }
+
+    if cancel_flag.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some((size_map, scanned))
 }
 
 fn scan_directory(
     dir: &Path,
+    root: usize,
     tx: &Sender<DedupMessage>,
     cancel_flag: &Arc<AtomicBool>,
     size_map: &mut HashMap<u64, Vec<FileEntry>>,
@@ -107,7 +404,7 @@ fn scan_directory(
         };
 
         if metadata.is_dir() {
-            scan_directory(&path, tx, cancel_flag, size_map, scanned);
+            scan_directory(&path, root, tx, cancel_flag, size_map, scanned);
         } else if metadata.is_file() {
             // Skip specific file names (matches removeduplicated.js lines 60-61)
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
@@ -129,12 +426,36 @@ fn scan_directory(
             size_map.entry(size).or_default().push(FileEntry {
                 path,
                 size,
+                modified: metadata.modified().ok(),
+                root,
+                inode: (metadata.dev(), metadata.ino()),
             });
         }
     }
 }
 
-fn compute_md5(path: &Path, file_size: u64, tx: &Sender<DedupMessage>, cancel_flag: &Arc<AtomicBool>) -> Option<String> {
+/// Read up to `limit` bytes into the hasher
+fn hash_chunk(reader: &mut impl Read, limit: u64, hasher: &mut Sha256) -> io::Result<()> {
+    let mut chunk = Vec::with_capacity(limit as usize);
+    reader.take(limit).read_to_end(&mut chunk)?;
+    hasher.update(&chunk);
+    Ok(())
+}
+
+/// Hash of the first and last `PARTIAL_BYTES` of a file. Files of at most
+/// twice that size are hashed whole, so for them this is the full hash.
+fn compute_partial_hash(path: &Path, file_size: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    hash_chunk(&mut file, PARTIAL_BYTES, &mut hasher)?;
+    if file_size > PARTIAL_BYTES * 2 {
+        file.seek(SeekFrom::End(-(PARTIAL_BYTES as i64)))?;
+    }
+    hash_chunk(&mut file, PARTIAL_BYTES, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn compute_sha256(path: &Path, file_size: u64, tx: &Sender<DedupMessage>, cancel_flag: &Arc<AtomicBool>) -> Option<String> {
     let file = match File::open(path) {
         Ok(f) => f,
         Err(e) => {
@@ -144,7 +465,7 @@ fn compute_md5(path: &Path, file_size: u64, tx: &Sender<DedupMessage>, cancel_fl
     };
 
     let mut reader = BufReader::new(file);
-    let mut hasher = Md5::new();
+    let mut hasher = Sha256::new();
     let mut buf = [0u8; READ_BUF_SIZE];
     let mut bytes_read: u64 = 0;
 
@@ -171,32 +492,41 @@ fn compute_md5(path: &Path, file_size: u64, tx: &Sender<DedupMessage>, cancel_fl
         }
     }
 
-    Some(format!("{:032x}", hasher.finalize()))
+    Some(hex::encode(hasher.finalize()))
+}
+
+pub(crate) fn send_cancelled(tx: &Sender<DedupMessage>) {
+    let _ = tx.send(DedupMessage::Log("Cancelled.".into()));
+    let _ = tx.send(DedupMessage::Complete);
 }
 
+/// Scan the directories of `scope` and find duplicate groups. Ends with
+/// `DedupMessage::Groups` when there are duplicates, with `DedupMessage::Complete` otherwise.
 pub fn run_dedup(
-    target_path: PathBuf,
+    scope: DedupScope,
     tx: Sender<DedupMessage>,
     cancel_flag: Arc<AtomicBool>,
 ) {
-    // Phase 1: Scan
+    // Tier 1: size
     let _ = tx.send(DedupMessage::Phase(DedupPhase::Scanning));
     let _ = tx.send(DedupMessage::Log("Scanning files...".into()));
 
-    let mut size_map: HashMap<u64, Vec<FileEntry>> = HashMap::new();
-    let mut scanned: usize = 0;
-
-    scan_directory(&target_path, &tx, &cancel_flag, &mut size_map, &mut scanned);
-
-    if cancel_flag.load(Ordering::Relaxed) {
-        let _ = tx.send(DedupMessage::Log("Cancelled.".into()));
-        let _ = tx.send(DedupMessage::Complete);
-        return;
-    }
+    let (size_map, scanned) = match scan_scope(&scope, &tx, &cancel_flag) {
+        Some(result) => result,
+        None => {
+            send_cancelled(&tx);
+            return;
+        }
+    };
 
-    // Filter to groups with 2+ files (potential duplicates)
+    // Filter to groups with 2+ distinct files (hard links share their data already)
     let candidate_groups: Vec<Vec<FileEntry>> = size_map
         .into_values()
+        .map(|mut group| {
+            let mut seen = std::collections::HashSet::new();
+            group.retain(|e| seen.insert(e.inode));
+            group
+        })
         .filter(|group| group.len() >= 2)
         .collect();
 
@@ -206,23 +536,53 @@ pub fn run_dedup(
         scanned, candidate_count, candidate_groups.len()
     )));
 
-    // Phase 2: Hash
+    // Tier 2: partial hash
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::PartialHashing));
+
+    let mut partial_groups: Vec<Vec<FileEntry>> = Vec::new();
+    let mut done: usize = 0;
+    for group in candidate_groups {
+        let mut by_partial: HashMap<String, Vec<FileEntry>> = HashMap::new();
+        for entry in group {
+            if cancel_flag.load(Ordering::Relaxed) {
+                send_cancelled(&tx);
+                return;
+            }
+            done += 1;
+            let pct = ((done as f64 / candidate_count as f64) * 100.0).round() as u8;
+            let _ = tx.send(DedupMessage::Hashing(entry.path.display().to_string(), pct));
+            match compute_partial_hash(&entry.path, entry.size) {
+                Ok(hash) => by_partial.entry(hash).or_default().push(entry),
+                Err(e) => {
+                    let _ = tx.send(DedupMessage::Error(format!("Cannot read {}: {}", entry.path.display(), e)));
+                }
+            }
+        }
+        partial_groups.extend(by_partial.into_values().filter(|g| g.len() >= 2));
+    }
+
+    let partial_count: usize = partial_groups.iter().map(|g| g.len()).sum();
+    let _ = tx.send(DedupMessage::Log(format!(
+        "Partial hash: {} of {} candidates remain",
+        partial_count, candidate_count
+    )));
+
+    // Tier 3: full SHA-256
     let _ = tx.send(DedupMessage::Phase(DedupPhase::Hashing));
 
-    let mut hash_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut hash_map: HashMap<(u64, String), Vec<FileEntry>> = HashMap::new();
 
     // Calculate total size for percentage
-    let total_bytes: u64 = candidate_groups.iter()
+    let total_bytes: u64 = partial_groups.iter()
         .flat_map(|g| g.iter())
         .map(|e| e.size)
         .sum();
     let mut accum_bytes: u64 = 0;
 
-    for group in &candidate_groups {
+    for group in partial_groups {
         for entry in group {
             if cancel_flag.load(Ordering::Relaxed) {
-                let _ = tx.send(DedupMessage::Log("Cancelled.".into()));
-                let _ = tx.send(DedupMessage::Complete);
+                send_cancelled(&tx);
                 return;
             }
 
@@ -233,22 +593,50 @@ pub fn run_dedup(
                 0
             };
 
-            if let Some(hash) = compute_md5(&entry.path, entry.size, &tx, &cancel_flag) {
+            // Small files were hashed whole by the partial tier already
+            let hash = if entry.size <= PARTIAL_BYTES * 2 {
+                compute_partial_hash(&entry.path, entry.size).ok()
+            } else {
+                compute_sha256(&entry.path, entry.size, &tx, &cancel_flag)
+            };
+            if let Some(hash) = hash {
                 let _ = tx.send(DedupMessage::Log(format!(
                     "{} {} % {} {}", hash, pct, entry.size, entry.path.display()
                 )));
-                hash_map.entry(hash).or_default().push(entry.path.clone());
+                hash_map.entry((entry.size, hash)).or_default().push(entry);
             }
         }
     }
 
-    // Filter to duplicate groups (2+ files with same hash)
-    let dup_groups: Vec<(&String, &Vec<PathBuf>)> = hash_map
-        .iter()
-        .filter(|(_, paths)| paths.len() >= 2)
+    let with_references = !scope.references.is_empty();
+    let mut groups: Vec<DuplicateGroup> = hash_map
+        .into_iter()
+        .filter(|(_, entries)| entries.len() >= 2)
+        .map(|((size, hash), entries)| {
+            let mut files: Vec<DupFile> = entries
+                .into_iter()
+                .map(|e| DupFile {
+                    path: e.path,
+                    size: e.size,
+                    modified: e.modified,
+                    dimensions: None,
+                    root: e.root,
+                    reference: scope.is_reference(e.root),
+                })
+                .collect();
+            files.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+            DuplicateGroup { hash, size, files, keep: 0, excluded: false }
+        })
+        // "Files in A that exist in B": a copy on both sides
+        .filter(|g| !with_references || (g.files.iter().any(|f| f.reference) && g.files.iter().any(|f| !f.reference)))
         .collect();
+    // Biggest savings first
+    groups.sort_by(|a, b| b.reclaimable().cmp(&a.reclaimable()).then_with(|| a.files[0].path.cmp(&b.files[0].path)));
 
-    let total_duplicates: usize = dup_groups.iter().map(|(_, paths)| paths.len() - 1).sum();
+    for group in groups.iter_mut() {
+        group.apply_rule(&KeepRule::Oldest);
+    }
+    let total_duplicates: usize = groups.iter().map(|g| g.to_process().count()).sum();
 
     if total_duplicates == 0 {
         let _ = tx.send(DedupMessage::Log("No duplicates found.".into()));
@@ -258,39 +646,68 @@ pub fn run_dedup(
         return;
     }
 
-    // Phase 3: Delete
-    let _ = tx.send(DedupMessage::Phase(DedupPhase::Deleting));
-    let _ = tx.send(DedupMessage::Log("Removing duplicates...".into()));
+    let reclaimable: u64 = groups.iter().map(|g| g.reclaimable()).sum();
+    let _ = tx.send(DedupMessage::Log(format!(
+        "Found {} duplicate files in {} groups ({} reclaimable)",
+        total_duplicates, groups.len(), format_size(reclaimable)
+    )));
+    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: 0, freed: 0 });
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::Review));
+    let _ = tx.send(DedupMessage::Groups(groups));
+}
 
-    let mut deleted_count: usize = 0;
+/// Apply `action` to every duplicate of the groups that are not excluded,
+/// keeping each group's survivor
+pub fn run_apply(
+    groups: Vec<DuplicateGroup>,
+    action: DedupAction,
+    scanned: usize,
+    tx: Sender<DedupMessage>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::Applying));
+    let _ = tx.send(DedupMessage::Log(format!("{} duplicates...", action.label())));
+
+    let mut processed: usize = 0;
     let mut freed_bytes: u64 = 0;
 
-    for (_hash, paths) in &dup_groups {
-        // Keep first file, delete the rest
-        for dup_path in paths.iter().skip(1) {
+    for group in groups.iter().filter(|g| !g.excluded) {
+        let keep = match group.files.get(group.keep) {
+            Some(f) => f,
+            None => continue,
+        };
+        // The survivor must still hold the content the group was built from
+        if let Err(e) = verify_unchanged(keep) {
+            let _ = tx.send(DedupMessage::Error(format!("Skipping group of {}: {}", keep.path.display(), e)));
+            continue;
+        }
+
+        for dup in group.to_process() {
             if cancel_flag.load(Ordering::Relaxed) {
                 let _ = tx.send(DedupMessage::Log(format!(
-                    "Cancelled. Removed {} files, freed {}",
-                    deleted_count, format_size(freed_bytes)
+                    "Cancelled. Processed {} files, freed {}",
+                    processed, format_size(freed_bytes)
                 )));
-                let _ = tx.send(DedupMessage::Stats { scanned, duplicates: deleted_count, freed: freed_bytes });
+                let _ = tx.send(DedupMessage::Stats { scanned, duplicates: processed, freed: freed_bytes });
                 let _ = tx.send(DedupMessage::Complete);
                 return;
             }
 
-            let file_size = fs::metadata(dup_path).map(|m| m.len()).unwrap_or(0);
-
-            match fs::remove_file(dup_path) {
+            let result = verify_unchanged(dup)
+                .and_then(|_| replace_duplicate(&keep.path, &dup.path, action));
+            match result {
                 Ok(()) => {
-                    deleted_count += 1;
-                    freed_bytes += file_size;
-                    let _ = tx.send(DedupMessage::Deleting(dup_path.display().to_string()));
-                    let _ = tx.send(DedupMessage::Log(format!("REMOVE {} {}", _hash, dup_path.display())));
-                    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: deleted_count, freed: freed_bytes });
+                    processed += 1;
+                    freed_bytes += dup.size;
+                    let _ = tx.send(DedupMessage::Applying(dup.path.display().to_string()));
+                    let _ = tx.send(DedupMessage::Log(format!(
+                        "{} {} {}", action.log_tag(), &group.hash[..group.hash.len().min(16)], dup.path.display()
+                    )));
+                    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: processed, freed: freed_bytes });
                 }
                 Err(e) => {
                     let _ = tx.send(DedupMessage::Error(format!(
-                        "Failed to delete {}: {}", dup_path.display(), e
+                        "Failed to {} {}: {}", action.label().to_lowercase(), dup.path.display(), e
                     )));
                 }
             }
@@ -298,14 +715,81 @@ pub fn run_dedup(
     }
 
     let _ = tx.send(DedupMessage::Log(format!(
-        "Complete! Removed {} duplicate files, freed {}",
-        deleted_count, format_size(freed_bytes)
+        "Complete! Processed {} duplicate files ({}), freed {}",
+        processed, action.label().to_lowercase(), format_size(freed_bytes)
     )));
-    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: deleted_count, freed: freed_bytes });
+    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: processed, freed: freed_bytes });
     let _ = tx.send(DedupMessage::Phase(DedupPhase::Complete));
     let _ = tx.send(DedupMessage::Complete);
 }
 
+/// Check that a file still looks like it did when it was hashed
+fn verify_unchanged(file: &DupFile) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(&file.path).map_err(|e| e.to_string())?;
+    if !metadata.is_file() || metadata.len() != file.size || metadata.modified().ok() != file.modified {
+        return Err("changed since the scan".to_string());
+    }
+    Ok(())
+}
+
+/// Replace (or delete) `dup`, which has the same content as `keep`. Links are
+/// created next to `dup` first and renamed over it, so a failure leaves it intact.
+pub fn replace_duplicate(keep: &Path, dup: &Path, action: DedupAction) -> Result<(), String> {
+    if action == DedupAction::Delete {
+        return fs::remove_file(dup).map_err(|e| e.to_string());
+    }
+
+    let name = dup.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dup.with_file_name(format!(".{}.cokacdir-dedup", name));
+    let result = match action {
+        DedupAction::Hardlink => fs::hard_link(keep, &tmp),
+        DedupAction::Reflink => reflink(keep, &tmp).and_then(|_| {
+            // A clone is a new file: give it the permissions of the one it replaces
+            let permissions = fs::metadata(dup)?.permissions();
+            fs::set_permissions(&tmp, permissions)
+        }),
+        DedupAction::Symlink => {
+            let target = if keep.is_absolute() {
+                keep.to_path_buf()
+            } else {
+                std::env::current_dir().map(|d| d.join(keep)).unwrap_or_else(|_| keep.to_path_buf())
+            };
+            std::os::unix::fs::symlink(target, &tmp)
+        }
+        DedupAction::Delete => unreachable!(),
+    }
+    .and_then(|_| fs::rename(&tmp, dup));
+
+    result.map_err(|e| {
+        if e.kind() != io::ErrorKind::AlreadyExists {
+            let _ = fs::remove_file(&tmp);
+        }
+        e.to_string()
+    })
+}
+
+/// Create `dst` as a copy-on-write clone of `src`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[allow(unsafe_code)]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let src_file = File::open(src)?;
+    let dst_file = fs::OpenOptions::new().write(true).create_new(true).open(dst)?;
+    let result = unsafe { libc::ioctl(dst_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) };
+    if result == -1 {
+        let err = io::Error::last_os_error();
+        drop(dst_file);
+        let _ = fs::remove_file(dst);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn reflink(_src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reflink is not supported on this platform"))
+}
+
 pub fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
@@ -317,3 +801,214 @@ pub fn format_size(bytes: u64) -> String {
         format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn file(path: &str, age_secs: u64) -> DupFile {
+        DupFile {
+            path: PathBuf::from(path),
+            size: 100,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 - age_secs)),
+            dimensions: None,
+            root: 0,
+            reference: false,
+        }
+    }
+
+    /// Run a scan of one directory and return its groups
+    fn scan(dir: &Path) -> Vec<DuplicateGroup> {
+        scan_scope(DedupScope::single(dir.to_path_buf()))
+    }
+
+    fn scan_scope(scope: DedupScope) -> Vec<DuplicateGroup> {
+        let (tx, rx) = mpsc::channel();
+        run_dedup(scope, tx, Arc::new(AtomicBool::new(false)));
+        rx.try_iter()
+            .find_map(|m| match m {
+                DedupMessage::Groups(groups) => Some(groups),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_choose_survivor() {
+        let files = vec![
+            file("/data/b/copy.txt", 10),
+            file("/data/a/long/original.txt", 30),
+            file("/data/c.txt", 20),
+        ];
+        assert_eq!(choose_survivor(&files, &KeepRule::Oldest), 1);
+        assert_eq!(choose_survivor(&files, &KeepRule::Newest), 0);
+        assert_eq!(choose_survivor(&files, &KeepRule::ShortestPath), 2);
+        assert_eq!(choose_survivor(&files, &KeepRule::PreferDir(PathBuf::from("/data/b"))), 0);
+        // No file inside the preferred directory: oldest
+        assert_eq!(choose_survivor(&files, &KeepRule::PreferDir(PathBuf::from("/other"))), 1);
+
+        // Not images: no pixels to compare, the bigger file wins
+        let mut sized = files.clone();
+        sized[2].size = 200;
+        assert_eq!(choose_survivor(&sized, &KeepRule::LargestImage), 2);
+        sized[0].dimensions = Some((640, 480));
+        sized[1].dimensions = Some((1920, 1080));
+        assert_eq!(choose_survivor(&sized, &KeepRule::LargestImage), 1);
+
+        let mut unknown = files.clone();
+        unknown[1].modified = None;
+        assert_eq!(choose_survivor(&unknown, &KeepRule::Oldest), 2);
+        assert_eq!(choose_survivor(&unknown, &KeepRule::Newest), 0);
+    }
+
+    #[test]
+    fn test_scope_parse_and_labels() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = tmp.path();
+        for dir in ["a/photos", "b/photos", "backup"] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+
+        let scope = DedupScope::parse(" a/photos ; b/photos;a/photos ", base).unwrap();
+        assert_eq!(scope.roots, vec![base.join("a/photos"), base.join("b/photos")]);
+        assert!(scope.references.is_empty());
+        assert_eq!(scope.labels(), vec!["photos#1", "photos#2"]);
+
+        let scope = DedupScope::parse("a/photos => backup", base).unwrap();
+        assert_eq!(scope.references, vec![base.join("backup")]);
+        assert_eq!(scope.labels(), vec!["photos", "ref:backup"]);
+        assert_eq!(DedupScope::parse(&scope.to_input(), Path::new("/")).unwrap(), scope);
+
+        assert_eq!(DedupScope::parse("missing", base).unwrap_err(), "Not a directory: missing");
+        assert_eq!(DedupScope::parse(" ; ", base).unwrap_err(), "No directory to search");
+        assert_eq!(DedupScope::parse("a =>", base).unwrap_err(), "No directory after '=>'");
+    }
+
+    #[test]
+    fn test_roots_and_references() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let a = tmp.path().join("a");
+        let b = tmp.path().join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("in-both.jpg"), "shared").unwrap();
+        fs::write(b.join("old-copy.jpg"), "shared").unwrap();
+        fs::write(a.join("only-a1.jpg"), "a-only").unwrap();
+        fs::write(a.join("only-a2.jpg"), "a-only").unwrap();
+        fs::write(b.join("only-b1.jpg"), "b-only").unwrap();
+        fs::write(b.join("only-b2.jpg"), "b-only").unwrap();
+
+        // Several roots: every group, each file tagged with its root
+        let groups = scan_scope(DedupScope { roots: vec![a.clone(), b.clone()], references: Vec::new() });
+        assert_eq!(groups.len(), 3);
+        let shared = groups.iter().find(|g| g.files.iter().any(|f| f.path.ends_with("in-both.jpg"))).unwrap();
+        assert_eq!(shared.files.iter().map(|f| f.root).collect::<Vec<_>>(), vec![0, 1]);
+
+        // A against B: only files of A that already exist in B
+        let groups = scan_scope(DedupScope { roots: vec![a.clone()], references: vec![b.clone()] });
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        let keep = &group.files[group.keep];
+        assert!(keep.reference && keep.path == b.join("old-copy.jpg"));
+        let process: Vec<&PathBuf> = group.to_process().map(|f| &f.path).collect();
+        assert_eq!(process, vec![&a.join("in-both.jpg")]);
+        assert_eq!(group.reclaimable(), 6);
+    }
+
+    #[test]
+    fn test_tiers_find_only_identical_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let big: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let mut tail_differs = big.clone();
+        *tail_differs.last_mut().unwrap() ^= 1;
+        let mut middle_differs = big.clone();
+        middle_differs[50_000] ^= 1;
+
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.bin"), &big).unwrap();
+        fs::write(dir.join("sub/b.bin"), &big).unwrap();
+        fs::write(dir.join("tail.bin"), &tail_differs).unwrap();
+        fs::write(dir.join("middle.bin"), &middle_differs).unwrap();
+        fs::write(dir.join("small1.txt"), "same").unwrap();
+        fs::write(dir.join("small2.txt"), "same").unwrap();
+        fs::write(dir.join("other.txt"), "diff").unwrap();
+        // A hard link is not a duplicate of its own data
+        fs::hard_link(dir.join("small1.txt"), dir.join("small1-link.txt")).unwrap();
+
+        let groups = scan(dir);
+        assert_eq!(groups.len(), 2);
+        // Biggest savings first
+        let names = |g: &DuplicateGroup| -> Vec<String> {
+            g.files.iter().map(|f| f.path.strip_prefix(dir).unwrap().display().to_string()).collect()
+        };
+        assert_eq!(names(&groups[0]), vec!["a.bin", "sub/b.bin"]);
+        assert_eq!(groups[0].size, 100_000);
+        assert_eq!(groups[0].hash, hex::encode(Sha256::digest(&big)));
+        assert_eq!(groups[1].files.len(), 2);
+        assert_eq!(groups[1].hash, hex::encode(Sha256::digest(b"same")));
+    }
+
+    #[test]
+    fn test_apply_actions() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        for name in ["keep.txt", "del.txt", "hard.txt", "sym.txt"] {
+            fs::write(dir.join(name), "content").unwrap();
+        }
+        let keep = dir.join("keep.txt");
+
+        replace_duplicate(&keep, &dir.join("del.txt"), DedupAction::Delete).unwrap();
+        assert!(!dir.join("del.txt").exists());
+
+        replace_duplicate(&keep, &dir.join("hard.txt"), DedupAction::Hardlink).unwrap();
+        assert_eq!(fs::metadata(dir.join("hard.txt")).unwrap().ino(), fs::metadata(&keep).unwrap().ino());
+
+        replace_duplicate(&keep, &dir.join("sym.txt"), DedupAction::Symlink).unwrap();
+        assert_eq!(fs::read_link(dir.join("sym.txt")).unwrap(), keep);
+        assert_eq!(fs::read_to_string(dir.join("sym.txt")).unwrap(), "content");
+
+        // Reflink depends on the filesystem; a failure must leave the file alone
+        fs::write(dir.join("clone.txt"), "content").unwrap();
+        if replace_duplicate(&keep, &dir.join("clone.txt"), DedupAction::Reflink).is_err() {
+            assert_eq!(fs::read_to_string(dir.join("clone.txt")).unwrap(), "content");
+        }
+        assert!(!dir.join(".clone.txt.cokacdir-dedup").exists());
+    }
+
+    #[test]
+    fn test_run_apply_skips_excluded_and_changed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        for name in ["a1", "a2", "b1", "b2", "c1", "c2"] {
+            fs::write(dir.join(name), format!("{}-data", &name[..1])).unwrap();
+        }
+        let mut groups = scan(dir);
+        assert_eq!(groups.len(), 3);
+        for group in groups.iter_mut() {
+            group.apply_rule(&KeepRule::ShortestPath);
+        }
+        let b = groups.iter().position(|g| g.files[0].path.ends_with("b1")).unwrap();
+        groups[b].excluded = true;
+        // c2 changes after the scan
+        fs::write(dir.join("c2"), "c-DATA").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        run_apply(groups, DedupAction::Delete, 6, tx, Arc::new(AtomicBool::new(false)));
+        let errors: Vec<String> = rx
+            .try_iter()
+            .filter_map(|m| match m {
+                DedupMessage::Error(e) => Some(e),
+                _ => None,
+            })
+            .collect();
+
+        assert!(dir.join("a1").exists() && !dir.join("a2").exists());
+        assert!(dir.join("b1").exists() && dir.join("b2").exists());
+        assert!(dir.join("c2").exists());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("changed since the scan"), "{}", errors[0]);
+    }
+}
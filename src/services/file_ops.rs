@@ -16,6 +16,9 @@ pub enum FileOperationType {
     Download,
     Encrypt,
     Decrypt,
+    Rekey,
+    Verify,
+    List,
 }
 
 /// Progress message for file operations
@@ -0,0 +1,374 @@
+//! Host key verification for SSH/SFTP connections.
+//!
+//! A server's key is accepted only when it is recorded for the host in the
+//! user's `~/.ssh/known_hosts` or in `~/.cokacdir/known_hosts`, where cokacdir
+//! saves the hosts the user chose to trust on first connection.
+//!
+//! The files are read like ssh does: host patterns may use `*`, `?` and `!`
+//! negation or be hashed, and a key marked `@revoked` is always refused.
+//! Lines ssh would use but cokacdir cannot read make the check fail rather
+//! than pass over them.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use russh_keys::key::PublicKey;
+
+/// A server's host key as presented during the SSH handshake
+#[derive(Debug, Clone)]
+pub struct HostKey {
+    pub host: String,
+    pub port: u16,
+    pub key: Box<PublicKey>,
+}
+
+impl HostKey {
+    /// Key type, e.g. "ssh-ed25519"
+    pub fn algorithm(&self) -> &'static str {
+        self.key.name()
+    }
+
+    /// SHA256 fingerprint in the format `ssh-keygen -l` prints
+    pub fn fingerprint(&self) -> String {
+        format!("SHA256:{}", self.key.fingerprint())
+    }
+
+    /// Host as written in known_hosts: `host` or `[host]:port`
+    pub fn host_label(&self) -> String {
+        if self.port == 22 {
+            self.host.clone()
+        } else {
+            format!("[{}]:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Why a server's host key was not accepted
+#[derive(Debug, Clone)]
+pub enum HostKeyError {
+    /// No key is recorded for the host; the user may choose to trust it
+    Unknown(HostKey),
+    /// The host presented another key than the one recorded for it
+    Changed { key: HostKey, file: PathBuf, line: usize },
+    /// The key the host presented is marked `@revoked`
+    Revoked { key: HostKey, file: PathBuf, line: usize },
+    /// A line for the host, of the type of its key, cannot be read
+    Unreadable { key: HostKey, file: PathBuf, line: usize, error: String },
+}
+
+impl fmt::Display for HostKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostKeyError::Unknown(key) => write!(
+                f,
+                "The authenticity of host '{}' can't be established. {} key fingerprint is {}.",
+                key.host_label(), key.algorithm(), key.fingerprint(),
+            ),
+            HostKeyError::Changed { key, file, line } => write!(
+                f,
+                "WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED for '{}'! Someone could be \
+                 eavesdropping on you right now (man-in-the-middle attack), or the host key has \
+                 just been changed. The server sent a {} key with fingerprint {}. Offending key \
+                 in {}:{}; remove it there only if you know the change is legitimate.",
+                key.host_label(), key.algorithm(), key.fingerprint(), file.display(), line,
+            ),
+            HostKeyError::Revoked { key, file, line } => write!(
+                f,
+                "The {} key of host '{}' with fingerprint {} is marked as revoked in {}:{}.",
+                key.algorithm(), key.host_label(), key.fingerprint(), file.display(), line,
+            ),
+            HostKeyError::Unreadable { key, file, line, error } => write!(
+                f,
+                "Cannot check the host key of '{}': {}:{} cannot be read ({}). Fix or remove \
+                 that line to connect.",
+                key.host_label(), file.display(), line, error,
+            ),
+        }
+    }
+}
+
+/// The known_hosts file cokacdir records trusted hosts in (~/.cokacdir/known_hosts)
+pub fn managed_path() -> Option<PathBuf> {
+    crate::config::Settings::config_dir().map(|dir| dir.join("known_hosts"))
+}
+
+/// The known_hosts files host keys are looked up in, the user's own first
+pub fn known_hosts_files() -> Vec<PathBuf> {
+    let user = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"));
+    user.into_iter().chain(managed_path()).collect()
+}
+
+/// Check a server's host key against the given known_hosts files
+pub fn verify(files: &[PathBuf], host: &str, port: u16, key: &PublicKey) -> Result<(), HostKeyError> {
+    let host_key = || HostKey { host: host.to_string(), port, key: Box::new(key.clone()) };
+    let host_port = host_key().host_label();
+    let presented_type = key_type(key.name());
+
+    // Like ssh, a matching entry in any file is enough unless the key is revoked
+    let mut known = false;
+    let mut changed = None;
+    let mut unreadable = None;
+    for file in files {
+        // A missing file records nothing
+        let Ok(content) = std::fs::read_to_string(file) else { continue };
+        for (index, text) in content.lines().enumerate() {
+            let Some(entry) = parse_line(text) else { continue };
+            // Keys of another type neither vouch for the host nor contradict it
+            if !matches!(entry.marker, None | Some("@revoked"))
+                || key_type(entry.key_type) != presented_type
+                || !match_hosts(entry.hosts, &host_port)
+            {
+                continue;
+            }
+            let line = index + 1;
+            let recorded = match russh_keys::parse_public_key_base64(entry.key) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    unreadable.get_or_insert((file.clone(), line, e.to_string(), entry.marker.is_some()));
+                    continue;
+                }
+            };
+            match (entry.marker.is_some(), recorded == *key) {
+                (true, true) => {
+                    return Err(HostKeyError::Revoked { key: host_key(), file: file.clone(), line });
+                }
+                (false, true) => known = true,
+                (false, false) => {
+                    changed.get_or_insert((file.clone(), line));
+                }
+                (true, false) => {}
+            }
+        }
+    }
+
+    match (unreadable, changed) {
+        // An unreadable revocation could be for this key
+        (Some((file, line, error, true)), _) => Err(HostKeyError::Unreadable { key: host_key(), file, line, error }),
+        _ if known => Ok(()),
+        (_, Some((file, line))) => Err(HostKeyError::Changed { key: host_key(), file, line }),
+        (Some((file, line, error, _)), None) => Err(HostKeyError::Unreadable { key: host_key(), file, line, error }),
+        (None, None) => Err(HostKeyError::Unknown(host_key())),
+    }
+}
+
+/// The fields of a known_hosts line: `[@marker] hosts key-type base64-key`
+struct Entry<'a> {
+    marker: Option<&'a str>,
+    hosts: &'a str,
+    key_type: &'a str,
+    key: &'a str,
+}
+
+fn parse_line(text: &str) -> Option<Entry<'_>> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+        return None;
+    }
+    let mut fields = text.split_whitespace().peekable();
+    let marker = fields.next_if(|field| field.starts_with('@'));
+    Some(Entry { marker, hosts: fields.next()?, key_type: fields.next()?, key: fields.next()? })
+}
+
+/// Key type as written in known_hosts; an RSA key is named after the
+/// signature hash it was negotiated with
+fn key_type(name: &str) -> &str {
+    match name {
+        "rsa-sha2-256" | "rsa-sha2-512" => "ssh-rsa",
+        name => name,
+    }
+}
+
+/// Whether `host_port` (`host` or `[host]:port`) matches the comma separated
+/// patterns of a known_hosts line, like ssh: a matching negated pattern
+/// overrides any other match
+fn match_hosts(patterns: &str, host_port: &str) -> bool {
+    if patterns.starts_with("|1|") {
+        return match_hashed(patterns, host_port);
+    }
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        match pattern.strip_prefix('!') {
+            Some(negated) if match_glob(negated, host_port) => return false,
+            Some(_) => {}
+            None => matched |= match_glob(pattern, host_port),
+        }
+    }
+    matched
+}
+
+/// `|1|salt|hash` as written with HashKnownHosts: HMAC-SHA1 of the host
+fn match_hashed(pattern: &str, host_port: &str) -> bool {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    use hmac::{Hmac, Mac};
+
+    let mut parts = pattern["|1|".len()..].split('|');
+    let (Some(Ok(salt)), Some(Ok(hash))) = (
+        parts.next().map(|p| BASE64.decode(p)),
+        parts.next().map(|p| BASE64.decode(p)),
+    ) else {
+        return false;
+    };
+    Hmac::<sha1::Sha1>::new_from_slice(&salt)
+        .map(|mac| mac.chain_update(host_port.as_bytes()).verify_slice(&hash).is_ok())
+        .unwrap_or(false)
+}
+
+/// Case-insensitive match with `*` (any run of characters) and `?` (one character)
+fn match_glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it is tried at
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((after, tried)) = star {
+            p = after;
+            t = tried + 1;
+            star = Some((after, tried + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Record a host key the user chose to trust in cokacdir's known_hosts file
+pub fn trust(key: &HostKey) -> Result<(), String> {
+    let path = managed_path().ok_or_else(|| "Cannot determine home directory".to_string())?;
+    learn(&path, key)
+}
+
+fn learn(path: &Path, key: &HostKey) -> Result<(), String> {
+    use russh_keys::PublicKeyBase64;
+    use std::io::Write;
+
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Keep an entry appended to a hand-edited file on its own line
+        let existing = std::fs::read(path).unwrap_or_default();
+        let separator = if existing.is_empty() || existing.ends_with(b"\n") { "" } else { "\n" };
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "{}{} {} {}",
+            separator, key.host_label(), key.algorithm(), key.key.public_key_base64(),
+        )
+    };
+    write().map_err(|e| format!("Failed to save host key to {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh_keys::key::KeyPair;
+
+    fn new_key() -> PublicKey {
+        KeyPair::generate_ed25519().clone_public_key().unwrap()
+    }
+
+    #[test]
+    fn test_verify_known_hosts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let user = tmp.path().join("user_known_hosts");
+        let managed = tmp.path().join("known_hosts");
+        let files = vec![user.clone(), managed.clone()];
+        let key = new_key();
+
+        // Missing files: every host is unknown
+        let unknown = verify(&files, "example.com", 2222, &key).unwrap_err();
+        let HostKeyError::Unknown(host_key) = unknown else { panic!("expected unknown host") };
+        assert_eq!(host_key.host_label(), "[example.com]:2222");
+        assert!(host_key.fingerprint().starts_with("SHA256:"));
+
+        // Trusted on first connection, then known
+        learn(&managed, &host_key).unwrap();
+        assert!(verify(&files, "example.com", 2222, &key).is_ok());
+        assert!(matches!(verify(&files, "example.com", 22, &key), Err(HostKeyError::Unknown(_))));
+
+        // Another key for a recorded host is refused
+        let impostor = new_key();
+        match verify(&files, "example.com", 2222, &impostor) {
+            Err(HostKeyError::Changed { file, line, .. }) => assert_eq!((file, line), (managed.clone(), 1)),
+            other => panic!("expected changed key, got {:?}", other),
+        }
+
+        // A match in the user's own file is enough
+        std::fs::write(&user, "# comment").unwrap();
+        let user_key = HostKey { host: "example.com".to_string(), port: 22, key: Box::new(impostor.clone()) };
+        learn(&user, &user_key).unwrap();
+        assert_eq!(std::fs::read_to_string(&user).unwrap().lines().count(), 2);
+        assert!(verify(&files, "example.com", 22, &impostor).is_ok());
+    }
+
+    #[test]
+    fn test_verify_fails_closed() {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+        use hmac::{Hmac, Mac};
+        use russh_keys::PublicKeyBase64;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let user = tmp.path().join("user_known_hosts");
+        let managed = tmp.path().join("known_hosts");
+        let files = vec![user.clone(), managed.clone()];
+        let key = new_key();
+        let encoded = key.public_key_base64();
+
+        // A damaged line of the key's type stops the check
+        std::fs::write(&user, "# damaged\ngw.example.org ssh-ed25519 AAAAnot-a-key\n").unwrap();
+        match verify(&files, "gw.example.org", 22, &key) {
+            Err(HostKeyError::Unreadable { file, line, .. }) => assert_eq!((file, line), (user.clone(), 2)),
+            other => panic!("expected unreadable entry, got {:?}", other),
+        }
+        // Unless another line vouches for the key
+        std::fs::write(&managed, format!("gw.example.org ssh-ed25519 {}\n", encoded)).unwrap();
+        assert!(verify(&files, "gw.example.org", 22, &key).is_ok());
+
+        // Key types cokacdir cannot read are skipped like other types
+        std::fs::write(&user, "gw.example.org sk-ssh-ed25519@openssh.com AAAAunsupported\n").unwrap();
+        std::fs::remove_file(&managed).unwrap();
+        assert!(matches!(verify(&files, "gw.example.org", 22, &key), Err(HostKeyError::Unknown(_))));
+
+        // A revoked key is refused even where another file trusts it
+        std::fs::write(&managed, format!("gw.example.org ssh-ed25519 {}\n", encoded)).unwrap();
+        std::fs::write(&user, format!("@revoked * ssh-ed25519 {}\n", encoded)).unwrap();
+        match verify(&files, "gw.example.org", 22, &key) {
+            Err(HostKeyError::Revoked { file, line, .. }) => assert_eq!((file, line), (user.clone(), 1)),
+            other => panic!("expected revoked key, got {:?}", other),
+        }
+        // An unreadable revocation could be this key
+        std::fs::write(&user, "@revoked * ssh-ed25519 AAAAnot-a-key\n").unwrap();
+        assert!(matches!(verify(&files, "gw.example.org", 22, &key), Err(HostKeyError::Unreadable { .. })));
+        // Revoking another key, or a CA line, does not affect this one
+        std::fs::write(&user, format!(
+            "@revoked * ssh-ed25519 {}\n@cert-authority * ssh-ed25519 {}\n",
+            new_key().public_key_base64(), encoded,
+        )).unwrap();
+        assert!(verify(&files, "gw.example.org", 22, &key).is_ok());
+
+        // Wildcards, negation and hashed hosts
+        std::fs::remove_file(&managed).unwrap();
+        std::fs::write(&user, format!("*.example.org,!bad.example.org ssh-ed25519 {}\n", encoded)).unwrap();
+        assert!(verify(&files, "GW.example.org", 22, &key).is_ok());
+        assert!(matches!(verify(&files, "bad.example.org", 22, &key), Err(HostKeyError::Unknown(_))));
+        assert!(matches!(verify(&files, "example.org", 22, &key), Err(HostKeyError::Unknown(_))));
+
+        let salt = b"0123456789abcdefghij";
+        let hash = Hmac::<sha1::Sha1>::new_from_slice(salt).unwrap()
+            .chain_update(b"[hashed.example.org]:2222")
+            .finalize()
+            .into_bytes();
+        std::fs::write(&user, format!(
+            "|1|{}|{} ssh-ed25519 {}\n", BASE64.encode(salt), BASE64.encode(hash), encoded,
+        )).unwrap();
+        assert!(verify(&files, "hashed.example.org", 2222, &key).is_ok());
+        assert!(matches!(verify(&files, "hashed.example.org", 22, &key), Err(HostKeyError::Unknown(_))));
+    }
+}
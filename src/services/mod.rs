@@ -1,7 +1,10 @@
 pub mod file_ops;
 pub mod process;
 pub mod claude;
+pub mod known_hosts;
 pub mod remote;
 pub mod remote_transfer;
+pub mod ssh_config;
 pub mod dedup;
+pub mod similar_images;
 pub mod telegram;
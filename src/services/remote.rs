@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::path::PathBuf;
 use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Serialize};
 use tokio::runtime::Runtime;
@@ -7,6 +8,9 @@ use russh::*;
 use russh::keys::*;
 use russh_sftp::client::SftpSession as RusshSftpSession;
 
+use crate::services::known_hosts::{self, HostKeyError};
+use crate::services::ssh_config::{self, SshConfig};
+
 // Obfuscation key for password storage (NOT real encryption — prevents casual viewing only)
 const OBFUSCATION_KEY: &[u8] = b"cokacdir_remote_v1_key";
 
@@ -85,9 +89,62 @@ pub enum RemoteAuth {
         path: String,
         #[serde(default, skip_serializing_if = "Option::is_none", with = "obfuscated_option_string")]
         passphrase: Option<String>,
+        /// OpenSSH certificate for the key; `<path>-cert.pub` is used when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        certificate: Option<String>,
+    },
+    /// Keys held by the running ssh-agent (SSH_AUTH_SOCK)
+    #[serde(rename = "agent")]
+    Agent,
+    /// Keyboard-interactive login; every hidden prompt is answered with the password
+    #[serde(rename = "keyboard_interactive")]
+    KeyboardInteractive {
+        #[serde(with = "obfuscated_string")]
+        password: String,
     },
 }
 
+impl RemoteAuth {
+    /// The password typed at the server's prompts, for auth methods that use one
+    pub fn password(&self) -> Option<&str> {
+        match self {
+            RemoteAuth::Password { password } | RemoteAuth::KeyboardInteractive { password } => Some(password),
+            RemoteAuth::KeyFile { .. } | RemoteAuth::Agent => None,
+        }
+    }
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest.trim_start_matches('/'));
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// A bastion the connection to a server is tunnelled through (like `ssh -J`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JumpHost {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub user: String,
+    pub auth: RemoteAuth,
+}
+
+impl JumpHost {
+    /// `user@host` or `user@host:port`, as in `ssh -J`
+    pub fn label(&self) -> String {
+        if self.port == 22 {
+            format!("{}@{}", self.user, self.host)
+        } else {
+            format!("{}@{}:{}", self.user, self.host, self.port)
+        }
+    }
+}
+
 /// Remote server profile stored in settings.json
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteProfile {
@@ -99,6 +156,9 @@ pub struct RemoteProfile {
     pub auth: RemoteAuth,
     #[serde(default)]
     pub default_path: String,
+    /// Jump hosts to tunnel through, the first one connected to directly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<JumpHost>,
 }
 
 fn default_port() -> u16 {
@@ -139,23 +199,260 @@ impl std::fmt::Debug for RemoteContext {
     }
 }
 
-/// SSH client handler for russh
-pub(crate) struct SshHandler;
+/// Why a connection could not be established
+#[derive(Debug)]
+pub enum ConnectError {
+    /// The server's host key is not known or does not match the recorded one
+    HostKey(HostKeyError),
+    Other(String),
+}
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectError::HostKey(e) => e.fmt(f),
+            ConnectError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<String> for ConnectError {
+    fn from(msg: String) -> Self {
+        ConnectError::Other(msg)
+    }
+}
+
+impl From<russh::Error> for ConnectError {
+    fn from(e: russh::Error) -> Self {
+        ConnectError::Other(e.to_string())
+    }
+}
+
+/// SSH client handler for russh; accepts only server keys found in known_hosts
+pub(crate) struct SshHandler {
+    host: String,
+    port: u16,
+    /// The known_hosts files the server's key is looked up in
+    known_hosts: Vec<PathBuf>,
+    /// The jump host connection this one is tunnelled through, kept open as long as this session runs
+    _via: Option<client::Handle<SshHandler>>,
+}
+
+impl SshHandler {
+    /// Open the SSH connection to `profile`'s server, through its jump hosts if any.
+    /// Every hop's host key must be recorded in one of the `known_hosts` files.
+    pub(crate) async fn connect(
+        profile: &RemoteProfile,
+        config: client::Config,
+        known_hosts: &[PathBuf],
+    ) -> Result<client::Handle<SshHandler>, ConnectError> {
+        let config = Arc::new(config);
+        let mut via = None;
+        for jump in &profile.jump_hosts {
+            let mut hop = Self::open(&config, known_hosts, &jump.host, jump.port, via.take())
+                .await
+                .map_err(|e| match e {
+                    ConnectError::Other(msg) => {
+                        ConnectError::Other(format!("SSH connection to jump host {} failed: {}", jump.label(), msg))
+                    }
+                    host_key => host_key,
+                })?;
+            authenticate(&mut hop, &jump.user, &jump.auth)
+                .await
+                .map_err(|e| format!("Jump host {}: {}", jump.label(), e))?;
+            via = Some(hop);
+        }
+        Self::open(&config, known_hosts, &profile.host, profile.port, via)
+            .await
+            .map_err(|e| match e {
+                ConnectError::Other(msg) => ConnectError::Other(format!("SSH connection failed: {}", msg)),
+                host_key => host_key,
+            })
+    }
+
+    /// Connect to `host`, directly or through a direct-tcpip channel of the `via` connection
+    async fn open(
+        config: &Arc<client::Config>,
+        known_hosts: &[PathBuf],
+        host: &str,
+        port: u16,
+        via: Option<client::Handle<SshHandler>>,
+    ) -> Result<client::Handle<SshHandler>, ConnectError> {
+        let mut handler = Self {
+            host: host.to_string(),
+            port,
+            known_hosts: known_hosts.to_vec(),
+            _via: None,
+        };
+        match via {
+            None => client::connect(config.clone(), (host, port), handler).await,
+            Some(via) => {
+                let channel = via
+                    .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| format!("cannot open tunnel to {}:{}: {}", host, port, e))?;
+                handler._via = Some(via);
+                client::connect_stream(config.clone(), channel.into_stream(), handler).await
+            }
+        }
+    }
+}
 
 #[async_trait::async_trait]
 impl client::Handler for SshHandler {
-    type Error = russh::Error;
+    type Error = ConnectError;
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &key::PublicKey,
+        server_public_key: &key::PublicKey,
     ) -> Result<bool, Self::Error> {
-        // Accept all server keys (like ssh -o StrictHostKeyChecking=no)
-        // In a production app, you'd verify against known_hosts
+        known_hosts::verify(&self.known_hosts, &self.host, self.port, server_public_key)
+            .map_err(ConnectError::HostKey)?;
         Ok(true)
     }
 }
 
+/// Log in to an open SSH connection
+pub(crate) async fn authenticate(
+    ssh: &mut client::Handle<SshHandler>,
+    user: &str,
+    auth: &RemoteAuth,
+) -> Result<(), String> {
+    let accepted = match auth {
+        RemoteAuth::Password { password } => {
+            ssh.authenticate_password(user, password)
+                .await
+                .map_err(|e| format!("Password auth failed: {}", e))?
+        }
+        RemoteAuth::KeyFile { path, passphrase, certificate } => {
+            let key_path = expand_home(path);
+            let key_pair = russh_keys::load_secret_key(&key_path, passphrase.as_deref())
+                .map(Arc::new)
+                .map_err(|e| format!("Failed to load key: {}", e))?;
+
+            // Like ssh, pick up `<key>-cert.pub` next to the key
+            let explicit = certificate.is_some();
+            let cert_path = match certificate {
+                Some(cert) => Some(expand_home(cert)),
+                None => {
+                    let mut default = key_path.into_os_string();
+                    default.push("-cert.pub");
+                    Some(PathBuf::from(default)).filter(|p| p.is_file())
+                }
+            };
+
+            let cert_result = match cert_path {
+                Some(cert_path) => {
+                    let result = match russh_keys::load_openssh_certificate(&cert_path) {
+                        Ok(cert) => ssh.authenticate_openssh_cert(user, key_pair.clone(), cert)
+                            .await
+                            .map_err(|e| format!("Certificate auth failed: {}", e)),
+                        Err(e) => Err(format!("Failed to load certificate {}: {}", cert_path.display(), e)),
+                    };
+                    Some(result)
+                }
+                None => None,
+            };
+
+            match cert_result {
+                Some(result) if explicit => result?,
+                Some(Ok(true)) => true,
+                // An auto-detected certificate that fails falls back to the bare key, as ssh does
+                _ => {
+                    ssh.authenticate_publickey(user, key_pair)
+                        .await
+                        .map_err(|e| format!("Key auth failed: {}", e))?
+                }
+            }
+        }
+        RemoteAuth::Agent => authenticate_agent(ssh, user).await?,
+        RemoteAuth::KeyboardInteractive { password } => {
+            authenticate_keyboard_interactive(ssh, user, password).await?
+        }
+    };
+
+    if !accepted {
+        return Err("Authentication rejected by server".to_string());
+    }
+    Ok(())
+}
+
+/// Try each identity of the running ssh-agent in turn
+async fn authenticate_agent(ssh: &mut client::Handle<SshHandler>, user: &str) -> Result<bool, String> {
+    if std::env::var_os("SSH_AUTH_SOCK").is_none() {
+        return Err("No ssh-agent available (SSH_AUTH_SOCK is not set)".to_string());
+    }
+    let mut agent = russh_keys::agent::client::AgentClient::connect_env()
+        .await
+        .map_err(|e| format!("Failed to connect to ssh-agent: {}", e))?;
+    let identities = agent.request_identities()
+        .await
+        .map_err(|e| format!("Failed to list ssh-agent keys: {}", e))?;
+    if identities.is_empty() {
+        return Err("The ssh-agent holds no keys (add one with ssh-add)".to_string());
+    }
+
+    for key in identities {
+        let (returned, result) = ssh.authenticate_future(user, key, agent).await;
+        agent = returned;
+        if result.map_err(|e| format!("Agent auth failed: {}", e))? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Keyboard-interactive rounds beyond this are treated as a failed login
+const MAX_KEYBOARD_INTERACTIVE_ROUNDS: usize = 5;
+
+/// Answer the server's keyboard-interactive prompts with the stored password.
+/// Only a hidden password prompt is answered, once; anything else (a one-time
+/// code, a push approval, a new password) needs a person to answer it.
+async fn authenticate_keyboard_interactive(
+    ssh: &mut client::Handle<SshHandler>,
+    user: &str,
+    password: &str,
+) -> Result<bool, String> {
+    use russh::client::KeyboardInteractiveAuthResponse as Response;
+
+    let fail = |e: russh::Error| format!("Keyboard-interactive auth failed: {}", e);
+    let mut response = ssh.authenticate_keyboard_interactive_start(user, None::<String>)
+        .await
+        .map_err(fail)?;
+    let mut password_sent = false;
+    for _ in 0..MAX_KEYBOARD_INTERACTIVE_ROUNDS {
+        let prompts = match response {
+            Response::Success => return Ok(true),
+            Response::Failure => return Ok(false),
+            Response::InfoRequest { prompts, .. } => prompts,
+        };
+        let answers = keyboard_interactive_answers(&prompts, password, &mut password_sent)?;
+        response = ssh.authenticate_keyboard_interactive_respond(answers)
+            .await
+            .map_err(fail)?;
+    }
+    Ok(matches!(response, Response::Success))
+}
+
+/// Answers to one round of keyboard-interactive prompts. Rounds without
+/// prompts (messages only) get an empty answer.
+fn keyboard_interactive_answers(
+    prompts: &[client::Prompt],
+    password: &str,
+    password_sent: &mut bool,
+) -> Result<Vec<String>, String> {
+    prompts.iter()
+        .map(|p| {
+            if !p.echo && !*password_sent && p.prompt.to_lowercase().contains("password") {
+                *password_sent = true;
+                Ok(password.to_string())
+            } else {
+                Err(format!("Server asked '{}', an interactive prompt which is not supported", p.prompt.trim()))
+            }
+        })
+        .collect()
+}
+
 /// SFTP session wrapper around russh
 pub struct SftpSession {
     runtime: Runtime,
@@ -173,12 +470,22 @@ impl std::fmt::Debug for SftpSession {
 
 impl SftpSession {
     /// Connect to remote host via SSH and open SFTP channel
-    pub fn connect(profile: &RemoteProfile) -> Result<Self, String> {
+    pub fn connect(profile: &RemoteProfile) -> Result<Self, ConnectError> {
+        Self::connect_checked(profile, &known_hosts::known_hosts_files())
+    }
+
+    /// Connect trusting only the host keys in `known_hosts`, e.g. a throwaway test sshd's
+    #[cfg(test)]
+    pub(crate) fn connect_with_known_hosts(profile: &RemoteProfile, known_hosts: &[PathBuf]) -> Result<Self, ConnectError> {
+        Self::connect_checked(profile, known_hosts)
+    }
+
+    fn connect_checked(profile: &RemoteProfile, known_hosts: &[PathBuf]) -> Result<Self, ConnectError> {
         let runtime = Runtime::new().map_err(|e| format!("Failed to create runtime: {}", e))?;
 
         let profile = profile.clone();
         let (ssh_handle, sftp) = runtime.block_on(async {
-            Self::connect_async(&profile).await
+            Self::connect_async(&profile, known_hosts).await
         })?;
 
         Ok(Self {
@@ -190,7 +497,8 @@ impl SftpSession {
 
     async fn connect_async(
         profile: &RemoteProfile,
-    ) -> Result<(client::Handle<SshHandler>, RusshSftpSession), String> {
+        known_hosts: &[PathBuf],
+    ) -> Result<(client::Handle<SshHandler>, RusshSftpSession), ConnectError> {
         let config = client::Config {
             inactivity_timeout: Some(std::time::Duration::from_secs(300)),
             keepalive_interval: Some(std::time::Duration::from_secs(30)),
@@ -198,45 +506,9 @@ impl SftpSession {
             ..Default::default()
         };
 
-        let mut ssh = client::connect(Arc::new(config), (profile.host.as_str(), profile.port), SshHandler)
-            .await
-            .map_err(|e| format!("SSH connection failed: {}", e))?;
-
-        // Authenticate
-        let auth_result = match &profile.auth {
-            RemoteAuth::Password { password } => {
-                ssh.authenticate_password(&profile.user, password)
-                    .await
-                    .map_err(|e| format!("Password auth failed: {}", e))?
-            }
-            RemoteAuth::KeyFile { path, passphrase } => {
-                let key_path = if path.starts_with('~') {
-                    if let Some(home) = dirs::home_dir() {
-                        home.join(path.trim_start_matches('~').trim_start_matches('/'))
-                    } else {
-                        std::path::PathBuf::from(path)
-                    }
-                } else {
-                    std::path::PathBuf::from(path)
-                };
+        let mut ssh = SshHandler::connect(profile, config, known_hosts).await?;
 
-                let key_pair = if let Some(pass) = passphrase {
-                    russh_keys::load_secret_key(&key_path, Some(pass))
-                        .map_err(|e| format!("Failed to load key: {}", e))?
-                } else {
-                    russh_keys::load_secret_key(&key_path, None)
-                        .map_err(|e| format!("Failed to load key: {}", e))?
-                };
-
-                ssh.authenticate_publickey(&profile.user, Arc::new(key_pair))
-                    .await
-                    .map_err(|e| format!("Key auth failed: {}", e))?
-            }
-        };
-
-        if !auth_result {
-            return Err("Authentication rejected by server".to_string());
-        }
+        authenticate(&mut ssh, &profile.user, &profile.auth).await?;
 
         // Open SFTP channel
         let channel = ssh
@@ -365,6 +637,18 @@ impl SftpSession {
         })
     }
 
+    /// Read the target of a symlink via SFTP
+    pub fn read_link(&self, path: &str) -> Result<String, String> {
+        let sftp = self.sftp.as_ref().ok_or("Not connected")?;
+        let path = path.to_string();
+
+        self.runtime.block_on(async {
+            sftp.read_link(&path)
+                .await
+                .map_err(|e| format!("Failed to read link '{}': {}", path, e))
+        })
+    }
+
     /// Create directory via SFTP
     pub fn mkdir(&self, path: &str) -> Result<(), String> {
         let sftp = self.sftp.as_ref().ok_or("Not connected")?;
@@ -528,17 +812,23 @@ impl Drop for SftpSession {
     }
 }
 
-/// Parse user@host:/path format
+/// Parse user@host:/path format (or alias:/path for a ~/.ssh/config Host alias,
+/// which is resolved to its HostName, User and Port)
 /// Returns (user, host, port, path) if matched
 pub fn parse_remote_path(input: &str) -> Option<(String, String, u16, String)> {
-    // Format: user@host:/path or user@host:port:/path
-    let at_pos = input.find('@')?;
-    let user = input[..at_pos].to_string();
-    if user.is_empty() {
+    parse_remote_path_with(input, &ssh_config::user_config())
+}
+
+fn parse_remote_path_with(input: &str, config: &SshConfig) -> Option<(String, String, u16, String)> {
+    // Format: user@host:/path or user@host:port:/path; the user may be left out for an alias
+    let (user, after_at) = match input.find('@') {
+        Some(at_pos) if !input[..at_pos].contains([':', '/']) => (Some(&input[..at_pos]), &input[at_pos + 1..]),
+        _ => (None, input),
+    };
+    if user == Some("") {
         return None;
     }
 
-    let after_at = &input[at_pos + 1..];
     let colon_pos = after_at.find(':')?;
     let host_part = &after_at[..colon_pos];
     let after_first_colon = &after_at[colon_pos + 1..];
@@ -548,19 +838,31 @@ pub fn parse_remote_path(input: &str) -> Option<(String, String, u16, String)> {
         let port_str = &after_first_colon[..second_colon];
         if let Ok(port) = port_str.parse::<u16>() {
             let path = &after_first_colon[second_colon + 1..];
-            (host_part.to_string(), port, if path.is_empty() { "/".to_string() } else { path.to_string() })
+            (host_part.to_string(), Some(port), if path.is_empty() { "/".to_string() } else { path.to_string() })
         } else {
             // Not a port number, treat entire after_first_colon as path
-            (host_part.to_string(), 22, if after_first_colon.is_empty() { "/".to_string() } else { after_first_colon.to_string() })
+            (host_part.to_string(), None, if after_first_colon.is_empty() { "/".to_string() } else { after_first_colon.to_string() })
         }
     } else {
-        (host_part.to_string(), 22, if after_first_colon.is_empty() { "/".to_string() } else { after_first_colon.to_string() })
+        (host_part.to_string(), None, if after_first_colon.is_empty() { "/".to_string() } else { after_first_colon.to_string() })
     };
 
     if host.is_empty() {
         return None;
     }
 
+    // Without a user only a configured alias is taken as remote, so local paths with ':' stay local
+    if user.is_none() && !config.is_alias(&host) {
+        return None;
+    }
+    let resolved = config.lookup(&host);
+    let user = match user {
+        Some(user) => user.to_string(),
+        None => resolved.user.or_else(ssh_config::local_user)?,
+    };
+    let port = port.or(resolved.port).unwrap_or(22);
+    let host = resolved.host_name.unwrap_or(host);
+
     // Ensure path starts with /
     let path = if path.starts_with('/') { path } else { format!("/{}", path) };
 
@@ -604,29 +906,92 @@ pub fn find_matching_profile<'a>(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_keyboard_interactive_answers() {
+        let prompt = |prompt: &str, echo: bool| client::Prompt { prompt: prompt.to_string(), echo };
+        let mut sent = false;
+
+        // Messages only, then the password prompt once
+        assert_eq!(keyboard_interactive_answers(&[], "pw", &mut sent), Ok(Vec::new()));
+        assert_eq!(keyboard_interactive_answers(&[prompt("Password: ", false)], "pw", &mut sent), Ok(vec!["pw".to_string()]));
+        assert!(sent);
+
+        // A changed password asks again; it is not answered with the old one
+        let err = keyboard_interactive_answers(&[prompt("New password: ", false)], "pw", &mut sent).unwrap_err();
+        assert!(err.contains("'New password:'"), "{}", err);
+
+        // One-time codes and visible questions are never answered
+        let mut sent = false;
+        for p in [prompt("Verification code: ", false), prompt("Duo passcode or option (1-3): ", true), prompt("Password: ", true)] {
+            assert!(keyboard_interactive_answers(std::slice::from_ref(&p), "pw", &mut sent).is_err());
+        }
+        assert!(!sent);
+        // Nor a second password prompt in the same round
+        let pair = [prompt("Password: ", false), prompt("Password again: ", false)];
+        assert!(keyboard_interactive_answers(&pair, "pw", &mut sent).is_err());
+    }
+
     #[test]
     fn test_parse_remote_path_basic() {
-        let result = parse_remote_path("user@host:/home/user");
+        let result = parse_remote_path_with("user@host:/home/user", &SshConfig::default());
         assert_eq!(result, Some(("user".to_string(), "host".to_string(), 22, "/home/user".to_string())));
     }
 
     #[test]
     fn test_parse_remote_path_with_port() {
-        let result = parse_remote_path("admin@server:2222:/var/log");
+        let result = parse_remote_path_with("admin@server:2222:/var/log", &SshConfig::default());
         assert_eq!(result, Some(("admin".to_string(), "server".to_string(), 2222, "/var/log".to_string())));
     }
 
     #[test]
     fn test_parse_remote_path_no_path() {
-        let result = parse_remote_path("user@host:");
+        let result = parse_remote_path_with("user@host:", &SshConfig::default());
         assert_eq!(result, Some(("user".to_string(), "host".to_string(), 22, "/".to_string())));
     }
 
     #[test]
     fn test_parse_remote_path_invalid() {
-        assert!(parse_remote_path("just/a/path").is_none());
-        assert!(parse_remote_path("@host:/path").is_none());
-        assert!(parse_remote_path("user@:/path").is_none());
+        let none = SshConfig::default();
+        assert!(parse_remote_path_with("just/a/path", &none).is_none());
+        assert!(parse_remote_path_with("@host:/path", &none).is_none());
+        assert!(parse_remote_path_with("user@:/path", &none).is_none());
+        assert!(parse_remote_path_with("host:/path", &none).is_none());
+    }
+
+    #[test]
+    fn test_parse_remote_path_ssh_alias() {
+        let config = SshConfig::parse(
+            "Host prod\n  HostName prod.example.com\n  User deploy\n  Port 2200\n",
+            std::path::Path::new("/nonexistent"),
+        );
+        let result = parse_remote_path_with("prod:/srv", &config);
+        assert_eq!(result, Some(("deploy".to_string(), "prod.example.com".to_string(), 2200, "/srv".to_string())));
+        // Explicit user and port win over the config
+        let result = parse_remote_path_with("root@prod:22:/", &config);
+        assert_eq!(result, Some(("root".to_string(), "prod.example.com".to_string(), 22, "/".to_string())));
+        // A local path that merely contains ':' is not remote
+        assert!(parse_remote_path_with("notes:draft", &config).is_none());
+    }
+
+    #[test]
+    fn test_profile_jump_hosts_serde() {
+        // Profiles saved before jump hosts existed still load
+        let json = r#"{"name":"a","host":"h","user":"u","auth":{"type":"agent"}}"#;
+        let mut profile: RemoteProfile = serde_json::from_str(json).unwrap();
+        assert!(profile.jump_hosts.is_empty());
+        assert!(!serde_json::to_string(&profile).unwrap().contains("jump_hosts"));
+
+        profile.jump_hosts.push(JumpHost {
+            host: "bastion".to_string(),
+            port: 2222,
+            user: "jump".to_string(),
+            auth: RemoteAuth::Password { password: "secret".to_string() },
+        });
+        let saved = serde_json::to_string(&profile).unwrap();
+        assert!(!saved.contains("secret"));
+        let loaded: RemoteProfile = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.jump_hosts[0].label(), "jump@bastion:2222");
+        assert_eq!(loaded.jump_hosts[0].auth.password(), Some("secret"));
     }
 
     #[test]
@@ -1,6 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc::Sender, Arc};
 use std::io::BufReader;
 
@@ -8,7 +8,9 @@ use tokio::runtime::Runtime;
 use russh::{client, ChannelMsg, Disconnect};
 
 use crate::services::file_ops::ProgressMessage;
-use crate::services::remote::{RemoteAuth, RemoteProfile, SshHandler};
+use crate::services::known_hosts;
+use crate::services::remote::{self, RemoteAuth, RemoteProfile, SshHandler};
+use crate::services::ssh_config;
 
 /// Transfer direction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -64,51 +66,13 @@ impl SshExec {
                 ..Default::default()
             };
 
-            let mut ssh = client::connect(
-                Arc::new(config),
-                (profile.host.as_str(), profile.port),
-                SshHandler,
-            )
-            .await
-            .map_err(|e| format!("SSH connection failed: {}", e))?;
-
-            // Authenticate
-            let auth_result = match &profile.auth {
-                RemoteAuth::Password { password } => {
-                    ssh.authenticate_password(&profile.user, password)
-                        .await
-                        .map_err(|e| format!("Password auth failed: {}", e))?
-                }
-                RemoteAuth::KeyFile { path, passphrase } => {
-                    let key_path = if path.starts_with('~') {
-                        if let Some(home) = dirs::home_dir() {
-                            home.join(path.trim_start_matches('~').trim_start_matches('/'))
-                        } else {
-                            PathBuf::from(path)
-                        }
-                    } else {
-                        PathBuf::from(path)
-                    };
-
-                    let key_pair = if let Some(pass) = passphrase {
-                        russh_keys::load_secret_key(&key_path, Some(pass))
-                            .map_err(|e| format!("Failed to load key: {}", e))?
-                    } else {
-                        russh_keys::load_secret_key(&key_path, None)
-                            .map_err(|e| format!("Failed to load key: {}", e))?
-                    };
-
-                    ssh.authenticate_publickey(&profile.user, Arc::new(key_pair))
-                        .await
-                        .map_err(|e| format!("Key auth failed: {}", e))?
-                }
-            };
+            let mut ssh = SshHandler::connect(&profile, config, &known_hosts::known_hosts_files())
+                .await
+                .map_err(|e| e.to_string())?;
 
-            if !auth_result {
-                return Err("Authentication rejected by server".to_string());
-            }
+            remote::authenticate(&mut ssh, &profile.user, &profile.auth).await?;
 
-            Ok(ssh)
+            Ok::<_, String>(ssh)
         })?;
 
         Ok(Self { runtime, handle })
@@ -173,35 +137,124 @@ fn has_sshpass() -> bool {
         .unwrap_or(false)
 }
 
-/// Build SSH command option string for rsync
-fn build_ssh_option(profile: &RemoteProfile) -> String {
-    let mut ssh_cmd = String::from("ssh");
+/// Build SSH command option string for rsync.
+/// `jump_config` is the file written by create_jump_config() for a profile with jump hosts.
+fn build_ssh_option(profile: &RemoteProfile, jump_config: Option<&Path>) -> String {
+    let mut args: Vec<String> = vec!["ssh".to_string()];
+
+    // Jump hosts; ssh passes only -F on to the ssh it starts for each hop
+    if !profile.jump_hosts.is_empty() {
+        if let Some(path) = jump_config {
+            args.extend(["-F".to_string(), path.display().to_string()]);
+        }
+        let hops: Vec<String> = profile.jump_hosts.iter().map(|j| j.label()).collect();
+        args.extend(["-J".to_string(), hops.join(",")]);
+    }
 
     // Port
     if profile.port != 22 {
-        ssh_cmd.push_str(&format!(" -p {}", profile.port));
+        args.extend(["-p".to_string(), profile.port.to_string()]);
     }
 
-    // Key file
-    if let RemoteAuth::KeyFile { ref path, .. } = profile.auth {
-        let expanded = if path.starts_with('~') {
-            if let Some(home) = dirs::home_dir() {
-                home.join(path.trim_start_matches('~').trim_start_matches('/'))
-                    .display()
-                    .to_string()
-            } else {
-                path.clone()
+    // Key file and certificate; agent keys are picked up by ssh from SSH_AUTH_SOCK
+    if let RemoteAuth::KeyFile { ref path, ref certificate, .. } = profile.auth {
+        args.extend(["-i".to_string(), remote::expand_home(path).display().to_string()]);
+        if let Some(cert) = certificate {
+            args.extend(["-o".to_string(), format!("CertificateFile={}", remote::expand_home(cert).display())]);
+        }
+    }
+
+    // Same host key policy as the SFTP connection: only keys recorded in the
+    // known_hosts files are accepted, and ssh must not prompt or learn new ones
+    args.extend([
+        "-o".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+        "-o".to_string(),
+        format!("UserKnownHostsFile={}", known_hosts_option(&known_hosts::known_hosts_files())),
+        "-o".to_string(),
+        "LogLevel=ERROR".to_string(),
+    ]);
+
+    args.iter().map(|arg| rsync_quote(arg)).collect::<Vec<_>>().join(" ")
+}
+
+/// Quote one word of rsync's `-e` command when it needs it. rsync splits
+/// that string itself, not through a shell: inside quotes a doubled quote
+/// character stands for itself, and a backslash has no special meaning.
+fn rsync_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.,:/=@+%".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "''"))
+    }
+}
+
+/// The known_hosts files as one UserKnownHostsFile value. ssh splits the value
+/// on whitespace, so each path is double-quoted to survive spaces in it.
+fn known_hosts_option(files: &[PathBuf]) -> String {
+    let files: Vec<String> = files
+        .iter()
+        .map(|path| {
+            let path = path.display().to_string().replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{}\"", path)
+        })
+        .collect();
+    files.join(" ")
+}
+
+/// ssh config for the hops of `ssh -J`: each hop's key file and the host key
+/// policy of build_ssh_option(), followed by the user's own ~/.ssh/config
+fn jump_config(profile: &RemoteProfile) -> String {
+    let mut config = String::new();
+    for jump in &profile.jump_hosts {
+        if let RemoteAuth::KeyFile { ref path, ref certificate, .. } = jump.auth {
+            config.push_str(&format!("Host {}\n", jump.host));
+            config.push_str(&format!("    IdentityFile \"{}\"\n", remote::expand_home(path).display()));
+            if let Some(cert) = certificate {
+                config.push_str(&format!("    CertificateFile \"{}\"\n", remote::expand_home(cert).display()));
             }
-        } else {
-            path.clone()
-        };
-        ssh_cmd.push_str(&format!(" -i '{}'", expanded));
+        }
     }
+    config.push_str("Host *\n    StrictHostKeyChecking yes\n    LogLevel ERROR\n");
+    config.push_str(&format!("    UserKnownHostsFile {}\n", known_hosts_option(&known_hosts::known_hosts_files())));
+    if let Some(user_config) = ssh_config::user_config_path().filter(|p| p.is_file()) {
+        config.push_str(&format!("    Include \"{}\"\n", user_config.display()));
+    }
+    config
+}
 
-    // Disable strict host key checking for convenience
-    ssh_cmd.push_str(" -o StrictHostKeyChecking=no -o UserKnownHostsFile=/dev/null -o LogLevel=ERROR");
+/// A path under ~/.cokacdir/tmp (created if missing) that no other transfer
+/// uses, in this process or another one
+fn tmp_path(prefix: &str) -> Result<PathBuf, String> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
 
-    ssh_cmd
+    let tmp_dir = dirs::home_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(".cokacdir")
+        .join("tmp");
+    std::fs::create_dir_all(&tmp_dir)
+        .map_err(|e| format!("Failed to create tmp dir: {}", e))?;
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    Ok(tmp_dir.join(format!("{}_{}_{}", prefix, std::process::id(), n)))
+}
+
+/// Temporary file under ~/.cokacdir/tmp, removed when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(prefix: &str, content: &str) -> Result<Self, String> {
+        let path = tmp_path(prefix)?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
 
 /// Build remote path string for rsync: user@host:/path
@@ -215,19 +268,9 @@ fn build_remote_spec(profile: &RemoteProfile, path: &str) -> String {
 
 /// Create a temporary SSH_ASKPASS script for password authentication.
 /// Returns the script path. Caller must clean up with cleanup_askpass_script().
-fn create_askpass_script(password: &str) -> Result<PathBuf, String> {
-    let tmp_dir = dirs::home_dir()
-        .unwrap_or_else(std::env::temp_dir)
-        .join(".cokacdir")
-        .join("tmp");
-    std::fs::create_dir_all(&tmp_dir)
-        .map_err(|e| format!("Failed to create tmp dir: {}", e))?;
-
-    let script_path = tmp_dir.join(format!("askpass_{}", std::process::id()));
-
-    // Escape single quotes in password: replace ' with '\''
-    let escaped = password.replace('\'', "'\\''");
-    let content = format!("#!/bin/sh\necho '{}'\n", escaped);
+fn create_askpass_script(profile: &RemoteProfile) -> Result<PathBuf, String> {
+    let script_path = tmp_path("askpass")?;
+    let content = askpass_script(profile);
 
     std::fs::write(&script_path, content)
         .map_err(|e| format!("Failed to create askpass script: {}", e))?;
@@ -242,6 +285,36 @@ fn create_askpass_script(password: &str) -> Result<PathBuf, String> {
     Ok(script_path)
 }
 
+/// Askpass script answering the password prompts of each jump host with its
+/// password; the server's own is the default. ssh asks "user@host's password: "
+/// (host in lower case), or "(user@host) ..." for a keyboard-interactive prompt,
+/// so whole prompts are matched and "ops@gw" never answers for "ops@gw2".
+/// Other prompts of a jump host get no answer rather than the server's password.
+fn askpass_script(profile: &RemoteProfile) -> String {
+    // Escape single quotes: replace ' with '\''
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "'\\''"));
+    let mut content = String::from("#!/bin/sh\ncase \"$1\" in\n");
+    for jump in &profile.jump_hosts {
+        let who = format!("{}@{}", jump.user, jump.host.to_lowercase());
+        let password_prompt = quote(&format!("{}'s password: ", who));
+        let any_prompt = format!("{}*", quote(&format!("({}) ", who)));
+        match jump.auth.password() {
+            Some(password) => {
+                content.push_str(&format!(
+                    "    {}|{}[Pp]assword*) echo {} ;;\n", password_prompt, any_prompt, quote(password),
+                ));
+                content.push_str(&format!("    {}) exit 1 ;;\n", any_prompt));
+            }
+            None => content.push_str(&format!("    {}|{}) exit 1 ;;\n", password_prompt, any_prompt)),
+        }
+    }
+    if let Some(password) = profile.auth.password() {
+        content.push_str(&format!("    *) echo {} ;;\n", quote(password)));
+    }
+    content.push_str("esac\n");
+    content
+}
+
 /// Remove the temporary askpass script
 fn cleanup_askpass_script(path: &PathBuf) {
     let _ = std::fs::remove_file(path);
@@ -255,19 +328,22 @@ fn transfer_rsync(
     cancel_flag: &Arc<AtomicBool>,
     tx: &Sender<ProgressMessage>,
 ) -> Result<(), String> {
-    let ssh_option = build_ssh_option(&config.profile);
+    let jump_config = if config.profile.jump_hosts.is_empty() {
+        None
+    } else {
+        Some(TempFile::create("ssh_config", &jump_config(&config.profile))?)
+    };
+    let ssh_option = build_ssh_option(&config.profile, jump_config.as_ref().map(|f| f.0.as_path()));
     let total_files = config.source_files.len();
     let mut completed_files: usize = 0;
 
-    // Prepare password auth mechanism
-    let needs_password = matches!(&config.profile.auth, RemoteAuth::Password { .. });
-    let use_sshpass = needs_password && has_sshpass();
+    // Prepare password auth mechanism; sshpass has one password for every prompt,
+    // so jump hosts with passwords of their own need the askpass script
+    let jump_passwords = config.profile.jump_hosts.iter().any(|j| j.auth.password().is_some());
+    let needs_password = config.profile.auth.password().is_some() || jump_passwords;
+    let use_sshpass = needs_password && !jump_passwords && has_sshpass();
     let askpass_script = if needs_password && !use_sshpass {
-        if let RemoteAuth::Password { ref password } = config.profile.auth {
-            Some(create_askpass_script(password)?)
-        } else {
-            None
-        }
+        Some(create_askpass_script(&config.profile)?)
     } else {
         None
     };
@@ -306,7 +382,7 @@ fn transfer_rsync(
 
         // Apply password auth
         let mut cmd = if use_sshpass {
-            if let RemoteAuth::Password { ref password } = config.profile.auth {
+            if let Some(password) = config.profile.auth.password() {
                 let mut sshpass_cmd = Command::new("sshpass");
                 sshpass_cmd.arg("-p").arg(password);
                 let program = cmd.get_program().to_string_lossy().to_string();
@@ -788,3 +864,240 @@ pub fn transfer_remote_to_remote_with_progress(
         }
     }
 }
+
+/// One directory's worth of files for `transfer_groups_with_progress`
+#[derive(Debug, Clone)]
+pub struct TransferGroup {
+    pub source_base: String,
+    pub target_path: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Run several transfers (one per source directory) as a single operation.
+/// `None` profiles mean local. Progress of every group is forwarded to `tx` with
+/// totals across all groups, followed by one combined `Completed`.
+pub fn transfer_groups_with_progress(
+    source_profile: Option<RemoteProfile>,
+    target_profile: Option<RemoteProfile>,
+    groups: Vec<TransferGroup>,
+    cancel_flag: Arc<AtomicBool>,
+    tx: Sender<ProgressMessage>,
+) {
+    let total_files: usize = groups.iter().map(|g| g.files.len()).sum();
+    let mut done_files = 0;
+    let mut success = 0;
+    let mut failure = 0;
+
+    for group in groups {
+        if cancel_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        let group_files = group.files.len();
+        let (group_tx, group_rx) = std::sync::mpsc::channel();
+        let cancel = cancel_flag.clone();
+        let source = source_profile.clone();
+        let target = target_profile.clone();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || match (source, target) {
+                (Some(source), Some(target)) => transfer_remote_to_remote_with_progress(
+                    source,
+                    target,
+                    group.files,
+                    group.source_base,
+                    group.target_path,
+                    cancel,
+                    group_tx,
+                    false,
+                ),
+                (source, target) => {
+                    let (direction, profile) = match (source, target) {
+                        (Some(p), None) => (TransferDirection::RemoteToLocal, p),
+                        (None, Some(p)) => (TransferDirection::LocalToRemote, p),
+                        _ => {
+                            let _ = group_tx.send(ProgressMessage::Error(
+                                String::new(),
+                                "Transfer needs a remote side".to_string(),
+                            ));
+                            let _ = group_tx.send(ProgressMessage::Completed(0, group_files));
+                            return;
+                        }
+                    };
+                    let config = TransferConfig {
+                        direction,
+                        profile,
+                        source_files: group.files,
+                        source_base: group.source_base,
+                        target_path: group.target_path,
+                    };
+                    transfer_files_with_progress(config, cancel, group_tx, false, None);
+                }
+            });
+
+            for msg in group_rx {
+                let msg = match msg {
+                    ProgressMessage::Completed(s, f) => {
+                        success += s;
+                        failure += f;
+                        continue;
+                    }
+                    ProgressMessage::TotalProgress(files, _, bytes, total_bytes) => {
+                        ProgressMessage::TotalProgress(done_files + files, total_files, bytes, total_bytes)
+                    }
+                    other => other,
+                };
+                let _ = tx.send(msg);
+            }
+        });
+        done_files += group_files;
+    }
+
+    let _ = tx.send(ProgressMessage::Completed(success, failure));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::remote::JumpHost;
+
+    fn profile_with_jumps() -> RemoteProfile {
+        RemoteProfile {
+            name: "app".to_string(),
+            host: "10.0.0.7".to_string(),
+            port: 22,
+            user: "deploy".to_string(),
+            auth: RemoteAuth::Password { password: "it's".to_string() },
+            default_path: String::new(),
+            jump_hosts: vec![
+                JumpHost {
+                    host: "bastion.example.com".to_string(),
+                    port: 2222,
+                    user: "jump".to_string(),
+                    auth: RemoteAuth::KeyFile {
+                        path: "/keys/bastion".to_string(),
+                        passphrase: None,
+                        certificate: None,
+                    },
+                },
+                JumpHost {
+                    host: "gw".to_string(),
+                    port: 22,
+                    user: "ops".to_string(),
+                    auth: RemoteAuth::Password { password: "gw-secret".to_string() },
+                },
+            ],
+        }
+    }
+
+    /// Split an `-e` command the way rsync does (see `rsync_quote`)
+    fn rsync_split(command: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut chars = command.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c == ' ' {
+                chars.next();
+                continue;
+            }
+            let mut word = String::new();
+            let mut quote = None;
+            while let Some(c) = chars.next() {
+                match quote {
+                    None if c == ' ' => break,
+                    None if c == '\'' || c == '"' => quote = Some(c),
+                    Some(q) if c == q => {
+                        if chars.peek() == Some(&q) {
+                            word.push(q);
+                            chars.next();
+                        } else {
+                            quote = None;
+                        }
+                    }
+                    _ => word.push(c),
+                }
+            }
+            assert_eq!(quote, None, "unterminated quote in {}", command);
+            words.push(word);
+        }
+        words
+    }
+
+    #[test]
+    fn test_temp_files_are_unique() {
+        let first = TempFile::create("test", "a").unwrap();
+        let second = TempFile::create("test", "b").unwrap();
+        assert_ne!(first.0, second.0);
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_to_string(&second.0).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_jump_hosts_for_rsync() {
+        let profile = profile_with_jumps();
+
+        let option = build_ssh_option(&profile, Some(Path::new("/tmp/cfg")));
+        assert!(option.starts_with("ssh -F /tmp/cfg -J jump@bastion.example.com:2222,ops@gw "));
+
+        // Every known_hosts path is quoted on its own
+        let files = known_hosts::known_hosts_files();
+        let quoted: Vec<String> = files.iter().map(|f| format!("\"{}\"", f.display())).collect();
+        let words = rsync_split(&option);
+        assert!(words.contains(&format!("UserKnownHostsFile={}", quoted.join(" "))));
+
+        // Paths with quotes survive rsync's own splitting of the -e string
+        let mut keyed = profile.clone();
+        keyed.auth = RemoteAuth::KeyFile {
+            path: "/home/o'neil/my key".to_string(),
+            passphrase: None,
+            certificate: Some("/home/o'neil/key-cert.pub".to_string()),
+        };
+        let words = rsync_split(&build_ssh_option(&keyed, Some(Path::new("/tmp/it's/cfg"))));
+        assert_eq!(&words[..3], ["ssh", "-F", "/tmp/it's/cfg"]);
+        assert!(words.windows(2).any(|w| w == ["-i", "/home/o'neil/my key"]));
+        assert!(words.windows(2).any(|w| w == ["-o", "CertificateFile=/home/o'neil/key-cert.pub"]));
+        let known = known_hosts_option(&[PathBuf::from("/home/o'neil/.ssh/known_hosts"), PathBuf::from("/a b")]);
+        assert_eq!(
+            rsync_split(&rsync_quote(&format!("UserKnownHostsFile={}", known))),
+            ["UserKnownHostsFile=\"/home/o'neil/.ssh/known_hosts\" \"/a b\""]
+        );
+
+        let config = jump_config(&profile);
+        assert!(config.contains(&format!("    UserKnownHostsFile {}\n", quoted.join(" "))));
+        assert!(config.starts_with("Host bastion.example.com\n    IdentityFile \"/keys/bastion\"\nHost *\n"));
+        assert!(config.contains("StrictHostKeyChecking yes"));
+
+        let script = askpass_script(&profile);
+        assert!(script.contains("'ops@gw'\\''s password: '|'(ops@gw) '*[Pp]assword*) echo 'gw-secret' ;;"));
+        assert!(script.contains("*) echo 'it'\\''s' ;;"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_askpass_matches_whole_prompt() {
+        let mut profile = profile_with_jumps();
+        profile.jump_hosts.push(JumpHost {
+            host: "GW2".to_string(),
+            port: 22,
+            user: "ops".to_string(),
+            auth: RemoteAuth::Password { password: "gw2-secret".to_string() },
+        });
+        let tmp = tempfile::TempDir::new().unwrap();
+        let script = tmp.path().join("askpass");
+        std::fs::write(&script, askpass_script(&profile)).unwrap();
+
+        let answer = |prompt: &str| {
+            let output = Command::new("sh").arg(&script).arg(prompt).output().unwrap();
+            String::from_utf8(output.stdout).unwrap().trim_end().to_string()
+        };
+        assert_eq!(answer("ops@gw's password: "), "gw-secret");
+        assert_eq!(answer("ops@gw2's password: "), "gw2-secret");
+        assert_eq!(answer("(ops@gw2) Password: "), "gw2-secret");
+        assert_eq!(answer("deploy@10.0.0.7's password: "), "it's");
+        // A jump host never gets the server's password
+        assert_eq!(answer("(ops@gw) Verification code: "), "");
+        assert_eq!(answer("jump@bastion.example.com's password: "), "");
+        // Only a whole prompt names a jump host
+        assert_eq!(answer("xops@gw's password: "), "it's");
+    }
+}
@@ -0,0 +1,478 @@
+//! Similar image detection.
+//!
+//! Resized or re-encoded copies of a photo have different bytes, so
+//! `dedup::run_dedup` never groups them. Here every image below a
+//! `DedupScope` is decoded once and reduced to three 64-bit perceptual
+//! hashes (aHash, dHash, pHash). Images whose hashes differ in at most
+//! `max_distance` bits end up in the same `DuplicateGroup`; grouping is cheap,
+//! so the review can switch algorithm and distance without decoding again.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+
+use crate::services::dedup::{
+    self, DedupMessage, DedupPhase, DedupScope, DupFile, DuplicateGroup, FileEntry, KeepRule,
+};
+use crate::ui::image_viewer::is_image_file;
+
+/// Default number of differing bits (out of 64) for two images to match
+pub const DEFAULT_DISTANCE: u32 = 8;
+
+/// Largest distance the review allows; beyond it unrelated images match
+pub const MAX_DISTANCE: u32 = 24;
+
+/// Upper bound on decoder threads
+const MAX_WORKERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// Pixels brighter than the mean
+    Average,
+    /// Pixels brighter than their right neighbour; robust to brightness changes
+    Difference,
+    /// Low DCT frequencies above their median; robust to re-encoding
+    Perceptual,
+}
+
+impl HashAlgorithm {
+    pub fn label(self) -> &'static str {
+        match self {
+            HashAlgorithm::Average => "aHash",
+            HashAlgorithm::Difference => "dHash",
+            HashAlgorithm::Perceptual => "pHash",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            HashAlgorithm::Average => HashAlgorithm::Difference,
+            HashAlgorithm::Difference => HashAlgorithm::Perceptual,
+            HashAlgorithm::Perceptual => HashAlgorithm::Average,
+        }
+    }
+}
+
+/// A decoded image and its hashes under every algorithm
+#[derive(Debug, Clone)]
+pub struct ImageHashes {
+    /// `dimensions` is always set
+    pub file: DupFile,
+    /// Indexed by `HashAlgorithm as usize`
+    hashes: [u64; 3],
+}
+
+impl ImageHashes {
+    pub fn hash(&self, algorithm: HashAlgorithm) -> u64 {
+        self.hashes[algorithm as usize]
+    }
+}
+
+/// Number of differing bits
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Pack booleans into a hash, the first one in the highest bit
+fn bits(values: impl Iterator<Item = bool>) -> u64 {
+    values.fold(0, |hash, bit| (hash << 1) | bit as u64)
+}
+
+fn shrink(gray: &GrayImage, width: u32, height: u32) -> GrayImage {
+    image::imageops::resize(gray, width, height, FilterType::Triangle)
+}
+
+pub fn average_hash(gray: &GrayImage) -> u64 {
+    let small = shrink(gray, 8, 8);
+    let mean = small.pixels().map(|p| p.0[0] as u32).sum::<u32>() / 64;
+    bits(small.pixels().map(|p| p.0[0] as u32 > mean))
+}
+
+pub fn difference_hash(gray: &GrayImage) -> u64 {
+    let small = shrink(gray, 9, 8);
+    bits((0..8).flat_map(|y| (0..8).map(move |x| (x, y))).map(|(x, y)| {
+        small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0]
+    }))
+}
+
+pub fn perceptual_hash(gray: &GrayImage) -> u64 {
+    const N: usize = 32;
+    const KEEP: usize = 8;
+    let small = shrink(gray, N as u32, N as u32);
+    let pixels: Vec<f64> = small.pixels().map(|p| p.0[0] as f64).collect();
+
+    // 2D DCT-II, only the KEEP x KEEP lowest frequencies
+    let cos: Vec<Vec<f64>> = (0..KEEP)
+        .map(|k| {
+            (0..N)
+                .map(|n| ((2 * n + 1) as f64 * k as f64 * std::f64::consts::PI / (2 * N) as f64).cos())
+                .collect()
+        })
+        .collect();
+    let rows: Vec<[f64; KEEP]> = pixels
+        .chunks(N)
+        .map(|row| {
+            let mut out = [0.0; KEEP];
+            for (u, c) in cos.iter().enumerate() {
+                out[u] = row.iter().zip(c).map(|(p, c)| p * c).sum();
+            }
+            out
+        })
+        .collect();
+    let mut coefficients = Vec::with_capacity(KEEP * KEEP);
+    for c in &cos {
+        for u in 0..KEEP {
+            coefficients.push(rows.iter().zip(c).map(|(row, c)| row[u] * c).sum::<f64>());
+        }
+    }
+
+    // The DC term is the overall brightness; leave it out of the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    bits(coefficients.iter().map(|&c| c > median))
+}
+
+/// Decode an image: its dimensions and hashes in `HashAlgorithm` order
+fn hash_image(path: &Path) -> Result<((u32, u32), [u64; 3]), String> {
+    let img: DynamicImage = image::open(path).map_err(|e| e.to_string())?;
+    // Every hash works on at most 32x32 pixels; shrink big photos once
+    let gray = img.resize_exact(64, 64, FilterType::Triangle).to_luma8();
+    Ok((
+        (img.width(), img.height()),
+        [average_hash(&gray), difference_hash(&gray), perceptual_hash(&gray)],
+    ))
+}
+
+/// Decode the images on a few threads; sorted like the scan
+fn hash_images(
+    entries: &[FileEntry],
+    scope: &DedupScope,
+    tx: &Sender<DedupMessage>,
+    cancel_flag: &Arc<AtomicBool>,
+) -> Vec<ImageHashes> {
+    let total = entries.len();
+    let next = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKERS)
+        .min(total.max(1));
+
+    let worker = || {
+        let mut hashed = Vec::new();
+        while !cancel_flag.load(Ordering::Relaxed) {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let entry = match entries.get(i) {
+                Some(entry) => entry,
+                None => break,
+            };
+            let pct = (((i + 1) as f64 / total as f64) * 100.0).round() as u8;
+            let _ = tx.send(DedupMessage::Hashing(entry.path.display().to_string(), pct));
+            match hash_image(&entry.path) {
+                Ok((dimensions, hashes)) => {
+                    let _ = tx.send(DedupMessage::Log(format!(
+                        "{:016x} {} % {} {}", hashes[HashAlgorithm::Difference as usize], pct, entry.size, entry.path.display()
+                    )));
+                    hashed.push(ImageHashes {
+                        file: DupFile {
+                            path: entry.path.clone(),
+                            size: entry.size,
+                            modified: entry.modified,
+                            dimensions: Some(dimensions),
+                            root: entry.root,
+                            reference: scope.is_reference(entry.root),
+                        },
+                        hashes,
+                    });
+                }
+                Err(e) => {
+                    let _ = tx.send(DedupMessage::Error(format!("Cannot decode {}: {}", entry.path.display(), e)));
+                }
+            }
+        }
+        hashed
+    };
+
+    let mut images: Vec<ImageHashes> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..workers).map(|_| s.spawn(worker)).collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).flatten().collect()
+    });
+    images.sort_by(|a, b| (a.file.root, &a.file.path).cmp(&(b.file.root, &b.file.path)));
+    images
+}
+
+/// Group images whose `algorithm` hashes differ in at most `max_distance`
+/// bits. Matches do not chain: every two images of a group are within
+/// `max_distance` of each other, so whichever one is kept, the others look
+/// like it. The largest images seed the groups, closest matches join first.
+/// With `with_references`, only groups with a copy on both sides are kept.
+/// Every pair is compared, so this takes a while for large libraries; it
+/// runs on worker threads and returns no groups once `cancel_flag` is set.
+pub fn similar_groups(
+    images: &[ImageHashes],
+    algorithm: HashAlgorithm,
+    max_distance: u32,
+    with_references: bool,
+    cancel_flag: &AtomicBool,
+) -> Vec<DuplicateGroup> {
+    let hashes: Vec<u64> = images.iter().map(|img| img.hash(algorithm)).collect();
+
+    // Seeds in `KeepRule::LargestImage` order, so a group forms around its likely survivor
+    let mut seeds: Vec<usize> = (0..images.len()).collect();
+    seeds.sort_by_key(|&i| {
+        let file = &images[i].file;
+        let pixels = file.dimensions.map(|(w, h)| w as u64 * h as u64);
+        (std::cmp::Reverse(pixels), std::cmp::Reverse(file.size), &file.path)
+    });
+
+    let mut grouped = vec![false; images.len()];
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for seed in seeds {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return Vec::new();
+        }
+        if grouped[seed] {
+            continue;
+        }
+        let mut candidates: Vec<(u32, usize)> = (0..images.len())
+            .filter(|&j| j != seed && !grouped[j])
+            .map(|j| (distance(hashes[seed], hashes[j]), j))
+            .filter(|&(d, _)| d <= max_distance)
+            .collect();
+        if candidates.is_empty() {
+            continue;
+        }
+        candidates.sort_unstable();
+
+        let mut group = vec![seed];
+        for (_, j) in candidates {
+            if group.iter().all(|&m| distance(hashes[m], hashes[j]) <= max_distance) {
+                group.push(j);
+            }
+        }
+        if group.len() >= 2 {
+            for &m in &group {
+                grouped[m] = true;
+            }
+            members.push(group);
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = members
+        .into_iter()
+        .map(|mut m| {
+            let hash = hashes[m[0]];
+            m.sort_unstable();
+            let files: Vec<DupFile> = m.iter().map(|&i| images[i].file.clone()).collect();
+            DuplicateGroup {
+                hash: format!("{:016x}", hash),
+                size: files.iter().map(|f| f.size).max().unwrap_or(0),
+                files,
+                keep: 0,
+                excluded: false,
+            }
+        })
+        .filter(|g| !with_references || (g.files.iter().any(|f| f.reference) && g.files.iter().any(|f| !f.reference)))
+        .collect();
+    for group in groups.iter_mut() {
+        group.apply_rule(&KeepRule::LargestImage);
+    }
+    // Biggest savings first
+    groups.sort_by(|a, b| b.reclaimable().cmp(&a.reclaimable()).then_with(|| a.files[0].path.cmp(&b.files[0].path)));
+    groups
+}
+
+/// Scan the directories of `scope` and hash every image. Ends with
+/// `DedupMessage::Images` and their `DedupMessage::Groups` when some look
+/// alike at `DEFAULT_DISTANCE` under `default_algorithm`, with
+/// `DedupMessage::Complete` otherwise.
+pub fn run_similar(
+    scope: DedupScope,
+    default_algorithm: HashAlgorithm,
+    tx: Sender<DedupMessage>,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::Scanning));
+    let _ = tx.send(DedupMessage::Log("Scanning images...".into()));
+
+    let (size_map, scanned) = match dedup::scan_scope(&scope, &tx, &cancel_flag) {
+        Some(result) => result,
+        None => {
+            dedup::send_cancelled(&tx);
+            return;
+        }
+    };
+
+    // Hard links to one image are the same image
+    let mut seen = HashSet::new();
+    let mut entries: Vec<FileEntry> = size_map
+        .into_values()
+        .flatten()
+        .filter(|e| is_image_file(&e.path) && seen.insert(e.inode))
+        .collect();
+    entries.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+    let _ = tx.send(DedupMessage::Log(format!(
+        "Scan complete: {} files scanned, {} images",
+        scanned, entries.len()
+    )));
+
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::Hashing));
+    let images = hash_images(&entries, &scope, &tx, &cancel_flag);
+    if cancel_flag.load(Ordering::Relaxed) {
+        dedup::send_cancelled(&tx);
+        return;
+    }
+
+    let groups = similar_groups(&images, default_algorithm, DEFAULT_DISTANCE, !scope.references.is_empty(), &cancel_flag);
+    if cancel_flag.load(Ordering::Relaxed) {
+        dedup::send_cancelled(&tx);
+        return;
+    }
+    let _ = tx.send(DedupMessage::Stats { scanned, duplicates: 0, freed: 0 });
+    if groups.is_empty() {
+        let _ = tx.send(DedupMessage::Log("No similar images found.".into()));
+        let _ = tx.send(DedupMessage::Phase(DedupPhase::Complete));
+        let _ = tx.send(DedupMessage::Complete);
+        return;
+    }
+
+    let _ = tx.send(DedupMessage::Log(format!(
+        "Found {} groups of similar images among {} images",
+        groups.len(), images.len()
+    )));
+    let _ = tx.send(DedupMessage::Phase(DedupPhase::Review));
+    let _ = tx.send(DedupMessage::Images(images));
+    let _ = tx.send(DedupMessage::Groups(groups));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    /// A soft gradient with a bright disc; `mirror` flips it left to right
+    fn picture(width: u32, height: u32, mirror: bool) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let fx = x as f64 / width as f64;
+            let fx = if mirror { 1.0 - fx } else { fx };
+            let fy = y as f64 / height as f64;
+            let disc = (fx - 0.3).powi(2) + (fy - 0.4).powi(2) < 0.04;
+            let v = if disc { 250.0 } else { 40.0 + 150.0 * fx * fy };
+            Rgb([v as u8, (v * 0.8) as u8, (255.0 * fy) as u8])
+        })
+    }
+
+    fn hashes(img: RgbImage) -> [u64; 3] {
+        let gray = DynamicImage::ImageRgb8(img).resize_exact(64, 64, FilterType::Triangle).to_luma8();
+        [average_hash(&gray), difference_hash(&gray), perceptual_hash(&gray)]
+    }
+
+    #[test]
+    fn test_hashes_survive_resizing_only() {
+        let original = hashes(picture(400, 300, false));
+        let resized = hashes(picture(160, 120, false));
+        let mirrored = hashes(picture(400, 300, true));
+        for alg in [HashAlgorithm::Average, HashAlgorithm::Difference, HashAlgorithm::Perceptual] {
+            let i = alg as usize;
+            assert!(distance(original[i], resized[i]) <= DEFAULT_DISTANCE, "{}", alg.label());
+            assert!(distance(original[i], mirrored[i]) > MAX_DISTANCE / 2, "{}", alg.label());
+        }
+    }
+
+    #[test]
+    fn test_groups_follow_distance() {
+        let image = |name: &str, hash: u64| ImageHashes {
+            file: DupFile {
+                path: PathBuf::from(name),
+                size: 10,
+                modified: None,
+                dimensions: Some((10, 10)),
+                root: 0,
+                reference: false,
+            },
+            hashes: [hash; 3],
+        };
+        let images = vec![image("a", 0), image("b", 0b1), image("c", 0b111), image("d", u64::MAX)];
+        let names = |groups: Vec<DuplicateGroup>| -> Vec<Vec<String>> {
+            groups
+                .iter()
+                .map(|g| g.files.iter().map(|f| f.path.display().to_string()).collect())
+                .collect()
+        };
+        assert!(similar_groups(&images, HashAlgorithm::Difference, 0, false, &AtomicBool::new(false)).is_empty());
+        assert_eq!(names(similar_groups(&images, HashAlgorithm::Difference, 1, false, &AtomicBool::new(false))), vec![vec!["a", "b"]]);
+        // b is within 2 of both a and c, but c is 3 away from a: no chaining
+        assert_eq!(names(similar_groups(&images, HashAlgorithm::Difference, 2, false, &AtomicBool::new(false))), vec![vec!["a", "b"]]);
+        assert_eq!(names(similar_groups(&images, HashAlgorithm::Difference, 3, false, &AtomicBool::new(false))), vec![vec!["a", "b", "c"]]);
+        // A cancelled regroup gives up
+        assert!(similar_groups(&images, HashAlgorithm::Difference, 2, false, &AtomicBool::new(true)).is_empty());
+    }
+
+    #[test]
+    fn test_groups_never_chain_past_the_survivor() {
+        let image = |name: &str, hash: u64, width: u32| ImageHashes {
+            file: DupFile {
+                path: PathBuf::from(name),
+                size: 10,
+                modified: None,
+                dimensions: Some((width, 10)),
+                root: 0,
+                reference: false,
+            },
+            hashes: [hash; 3],
+        };
+        // The largest image b is within 2 of a and of c, which are 4 apart
+        let images = vec![image("a", 0, 10), image("b", 0b11, 50), image("c", 0b1111, 10)];
+        let groups = similar_groups(&images, HashAlgorithm::Average, 2, false, &AtomicBool::new(false));
+        assert_eq!(groups.len(), 1);
+        let names: Vec<String> = groups[0].files.iter().map(|f| f.path.display().to_string()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(groups[0].files[groups[0].keep].path, PathBuf::from("b"));
+        for file in &groups[0].files {
+            let hash = images.iter().find(|i| i.file.path == file.path).unwrap().hash(HashAlgorithm::Average);
+            assert!(distance(hash, 0b11) <= 2);
+        }
+    }
+
+    #[test]
+    fn test_run_similar_finds_resized_copy() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        picture(400, 300, false).save(dir.join("photo.png")).unwrap();
+        picture(200, 150, false).save(dir.join("photo-small.png")).unwrap();
+        picture(400, 300, true).save(dir.join("other.png")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+        std::fs::write(dir.join("broken.jpg"), "not a jpeg").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        run_similar(DedupScope::single(dir.to_path_buf()), HashAlgorithm::Difference, tx, Arc::new(AtomicBool::new(false)));
+        let messages: Vec<DedupMessage> = rx.try_iter().collect();
+        assert!(messages.iter().any(|m| matches!(m, DedupMessage::Error(e) if e.contains("broken.jpg"))));
+        let images = messages
+            .into_iter()
+            .find_map(|m| match m {
+                DedupMessage::Images(images) => Some(images),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(images.len(), 3);
+
+        let groups = similar_groups(&images, HashAlgorithm::Difference, DEFAULT_DISTANCE, false, &AtomicBool::new(false));
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.files.len(), 2);
+        // The bigger original survives
+        assert_eq!(group.files[group.keep].path, dir.join("photo.png"));
+        assert_eq!(group.files[group.keep].dimensions, Some((400, 300)));
+        let removed: Vec<&Path> = group.to_process().map(|f| f.path.as_path()).collect();
+        assert_eq!(removed, vec![dir.join("photo-small.png").as_path()]);
+    }
+}
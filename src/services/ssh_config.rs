@@ -0,0 +1,531 @@
+//! Reading OpenSSH client configuration (~/.ssh/config).
+//!
+//! Only the options cokacdir can use are evaluated: HostName, User, Port,
+//! IdentityFile, CertificateFile and ProxyJump. As in ssh, the first value found for an
+//! option wins, `Host` patterns may use `*`, `?` and `!`, and `Include` pulls
+//! in other files. `Match` blocks are skipped.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::services::remote::{JumpHost, RemoteAuth, RemoteProfile};
+
+/// Nesting limit for `Include`, as in ssh
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Nesting limit for jump hosts that have a ProxyJump of their own
+const MAX_JUMP_DEPTH: usize = 8;
+
+/// Settings ssh would use to connect to one host
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostConfig {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub certificate_file: Option<String>,
+    /// Comma separated `[user@]host[:port]` hops; `none` is dropped
+    pub proxy_jump: Option<String>,
+}
+
+impl HostConfig {
+    /// Auth as ssh would try it: the configured IdentityFile, else the agent
+    fn auth(&self) -> RemoteAuth {
+        match &self.identity_file {
+            Some(path) => RemoteAuth::KeyFile {
+                path: path.clone(),
+                passphrase: None,
+                certificate: self.certificate_file.clone(),
+            },
+            None => RemoteAuth::Agent,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    /// Host patterns; `None` for the lines before the first `Host` and for `Match` blocks
+    patterns: Option<Vec<String>>,
+    /// Whether the block applies to every host (the lines before the first `Host`)
+    global: bool,
+    /// Lowercased keyword and its value
+    options: Vec<(String, String)>,
+}
+
+impl Block {
+    fn matches(&self, host: &str) -> bool {
+        let Some(patterns) = &self.patterns else { return self.global };
+        let mut matched = false;
+        for pattern in patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if wildcard_match(negated, host) {
+                    return false;
+                }
+            } else if wildcard_match(pattern, host) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+/// A parsed ssh client configuration
+#[derive(Debug, Clone, Default)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+}
+
+impl SshConfig {
+    /// Parse configuration text; `Include` paths are relative to `base_dir`
+    pub fn parse(text: &str, base_dir: &Path) -> Self {
+        let mut config = SshConfig {
+            blocks: vec![Block { patterns: None, global: true, options: Vec::new() }],
+        };
+        config.parse_into(text, base_dir, 0);
+        config
+    }
+
+    fn parse_into(&mut self, text: &str, base_dir: &Path, depth: usize) {
+        for line in text.lines() {
+            let Some((keyword, args)) = split_line(line) else { continue };
+            match keyword.as_str() {
+                "host" => self.blocks.push(Block { patterns: Some(args), global: false, options: Vec::new() }),
+                "match" => self.blocks.push(Block { patterns: None, global: false, options: Vec::new() }),
+                "include" if depth < MAX_INCLUDE_DEPTH => {
+                    let (patterns, global) = self.blocks.last()
+                        .map(|b| (b.patterns.clone(), b.global))
+                        .unwrap_or((None, true));
+                    let blocks = self.blocks.len();
+                    for pattern in args {
+                        for path in expand_include(&pattern, base_dir) {
+                            if let Ok(text) = std::fs::read_to_string(&path) {
+                                self.parse_into(&text, base_dir, depth + 1);
+                            }
+                        }
+                    }
+                    // Host lines in an included file do not end the including block
+                    if self.blocks.len() > blocks {
+                        self.blocks.push(Block { patterns, global, options: Vec::new() });
+                    }
+                }
+                _ => {
+                    if let (Some(block), Some(value)) = (self.blocks.last_mut(), args.into_iter().next()) {
+                        block.options.push((keyword, value));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Settings for `alias` as ssh would compute them; unset options stay `None`
+    pub fn lookup(&self, alias: &str) -> HostConfig {
+        let mut found = HostConfig::default();
+        for block in self.blocks.iter().filter(|b| b.matches(alias)) {
+            for (keyword, value) in &block.options {
+                match keyword.as_str() {
+                    "hostname" => { found.host_name.get_or_insert_with(|| value.clone()); }
+                    "user" => { found.user.get_or_insert_with(|| value.clone()); }
+                    "port" if found.port.is_none() => found.port = value.parse().ok(),
+                    "identityfile" => { found.identity_file.get_or_insert_with(|| value.clone()); }
+                    "certificatefile" => { found.certificate_file.get_or_insert_with(|| value.clone()); }
+                    "proxyjump" => { found.proxy_jump.get_or_insert_with(|| value.clone()); }
+                    _ => {}
+                }
+            }
+        }
+
+        // Percent tokens, expanded once every option is known
+        if let Some(name) = found.host_name.take() {
+            found.host_name = Some(expand_tokens(&name, alias, alias, &found));
+        }
+        let host_name = found.host_name.clone().unwrap_or_else(|| alias.to_string());
+        if found.proxy_jump.as_deref().is_some_and(|j| j.eq_ignore_ascii_case("none")) {
+            found.proxy_jump = None;
+        }
+        let known = found.clone();
+        for path in [&mut found.identity_file, &mut found.certificate_file] {
+            if let Some(value) = path.take() {
+                *path = Some(expand_tokens(&value, alias, &host_name, &known));
+            }
+        }
+        found
+    }
+
+    /// Concrete host aliases (Host names without wildcards), in file order
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for pattern in self.blocks.iter().filter_map(|b| b.patterns.as_ref()).flatten() {
+            if !pattern.contains(['*', '?', '!']) && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+        aliases
+    }
+
+    /// Whether `name` is one of the concrete host aliases
+    pub fn is_alias(&self, name: &str) -> bool {
+        self.aliases().iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Settings for a server given by alias, name or address. A name that is not
+    /// an alias uses the alias whose HostName and Port point at it, so
+    /// `user@real-host` still finds the IdentityFile configured under a short alias.
+    pub fn find_host(&self, host: &str, port: u16) -> HostConfig {
+        if self.is_alias(host) {
+            return self.lookup(host);
+        }
+        self.aliases()
+            .iter()
+            .map(|alias| self.lookup(alias))
+            .find(|c| c.host_name.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(host)) && c.port.unwrap_or(22) == port)
+            .unwrap_or_else(|| self.lookup(host))
+    }
+
+    /// Jump hosts for a ProxyJump value (`[user@]host[:port],...`). Each hop is
+    /// resolved like a host of its own, including a ProxyJump it has itself.
+    pub fn jump_hosts(&self, proxy_jump: &str) -> Result<Vec<JumpHost>, String> {
+        self.jump_hosts_depth(proxy_jump, 0)
+    }
+
+    fn jump_hosts_depth(&self, proxy_jump: &str, depth: usize) -> Result<Vec<JumpHost>, String> {
+        if depth > MAX_JUMP_DEPTH {
+            return Err("Too many nested jump hosts".to_string());
+        }
+        let mut hops = Vec::new();
+        for hop in proxy_jump.split(',').map(str::trim).filter(|h| !h.is_empty()) {
+            let (user, host_port) = match hop.rsplit_once('@') {
+                Some((user, rest)) => (Some(user), rest),
+                None => (None, hop),
+            };
+            let (alias, port) = match host_port.rsplit_once(':') {
+                Some((host, port)) => {
+                    let port = port.parse::<u16>().map_err(|_| format!("Invalid port in jump host '{}'", hop))?;
+                    (host, Some(port))
+                }
+                None => (host_port, None),
+            };
+            if alias.is_empty() {
+                return Err(format!("Invalid jump host '{}'", hop));
+            }
+
+            let found = self.lookup(alias);
+            if let Some(inner) = &found.proxy_jump {
+                hops.extend(self.jump_hosts_depth(inner, depth + 1)?);
+            }
+            let user = user
+                .map(str::to_string)
+                .or_else(|| found.user.clone())
+                .or_else(local_user)
+                .ok_or_else(|| format!("No user for jump host '{}'", hop))?;
+            hops.push(JumpHost {
+                host: found.host_name.clone().unwrap_or_else(|| alias.to_string()),
+                port: port.or(found.port).unwrap_or(22),
+                user,
+                auth: found.auth(),
+            });
+        }
+        Ok(hops)
+    }
+
+    /// A remote profile for every concrete host alias
+    pub fn profiles(&self) -> Vec<RemoteProfile> {
+        self.aliases()
+            .into_iter()
+            .filter_map(|alias| {
+                let found = self.lookup(&alias);
+                let user = found.user.clone().or_else(local_user)?;
+                // A host whose jump hosts cannot be worked out is unreachable; leave it out
+                let jump_hosts = match &found.proxy_jump {
+                    Some(proxy_jump) => self.jump_hosts(proxy_jump).ok()?,
+                    None => Vec::new(),
+                };
+                Some(RemoteProfile {
+                    auth: found.auth(),
+                    host: found.host_name.unwrap_or_else(|| alias.clone()),
+                    port: found.port.unwrap_or(22),
+                    name: alias,
+                    user,
+                    default_path: String::new(),
+                    jump_hosts,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Add a profile for every host alias in ~/.ssh/config that has none yet.
+/// Returns the names of the added profiles.
+pub fn import_profiles(profiles: &mut Vec<RemoteProfile>) -> Vec<String> {
+    let mut added = Vec::new();
+    for profile in user_config().profiles() {
+        let exists = profiles.iter().any(|p| {
+            p.name == profile.name || (p.host == profile.host && p.port == profile.port && p.user == profile.user)
+        });
+        if !exists {
+            added.push(profile.name.clone());
+            profiles.push(profile);
+        }
+    }
+    added
+}
+
+/// The user's ~/.ssh/config
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// The user's ~/.ssh/config, parsed again only when the file changes
+pub fn user_config() -> Arc<SshConfig> {
+    static CACHE: Mutex<Option<(Option<SystemTime>, Arc<SshConfig>)>> = Mutex::new(None);
+
+    let Some(path) = user_config_path() else { return Arc::default() };
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_at, config)) = cache.as_ref() {
+        if *cached_at == modified {
+            return config.clone();
+        }
+    }
+    let text = std::fs::read_to_string(&path).unwrap_or_default();
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let config = Arc::new(SshConfig::parse(&text, &base_dir));
+    *cache = Some((modified, config.clone()));
+    config
+}
+
+/// The local login name, ssh's default remote user
+pub fn local_user() -> Option<String> {
+    std::env::var("USER").or_else(|_| std::env::var("LOGNAME")).ok().filter(|u| !u.is_empty())
+}
+
+/// Split a config line into its lowercased keyword and arguments
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let keyword = line[..end].to_ascii_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    // Arguments are whitespace separated; double quotes group
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+/// Files named by an `Include` argument; `*` and `?` are allowed in the file name
+fn expand_include(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let path = if let Some(rest) = pattern.strip_prefix("~/") {
+        match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => return Vec::new(),
+        }
+    } else {
+        base_dir.join(pattern)
+    };
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else { return Vec::new() };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.file_name().is_some_and(|n| wildcard_match(&name, &n.to_string_lossy())))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Match `text` against a pattern with `*` and `?`, ignoring ASCII case
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let text: Vec<char> = text.to_ascii_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Expand ssh's percent tokens (%h %n %p %r %u %d %%)
+fn expand_tokens(value: &str, alias: &str, host_name: &str, found: &HostConfig) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => out.push_str(host_name),
+            Some('n') => out.push_str(alias),
+            Some('p') => out.push_str(&found.port.unwrap_or(22).to_string()),
+            Some('r') => out.push_str(&found.user.clone().or_else(local_user).unwrap_or_default()),
+            Some('u') => out.push_str(&local_user().unwrap_or_default()),
+            Some('d') => out.push('~'),
+            Some(other) => {
+                out.push('%');
+                if other != '%' {
+                    out.push(other);
+                }
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# global defaults
+User nobody
+
+Host web web-staging
+    HostName %h.example.com
+    Port 2222
+    IdentityFile ~/.ssh/id_%n
+    CertificateFile=~/.ssh/id_%n-cert.pub
+
+Host db
+    HostName 10.0.0.5
+    User postgres
+
+Host *.internal !secret.internal
+    User ops
+
+Match exec \"true\"
+    User ignored
+
+Host *
+    Port 22
+    IdentityFile ~/.ssh/id_default
+";
+
+    #[test]
+    fn test_lookup_first_value_wins() {
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+
+        let web = config.lookup("web");
+        assert_eq!(web.host_name.as_deref(), Some("web.example.com"));
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.user.as_deref(), Some("nobody"));
+        assert_eq!(web.identity_file.as_deref(), Some("~/.ssh/id_web"));
+        assert_eq!(web.certificate_file.as_deref(), Some("~/.ssh/id_web-cert.pub"));
+
+        let db = config.lookup("DB");
+        assert_eq!(db.host_name.as_deref(), Some("10.0.0.5"));
+        assert_eq!(db.user.as_deref(), Some("nobody"));
+        assert_eq!(db.identity_file.as_deref(), Some("~/.ssh/id_default"));
+
+        // Global lines come first; negated patterns exclude
+        assert_eq!(config.lookup("a.internal").user.as_deref(), Some("nobody"));
+        assert!(wildcard_match("*.internal", "a.internal"));
+        assert!(!Block {
+            patterns: Some(vec!["*.internal".into(), "!secret.internal".into()]),
+            global: false,
+            options: Vec::new(),
+        }
+        .matches("secret.internal"));
+    }
+
+    #[test]
+    fn test_aliases_and_profiles() {
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+        assert_eq!(config.aliases(), vec!["web", "web-staging", "db"]);
+        assert!(config.is_alias("web-staging"));
+        assert!(!config.is_alias("x.internal"));
+
+        // Reverse lookup by the real host name
+        let found = config.find_host("web-staging.example.com", 2222);
+        assert_eq!(found.identity_file.as_deref(), Some("~/.ssh/id_web-staging"));
+        assert_eq!(config.find_host("web-staging.example.com", 22), config.lookup("web-staging.example.com"));
+
+        let profiles = config.profiles();
+        assert_eq!(profiles.len(), 3);
+        assert_eq!(profiles[2].name, "db");
+        assert_eq!(profiles[2].host, "10.0.0.5");
+        assert_eq!(profiles[2].port, 22);
+        assert_eq!(profiles[2].user, "nobody");
+        assert!(matches!(
+            &profiles[0].auth,
+            RemoteAuth::KeyFile { path, certificate: Some(_), .. } if path == "~/.ssh/id_web"
+        ));
+    }
+
+    #[test]
+    fn test_proxy_jump() {
+        let config = SshConfig::parse(
+            "Host bastion\n  HostName bastion.example.com\n  User jump\n  IdentityFile ~/.ssh/id_bastion\n\
+             Host inner\n  HostName 10.1.0.1\n  User admin\n  ProxyJump bastion\n\
+             Host app\n  HostName 10.2.0.1\n  User app\n  ProxyJump inner,ops@gw:2222\n\
+             Host direct\n  ProxyJump none\n",
+            Path::new("/nonexistent"),
+        );
+        assert_eq!(config.lookup("direct").proxy_jump, None);
+
+        // inner's own ProxyJump comes first
+        let hops = config.jump_hosts("inner,ops@gw:2222").unwrap();
+        let labels: Vec<String> = hops.iter().map(|h| h.label()).collect();
+        assert_eq!(labels, vec!["jump@bastion.example.com", "admin@10.1.0.1", "ops@gw:2222"]);
+        assert!(matches!(&hops[0].auth, RemoteAuth::KeyFile { path, .. } if path == "~/.ssh/id_bastion"));
+        assert!(matches!(hops[2].auth, RemoteAuth::Agent));
+        assert!(config.jump_hosts("ops@gw:ssh").is_err());
+
+        let app = config.profiles().into_iter().find(|p| p.name == "app").unwrap();
+        assert_eq!(app.jump_hosts.len(), 3);
+    }
+
+    #[test]
+    fn test_include() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("config.d")).unwrap();
+        std::fs::write(tmp.path().join("config.d").join("10-jump"), "Host jump\n  HostName jump.example.com\n").unwrap();
+        std::fs::write(tmp.path().join("config.d").join("20-git"), "Host git\n  User git\n").unwrap();
+
+        let config = SshConfig::parse("Host other\n  Include config.d/*\n  User me\n", tmp.path());
+        assert_eq!(config.aliases(), vec!["other", "jump", "git"]);
+        assert_eq!(config.lookup("jump").host_name.as_deref(), Some("jump.example.com"));
+        // Lines after an Include still belong to the including block
+        assert_eq!(config.lookup("other").user.as_deref(), Some("me"));
+        assert_eq!(config.lookup("git").user.as_deref(), Some("git"));
+    }
+}
@@ -6,16 +6,20 @@ use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
 use std::thread;
 use std::time::{Instant, SystemTime};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, TimeZone};
 
 use crate::config::Settings;
 use crate::keybindings::Keybindings;
 use crate::services::file_ops::{self, FileOperationType, ProgressMessage, FileOperationResult};
+use crate::services::known_hosts::{self, HostKey, HostKeyError};
 use crate::services::remote::{self, RemoteContext, RemoteProfile, ConnectionStatus, SftpFileEntry};
 use crate::services::remote_transfer;
+use crate::services::ssh_config;
 use crate::ui::file_viewer::ViewerState;
 use crate::ui::file_editor::EditorState;
 use crate::ui::file_info::FileInfoState;
+use crate::ui::diff_side::DiffSide;
+use crate::ui::git_status::{self, GitPanelStatus};
 use crate::ui::theme::DEFAULT_THEME_NAME;
 
 /// Encode a command as base64 for safe shell execution
@@ -193,9 +197,30 @@ pub enum DialogType {
     RemoteConnect,
     /// Remote profile save prompt - ask to save after successful connect
     RemoteProfileSave,
+    /// First connection to a host: trust its key (`pending_host_key`)?
+    HostKeyConfirm,
+    /// The host presented another key than the recorded one
+    HostKeyChanged,
     EncryptConfirm,
     DecryptConfirm,
-    DedupConfirm,
+    /// Key source for encrypt / re-key (Tab cycles machine, passphrase, keyring)
+    EncryptKey,
+    /// Masked passphrase entry
+    Passphrase,
+    /// Confirm re-encrypting the .cokacenc files for another key
+    RekeyConfirm,
+    /// Contents of the .cokacenc groups (`enc_list_state`)
+    EncList,
+    /// Directories to search for duplicates
+    DedupRoots,
+    /// Directories to search for similar images
+    SimilarRoots,
+    /// Diff export prompt - output path for patch / JSON / CSV
+    DiffExport,
+    /// Diff exclude patterns prompt (Tab cycles saved presets)
+    DiffExclude,
+    /// Confirm copying diff entries to the other (remote) side
+    DiffSyncConfirm,
 }
 
 /// Settings dialog state
@@ -307,6 +332,7 @@ pub enum RemoteField {
     Host,
     Port,
     User,
+    Jump,
     AuthType,
     Credential,  // password or key_path depending on auth_type
     Passphrase,
@@ -316,6 +342,28 @@ pub enum RemoteField {
 pub enum RemoteAuthType {
     Password,
     KeyFile,
+    Agent,
+    KeyboardInteractive,
+}
+
+impl RemoteAuthType {
+    /// The ssh-agent when one is running, a password otherwise
+    fn preferred() -> Self {
+        if std::env::var_os("SSH_AUTH_SOCK").is_some() {
+            RemoteAuthType::Agent
+        } else {
+            RemoteAuthType::Password
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RemoteAuthType::Password => "Password",
+            RemoteAuthType::KeyFile => "Key File",
+            RemoteAuthType::Agent => "SSH Agent",
+            RemoteAuthType::KeyboardInteractive => "Keyboard-Interactive",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -328,6 +376,12 @@ pub struct RemoteConnectState {
     pub password: String,
     pub key_path: String,
     pub passphrase: String,
+    /// OpenSSH certificate for the key file, kept from the profile or ~/.ssh/config
+    pub certificate: Option<String>,
+    /// Jump hosts as typed: `[user@]host[:port]` or ~/.ssh/config aliases, comma separated
+    pub jump: String,
+    /// Jump hosts with their auth, worked out from `jump` by resolve_jump_hosts()
+    pub jump_hosts: Vec<remote::JumpHost>,
     pub remote_path: String,
     pub profile_name: String,
     pub error: Option<String>,
@@ -343,10 +397,13 @@ impl RemoteConnectState {
             host: String::new(),
             port: "22".to_string(),
             user: String::new(),
-            auth_type: RemoteAuthType::Password,
+            auth_type: RemoteAuthType::preferred(),
             password: String::new(),
             key_path: "~/.ssh/id_rsa".to_string(),
             passphrase: String::new(),
+            certificate: None,
+            jump: String::new(),
+            jump_hosts: Vec::new(),
             remote_path: "/".to_string(),
             profile_name: String::new(),
             error: None,
@@ -356,13 +413,21 @@ impl RemoteConnectState {
     }
 
     pub fn from_profile(profile: &remote::RemoteProfile, profile_index: usize) -> Self {
+        let mut certificate = None;
         let (auth_type, password, key_path, passphrase) = match &profile.auth {
             remote::RemoteAuth::Password { password } => {
                 (RemoteAuthType::Password, password.clone(), "~/.ssh/id_rsa".to_string(), String::new())
             }
-            remote::RemoteAuth::KeyFile { path, passphrase } => {
+            remote::RemoteAuth::KeyFile { path, passphrase, certificate: cert } => {
+                certificate = cert.clone();
                 (RemoteAuthType::KeyFile, String::new(), path.clone(), passphrase.clone().unwrap_or_default())
             }
+            remote::RemoteAuth::Agent => {
+                (RemoteAuthType::Agent, String::new(), "~/.ssh/id_rsa".to_string(), String::new())
+            }
+            remote::RemoteAuth::KeyboardInteractive { password } => {
+                (RemoteAuthType::KeyboardInteractive, password.clone(), "~/.ssh/id_rsa".to_string(), String::new())
+            }
         };
         Self {
             selected_field: RemoteField::Host,
@@ -373,6 +438,9 @@ impl RemoteConnectState {
             password,
             key_path,
             passphrase,
+            certificate,
+            jump: profile.jump_hosts.iter().map(|j| j.label()).collect::<Vec<_>>().join(","),
+            jump_hosts: profile.jump_hosts.clone(),
             remote_path: profile.default_path.clone(),
             profile_name: profile.name.clone(),
             error: None,
@@ -382,21 +450,88 @@ impl RemoteConnectState {
     }
 
     pub fn from_parsed(user: &str, host: &str, port: u16, path: &str) -> Self {
-        Self {
+        let mut state = Self {
             selected_field: if user.is_empty() { RemoteField::User } else { RemoteField::AuthType },
             host: host.to_string(),
             port: port.to_string(),
             user: user.to_string(),
-            auth_type: RemoteAuthType::Password,
+            auth_type: RemoteAuthType::preferred(),
             password: String::new(),
             key_path: "~/.ssh/id_rsa".to_string(),
             passphrase: String::new(),
+            certificate: None,
+            jump: String::new(),
+            jump_hosts: Vec::new(),
             remote_path: path.to_string(),
             profile_name: String::new(),
             error: None,
             cursor_pos: 0,
             editing_profile_index: None,
+        };
+        // Pick up the IdentityFile configured for this server in ~/.ssh/config
+        let found = ssh_config::user_config().find_host(host, port);
+        if let Some(identity) = found.identity_file {
+            state.use_identity_file(identity, found.certificate_file);
         }
+        state.jump = found.proxy_jump.unwrap_or_default();
+        state
+    }
+
+    /// Replace a ~/.ssh/config Host alias typed into the Host field with its
+    /// HostName, and fill in the Port, User and IdentityFile configured for it
+    pub fn resolve_host_alias(&mut self) {
+        let config = ssh_config::user_config();
+        let alias = self.host.trim().to_string();
+        if !config.is_alias(&alias) {
+            return;
+        }
+        let found = config.lookup(&alias);
+        self.host = found.host_name.unwrap_or_else(|| alias.clone());
+        if let Some(port) = found.port {
+            self.port = port.to_string();
+        }
+        if self.user.is_empty() {
+            if let Some(user) = found.user.or_else(ssh_config::local_user) {
+                self.user = user;
+            }
+        }
+        if let Some(identity) = found.identity_file {
+            self.use_identity_file(identity, found.certificate_file);
+        }
+        if let (true, Some(proxy_jump)) = (self.jump.is_empty(), found.proxy_jump) {
+            self.jump = proxy_jump;
+        }
+        if self.profile_name.is_empty() {
+            self.profile_name = alias;
+        }
+    }
+
+    /// Work out the jump hosts typed into the Jump field. Hops already in the
+    /// profile keep their auth; others are resolved through ~/.ssh/config and
+    /// take the auth of a saved profile for the same server when there is one.
+    pub fn resolve_jump_hosts(&mut self, profiles: &[remote::RemoteProfile]) -> Result<(), String> {
+        let config = ssh_config::user_config();
+        let mut hops = Vec::new();
+        for entry in self.jump.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            if let Some(known) = self.jump_hosts.iter().find(|j| j.label() == entry) {
+                hops.push(known.clone());
+                continue;
+            }
+            for mut hop in config.jump_hosts(entry)? {
+                if let Some(saved) = remote::find_matching_profile(profiles, &hop.user, &hop.host, hop.port) {
+                    hop.auth = saved.auth.clone();
+                }
+                hops.push(hop);
+            }
+        }
+        self.jump_hosts = hops;
+        Ok(())
+    }
+
+    fn use_identity_file(&mut self, path: String, certificate: Option<String>) {
+        self.auth_type = RemoteAuthType::KeyFile;
+        self.key_path = path;
+        self.certificate = certificate;
     }
 
     pub fn is_auth_type_field(&self) -> bool {
@@ -406,7 +541,9 @@ impl RemoteConnectState {
     pub fn toggle_auth_type(&mut self) {
         self.auth_type = match self.auth_type {
             RemoteAuthType::Password => RemoteAuthType::KeyFile,
-            RemoteAuthType::KeyFile => RemoteAuthType::Password,
+            RemoteAuthType::KeyFile => RemoteAuthType::Agent,
+            RemoteAuthType::Agent => RemoteAuthType::KeyboardInteractive,
+            RemoteAuthType::KeyboardInteractive => RemoteAuthType::Password,
         };
     }
 
@@ -414,11 +551,15 @@ impl RemoteConnectState {
         match self.selected_field {
             RemoteField::Host => RemoteField::Port,
             RemoteField::Port => RemoteField::User,
-            RemoteField::User => RemoteField::AuthType,
-            RemoteField::AuthType => RemoteField::Credential,
+            RemoteField::User => RemoteField::Jump,
+            RemoteField::Jump => RemoteField::AuthType,
+            RemoteField::AuthType => match self.auth_type {
+                RemoteAuthType::Agent => RemoteField::Host,  // no credential, wrap around
+                _ => RemoteField::Credential,
+            },
             RemoteField::Credential => match self.auth_type {
-                RemoteAuthType::Password => RemoteField::Host,  // wrap around
                 RemoteAuthType::KeyFile => RemoteField::Passphrase,
+                _ => RemoteField::Host,  // wrap around
             },
             RemoteField::Passphrase => RemoteField::Host,  // wrap around
         }
@@ -427,12 +568,14 @@ impl RemoteConnectState {
     pub fn prev_field(&self) -> RemoteField {
         match self.selected_field {
             RemoteField::Host => match self.auth_type {
-                RemoteAuthType::Password => RemoteField::Credential,  // wrap around
-                RemoteAuthType::KeyFile => RemoteField::Passphrase,
+                RemoteAuthType::KeyFile => RemoteField::Passphrase,  // wrap around
+                RemoteAuthType::Agent => RemoteField::AuthType,
+                _ => RemoteField::Credential,
             },
             RemoteField::Port => RemoteField::Host,
             RemoteField::User => RemoteField::Port,
-            RemoteField::AuthType => RemoteField::User,
+            RemoteField::Jump => RemoteField::User,
+            RemoteField::AuthType => RemoteField::Jump,
             RemoteField::Credential => RemoteField::AuthType,
             RemoteField::Passphrase => RemoteField::Credential,
         }
@@ -443,10 +586,11 @@ impl RemoteConnectState {
             RemoteField::Host => &mut self.host,
             RemoteField::Port => &mut self.port,
             RemoteField::User => &mut self.user,
+            RemoteField::Jump => &mut self.jump,
             RemoteField::AuthType => &mut self.password, // placeholder - handled by toggle
             RemoteField::Credential => match self.auth_type {
-                RemoteAuthType::Password => &mut self.password,
                 RemoteAuthType::KeyFile => &mut self.key_path,
+                _ => &mut self.password,
             },
             RemoteField::Passphrase => &mut self.passphrase,
         }
@@ -457,13 +601,11 @@ impl RemoteConnectState {
             RemoteField::Host => &self.host,
             RemoteField::Port => &self.port,
             RemoteField::User => &self.user,
-            RemoteField::AuthType => match self.auth_type {
-                RemoteAuthType::Password => "Password",
-                RemoteAuthType::KeyFile => "Key File",
-            },
+            RemoteField::Jump => &self.jump,
+            RemoteField::AuthType => self.auth_type.label(),
             RemoteField::Credential => match self.auth_type {
-                RemoteAuthType::Password => &self.password,
                 RemoteAuthType::KeyFile => &self.key_path,
+                _ => &self.password,
             },
             RemoteField::Passphrase => &self.passphrase,
         }
@@ -482,6 +624,11 @@ impl RemoteConnectState {
                 } else {
                     Some(self.passphrase.clone())
                 },
+                certificate: self.certificate.clone(),
+            },
+            RemoteAuthType::Agent => remote::RemoteAuth::Agent,
+            RemoteAuthType::KeyboardInteractive => remote::RemoteAuth::KeyboardInteractive {
+                password: self.password.clone(),
             },
         };
 
@@ -498,6 +645,7 @@ impl RemoteConnectState {
             user: self.user.clone(),
             auth,
             default_path: self.remote_path.clone(),
+            jump_hosts: self.jump_hosts.clone(),
         }
     }
 }
@@ -546,6 +694,13 @@ pub struct ConflictState {
     pub target_path: PathBuf,
 }
 
+/// Contents of the .cokacenc groups of a directory, one line per group
+#[derive(Debug, Clone)]
+pub struct EncListState {
+    pub lines: Vec<String>,
+    pub scroll_offset: usize,
+}
+
 /// State for tar exclude confirmation dialog
 #[derive(Debug, Clone)]
 pub struct TarExcludeState {
@@ -736,6 +891,40 @@ pub enum PendingRemoteOpen {
     },
 }
 
+/// Which encryption operation a `PendingCrypto` runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoOp {
+    Encrypt { split_size_mb: u64, use_md5: bool, compress: bool },
+    Decrypt,
+    Rekey,
+    Verify,
+    List,
+    /// Switch the active panel to the vault view
+    Vault,
+}
+
+/// A panel connection stopped at a host key that is not known yet
+#[derive(Debug, Clone)]
+pub struct PendingHostKey {
+    pub key: HostKey,
+    pub profile: RemoteProfile,
+    pub path: String,
+}
+
+/// An encryption operation waiting for its key choice and passphrases
+#[derive(Debug, Clone)]
+pub struct PendingCrypto {
+    pub op: CryptoOp,
+    /// Key to encrypt with, once picked (encrypt and re-key)
+    pub target: Option<crate::enc::keys::KeySource>,
+    /// Whether some groups in the directory were encrypted with a passphrase
+    pub needs_passphrase: bool,
+    /// Passphrase of those groups, once entered
+    pub passphrase: Option<String>,
+    /// First entry of a new passphrase, awaiting confirmation
+    pub new_passphrase: Option<String>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PathCompletion {
     pub suggestions: Vec<String>,  // 자동완성 후보 목록
@@ -753,6 +942,7 @@ pub struct Dialog {
     pub selected_button: usize,  // 버튼 선택 인덱스 (0: Yes, 1: No)
     pub selection: Option<(usize, usize)>,  // 선택 범위 (start, end) - None이면 선택 없음
     pub use_md5: bool,  // MD5 검증 옵션 (EncryptConfirm에서 사용)
+    pub compress: bool,  // zstd 압축 옵션 (EncryptConfirm에서 사용)
 }
 
 #[derive(Debug, Clone)]
@@ -824,6 +1014,12 @@ pub enum RemoteSpinnerResult {
         result: Result<ConnectSuccess, String>,
         panel_idx: usize,
     },
+    /// New connection refused the server's host key
+    HostKeyRejected {
+        error: HostKeyError,
+        profile: RemoteProfile,
+        path: String,
+    },
     /// Local background operation completed (no remote ctx)
     LocalOp {
         message: Result<String, String>,
@@ -888,6 +1084,12 @@ pub struct PanelState {
     pub remote_ctx: Option<Box<RemoteContext>>,
     /// Cached remote display info (user, host, port) — survives while remote_ctx is temporarily taken
     pub remote_display: Option<(String, String, u16)>,
+    /// Git status of the entries when the (local) panel is inside a work tree
+    pub git_status: Option<GitPanelStatus>,
+    /// Pending background git status refresh
+    git_status_rx: Option<Receiver<Option<GitPanelStatus>>>,
+    /// Vault view: .cokacenc groups are listed as their original files
+    pub vault: Option<crate::enc::vault::Vault>,
 }
 
 impl PanelState {
@@ -909,6 +1111,9 @@ impl PanelState {
             disk_available: 0,
             remote_ctx: None,
             remote_display: None,
+            git_status: None,
+            git_status_rx: None,
+            vault: None,
         };
         state.load_files();
         state
@@ -936,6 +1141,9 @@ impl PanelState {
             disk_available: 0,
             remote_ctx: None,
             remote_display: None,
+            git_status: None,
+            git_status_rx: None,
+            vault: None,
         };
         state.load_files();
         state
@@ -1027,7 +1235,7 @@ impl PanelState {
                                 let mut reader = std::io::BufReader::new(f);
                                 crate::enc::crypto::read_header(&mut reader).ok()
                             })
-                            .map(|(_, _, hdr_name)| hdr_name)
+                            .map(|header| header.filename)
                     } else {
                         None
                     };
@@ -1043,6 +1251,24 @@ impl PanelState {
                     })
                 }));
 
+            if let Some(ref mut vault) = self.vault {
+                // One entry per group instead of its chunk files, named by the group ID
+                if let Ok(entries) = vault.scan(&self.path) {
+                    items.retain(|item| {
+                        item.is_directory || crate::enc::naming::parse_enc_filename(Path::new(&item.name)).is_none()
+                    });
+                    items.extend(entries.into_iter().map(|entry| FileItem {
+                        name: entry.group_id,
+                        display_name: Some(entry.name),
+                        is_directory: false,
+                        is_symlink: false,
+                        size: entry.size,
+                        modified: Local.timestamp_opt(entry.modified, 0).single().unwrap_or_else(Local::now),
+                        permissions: String::new(),
+                    }));
+                }
+            }
+
             self.sort_items(&mut items);
             self.files.reserve(items.len());
             self.files.extend(items);
@@ -1050,10 +1276,13 @@ impl PanelState {
 
         self.finalize_load();
         self.update_disk_info();
+        self.request_git_status();
     }
 
     fn load_files_remote(&mut self) {
         self.files.clear();
+        self.git_status = None;
+        self.git_status_rx = None;
 
         let remote_path = self.path.display().to_string();
 
@@ -1156,6 +1385,14 @@ impl PanelState {
 
     /// Sort file items (shared between local and remote)
     fn sort_items(&self, items: &mut Vec<FileItem>) {
+        // Vault entries are named by group ID; sort them by their original name
+        let vault = self.vault.is_some();
+        fn sort_name(f: &FileItem, vault: bool) -> &str {
+            match &f.display_name {
+                Some(original) if vault => original,
+                _ => &f.name,
+            }
+        }
         items.sort_by(|a, b| {
             // Directories always first
             if a.is_directory && !b.is_directory {
@@ -1166,14 +1403,14 @@ impl PanelState {
             }
 
             let cmp = match self.sort_by {
-                SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SortBy::Name => sort_name(a, vault).to_lowercase().cmp(&sort_name(b, vault).to_lowercase()),
                 SortBy::Type => {
-                    let ext_a = std::path::Path::new(&a.name)
+                    let ext_a = std::path::Path::new(sort_name(a, vault))
                         .extension()
                         .and_then(|e| e.to_str())
                         .unwrap_or("")
                         .to_lowercase();
-                    let ext_b = std::path::Path::new(&b.name)
+                    let ext_b = std::path::Path::new(sort_name(b, vault))
                         .extension()
                         .and_then(|e| e.to_str())
                         .unwrap_or("")
@@ -1206,6 +1443,31 @@ impl PanelState {
         }
     }
 
+    /// Refresh the git status in the background; the current one stays shown
+    /// until the new one arrives unless the panel moved to another directory
+    fn request_git_status(&mut self) {
+        if self.git_status.as_ref().is_some_and(|s| s.dir != self.path) {
+            self.git_status = None;
+        }
+        self.git_status_rx = Some(git_status::spawn_collect(self.path.clone()));
+    }
+
+    /// Apply a finished git status refresh. Returns true if the status changed.
+    pub fn poll_git_status(&mut self) -> bool {
+        let result = match self.git_status_rx {
+            Some(ref rx) => match rx.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => None,
+            },
+            None => return false,
+        };
+        self.git_status_rx = None;
+        // Ignore results for a directory the panel has already left
+        self.git_status = result.filter(|s| s.dir == self.path && !self.is_remote());
+        true
+    }
+
     fn update_disk_info(&mut self) {
         if self.is_remote() {
             self.disk_total = 0;
@@ -1398,14 +1660,27 @@ pub struct App {
     // Pending extract directory name (for focusing after completion)
     pub pending_extract_dir: Option<String>,
 
+    // Groups read by a running .cokacenc listing (shown after completion)
+    pub pending_enc_list: Option<Receiver<crate::enc::GroupListing>>,
+
+    // Panel index and metadata of a running vault read (merged after completion)
+    pub pending_vault_read: Option<(usize, Receiver<crate::enc::vault::VaultRead>)>,
+
     // Pending paste focus names (for focusing on first pasted file after completion)
     pub pending_paste_focus: Option<Vec<String>>,
 
+    // Diff sync direction awaiting confirmation (true = left to right)
+    pub pending_diff_sync: Option<bool>,
+
+    // Re-run the folder comparison once the running diff sync completes
+    pub pending_diff_resync: bool,
+
     // Conflict resolution state for duplicate file handling
     pub conflict_state: Option<ConflictState>,
 
     // Tar exclude confirmation state
     pub tar_exclude_state: Option<TarExcludeState>,
+    pub enc_list_state: Option<EncListState>,
 
     // Help screen state
     pub help_state: HelpState,
@@ -1433,6 +1708,12 @@ pub struct App {
     // Pending remote download → open action
     pub pending_remote_open: Option<PendingRemoteOpen>,
 
+    // Encryption operation waiting for its key / passphrase dialogs
+    pub pending_crypto: Option<PendingCrypto>,
+
+    // Panel connection waiting for the user to trust the host key
+    pub pending_host_key: Option<PendingHostKey>,
+
     // Remote operation spinner (SSH/SFTP background task)
     pub remote_spinner: Option<RemoteSpinner>,
 }
@@ -1499,9 +1780,14 @@ impl App {
             file_operation_progress: None,
             pending_tar_archive: None,
             pending_extract_dir: None,
+            pending_enc_list: None,
+            pending_vault_read: None,
             pending_paste_focus: None,
+            pending_diff_sync: None,
+            pending_diff_resync: false,
             conflict_state: None,
             tar_exclude_state: None,
+            enc_list_state: None,
             help_state: HelpState::default(),
             settings_state: None,
             remote_connect_state: None,
@@ -1512,6 +1798,8 @@ impl App {
             dedup_screen_state: None,
             git_log_diff_state: None,
             pending_remote_open: None,
+            pending_crypto: None,
+            pending_host_key: None,
             remote_spinner: None,
         }
     }
@@ -1601,9 +1889,14 @@ impl App {
             file_operation_progress: None,
             pending_tar_archive: None,
             pending_extract_dir: None,
+            pending_enc_list: None,
+            pending_vault_read: None,
             pending_paste_focus: None,
+            pending_diff_sync: None,
+            pending_diff_resync: false,
             conflict_state: None,
             tar_exclude_state: None,
+            enc_list_state: None,
             help_state: HelpState::default(),
             settings_state: None,
             remote_connect_state: None,
@@ -1614,6 +1907,8 @@ impl App {
             dedup_screen_state: None,
             git_log_diff_state: None,
             pending_remote_open: None,
+            pending_crypto: None,
+            pending_host_key: None,
             remote_spinner: None,
         }
     }
@@ -1686,8 +1981,11 @@ impl App {
         // Update extension_handler setting
         self.settings.extension_handler = new_settings.extension_handler;
 
-        // Update diff compare method
+        // Update diff compare method and exclude settings
         self.settings.diff_compare_method = new_settings.diff_compare_method;
+        self.settings.diff_use_gitignore = new_settings.diff_use_gitignore;
+        self.settings.diff_exclude_presets = new_settings.diff_exclude_presets;
+        self.settings.diff_default_exclude_preset = new_settings.diff_default_exclude_preset;
 
         // Update keybindings
         self.keybindings = crate::keybindings::Keybindings::from_config(&new_settings.keybindings);
@@ -1722,6 +2020,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -1961,6 +2260,11 @@ impl App {
             return;
         }
 
+        if let Some(group_id) = self.current_vault_group() {
+            self.open_vault_entry(&group_id);
+            return;
+        }
+
         let panel = self.active_panel_mut();
         if let Some(file) = panel.current_file().cloned() {
             if file.is_directory {
@@ -2051,6 +2355,7 @@ impl App {
                             selected_button: 1, // Default to "No"
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                     } else {
                         self.pending_large_file = Some(path);
@@ -2063,6 +2368,7 @@ impl App {
                             selected_button: 1, // Default to "No"
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                     }
                 } else if is_image {
@@ -2082,6 +2388,7 @@ impl App {
                             selected_button: 1, // Default to "No"
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                     } else {
                         self.image_viewer_state = Some(
@@ -2106,6 +2413,7 @@ impl App {
                             selected_button: 0, // 0: Set mode (no existing handler)
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                     } else {
                         // Text file - open editor
@@ -2365,6 +2673,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -2420,6 +2729,7 @@ impl App {
             selected_button: if is_edit_mode { 1 } else { 0 }, // 0: Set, 1: Edit
             selection,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -2667,11 +2977,6 @@ impl App {
     /// With 2 panels: immediately enter diff screen
     /// With 3+ panels: first call selects first panel, second call selects second panel
     pub fn start_diff(&mut self) {
-        if self.panels.iter().any(|p| p.is_remote()) {
-            self.show_message("Diff is not supported for remote panels");
-            return;
-        }
-
         // Priority: if exactly 2 directories are selected in active panel, diff them
         let panel = &self.panels[self.active_panel_index];
         let selected_dirs: Vec<PathBuf> = panel.files.iter()
@@ -2679,10 +2984,10 @@ impl App {
             .map(|f| panel.path.join(&f.name))
             .collect();
         if selected_dirs.len() == 2 {
-            let left = selected_dirs[0].clone();
-            let right = selected_dirs[1].clone();
+            let left = self.diff_side_for(self.active_panel_index, selected_dirs[0].clone());
+            let right = self.diff_side_for(self.active_panel_index, selected_dirs[1].clone());
             self.panels[self.active_panel_index].selected_files.clear();
-            self.enter_diff_screen(left, right);
+            self.enter_diff_screen_sides(left, right);
             return;
         }
 
@@ -2693,9 +2998,9 @@ impl App {
 
         if self.panels.len() == 2 {
             // 2 panels: immediate diff
-            let left = self.panels[0].path.clone();
-            let right = self.panels[1].path.clone();
-            self.enter_diff_screen(left, right);
+            let left = self.diff_side_for(0, self.panels[0].path.clone());
+            let right = self.diff_side_for(1, self.panels[1].path.clone());
+            self.enter_diff_screen_sides(left, right);
         } else {
             // 3+ panels: 2-stage selection
             if let Some(first) = self.diff_first_panel {
@@ -2705,10 +3010,10 @@ impl App {
                     self.show_message("Select a different panel for diff");
                     return;
                 }
-                let left = self.panels[first].path.clone();
-                let right = self.panels[second].path.clone();
+                let left = self.diff_side_for(first, self.panels[first].path.clone());
+                let right = self.diff_side_for(second, self.panels[second].path.clone());
                 self.diff_first_panel = None;
-                self.enter_diff_screen(left, right);
+                self.enter_diff_screen_sides(left, right);
             } else {
                 // First selection
                 self.diff_first_panel = Some(self.active_panel_index);
@@ -2719,9 +3024,22 @@ impl App {
         }
     }
 
-    /// Enter diff screen with two directory paths
+    /// Diff side for a directory shown in the given panel (remote panels compare over SFTP)
+    fn diff_side_for(&self, panel_idx: usize, path: PathBuf) -> DiffSide {
+        match self.panels[panel_idx].remote_ctx {
+            Some(ref ctx) => DiffSide::remote(path, ctx.profile.clone()),
+            None => DiffSide::local(path),
+        }
+    }
+
+    /// Enter diff screen with two local directory paths
     pub fn enter_diff_screen(&mut self, left: PathBuf, right: PathBuf) {
-        if left == right {
+        self.enter_diff_screen_sides(DiffSide::local(left), DiffSide::local(right));
+    }
+
+    /// Enter diff screen with two directories, either of which may be remote
+    pub fn enter_diff_screen_sides(&mut self, left: DiffSide, right: DiffSide) {
+        if left.same_location(&right) {
             self.show_message("Both paths are the same");
             return;
         }
@@ -2729,8 +3047,12 @@ impl App {
         let sort_by = self.active_panel().sort_by;
         let sort_order = self.active_panel().sort_order;
         let mut state = crate::ui::diff_screen::DiffState::new(
-            left, right, compare_method, sort_by, sort_order,
+            left.root.clone(), right.root.clone(), compare_method, sort_by, sort_order,
         );
+        state.left_side = left;
+        state.right_side = right;
+        state.excludes.use_ignore_files = self.settings.diff_use_gitignore;
+        state.excludes.patterns = self.settings.default_diff_exclude_patterns();
         state.start_comparison();
         self.diff_state = Some(state);
         self.current_screen = Screen::DiffScreen;
@@ -2744,6 +3066,223 @@ impl App {
         self.current_screen = Screen::DiffFileView;
     }
 
+    /// Show the export dialog for the diff screen or the file content diff view
+    pub fn show_diff_export_dialog(&mut self) {
+        let (left_root, right_root, local_root) = match self.diff_state {
+            Some(ref state) => {
+                // Exports are written locally: start next to a local side if there is one
+                let local_root = [&state.left_side, &state.right_side]
+                    .into_iter()
+                    .find(|side| !side.is_remote())
+                    .map(|side| side.root.clone());
+                (state.left_root.clone(), state.right_root.clone(), local_root)
+            }
+            None => return,
+        };
+        let base_dir = match local_root {
+            Some(root) => root.parent().map(|p| p.to_path_buf()).unwrap_or(root),
+            None => dirs::home_dir().unwrap_or_else(|| PathBuf::from("/tmp")),
+        };
+        let dir_name = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "root".to_string());
+
+        let (file_name, message) = if self.current_screen == Screen::DiffFileView {
+            let name = self.diff_file_view_state.as_ref()
+                .map(|s| s.file_name.rsplit('/').next().unwrap_or(&s.file_name).to_string())
+                .unwrap_or_default();
+            (format!("{}.patch", name), "Unified patch of the current file".to_string())
+        } else {
+            (
+                format!("{}_vs_{}.patch", dir_name(&left_root), dir_name(&right_root)),
+                "Format by extension: .patch .diff .json .csv".to_string(),
+            )
+        };
+
+        let input = base_dir.join(file_name).display().to_string();
+        let cursor_pos = input.chars().count();
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::DiffExport,
+            input,
+            cursor_pos,
+            message,
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Ask to copy the selected (or current) diff entries towards one side.
+    /// Syncing goes through the remote transfer, so one side must be remote.
+    pub fn show_diff_sync_dialog(&mut self, to_right: bool) {
+        let state = match self.diff_state {
+            Some(ref state) => state,
+            None => return,
+        };
+        if !state.left_side.is_remote() && !state.right_side.is_remote() {
+            self.show_message("Sync needs a remote side; use copy in the file panel for local folders");
+            return;
+        }
+        let count: usize = crate::ui::diff_screen::plan_sync(state, to_right)
+            .iter()
+            .map(|(_, names)| names.len())
+            .sum();
+        if count == 0 {
+            self.show_message("Nothing to sync");
+            return;
+        }
+        let (from, to) = if to_right {
+            ("left", state.right_side.display_root())
+        } else {
+            ("right", state.left_side.display_root())
+        };
+        self.pending_diff_sync = Some(to_right);
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::DiffSyncConfirm,
+            input: String::new(),
+            cursor_pos: 0,
+            message: format!("Copy {} item(s) from {} to {}?", count, from, to),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Run the sync confirmed in the diff sync dialog
+    pub fn execute_diff_sync(&mut self) {
+        let to_right = match self.pending_diff_sync.take() {
+            Some(d) => d,
+            None => return,
+        };
+        let state = match self.diff_state {
+            Some(ref state) => state,
+            None => return,
+        };
+        let (source, target) = if to_right {
+            (&state.left_side, &state.right_side)
+        } else {
+            (&state.right_side, &state.left_side)
+        };
+        let groups = crate::ui::diff_screen::sync_transfer_groups(state, to_right);
+        let source_profile = source.remote.clone();
+        let target_profile = target.remote.clone();
+
+        let mut progress = FileOperationProgress::new(FileOperationType::Copy);
+        progress.is_active = true;
+        progress.total_files = groups.iter().map(|g| g.files.len()).sum();
+        let cancel_flag = progress.cancel_flag.clone();
+        let (tx, rx) = mpsc::channel();
+        progress.receiver = Some(rx);
+
+        thread::spawn(move || {
+            remote_transfer::transfer_groups_with_progress(
+                source_profile,
+                target_profile,
+                groups,
+                cancel_flag,
+                tx,
+            );
+        });
+
+        self.pending_diff_resync = true;
+        self.file_operation_progress = Some(progress);
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::Progress,
+            input: String::new(),
+            cursor_pos: 0,
+            message: String::new(),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Show the exclude patterns dialog for the diff screen
+    pub fn show_diff_exclude_dialog(&mut self) {
+        let input = match self.diff_state {
+            Some(ref state) => crate::ui::diff_exclude::format_patterns(&state.excludes.patterns),
+            None => return,
+        };
+        let cursor_pos = input.chars().count();
+        let message = if self.settings.diff_exclude_presets.is_empty() {
+            "Globs separated by comma or space".to_string()
+        } else {
+            "Globs separated by comma or space (Tab: presets)".to_string()
+        };
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::DiffExclude,
+            input,
+            cursor_pos,
+            message,
+            completion: None,
+            // Index of the next preset to insert with Tab
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Apply exclude patterns from the dialog and re-run the comparison
+    pub fn apply_diff_excludes(&mut self, input: &str) {
+        if let Some(ref mut state) = self.diff_state {
+            state.excludes.patterns = crate::ui::diff_exclude::parse_patterns(input);
+            state.start_comparison();
+        }
+    }
+
+    /// Toggle .gitignore / .ignore handling for the current comparison and re-run it
+    pub fn toggle_diff_gitignore(&mut self) {
+        if let Some(ref mut state) = self.diff_state {
+            state.excludes.use_ignore_files = !state.excludes.use_ignore_files;
+            state.start_comparison();
+        }
+    }
+
+    /// Write the current diff to `input`. On failure returns an error message
+    /// (ending with '!') for display in the dialog.
+    pub fn execute_diff_export(&mut self, input: &str) -> Result<(), String> {
+        use crate::ui::diff_export::{self, ExportFormat};
+
+        let path = {
+            let expanded = if let Some(rest) = input.trim().strip_prefix("~/") {
+                dirs::home_dir().map(|h| h.join(rest)).unwrap_or_else(|| PathBuf::from(input.trim()))
+            } else {
+                PathBuf::from(input.trim())
+            };
+            if expanded.is_absolute() {
+                expanded
+            } else {
+                self.active_panel().path.join(expanded)
+            }
+        };
+
+        let (content, format) = if self.current_screen == Screen::DiffFileView {
+            let state = self.diff_file_view_state.as_ref().ok_or("No file diff!")?;
+            let patch = diff_export::file_patch(&state.file_name, &state.left_path, &state.right_path)
+                .map_err(|e| format!("{}!", e))?;
+            (patch, ExportFormat::Patch)
+        } else {
+            let format = ExportFormat::from_path(&path)
+                .ok_or("Use a .patch, .diff, .json or .csv extension!")?;
+            let state = self.diff_state.as_ref().ok_or("No diff result!")?;
+            let content = diff_export::render(format, state).map_err(|e| format!("{}!", e))?;
+            (content, format)
+        };
+
+        if format == ExportFormat::Patch && content.is_empty() {
+            return Err("No textual differences to export!".to_string());
+        }
+
+        diff_export::write_export(&path, &content)?;
+        self.show_message(&format!("Exported {} to {}", format.display_name(), path.display()));
+        Ok(())
+    }
+
     pub fn get_operation_files(&self) -> Vec<String> {
         let panel = self.active_panel();
         if !panel.selected_files.is_empty() {
@@ -2872,6 +3411,7 @@ impl App {
                             selected_button: 1, // Default to "No"
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                         return;
                     }
@@ -2895,6 +3435,7 @@ impl App {
                             selected_button: 1, // Default to "No"
                             selection: None,
                             use_md5: false,
+                            compress: false,
                         });
                         return;
                     }
@@ -3031,6 +3572,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3099,6 +3641,7 @@ impl App {
             selected_button: 1,  // 기본값: No (안전을 위해)
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3137,17 +3680,215 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: self.settings.encrypt_compress,
         });
     }
 
     pub fn show_decrypt_dialog(&mut self) {
-        if self.active_panel().is_remote() {
-            self.show_message("Decryption is not available on remote panels");
+        if self.active_panel().vault.is_some() {
+            self.show_extract_dialog();
+        } else {
+            self.show_enc_files_dialog(DialogType::DecryptConfirm);
+        }
+    }
+
+    pub fn show_rekey_dialog(&mut self) {
+        self.show_enc_files_dialog(DialogType::RekeyConfirm);
+    }
+
+    /// Switch the active panel between the vault view and the plain listing
+    pub fn toggle_vault(&mut self) {
+        if self.active_panel().vault.is_some() {
+            let panel = self.active_panel_mut();
+            panel.vault = None;
+            panel.selected_files.clear();
+            panel.load_files();
+            self.show_message("Vault view off");
+        } else if self.active_panel().is_remote() {
+            self.show_message("Vault view is not available on remote panels");
+        } else {
+            self.start_crypto(CryptoOp::Vault);
+        }
+    }
+
+    /// Read the metadata of vault entries the panels list without it, once
+    /// nothing else is in progress
+    pub fn poll_vault(&mut self) {
+        if self.file_operation_progress.is_some() || self.dialog.is_some() || self.current_screen != Screen::FilePanel {
             return;
         }
+        let unread = self.panels.iter().enumerate().find_map(|(i, panel)| {
+            let vault = panel.vault.as_ref()?;
+            let groups = vault.unread();
+            (!groups.is_empty()).then(|| (i, groups, vault.keys()))
+        });
+        let Some((panel_index, groups, keys)) = unread else { return };
+
+        let mut progress = FileOperationProgress::new(FileOperationType::List);
+        progress.is_active = true;
+        let cancel_flag = progress.cancel_flag.clone();
+
+        let (tx, rx) = mpsc::channel();
+        progress.receiver = Some(rx);
+        let (result_tx, result_rx) = mpsc::channel();
+        self.pending_vault_read = Some((panel_index, result_rx));
+
+        thread::spawn(move || crate::enc::vault::read_infos_with_progress(groups, keys, tx, result_tx, cancel_flag));
+
+        self.file_operation_progress = Some(progress);
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::Progress,
+            input: String::new(),
+            cursor_pos: 0,
+            message: String::new(),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Merge a finished vault read and list the panel again
+    pub fn finish_vault_read(&mut self) {
+        let Some((panel_index, rx)) = self.pending_vault_read.take() else { return };
+        self.file_operation_progress = None;
+        self.dialog = None;
+
+        let Ok(read) = rx.try_recv() else { return };
+        let Some(panel) = self.panels.get_mut(panel_index) else { return };
+        let Some(vault) = panel.vault.as_mut() else { return };
+        vault.merge(read);
+        // Names change, so keep the cursor on the same entry
+        panel.pending_focus = panel.current_file().map(|f| f.name.clone());
+        panel.load_files();
+    }
 
+    /// Group ID of the vault entry under the cursor
+    fn current_vault_group(&self) -> Option<String> {
+        let panel = self.active_panel();
+        let file = panel.current_file()?;
+        panel.vault.as_ref()?.chunks(&file.name).map(|_| file.name.clone())
+    }
+
+    /// Decrypt a vault entry to memory and show it in the image or file viewer
+    fn open_vault_entry(&mut self, group_id: &str) {
         let dir = self.active_panel().path.clone();
-        let count = match fs::read_dir(&dir) {
+        let Some(vault) = self.active_panel_mut().vault.as_mut() else { return };
+        let (info, data) = match vault.open(group_id, ViewerState::MAX_FILE_SIZE) {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.show_message(&format!("Cannot open: {}", e));
+                return;
+            }
+        };
+        // Only names the viewer; nothing is written there
+        let name = Path::new(&info.filename).file_name().map_or_else(|| group_id.into(), |n| n.to_os_string());
+        let path = dir.join(name);
+
+        if crate::ui::image_viewer::is_image_file(&path) {
+            self.image_viewer_state = Some(crate::ui::image_viewer::ImageViewerState::from_bytes(&path, data));
+            self.current_screen = Screen::ImageViewer;
+        } else {
+            let mut viewer = ViewerState::new();
+            viewer.set_syntax_colors(self.theme.syntax);
+            viewer.load_memory(&path, data);
+            self.viewer_state = Some(viewer);
+            self.current_screen = Screen::FileViewer;
+        }
+    }
+
+    /// Chunks of the selected vault entries, or of the one under the cursor
+    fn vault_operation_groups(&self) -> Vec<Vec<crate::enc::naming::EncFileInfo>> {
+        let Some(vault) = self.active_panel().vault.as_ref() else { return Vec::new() };
+        self.get_operation_files().iter()
+            .filter_map(|name| vault.chunks(name).map(<[_]>::to_vec))
+            .collect()
+    }
+
+    /// Where extracted vault entries go: the target panel if it is local
+    fn vault_extract_dir(&self) -> PathBuf {
+        let target = self.target_panel();
+        if target.is_remote() {
+            self.active_panel().path.clone()
+        } else {
+            target.path.clone()
+        }
+    }
+
+    fn show_extract_dialog(&mut self) {
+        let count = self.vault_operation_groups().len();
+        if count == 0 {
+            self.show_message("Select vault entries to extract");
+            return;
+        }
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::DecryptConfirm,
+            input: String::new(),
+            cursor_pos: 0,
+            message: format!("Extract {} file(s) to {}?", count, self.vault_extract_dir().display()),
+            completion: None,
+            selected_button: 1,  // Default: No
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Decrypt the chosen vault entries next to the other panel, keeping the chunks
+    fn execute_extract(&mut self) {
+        let groups = self.vault_operation_groups();
+        let Some(vault) = self.active_panel().vault.as_ref() else { return };
+        let keys = vault.keys();
+        let target = self.vault_extract_dir();
+
+        let mut progress = FileOperationProgress::new(FileOperationType::Decrypt);
+        progress.is_active = true;
+        let cancel_flag = progress.cancel_flag.clone();
+        let (tx, rx) = mpsc::channel();
+        progress.receiver = Some(rx);
+
+        thread::spawn(move || {
+            crate::enc::extract_groups_with_progress(&groups, &target, keys, tx, cancel_flag);
+        });
+
+        self.file_operation_progress = Some(progress);
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::Progress,
+            input: String::new(),
+            cursor_pos: 0,
+            message: String::new(),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Check every .cokacenc group of the active panel, decrypting only to memory
+    pub fn verify_encrypted(&mut self) {
+        if self.enc_file_count("verify", "Verification") > 0 {
+            self.start_crypto(CryptoOp::Verify);
+        }
+    }
+
+    /// Show the original files of the .cokacenc groups of the active panel
+    pub fn list_encrypted(&mut self) {
+        if self.enc_file_count("list", "Listing") > 0 {
+            self.start_crypto(CryptoOp::List);
+        }
+    }
+
+    /// Number of .cokacenc files in the active panel; when there are none, or
+    /// the panel is remote, says so and returns 0
+    fn enc_file_count(&mut self, verb: &str, operation: &str) -> usize {
+        if self.active_panel().is_remote() {
+            self.show_message(&format!("{} is not available on remote panels", operation));
+            return 0;
+        }
+
+        let count = match fs::read_dir(&self.active_panel().path) {
             Ok(rd) => rd.filter_map(|e| e.ok())
                 .filter(|e| {
                     let path = e.path();
@@ -3158,80 +3899,223 @@ impl App {
         };
 
         if count == 0 {
-            self.show_message("No .cokacenc files to decrypt");
+            self.show_message(&format!("No .cokacenc files to {}", verb));
+        }
+        count
+    }
+
+    /// Confirm decrypting or re-keying the .cokacenc files of the active panel
+    fn show_enc_files_dialog(&mut self, dialog_type: DialogType) {
+        let (verb, operation) = if dialog_type == DialogType::RekeyConfirm {
+            ("Re-key", "Re-keying")
+        } else {
+            ("Decrypt", "Decryption")
+        };
+        let count = self.enc_file_count(&verb.to_lowercase(), operation);
+        if count == 0 {
             return;
         }
 
+        let dir = self.active_panel().path.clone();
         self.dialog = Some(Dialog {
-            dialog_type: DialogType::DecryptConfirm,
+            dialog_type,
             input: String::new(),
             cursor_pos: 0,
-            message: format!("Decrypt {} .cokacenc file(s) in {}?", count, dir.display()),
+            message: format!("{} {} .cokacenc file(s) in {}?", verb, count, dir.display()),
             completion: None,
             selected_button: 1,  // Default: No
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
-    pub fn execute_encrypt(&mut self, split_size_mb: u64, use_md5: bool) {
-        // Remember split size for next time
+    pub fn execute_encrypt(&mut self, split_size_mb: u64, use_md5: bool, compress: bool) {
+        // Remember split size and compression for next time
         self.settings.encrypt_split_size = split_size_mb;
+        self.settings.encrypt_compress = compress;
+        self.start_crypto(CryptoOp::Encrypt { split_size_mb, use_md5, compress });
+    }
 
-        let key_path = match crate::enc::ensure_key() {
-            Ok(p) => p,
-            Err(e) => {
-                self.show_message(&format!("Key error: {}", e));
-                return;
-            }
-        };
+    pub fn execute_decrypt(&mut self) {
+        if self.active_panel().vault.is_some() {
+            self.execute_extract();
+        } else {
+            self.start_crypto(CryptoOp::Decrypt);
+        }
+    }
 
-        let dir = self.active_panel().path.clone();
+    pub fn execute_rekey(&mut self) {
+        self.start_crypto(CryptoOp::Rekey);
+    }
 
-        let mut progress = FileOperationProgress::new(FileOperationType::Encrypt);
-        progress.is_active = true;
-        let cancel_flag = progress.cancel_flag.clone();
+    fn start_crypto(&mut self, op: CryptoOp) {
+        // The headers tell which groups need a passphrase; key files are found by themselves
+        let needs_passphrase = !matches!(op, CryptoOp::Encrypt { .. })
+            && crate::enc::required_keys(&self.active_panel().path)
+                .map(|keys| keys.iter().any(|k| matches!(k, crate::enc::keys::KeyInfo::Passphrase { .. })))
+                .unwrap_or(false);
+        self.pending_crypto = Some(PendingCrypto {
+            op,
+            target: None,
+            needs_passphrase,
+            passphrase: None,
+            new_passphrase: None,
+        });
+        self.continue_crypto();
+    }
 
-        let (tx, rx) = mpsc::channel();
-        progress.receiver = Some(rx);
+    /// Open the next dialog the pending operation needs, or start it
+    pub fn continue_crypto(&mut self) {
+        let Some(pending) = self.pending_crypto.as_ref() else { return };
+        let needs_key = matches!(pending.op, CryptoOp::Encrypt { .. } | CryptoOp::Rekey) && pending.target.is_none();
+        let prompt = if pending.needs_passphrase && pending.passphrase.is_none() {
+            Some("Passphrase of the encrypted files:")
+        } else if matches!(&pending.target, Some(crate::enc::keys::KeySource::Passphrase(p)) if p.is_empty()) {
+            Some(if pending.new_passphrase.is_none() { "New passphrase:" } else { "Repeat the new passphrase:" })
+        } else {
+            None
+        };
 
-        thread::spawn(move || {
-            crate::enc::pack_directory_with_progress(&dir, &key_path, tx, cancel_flag, split_size_mb, use_md5);
+        if needs_key {
+            self.show_encrypt_key_dialog();
+        } else if let Some(message) = prompt {
+            self.show_passphrase_dialog(message);
+        } else if let Some(pending) = self.pending_crypto.take() {
+            self.run_crypto(pending);
+        }
+    }
+
+    fn show_encrypt_key_dialog(&mut self) {
+        let input = self.settings.encrypt_key.clone();
+        let cursor = input.chars().count();
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::EncryptKey,
+            input,
+            cursor_pos: cursor,
+            message: "Key: machine, passphrase, keyring name or @recipients".to_string(),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
         });
+    }
 
-        self.file_operation_progress = Some(progress);
+    fn show_passphrase_dialog(&mut self, message: &str) {
         self.dialog = Some(Dialog {
-            dialog_type: DialogType::Progress,
+            dialog_type: DialogType::Passphrase,
             input: String::new(),
             cursor_pos: 0,
-            message: String::new(),
+            message: message.to_string(),
             completion: None,
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
-    pub fn execute_decrypt(&mut self) {
-        let key_path = match crate::enc::ensure_key() {
-            Ok(p) => p,
+    /// Key picked in the key dialog
+    pub fn select_encrypt_key(&mut self, source: crate::enc::keys::KeySource) {
+        // Remember the choice for next time (never the passphrase itself)
+        self.settings.encrypt_key = source.spec();
+        if let Some(ref mut pending) = self.pending_crypto {
+            pending.target = Some(source);
+        }
+        self.continue_crypto();
+    }
+
+    /// Enter in the passphrase dialog. An error keeps the dialog open.
+    pub fn submit_passphrase(&mut self, input: String) -> Result<(), String> {
+        use crate::enc::keys::{KeySource, MIN_PASSPHRASE_LEN};
+
+        let Some(pending) = self.pending_crypto.as_mut() else {
+            self.dialog = None;
+            return Ok(());
+        };
+        if input.is_empty() {
+            return Err("Passphrase is empty!".to_string());
+        }
+        if pending.needs_passphrase && pending.passphrase.is_none() {
+            pending.passphrase = Some(input);
+        } else if let Some(KeySource::Passphrase(target)) = pending.target.as_mut() {
+            match pending.new_passphrase.take() {
+                None if input.chars().count() < MIN_PASSPHRASE_LEN => {
+                    return Err(format!("Use at least {} characters!", MIN_PASSPHRASE_LEN));
+                }
+                None => pending.new_passphrase = Some(input),
+                Some(first) if first == input => *target = input,
+                Some(_) => {
+                    self.show_passphrase_dialog("Passphrases differ. New passphrase!");
+                    return Ok(());
+                }
+            }
+        }
+        self.dialog = None;
+        self.continue_crypto();
+        Ok(())
+    }
+
+    fn run_crypto(&mut self, pending: PendingCrypto) {
+        let credential_dir = match crate::enc::keys::credential_dir() {
+            Ok(dir) => dir,
             Err(e) => {
                 self.show_message(&format!("Key error: {}", e));
                 return;
             }
         };
+        let new_key = match &pending.target {
+            Some(source) => match source.load(&credential_dir) {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    self.show_message(&format!("Key error: {}", e));
+                    return;
+                }
+            },
+            None => None,
+        };
+        let keys = crate::enc::keys::KeyResolver::new(credential_dir, pending.passphrase);
 
         let dir = self.active_panel().path.clone();
 
-        let mut progress = FileOperationProgress::new(FileOperationType::Decrypt);
+        let operation_type = match pending.op {
+            CryptoOp::Encrypt { .. } => FileOperationType::Encrypt,
+            CryptoOp::Decrypt => FileOperationType::Decrypt,
+            CryptoOp::Rekey => FileOperationType::Rekey,
+            CryptoOp::Verify => FileOperationType::Verify,
+            CryptoOp::List => FileOperationType::List,
+            CryptoOp::Vault => {
+                let panel = self.active_panel_mut();
+                panel.vault = Some(crate::enc::vault::Vault::new(keys));
+                panel.selected_files.clear();
+                panel.load_files();
+                self.show_message("Vault view: .cokacenc files are shown as their originals");
+                return;
+            }
+        };
+        let mut progress = FileOperationProgress::new(operation_type);
         progress.is_active = true;
         let cancel_flag = progress.cancel_flag.clone();
 
         let (tx, rx) = mpsc::channel();
         progress.receiver = Some(rx);
+        let (listing_tx, listing_rx) = mpsc::channel();
+        if pending.op == CryptoOp::List {
+            self.pending_enc_list = Some(listing_rx);
+        }
 
-        thread::spawn(move || {
-            crate::enc::unpack_directory_with_progress(&dir, &key_path, tx, cancel_flag);
+        thread::spawn(move || match (pending.op, new_key) {
+            (CryptoOp::Encrypt { split_size_mb, use_md5, compress }, Some(key)) => {
+                crate::enc::pack_directory_with_progress(&dir, &key, tx, cancel_flag, split_size_mb, use_md5, compress);
+            }
+            (CryptoOp::Rekey, Some(key)) => {
+                crate::enc::rekey_directory_with_progress(&dir, keys, &key, tx, cancel_flag);
+            }
+            (CryptoOp::Verify, _) => crate::enc::verify_directory_with_progress(&dir, keys, tx, cancel_flag),
+            // Only the metadata at the start of each group is read
+            (CryptoOp::List, _) => crate::enc::list_groups_with_progress(&dir, keys, tx, listing_tx, cancel_flag),
+            _ => crate::enc::unpack_directory_with_progress(&dir, keys, tx, cancel_flag),
         });
 
         self.file_operation_progress = Some(progress);
@@ -3244,6 +4128,61 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Show the groups read by a listing once its progress completes
+    pub fn finish_enc_list(&mut self, message: Option<String>) {
+        let Some(rx) = self.pending_enc_list.take() else { return };
+        let cancelled = self.file_operation_progress.take()
+            .map(|progress| progress.cancel_flag.load(Ordering::Relaxed))
+            .unwrap_or(false);
+        self.dialog = None;
+
+        let groups: Vec<_> = rx.try_iter().collect();
+        if cancelled {
+            self.show_message("Listing cancelled");
+        } else if groups.is_empty() {
+            // The directory could not be read
+            if let Some(msg) = message {
+                self.show_message(&msg);
+            }
+        } else {
+            self.show_enc_list(&groups);
+        }
+    }
+
+    fn show_enc_list(&mut self, groups: &[crate::enc::GroupListing]) {
+        let lines = groups.iter().map(|group| match &group.info {
+            Ok(info) => {
+                let modified = Local.timestamp_opt(info.modified, 0).single()
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                let chunks = if group.chunks_found == info.chunks {
+                    format!("{} chunk(s)", info.chunks)
+                } else {
+                    format!("{}/{} chunk(s)", group.chunks_found, info.chunks)
+                };
+                format!(
+                    "{}  {}  {}  {}  {}",
+                    info.filename, crate::utils::format::format_size(info.size), modified, chunks, info.key.label(),
+                )
+            }
+            Err(e) => format!("{}  {} chunk(s)  {}", group.group_id, group.chunks_found, e),
+        }).collect();
+
+        self.enc_list_state = Some(EncListState { lines, scroll_offset: 0 });
+        self.dialog = Some(Dialog {
+            dialog_type: DialogType::EncList,
+            input: String::new(),
+            cursor_pos: 0,
+            message: format!("{} group(s) in {}", groups.len(), self.active_panel().path.display()),
+            completion: None,
+            selected_button: 0,
+            selection: None,
+            use_md5: false,
+            compress: false,
         });
     }
 
@@ -3257,6 +4196,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3270,6 +4210,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3306,6 +4247,7 @@ impl App {
                     selected_button: 0,
                     selection: Some((0, selection_end)),
                     use_md5: false,
+                    compress: false,
                 });
             } else {
                 self.show_message("Select a file to rename");
@@ -3340,6 +4282,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3353,6 +4296,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3368,6 +4312,7 @@ impl App {
             selected_button: 0,
             selection: Some((0, len)),  // 전체 선택
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3441,26 +4386,114 @@ impl App {
         self.current_screen = Screen::GitScreen;
     }
 
+    /// Open the git screen's log limited to the current file (or directory)
+    pub fn show_git_file_history(&mut self) {
+        let panel = self.active_panel();
+        if panel.is_remote() {
+            self.show_message("Not available on remote panels");
+            return;
+        }
+        let file = match panel.files.get(panel.selected_index) {
+            Some(f) if f.name != ".." => f.clone(),
+            _ => return,
+        };
+        let dir = panel.path.clone();
+        let repo_root = match crate::ui::git_screen::get_repo_root(&dir) {
+            Some(r) => r,
+            None => {
+                self.show_message("Not a git repository");
+                return;
+            }
+        };
+        let rel = match crate::ui::git_screen::repo_relative_path(&dir, &file.name) {
+            Some(rel) => rel,
+            None => {
+                self.show_message("Failed to resolve path in repository");
+                return;
+            }
+        };
+
+        let mut state = crate::ui::git_screen::GitScreenState::load(repo_root);
+        // --follow only works for a single file
+        state.open_file_history(&rel, !file.is_directory);
+        self.git_screen_state = Some(state);
+        self.current_screen = Screen::GitScreen;
+    }
+
+    /// Open the git screen on a commit's diff (from the file viewer's blame)
+    pub fn show_git_commit(&mut self, repo_root: PathBuf, hash: &str) {
+        let mut state = crate::ui::git_screen::GitScreenState::load(repo_root);
+        state.open_commit(hash);
+        self.git_screen_state = Some(state);
+        self.previous_screen = Some(Screen::FileViewer);
+        self.current_screen = Screen::GitScreen;
+    }
+
+    /// Open a git worktree in a new panel next to the active one
+    pub fn open_worktree_panel(&mut self, path: &Path) {
+        let count = self.panels.len();
+        self.add_panel();
+        if self.panels.len() == count {
+            self.show_message("Too many panels");
+            return;
+        }
+        self.goto_directory_with_focus(path, None);
+        self.show_message(&format!("Opened worktree: {}", path.display()));
+    }
+
+    /// Ask which directories to search for duplicates, starting with the active panel
     pub fn show_dedup_screen(&mut self) {
-        let path = self.active_panel().path.clone();
+        self.show_dedup_roots_dialog(DialogType::DedupRoots);
+    }
+
+    /// Like `show_dedup_screen`, for images that only look alike
+    pub fn show_similar_images_screen(&mut self) {
+        self.show_dedup_roots_dialog(DialogType::SimilarRoots);
+    }
+
+    fn show_dedup_roots_dialog(&mut self, dialog_type: DialogType) {
+        if self.active_panel().is_remote() {
+            self.show_message("Not available on remote panels");
+            return;
+        }
+        let input = crate::services::dedup::DedupScope::single(self.active_panel().path.clone()).to_input();
+        let cursor_pos = input.chars().count();
         self.dialog = Some(Dialog {
-            dialog_type: DialogType::DedupConfirm,
-            input: String::new(),
-            cursor_pos: 0,
-            message: format!("WARNING: This will PERMANENTLY DELETE duplicate files in {}. This action cannot be undone. Proceed?", path.display()),
+            dialog_type,
+            input,
+            cursor_pos,
+            message: "Dirs separated by ';', A => B: files in A found in B (Tab: panels)".to_string(),
             completion: None,
-            selected_button: 1,  // Default: No
+            // Index of the next preset to insert with Tab
+            selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
-    pub fn execute_dedup(&mut self) {
-        let path = self.active_panel().path.clone();
-        self.dedup_screen_state = Some(crate::ui::dedup_screen::DedupScreenState::new(path));
+    pub fn execute_dedup(&mut self, scope: crate::services::dedup::DedupScope) {
+        self.dedup_screen_state = Some(crate::ui::dedup_screen::DedupScreenState::new(scope));
         self.current_screen = Screen::DedupScreen;
     }
 
+    pub fn execute_similar_images(&mut self, scope: crate::services::dedup::DedupScope) {
+        self.dedup_screen_state = Some(crate::ui::dedup_screen::DedupScreenState::new_similar(scope));
+        self.current_screen = Screen::DedupScreen;
+    }
+
+    /// Show a group of similar images in the image viewer; closing it
+    /// returns to the dedup review
+    pub fn open_dedup_preview(&mut self, images: Vec<PathBuf>, index: usize) {
+        let path = match images.get(index) {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        self.image_viewer_state = Some(crate::ui::image_viewer::ImageViewerState::with_list(&path, images, index));
+        self.previous_screen = Some(Screen::DedupScreen);
+        self.current_screen = Screen::ImageViewer;
+    }
+
     pub fn show_git_log_diff_dialog(&mut self) {
         let path = self.active_panel().path.clone();
         if !crate::ui::git_screen::is_git_repo(&path) {
@@ -3501,6 +4534,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -3868,6 +4902,7 @@ impl App {
                     selected_button: 0,
                     selection: None,
                     use_md5: false,
+                    compress: false,
                 });
 
                 // Keep clipboard for copy, consume for cut
@@ -3946,6 +4981,7 @@ impl App {
                 selected_button: 0,
                 selection: None,
                 use_md5: false,
+                compress: false,
             });
 
             // Keep clipboard for copy, consume for cut
@@ -4110,6 +5146,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
     }
 
@@ -4179,6 +5216,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
 
         // Keep clipboard for copy operations (can paste multiple times)
@@ -4311,6 +5349,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
 
         // Keep clipboard for copy operations
@@ -4426,6 +5465,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
 
         // Keep clipboard for copy operations (can paste multiple times)
@@ -4755,6 +5795,7 @@ impl App {
                 selected_button: 0,
                 selection: None,
                 use_md5: false,
+                compress: false,
             });
             return;
         }
@@ -4813,6 +5854,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
 
         // Clone tar_path from settings for use in background thread
@@ -5187,6 +6229,7 @@ impl App {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         });
 
         // Clone tar_path from settings for use in background thread
@@ -5593,6 +6636,7 @@ impl App {
                 selected_button: 0,
                 selection: None,
                 use_md5: false,
+                compress: false,
             });
         }
     }
@@ -5662,6 +6706,14 @@ impl App {
                         }
                     }
                 }
+                Err(remote::ConnectError::HostKey(error)) => {
+                    let _ = tx.send(RemoteSpinnerResult::HostKeyRejected {
+                        error,
+                        profile: profile_clone,
+                        path: path_clone,
+                    });
+                    return;
+                }
                 Err(e) => Err(format!("Connection failed: {}", e)),
             };
             let _ = tx.send(RemoteSpinnerResult::Connected { result, panel_idx });
@@ -5674,6 +6726,39 @@ impl App {
         });
     }
 
+    /// Ask whether to trust the key of a host seen for the first time, or
+    /// say why the key of a known host was refused
+    fn show_host_key_dialog(&mut self, error: HostKeyError, profile: RemoteProfile, path: String) {
+        let (dialog_type, message) = match error {
+            HostKeyError::Unknown(key) => {
+                let message = format!("{}\n\nTrust this host and connect?", HostKeyError::Unknown(key.clone()));
+                self.pending_host_key = Some(PendingHostKey { key, profile, path });
+                (DialogType::HostKeyConfirm, message)
+            }
+            changed => (DialogType::HostKeyChanged, changed.to_string()),
+        };
+        self.dialog = Some(Dialog {
+            dialog_type,
+            input: String::new(),
+            cursor_pos: 0,
+            message,
+            completion: None,
+            selected_button: 1,  // Default: No
+            selection: None,
+            use_md5: false,
+            compress: false,
+        });
+    }
+
+    /// Record the pending host key as trusted and connect again
+    pub fn trust_host_key(&mut self) {
+        let Some(pending) = self.pending_host_key.take() else { return };
+        match known_hosts::trust(&pending.key) {
+            Ok(()) => self.connect_remote_panel(&pending.profile, &pending.path),
+            Err(e) => self.show_message(&e),
+        }
+    }
+
     /// Disconnect remote panel and switch back to local
     pub fn disconnect_remote_panel(&mut self) {
         let panel = self.active_panel_mut();
@@ -5756,6 +6841,13 @@ impl App {
         });
     }
 
+    /// Apply finished git status refreshes of all panels
+    pub fn poll_git_status(&mut self) {
+        for panel in self.panels.iter_mut() {
+            panel.poll_git_status();
+        }
+    }
+
     /// Poll the remote spinner for completion
     pub fn poll_remote_spinner(&mut self) {
         let result = if let Some(ref spinner) = self.remote_spinner {
@@ -5836,6 +6928,9 @@ impl App {
                     }
                 }
             }
+            RemoteSpinnerResult::HostKeyRejected { error, profile, path } => {
+                self.show_host_key_dialog(error, profile, path);
+            }
             RemoteSpinnerResult::PanelOp { ctx, panel_idx, outcome } => {
                 // Return ctx to panel
                 self.panels[panel_idx].remote_ctx = Some(ctx);
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Receiver;
 use std::sync::Arc;
@@ -12,13 +12,20 @@ use ratatui::{
     Frame,
 };
 
-use crate::services::dedup::{self, DedupMessage, DedupPhase};
+use crate::services::dedup::{self, DedupAction, DedupMessage, DedupPhase, DedupScope, DuplicateGroup, KeepRule};
+use crate::services::similar_images::{self, HashAlgorithm, ImageHashes};
+use crate::ui::app::PanelState;
 use crate::ui::theme::Theme;
 
 const MAX_LOG_LINES: usize = 10_000;
 
+/// Log tags of processed duplicates, see `DedupAction::log_tag`
+const APPLY_TAGS: [&str; 4] = ["REMOVE", "HARDLINK", "REFLINK", "SYMLINK"];
+
 pub struct DedupScreenState {
-    pub target_path: PathBuf,
+    pub scope: DedupScope,
+    /// Short names of the scope's directories, see `DedupScope::labels`
+    pub root_labels: Vec<String>,
     pub phase: DedupPhase,
     pub current_file: String,
     pub progress: u8,
@@ -30,21 +37,65 @@ pub struct DedupScreenState {
     pub is_complete: bool,
     pub receiver: Option<Receiver<DedupMessage>>,
     pub cancel_flag: Arc<AtomicBool>,
+
+    // Review of the found groups before anything is changed
+    pub groups: Vec<DuplicateGroup>,
+    /// Row of the review list (group headers and files)
+    pub review_selected: usize,
+    pub review_scroll: usize,
+    pub action: DedupAction,
+    pub keep_rule: KeepRule,
+    /// Waiting for y/n before `action` is applied
+    pub confirm_apply: bool,
+
+    // Similar images mode, see `new_similar`
+    pub similar: bool,
+    /// Every hashed image; `groups` is rebuilt from these on each change
+    pub images: Arc<Vec<ImageHashes>>,
+    pub algorithm: HashAlgorithm,
+    pub max_distance: u32,
+    /// Images to open in the image viewer and the one to show first; taken
+    /// by the caller of `handle_input`
+    pub preview: Option<(Vec<PathBuf>, usize)>,
+}
+
+/// Row of the review list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReviewRow {
+    Group(usize),
+    File(usize, usize),
 }
 
 impl DedupScreenState {
-    pub fn new(path: PathBuf) -> Self {
+    /// Find byte-identical files
+    pub fn new(scope: DedupScope) -> Self {
+        Self::start(scope, false)
+    }
+
+    /// Find images that look alike (resized or re-encoded copies); only
+    /// `DedupAction::Delete` is offered since the copies differ
+    pub fn new_similar(scope: DedupScope) -> Self {
+        Self::start(scope, true)
+    }
+
+    fn start(scope: DedupScope, similar: bool) -> Self {
         let (tx, rx) = std::sync::mpsc::channel();
         let cancel_flag = Arc::new(AtomicBool::new(false));
         let flag_clone = cancel_flag.clone();
-        let path_clone = path.clone();
+        let scope_clone = scope.clone();
+        let algorithm = HashAlgorithm::Difference;
 
         std::thread::spawn(move || {
-            dedup::run_dedup(path_clone, tx, flag_clone);
+            if similar {
+                similar_images::run_similar(scope_clone, algorithm, tx, flag_clone);
+            } else {
+                dedup::run_dedup(scope_clone, tx, flag_clone);
+            }
         });
 
         Self {
-            target_path: path,
+            root_labels: scope.labels(),
+            scope,
             phase: DedupPhase::Scanning,
             current_file: String::new(),
             progress: 0,
@@ -56,9 +107,107 @@ impl DedupScreenState {
             is_complete: false,
             receiver: Some(rx),
             cancel_flag,
+            groups: Vec::new(),
+            review_selected: 0,
+            review_scroll: 0,
+            action: DedupAction::Delete,
+            keep_rule: if similar { KeepRule::LargestImage } else { KeepRule::Oldest },
+            confirm_apply: false,
+            similar,
+            images: Arc::new(Vec::new()),
+            algorithm,
+            max_distance: similar_images::DEFAULT_DISTANCE,
+            preview: None,
         }
     }
 
+    /// A background scan or apply is still running
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    fn review_rows(&self) -> Vec<ReviewRow> {
+        let mut rows = Vec::new();
+        for (g, group) in self.groups.iter().enumerate() {
+            rows.push(ReviewRow::Group(g));
+            rows.extend((0..group.files.len()).map(|f| ReviewRow::File(g, f)));
+        }
+        rows
+    }
+
+    fn selected_row(&self) -> Option<ReviewRow> {
+        self.review_rows().get(self.review_selected).copied()
+    }
+
+    fn set_keep_rule(&mut self, rule: KeepRule) {
+        for group in self.groups.iter_mut() {
+            group.apply_rule(&rule);
+        }
+        self.keep_rule = rule;
+    }
+
+    /// Rebuild the similar image groups after the algorithm or distance
+    /// changed. Large libraries take a while, so this runs in the background
+    /// and `poll` picks up the new `DedupMessage::Groups`.
+    fn regroup(&mut self) {
+        // A newer regroup replaces one still running
+        self.cancel_flag.store(true, Ordering::Relaxed);
+        self.cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let images = self.images.clone();
+        let algorithm = self.algorithm;
+        let max_distance = self.max_distance;
+        let with_references = !self.scope.references.is_empty();
+        let flag_clone = self.cancel_flag.clone();
+
+        std::thread::spawn(move || {
+            let groups = similar_images::similar_groups(&images, algorithm, max_distance, with_references, &flag_clone);
+            let _ = tx.send(DedupMessage::Groups(groups));
+        });
+
+        self.receiver = Some(rx);
+    }
+
+    /// Make `path` the survivor of its group; false if it is in none
+    pub fn keep_path(&mut self, path: &Path) -> bool {
+        for group in self.groups.iter_mut() {
+            if let Some(i) = group.files.iter().position(|f| f.path == path) {
+                group.keep = i;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn is_kept(&self, path: &Path) -> bool {
+        self.groups.iter().any(|g| g.files.get(g.keep).is_some_and(|f| f.path == path))
+    }
+
+    /// (files to process, bytes freed) of the groups that are not excluded
+    fn pending_totals(&self) -> (usize, u64) {
+        self.groups
+            .iter()
+            .filter(|g| !g.excluded)
+            .fold((0, 0), |(files, bytes), g| (files + g.to_process().count(), bytes + g.reclaimable()))
+    }
+
+    fn start_apply(&mut self) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let groups = std::mem::take(&mut self.groups);
+        let action = self.action;
+        let scanned = self.scanned;
+        let flag_clone = self.cancel_flag.clone();
+
+        std::thread::spawn(move || {
+            dedup::run_apply(groups, action, scanned, tx, flag_clone);
+        });
+
+        self.phase = DedupPhase::Applying;
+        self.progress = 0;
+        self.receiver = Some(rx);
+    }
+
     fn push_log(&mut self, line: String) {
         if self.log_lines.len() >= MAX_LOG_LINES {
             self.log_lines.remove(0);
@@ -93,7 +242,7 @@ impl DedupScreenState {
                     self.current_file = path;
                     self.progress = pct;
                 }
-                DedupMessage::Deleting(path) => {
+                DedupMessage::Applying(path) => {
                     self.current_file = path;
                 }
                 DedupMessage::Log(msg) => {
@@ -107,6 +256,17 @@ impl DedupScreenState {
                 DedupMessage::Error(msg) => {
                     self.push_log(format!("[ERROR] {}", msg));
                 }
+                DedupMessage::Groups(groups) => {
+                    // The scan or regroup is done; wait for the review
+                    self.groups = groups;
+                    let rule = self.keep_rule.clone();
+                    self.set_keep_rule(rule);
+                    self.review_selected = self.review_selected.min(self.review_rows().len().saturating_sub(1));
+                    self.receiver = None;
+                }
+                DedupMessage::Images(images) => {
+                    self.images = Arc::new(images);
+                }
                 DedupMessage::Complete => {
                     self.is_complete = true;
                     self.receiver = None;
@@ -116,6 +276,30 @@ impl DedupScreenState {
     }
 }
 
+/// Scopes offered by Tab in the dedup dialog: the active panel, all local
+/// panels, and the active panel against the other panels
+pub fn scope_presets(panels: &[PanelState], active_index: usize) -> Vec<(&'static str, DedupScope)> {
+    let active = match panels.get(active_index) {
+        Some(panel) => panel.path.clone(),
+        None => return Vec::new(),
+    };
+    let mut others: Vec<PathBuf> = Vec::new();
+    for (i, panel) in panels.iter().enumerate() {
+        if i != active_index && !panel.is_remote() && panel.path != active && !others.contains(&panel.path) {
+            others.push(panel.path.clone());
+        }
+    }
+
+    let mut presets = vec![("Active panel", DedupScope::single(active.clone()))];
+    if !others.is_empty() {
+        let mut all = vec![active.clone()];
+        all.extend(others.iter().cloned());
+        presets.push(("All panels", DedupScope { roots: all, references: Vec::new() }));
+        presets.push(("Active panel in other panels", DedupScope { roots: vec![active], references: others }));
+    }
+    presets
+}
+
 pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme: &Theme) {
     // Poll messages before drawing
     state.poll();
@@ -135,8 +319,11 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
     // ── Unified info box ──
     let phase_text = match state.phase {
         DedupPhase::Scanning => "Scanning...",
+        DedupPhase::PartialHashing => "Comparing Partial Hashes...",
+        DedupPhase::Hashing if state.similar => "Hashing Images...",
         DedupPhase::Hashing => "Computing Hashes...",
-        DedupPhase::Deleting => "Removing Duplicates...",
+        DedupPhase::Review => "Review",
+        DedupPhase::Applying => "Applying...",
         DedupPhase::Complete => "Complete",
     };
 
@@ -144,7 +331,7 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
         .borders(Borders::ALL)
         .border_style(Style::default().fg(colors.border))
         .title(Span::styled(
-            " Remove Duplicates ",
+            if state.similar { " Similar Images " } else { " Remove Duplicates " },
             Style::default().fg(colors.title).add_modifier(Modifier::BOLD),
         ))
         .style(Style::default().bg(colors.bg));
@@ -153,7 +340,7 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
     let line1 = Line::from(vec![
         Span::styled("Target: ", Style::default().fg(colors.stats_text)),
         Span::styled(
-            state.target_path.display().to_string(),
+            state.scope.to_input(),
             Style::default().fg(colors.phase_text),
         ),
         Span::raw("  "),
@@ -164,13 +351,54 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
     ]);
 
     // Line 2: stats
-    let line2 = Line::from(vec![
+    let line2 = if state.phase == DedupPhase::Review {
+        let (files, bytes) = state.pending_totals();
+        let mut spans = vec![
+            Span::styled("Groups: ", Style::default().fg(colors.stats_text)),
+            Span::styled(
+                format!("{}", state.groups.len()),
+                Style::default().fg(colors.phase_text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  |  Duplicates: ", Style::default().fg(colors.stats_text)),
+            Span::styled(
+                format!("{}", files),
+                Style::default().fg(colors.log_deleted).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  |  Reclaimable: ", Style::default().fg(colors.stats_text)),
+            Span::styled(
+                dedup::format_size(bytes),
+                Style::default().fg(colors.phase_text).add_modifier(Modifier::BOLD),
+            ),
+        ];
+        // Similar images are always deleted
+        if !state.similar {
+            spans.push(Span::styled("  |  Action: ", Style::default().fg(colors.stats_text)));
+            spans.push(Span::styled(
+                state.action.label(),
+                Style::default().fg(colors.log_deleted).add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.push(Span::styled("  |  Keep: ", Style::default().fg(colors.stats_text)));
+        spans.push(Span::styled(
+            state.keep_rule.label(),
+            Style::default().fg(colors.keep_text).add_modifier(Modifier::BOLD),
+        ));
+        if state.similar {
+            spans.push(Span::styled("  |  Match: ", Style::default().fg(colors.stats_text)));
+            spans.push(Span::styled(
+                format!("{} <= {} bits", state.algorithm.label(), state.max_distance),
+                Style::default().fg(colors.phase_text).add_modifier(Modifier::BOLD),
+            ));
+        }
+        Line::from(spans)
+    } else {
+        Line::from(vec![
         Span::styled("Scanned: ", Style::default().fg(colors.stats_text)),
         Span::styled(
             format!("{}", state.scanned),
             Style::default().fg(colors.phase_text).add_modifier(Modifier::BOLD),
         ),
-        Span::styled("  |  Duplicates removed: ", Style::default().fg(colors.stats_text)),
+        Span::styled("  |  Duplicates processed: ", Style::default().fg(colors.stats_text)),
         Span::styled(
             format!("{}", state.duplicates),
             Style::default().fg(colors.log_deleted).add_modifier(Modifier::BOLD),
@@ -180,11 +408,24 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
             dedup::format_size(state.freed),
             Style::default().fg(colors.phase_text).add_modifier(Modifier::BOLD),
         ),
-    ]);
+        ])
+    };
 
     let info = Paragraph::new(vec![line1, line2]).block(info_block);
     frame.render_widget(info, chunks[0]);
 
+    if state.phase == DedupPhase::Review {
+        draw_review(frame, state, chunks[1], theme);
+    } else {
+        draw_log(frame, state, chunks[1], theme);
+    }
+
+    draw_footer(frame, state, chunks[2], theme);
+}
+
+fn draw_log(frame: &mut Frame, state: &DedupScreenState, area: Rect, theme: &Theme) {
+    let colors = &theme.dedup_screen;
+
     // ── Log area ──
     let log_block = Block::default()
         .borders(Borders::ALL)
@@ -195,7 +436,7 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
         ))
         .style(Style::default().bg(colors.bg));
 
-    let inner_height = chunks[1].height.saturating_sub(2) as usize; // borders
+    let inner_height = area.height.saturating_sub(2) as usize; // borders
 
     let skip_count = state.log_scroll.saturating_sub(inner_height.saturating_sub(1));
     let ca = Style::default().fg(colors.log_text);
@@ -208,23 +449,22 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
         .map(|line| {
             if line.starts_with("[ERROR]") {
                 Line::from(Span::styled(line.as_str(), Style::default().fg(colors.log_error)))
-            } else if line.starts_with("REMOVE ") {
-                // REMOVE {hash} {path}
-                let rest = &line[7..];
+            } else if let Some((tag, rest)) = line.split_once(' ').filter(|(tag, _)| APPLY_TAGS.contains(tag)) {
+                // {REMOVE|HARDLINK|REFLINK|SYMLINK} {hash} {path}
                 if let Some(sp) = rest.find(' ') {
                     Line::from(vec![
-                        Span::styled("REMOVE ", Style::default().fg(colors.log_deleted)),
+                        Span::styled(format!("{} ", tag), Style::default().fg(colors.log_deleted)),
                         Span::styled(&rest[..sp], ca),
                         Span::styled(&rest[sp..], cb),
                     ])
                 } else {
                     Line::from(Span::styled(line.as_str(), Style::default().fg(colors.log_deleted)))
                 }
-            } else if line.starts_with("READING ") {
+            } else if let Some(path) = line.strip_prefix("READING ") {
                 // READING {path}
                 Line::from(vec![
                     Span::styled("READING ", ca),
-                    Span::styled(&line[8..], cb),
+                    Span::styled(path, cb),
                 ])
             } else if let Some(pct_pos) = line.find(" % ") {
                 // {hash} {pct} % {size} {path}
@@ -254,10 +494,141 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
     let log = Paragraph::new(log_lines)
         .block(log_block)
         .wrap(Wrap { trim: false });
-    frame.render_widget(log, chunks[1]);
+    frame.render_widget(log, area);
+}
+
+fn draw_review(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme: &Theme) {
+    let colors = &theme.dedup_screen;
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.border))
+        .title(Span::styled(
+            if state.is_running() { " Review Duplicates (grouping...) " } else { " Review Duplicates " },
+            Style::default().fg(colors.title),
+        ))
+        .style(Style::default().bg(colors.bg));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = state.review_rows();
+    let visible_height = inner.height as usize;
+    if state.review_selected < state.review_scroll {
+        state.review_scroll = state.review_selected;
+    }
+    if visible_height > 0 && state.review_selected >= state.review_scroll + visible_height {
+        state.review_scroll = state.review_selected - visible_height + 1;
+    }
+
+    let width = inner.width as usize;
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(state.review_scroll)
+        .take(visible_height)
+        .map(|(i, row)| {
+            let (text, style) = match *row {
+                ReviewRow::Group(g) => {
+                    let group = &state.groups[g];
+                    let mut text = if state.similar {
+                        format!("{} images  {}:{}", group.files.len(), state.algorithm.label(), group.hash)
+                    } else {
+                        format!(
+                            "{} files x {}  sha256:{}",
+                            group.files.len(),
+                            dedup::format_size(group.size),
+                            &group.hash[..group.hash.len().min(12)]
+                        )
+                    };
+                    if group.excluded {
+                        text.push_str("  [skipped]");
+                    }
+                    (text, Style::default().fg(colors.log_text_alt))
+                }
+                ReviewRow::File(g, f) => {
+                    let group = &state.groups[g];
+                    let file = &group.files[f];
+                    let root = state.scope.all_dirs().nth(file.root);
+                    let rel = root.and_then(|r| file.path.strip_prefix(r).ok()).unwrap_or(&file.path).display();
+                    // With several directories, say which one each copy lives in
+                    let mut shown = match state.root_labels.get(file.root) {
+                        Some(label) if state.root_labels.len() > 1 => format!("[{}] {}", label, rel),
+                        _ => rel.to_string(),
+                    };
+                    // Similar images differ: show what is being compared
+                    if let Some((w, h)) = file.dimensions {
+                        let detail = format!("{:>11} {:>9}  ", format!("{}x{}", w, h), dedup::format_size(file.size));
+                        shown.insert_str(0, &detail);
+                    }
+                    if group.excluded {
+                        (format!("           {}", shown), Style::default().fg(colors.log_text_alt))
+                    } else if f == group.keep {
+                        (format!("  {:<9}{}", "KEEP", shown), Style::default().fg(colors.keep_text))
+                    } else if file.reference {
+                        (format!("  {:<9}{}", "REF", shown), Style::default().fg(colors.log_text))
+                    } else {
+                        (format!("  {:<9}{}", state.action.log_tag(), shown), Style::default().fg(colors.log_deleted))
+                    }
+                }
+            };
+            if i == state.review_selected {
+                let padded = format!("{:<width$}", text, width = width);
+                Line::from(Span::styled(padded, Style::default().fg(colors.selected_text).bg(colors.selected_bg)))
+            } else {
+                Line::from(Span::styled(text, style))
+            }
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_footer(frame: &mut Frame, state: &DedupScreenState, area: Rect, theme: &Theme) {
+    let colors = &theme.dedup_screen;
+
+    if state.confirm_apply {
+        let (files, bytes) = state.pending_totals();
+        let prompt = Line::from(vec![
+            Span::styled(
+                format!(" {} {} file(s), freeing {}? ", state.action.label(), files, dedup::format_size(bytes)),
+                Style::default().fg(colors.log_deleted).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("y", Style::default().fg(colors.footer_key).add_modifier(Modifier::BOLD)),
+            Span::styled(" Yes  ", Style::default().fg(colors.footer_text)),
+            Span::styled("n", Style::default().fg(colors.footer_key).add_modifier(Modifier::BOLD)),
+            Span::styled(" No", Style::default().fg(colors.footer_text)),
+        ]);
+        frame.render_widget(Paragraph::new(prompt).style(Style::default().bg(colors.bg)), area);
+        return;
+    }
 
     // ── Footer ──
-    let footer_items = if state.is_complete {
+    let footer_items = if state.phase == DedupPhase::Review && state.similar {
+        let key = |k: &'static str| Span::styled(k, Style::default().fg(colors.footer_key).add_modifier(Modifier::BOLD));
+        let text = |t: &'static str| Span::styled(t, Style::default().fg(colors.footer_text));
+        vec![
+            key(" Enter"), text(" Delete  "),
+            key("v"), text(" Preview  "),
+            key("Space"), text(" Keep this  "),
+            key("h"), text(" Hash  "),
+            key("+/-"), text(" Distance  "),
+            key("k"), text(" Keep rule  "),
+            key("x"), text(" Skip group  "),
+            key("Esc"), text(" Close"),
+        ]
+    } else if state.phase == DedupPhase::Review {
+        let key = |k: &'static str| Span::styled(k, Style::default().fg(colors.footer_key).add_modifier(Modifier::BOLD));
+        let text = |t: &'static str| Span::styled(t, Style::default().fg(colors.footer_text));
+        vec![
+            key(" Enter"), text(" Apply  "),
+            key("a"), text(" Action  "),
+            key("Space"), text(" Keep this  "),
+            key("k"), text(" Keep rule  "),
+            key("p"), text(" Prefer dir  "),
+            key("x"), text(" Skip group  "),
+            key("Esc"), text(" Close"),
+        ]
+    } else if state.is_complete {
         vec![
             Span::styled(" Esc", Style::default().fg(colors.footer_key).add_modifier(Modifier::BOLD)),
             Span::styled(" Close  ", Style::default().fg(colors.footer_text)),
@@ -279,11 +650,15 @@ pub fn draw(frame: &mut Frame, state: &mut DedupScreenState, area: Rect, theme:
 
     let footer = Paragraph::new(Line::from(footer_items))
         .style(Style::default().bg(colors.bg));
-    frame.render_widget(footer, chunks[2]);
+    frame.render_widget(footer, area);
 }
 
 /// Handle input. Returns true if screen should close.
 pub fn handle_input(state: &mut DedupScreenState, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    if state.phase == DedupPhase::Review {
+        return handle_review_input(state, code);
+    }
+
     let shift = modifiers.contains(KeyModifiers::SHIFT);
     match code {
         KeyCode::Esc => {
@@ -325,4 +700,106 @@ pub fn handle_input(state: &mut DedupScreenState, code: KeyCode, modifiers: KeyM
         _ => {}
     }
     false
-}
\ No newline at end of file
+}
+
+/// Keys of the review list. Returns true if screen should close.
+fn handle_review_input(state: &mut DedupScreenState, code: KeyCode) -> bool {
+    if state.confirm_apply {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                state.confirm_apply = false;
+                state.start_apply();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                state.confirm_apply = false;
+            }
+            _ => {}
+        }
+        return false;
+    }
+
+    let row_count = state.review_rows().len();
+    match code {
+        // Nothing has been touched yet
+        KeyCode::Esc => return true,
+        KeyCode::Up => {
+            state.review_selected = state.review_selected.saturating_sub(1);
+        }
+        KeyCode::Down if state.review_selected + 1 < row_count => {
+            state.review_selected += 1;
+        }
+        KeyCode::PageUp => {
+            state.review_selected = state.review_selected.saturating_sub(10);
+        }
+        KeyCode::PageDown => {
+            state.review_selected = (state.review_selected + 10).min(row_count.saturating_sub(1));
+        }
+        KeyCode::Home => {
+            state.review_selected = 0;
+        }
+        KeyCode::End => {
+            state.review_selected = row_count.saturating_sub(1);
+        }
+        KeyCode::Char(' ') => {
+            if let Some(ReviewRow::File(g, f)) = state.selected_row() {
+                state.groups[g].keep = f;
+            }
+        }
+        KeyCode::Char('x') => {
+            let g = match state.selected_row() {
+                Some(ReviewRow::Group(g)) | Some(ReviewRow::File(g, _)) => g,
+                None => return false,
+            };
+            state.groups[g].excluded = !state.groups[g].excluded;
+        }
+        // Links would replace an image with a different one
+        KeyCode::Char('a') if !state.similar => {
+            state.action = state.action.next();
+        }
+        KeyCode::Char('k') => {
+            let next = match state.keep_rule {
+                KeepRule::Oldest => KeepRule::Newest,
+                KeepRule::Newest => KeepRule::ShortestPath,
+                KeepRule::ShortestPath if state.similar => KeepRule::LargestImage,
+                KeepRule::ShortestPath | KeepRule::PreferDir(_) | KeepRule::LargestImage => KeepRule::Oldest,
+            };
+            state.set_keep_rule(next);
+        }
+        KeyCode::Char('h') if state.similar => {
+            state.algorithm = state.algorithm.next();
+            state.regroup();
+        }
+        KeyCode::Char('+') | KeyCode::Char('=') if state.similar => {
+            state.max_distance = (state.max_distance + 1).min(similar_images::MAX_DISTANCE);
+            state.regroup();
+        }
+        KeyCode::Char('-') if state.similar => {
+            state.max_distance = state.max_distance.saturating_sub(1);
+            state.regroup();
+        }
+        KeyCode::Char('v') if state.similar => {
+            let (g, f) = match state.selected_row() {
+                Some(ReviewRow::File(g, f)) => (g, f),
+                Some(ReviewRow::Group(g)) => (g, state.groups[g].keep),
+                None => return false,
+            };
+            let paths = state.groups[g].files.iter().map(|file| file.path.clone()).collect();
+            state.preview = Some((paths, f));
+        }
+        KeyCode::Char('p') => {
+            if let Some(ReviewRow::File(g, f)) = state.selected_row() {
+                if let Some(dir) = state.groups[g].files[f].path.parent() {
+                    let dir = dir.to_path_buf();
+                    state.set_keep_rule(KeepRule::PreferDir(dir));
+                }
+            }
+        }
+        // Not while a regroup is about to replace the groups
+        KeyCode::Enter if state.pending_totals().0 > 0 && !state.is_running() => {
+            state.confirm_apply = true;
+        }
+        _ => {}
+    }
+    false
+}
+
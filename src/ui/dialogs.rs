@@ -13,7 +13,7 @@ use ratatui::{
 
 use crate::keybindings::GotoAction;
 use crate::services::file_ops::FileOperationType;
-use crate::utils::format::{safe_suffix, safe_prefix};
+use crate::utils::format::{safe_suffix, safe_prefix, truncate_with_ellipsis};
 
 use super::{
     app::{App, ConflictResolution, ConflictState, Dialog, DialogType, GitLogDiffState, PathCompletion, RemoteConnectState, SettingsState, fuzzy_match},
@@ -271,16 +271,20 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
     // Y좌표는 max_height 기준 고정, 실제 높이는 동적
     let (width, height, max_height) = match dialog.dialog_type {
         DialogType::Delete | DialogType::LargeImageConfirm | DialogType::LargeFileConfirm | DialogType::TrueColorWarning
-        | DialogType::DecryptConfirm => {
+        | DialogType::DecryptConfirm | DialogType::RekeyConfirm | DialogType::DiffSyncConfirm => {
             (SIMPLE_DIALOG_WIDTH, CONFIRM_DIALOG_HEIGHT, CONFIRM_DIALOG_HEIGHT)
         }
-        DialogType::DedupConfirm => {
-            (60, 10, 10)
-        }
         DialogType::ExtensionHandlerError => {
             // Error dialog: wider to accommodate error messages, taller for multi-line
             (65, 8, 8)
         }
+        DialogType::HostKeyConfirm => {
+            // Host, key type and fingerprint, then the question
+            (70, 12, 12)
+        }
+        DialogType::HostKeyChanged => {
+            (70, 14, 14)
+        }
         DialogType::Goto => {
             let w = area.width.saturating_sub(DIALOG_MARGIN).max(DIALOG_MIN_WIDTH);
             let max_h = GOTO_BASE_HEIGHT + MAX_COMPLETION_HEIGHT;
@@ -302,8 +306,16 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         DialogType::Search | DialogType::Mkdir | DialogType::Mkfile | DialogType::Rename | DialogType::Tar => {
             (SIMPLE_DIALOG_WIDTH, SIMPLE_INPUT_HEIGHT, SIMPLE_INPUT_HEIGHT)
         }
+        DialogType::DiffExport | DialogType::DiffExclude | DialogType::DedupRoots
+        | DialogType::SimilarRoots => {
+            let w = area.width.saturating_sub(DIALOG_MARGIN).max(DIALOG_MIN_WIDTH);
+            (w, SIMPLE_INPUT_HEIGHT, SIMPLE_INPUT_HEIGHT)
+        }
+        DialogType::EncryptKey | DialogType::Passphrase => {
+            (DIALOG_MIN_WIDTH, SIMPLE_INPUT_HEIGHT, SIMPLE_INPUT_HEIGHT)
+        }
         DialogType::EncryptConfirm => {
-            (SIMPLE_DIALOG_WIDTH, 7, 7)
+            (SIMPLE_DIALOG_WIDTH, 8, 8)
         }
         DialogType::Progress => {
             (SIMPLE_DIALOG_WIDTH, PROGRESS_DIALOG_HEIGHT, PROGRESS_DIALOG_HEIGHT)
@@ -314,6 +326,12 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         DialogType::TarExcludeConfirm => {
             (60, 15, 15) // Exclude confirm dialog
         }
+        DialogType::EncList => {
+            let w = area.width.saturating_sub(DIALOG_MARGIN).max(DIALOG_MIN_WIDTH);
+            let lines = app.enc_list_state.as_ref().map_or(0, |s| s.lines.len()) as u16;
+            let h = (lines + ENC_LIST_EXTRA_HEIGHT).clamp(8, area.height.saturating_sub(4).max(8));
+            (w, h, h)
+        }
         DialogType::Settings => {
             (42, 6, 6) // Settings dialog: width=42, height=6
         }
@@ -340,14 +358,14 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         }
         DialogType::RemoteConnect => {
             let w = area.width.saturating_sub(DIALOG_MARGIN).max(DIALOG_MIN_WIDTH).min(80);
-            // Password: 5 fields + 1 gap + 1 help + 2 border = 9
-            // KeyFile:  6 fields + 1 gap + 1 help + 2 border = 10
+            // Password: 6 fields + 1 gap + 1 help + 2 border = 10
+            // KeyFile:  7 fields + 1 gap + 1 help + 2 border = 11
             // +1 for possible error message
             let has_error = app.remote_connect_state.as_ref().map(|s| s.error.is_some()).unwrap_or(false);
             let is_keyfile = app.remote_connect_state.as_ref()
                 .map(|s| s.auth_type == super::app::RemoteAuthType::KeyFile)
                 .unwrap_or(false);
-            let h = if is_keyfile { 10_u16 } else { 9_u16 } + if has_error { 1 } else { 0 };
+            let h = if is_keyfile { 11_u16 } else { 10_u16 } + if has_error { 1 } else { 0 };
             (w, h, h)
         }
         DialogType::RemoteProfileSave => {
@@ -388,8 +406,11 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         DialogType::DecryptConfirm => {
             draw_confirm_dialog(frame, dialog, dialog_area, theme, " Decrypt ");
         }
-        DialogType::DedupConfirm => {
-            draw_dedup_confirm_dialog(frame, dialog, dialog_area, theme);
+        DialogType::RekeyConfirm => {
+            draw_confirm_dialog(frame, dialog, dialog_area, theme, " Re-key ");
+        }
+        DialogType::DiffSyncConfirm => {
+            draw_confirm_dialog(frame, dialog, dialog_area, theme, " Sync ");
         }
         DialogType::LargeImageConfirm => {
             draw_confirm_dialog(frame, dialog, dialog_area, theme, " Large Image ");
@@ -403,7 +424,9 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         DialogType::Goto => {
             draw_goto_dialog(frame, app, dialog, dialog_area, theme);
         }
-        DialogType::Search | DialogType::Mkdir | DialogType::Mkfile | DialogType::Rename | DialogType::Tar => {
+        DialogType::Search | DialogType::Mkdir | DialogType::Mkfile | DialogType::Rename | DialogType::Tar
+        | DialogType::DiffExport | DialogType::DiffExclude | DialogType::DedupRoots
+        | DialogType::SimilarRoots | DialogType::EncryptKey | DialogType::Passphrase => {
             draw_simple_input_dialog(frame, dialog, dialog_area, theme);
         }
         DialogType::Progress => {
@@ -419,6 +442,11 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
                 draw_tar_exclude_confirm_dialog(frame, dialog, state, dialog_area, theme);
             }
         }
+        DialogType::EncList => {
+            if let Some(ref state) = app.enc_list_state {
+                draw_enc_list_dialog(frame, dialog, state, dialog_area, theme);
+            }
+        }
         DialogType::Settings => {
             if let Some(ref state) = app.settings_state {
                 draw_settings_dialog(frame, state, dialog_area, theme);
@@ -427,6 +455,12 @@ pub fn draw_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, th
         DialogType::ExtensionHandlerError => {
             draw_error_dialog(frame, dialog, dialog_area, theme, " Handler Error ");
         }
+        DialogType::HostKeyConfirm => {
+            draw_host_key_dialog(frame, dialog, dialog_area, theme);
+        }
+        DialogType::HostKeyChanged => {
+            draw_error_dialog(frame, dialog, dialog_area, theme, " Host Key Refused ");
+        }
         DialogType::BinaryFileHandler => {
             draw_binary_file_handler_dialog(frame, dialog, dialog_area, theme);
         }
@@ -816,6 +850,12 @@ fn draw_simple_input_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, them
         DialogType::Tar => " Create Archive ",
         DialogType::RemoteProfileSave => " Save Profile ",
         DialogType::EncryptConfirm => " Encrypt ",
+        DialogType::DiffExport => " Export Diff ",
+        DialogType::DiffExclude => " Exclude Patterns ",
+        DialogType::DedupRoots => " Find Duplicates ",
+        DialogType::SimilarRoots => " Find Similar Images ",
+        DialogType::EncryptKey => " Encryption Key ",
+        DialogType::Passphrase => " Passphrase ",
         _ => " Input ",
     };
 
@@ -831,7 +871,11 @@ fn draw_simple_input_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, them
 
     // 입력 필드만 표시 (중앙 정렬)
     let max_input_width = (inner.width - 4) as usize;
-    let input_chars: Vec<char> = dialog.input.chars().collect();
+    let input_chars: Vec<char> = if dialog.dialog_type == DialogType::Passphrase {
+        vec!['*'; dialog.input.chars().count()]
+    } else {
+        dialog.input.chars().collect()
+    };
     let cursor_pos = dialog.cursor_pos.min(input_chars.len());
 
     // Calculate display width of input
@@ -941,7 +985,13 @@ fn draw_simple_input_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, them
         || dialog.dialog_type == DialogType::Mkfile
         || dialog.dialog_type == DialogType::Rename
         || dialog.dialog_type == DialogType::RemoteProfileSave
-        || dialog.dialog_type == DialogType::EncryptConfirm)
+        || dialog.dialog_type == DialogType::EncryptConfirm
+        || dialog.dialog_type == DialogType::DiffExport
+        || dialog.dialog_type == DialogType::DiffExclude
+        || dialog.dialog_type == DialogType::DedupRoots
+        || dialog.dialog_type == DialogType::SimilarRoots
+        || dialog.dialog_type == DialogType::EncryptKey
+        || dialog.dialog_type == DialogType::Passphrase)
         && !dialog.message.is_empty()
     {
         let message_y = inner.y;
@@ -973,6 +1023,19 @@ fn draw_simple_input_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, them
             };
             let md5_area = Rect::new(inner.x + 1, inner.y + 4, inner.width - 2, 1);
             frame.render_widget(Paragraph::new(md5_label).style(md5_style), md5_area);
+
+            let compress_label = if dialog.compress {
+                "  Shift+Tab: zstd compression [ON]"
+            } else {
+                "  Shift+Tab: zstd compression [OFF]"
+            };
+            let compress_style = if dialog.compress {
+                Style::default().fg(theme.dialog.text).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.dialog.text).add_modifier(Modifier::DIM)
+            };
+            let compress_area = Rect::new(inner.x + 1, inner.y + 5, inner.width - 2, 1);
+            frame.render_widget(Paragraph::new(compress_label).style(compress_style), compress_area);
         }
     } else {
         // 수직 중앙에 배치
@@ -1026,9 +1089,10 @@ fn draw_confirm_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, theme: &T
     );
 }
 
-fn draw_dedup_confirm_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, theme: &Theme) {
+/// Yes/No dialog with a multi-line message (unknown host key)
+fn draw_host_key_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, theme: &Theme) {
     let block = Block::default()
-        .title(" Remove Duplicates ")
+        .title(" Unknown Host ")
         .title_style(Style::default().fg(theme.confirm_dialog.title).add_modifier(Modifier::BOLD))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(theme.confirm_dialog.border))
@@ -1037,40 +1101,25 @@ fn draw_dedup_confirm_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect, the
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    // Warning line
-    let warn_area = Rect::new(inner.x + 1, inner.y + 1, inner.width - 2, 1);
-    frame.render_widget(
-        Paragraph::new("!! WARNING !!")
-            .style(Style::default().fg(theme.state.error).add_modifier(Modifier::BOLD))
-            .alignment(ratatui::layout::Alignment::Center),
-        warn_area,
-    );
-
-    // Message (wrapped)
-    let msg_area = Rect::new(inner.x + 1, inner.y + 3, inner.width - 2, 3);
+    let message_area = Rect::new(inner.x + 1, inner.y + 1, inner.width - 2, inner.height - 4);
     frame.render_widget(
         Paragraph::new(dialog.message.clone())
             .style(Style::default().fg(theme.confirm_dialog.message_text))
-            .wrap(ratatui::widgets::Wrap { trim: true })
-            .alignment(ratatui::layout::Alignment::Center),
-        msg_area,
+            .wrap(ratatui::widgets::Wrap { trim: true }),
+        message_area,
     );
 
-    // Buttons
     let selected_style = Style::default()
         .fg(theme.confirm_dialog.button_selected_text)
         .bg(theme.confirm_dialog.button_selected_bg);
     let normal_style = Style::default().fg(theme.confirm_dialog.button_text);
-
     let yes_style = if dialog.selected_button == 0 { selected_style } else { normal_style };
     let no_style = if dialog.selected_button == 1 { selected_style } else { normal_style };
 
     let buttons = Line::from(vec![
-        Span::styled("  ", Style::default()),
         Span::styled(" Yes ", yes_style),
         Span::styled("    ", Style::default()),
         Span::styled(" No ", no_style),
-        Span::styled("  ", Style::default()),
     ]);
     let button_area = Rect::new(inner.x + 1, inner.y + inner.height - 2, inner.width - 2, 1);
     frame.render_widget(
@@ -1473,6 +1522,7 @@ fn draw_goto_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, t
         }
 
         // 북마크 모드 도움말
+        let import_key = app.keybindings.goto_first_key(GotoAction::ImportSshConfig);
         let help_line = if has_bookmarks {
             let del_key = app.keybindings.goto_first_key(GotoAction::BookmarkDelete);
             let edit_key = app.keybindings.goto_first_key(GotoAction::BookmarkEdit);
@@ -1485,6 +1535,8 @@ fn draw_goto_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, t
                 Span::styled(":del ", help_label_style),
                 Span::styled(edit_key.to_string(), help_key_style),
                 Span::styled(":edit ", help_label_style),
+                Span::styled(import_key.to_string(), help_key_style),
+                Span::styled(":ssh import ", help_label_style),
                 Span::styled("Esc", help_key_style),
                 Span::styled(":cancel", help_label_style),
             ])
@@ -1492,6 +1544,8 @@ fn draw_goto_dialog(frame: &mut Frame, app: &App, dialog: &Dialog, area: Rect, t
             Line::from(vec![
                 Span::styled("Enter", help_key_style),
                 Span::styled(":go ", help_label_style),
+                Span::styled(import_key.to_string(), help_key_style),
+                Span::styled(":ssh import ", help_label_style),
                 Span::styled("Esc", help_key_style),
                 Span::styled(":cancel", help_label_style),
             ])
@@ -1516,6 +1570,9 @@ fn draw_progress_dialog(frame: &mut Frame, app: &App, area: Rect, theme: &Theme)
         FileOperationType::Download => " Downloading ",
         FileOperationType::Encrypt => " Encrypting ",
         FileOperationType::Decrypt => " Decrypting ",
+        FileOperationType::Rekey => " Re-keying ",
+        FileOperationType::Verify => " Verifying ",
+        FileOperationType::List => " Reading ",
     };
 
     let block = Block::default()
@@ -1734,6 +1791,60 @@ fn draw_duplicate_conflict_dialog(
 }
 
 /// Tar exclude confirmation dialog
+/// Border, message and footer rows around the group list
+const ENC_LIST_EXTRA_HEIGHT: u16 = 6;
+
+fn draw_enc_list_dialog(
+    frame: &mut Frame,
+    dialog: &Dialog,
+    state: &crate::ui::app::EncListState,
+    area: Rect,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title(" Encrypted Files ")
+        .title_style(Style::default().fg(theme.confirm_dialog.title).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.confirm_dialog.border))
+        .style(Style::default().bg(theme.confirm_dialog.bg));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let width = inner.width.saturating_sub(4) as usize;
+
+    frame.render_widget(
+        Paragraph::new(truncate_with_ellipsis(&dialog.message, width))
+            .style(Style::default().fg(theme.confirm_dialog.message_text)),
+        Rect::new(inner.x + 2, inner.y + 1, inner.width.saturating_sub(4), 1),
+    );
+
+    let list_height = inner.height.saturating_sub(ENC_LIST_EXTRA_HEIGHT - 2) as usize;
+    for (i, line) in state.lines.iter().skip(state.scroll_offset).take(list_height).enumerate() {
+        frame.render_widget(
+            Paragraph::new(truncate_with_ellipsis(line, width))
+                .style(Style::default().fg(theme.confirm_dialog.message_text)),
+            Rect::new(inner.x + 2, inner.y + 2 + i as u16, inner.width.saturating_sub(4), 1),
+        );
+    }
+
+    let footer = if state.lines.len() > list_height {
+        format!(
+            "[{}-{}/{}]  Enter/Esc: close  v: verify",
+            state.scroll_offset + 1,
+            (state.scroll_offset + list_height).min(state.lines.len()),
+            state.lines.len(),
+        )
+    } else {
+        "Enter/Esc: close  v: verify".to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(footer)
+            .style(Style::default().fg(theme.confirm_dialog.message_text))
+            .alignment(ratatui::layout::Alignment::Center),
+        Rect::new(inner.x + 1, inner.y + inner.height.saturating_sub(2), inner.width.saturating_sub(2), 1),
+    );
+}
+
 fn draw_tar_exclude_confirm_dialog(
     frame: &mut Frame,
     dialog: &Dialog,
@@ -2025,7 +2136,9 @@ pub fn handle_paste(app: &mut App, text: &str) {
             // Dialog types with text input
             DialogType::Search | DialogType::Mkdir | DialogType::Mkfile
             | DialogType::Rename | DialogType::Tar | DialogType::BinaryFileHandler
-            | DialogType::EncryptConfirm => {
+            | DialogType::EncryptConfirm | DialogType::DiffExport | DialogType::DiffExclude
+            | DialogType::DedupRoots | DialogType::SimilarRoots | DialogType::EncryptKey
+            | DialogType::Passphrase => {
                 // Delete selection if exists
                 if let Some((sel_start, sel_end)) = dialog.selection.take() {
                     let mut chars: Vec<char> = dialog.input.chars().collect();
@@ -2124,37 +2237,36 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                     _ => {}
                 }
             }
-            DialogType::DecryptConfirm => {
-                match code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        app.dialog = None;
-                        app.execute_decrypt();
-                    }
-                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        app.dialog = None;
-                    }
+            DialogType::DecryptConfirm | DialogType::RekeyConfirm => {
+                let rekey = dialog.dialog_type == DialogType::RekeyConfirm;
+                let confirmed = match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => Some(true),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
                     KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
                         dialog.selected_button = 1 - dialog.selected_button;
+                        None
                     }
-                    KeyCode::Enter => {
-                        if dialog.selected_button == 0 {
-                            app.dialog = None;
-                            app.execute_decrypt();
-                        } else {
-                            app.dialog = None;
-                        }
+                    KeyCode::Enter => Some(dialog.selected_button == 0),
+                    _ => None,
+                };
+                if let Some(confirmed) = confirmed {
+                    app.dialog = None;
+                    if confirmed && rekey {
+                        app.execute_rekey();
+                    } else if confirmed {
+                        app.execute_decrypt();
                     }
-                    _ => {}
                 }
             }
-            DialogType::DedupConfirm => {
+            DialogType::DiffSyncConfirm => {
                 match code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
                         app.dialog = None;
-                        app.execute_dedup();
+                        app.execute_diff_sync();
                     }
                     KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                         app.dialog = None;
+                        app.pending_diff_sync = None;
                     }
                     KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
                         dialog.selected_button = 1 - dialog.selected_button;
@@ -2162,9 +2274,10 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                     KeyCode::Enter => {
                         if dialog.selected_button == 0 {
                             app.dialog = None;
-                            app.execute_dedup();
+                            app.execute_diff_sync();
                         } else {
                             app.dialog = None;
+                            app.pending_diff_sync = None;
                         }
                     }
                     _ => {}
@@ -2232,10 +2345,33 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
             DialogType::TarExcludeConfirm => {
                 return handle_tar_exclude_confirm_input(app, code);
             }
+            DialogType::EncList => {
+                return handle_enc_list_input(app, code);
+            }
             DialogType::Settings => {
                 return handle_settings_dialog_input(app, code);
             }
-            DialogType::ExtensionHandlerError => {
+            DialogType::HostKeyConfirm => {
+                let confirmed = match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => Some(true),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+                    KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                        dialog.selected_button = 1 - dialog.selected_button;
+                        None
+                    }
+                    KeyCode::Enter => Some(dialog.selected_button == 0),
+                    _ => None,
+                };
+                if let Some(confirmed) = confirmed {
+                    app.dialog = None;
+                    if confirmed {
+                        app.trust_host_key();
+                    } else {
+                        app.pending_host_key = None;
+                    }
+                }
+            }
+            DialogType::ExtensionHandlerError | DialogType::HostKeyChanged => {
                 // Simple error dialog - any key closes it
                 match code {
                     KeyCode::Enter | KeyCode::Esc | KeyCode::Char(_) => {
@@ -2311,11 +2447,148 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                     return false;
                 }
 
+                // Shift+Tab: toggle zstd compression for EncryptConfirm
+                if code == KeyCode::BackTab && dialog.dialog_type == DialogType::EncryptConfirm {
+                    dialog.compress = !dialog.compress;
+                    return false;
+                }
+
+                // Tab: insert the next saved preset for DiffExclude
+                if code == KeyCode::Tab && dialog.dialog_type == DialogType::DiffExclude {
+                    let presets = &app.settings.diff_exclude_presets;
+                    if !presets.is_empty() {
+                        let idx = dialog.selected_button % presets.len();
+                        let preset = &presets[idx];
+                        dialog.input = crate::ui::diff_exclude::format_patterns(&preset.patterns);
+                        dialog.cursor_pos = dialog.input.chars().count();
+                        dialog.message = format!("Preset: {} ({}/{})", preset.name, idx + 1, presets.len());
+                        dialog.selected_button = idx + 1;
+                    }
+                    return false;
+                }
+
+                // Tab: cycle machine, passphrase, the keyring keys and recipients for
+                // EncryptKey; after a comma, cycle the next recipient
+                if code == KeyCode::Tab && dialog.dialog_type == DialogType::EncryptKey {
+                    use crate::enc::keys;
+                    let (head, last) = match dialog.input.rfind(',') {
+                        Some(i) => (dialog.input[..=i].to_string(), dialog.input[i + 1..].trim().to_string()),
+                        None => (String::new(), dialog.input.trim().to_string()),
+                    };
+                    let choices = keys::credential_dir()
+                        .map(|dir| if head.is_empty() { keys::key_choices(&dir) } else { keys::recipient_choices(&dir) })
+                        .unwrap_or_default();
+                    if !choices.is_empty() {
+                        let idx = choices.iter()
+                            .position(|c| c.eq_ignore_ascii_case(&last))
+                            .map_or(0, |i| (i + 1) % choices.len());
+                        dialog.input = format!("{}{}", head, choices[idx]);
+                        dialog.cursor_pos = dialog.input.chars().count();
+                        dialog.message = format!("Key {}/{} (Tab: next, ',' adds a recipient)", idx + 1, choices.len());
+                    }
+                    return false;
+                }
+
+                // Tab: cycle the panel presets for DedupRoots and SimilarRoots
+                if code == KeyCode::Tab
+                    && matches!(dialog.dialog_type, DialogType::DedupRoots | DialogType::SimilarRoots)
+                {
+                    let presets = crate::ui::dedup_screen::scope_presets(&app.panels, app.active_panel_index);
+                    if !presets.is_empty() {
+                        let idx = dialog.selected_button % presets.len();
+                        let (name, scope) = &presets[idx];
+                        dialog.input = scope.to_input();
+                        dialog.cursor_pos = dialog.input.chars().count();
+                        dialog.message = format!("Preset: {} ({}/{})", name, idx + 1, presets.len());
+                        dialog.selected_button = idx + 1;
+                    }
+                    return false;
+                }
+
                 match code {
                     KeyCode::Enter => {
                         let input = dialog.input.clone();
                         let dialog_type = dialog.dialog_type;
 
+                        // For DiffExclude, an empty input clears all patterns
+                        if dialog_type == DialogType::DiffExclude {
+                            app.dialog = None;
+                            app.apply_diff_excludes(&input);
+                            return false;
+                        }
+
+                        if matches!(dialog_type, DialogType::DedupRoots | DialogType::SimilarRoots) {
+                            let base = app.active_panel().path.clone();
+                            match crate::services::dedup::DedupScope::parse(&input, &base) {
+                                Ok(scope) => {
+                                    app.dialog = None;
+                                    if dialog_type == DialogType::SimilarRoots {
+                                        app.execute_similar_images(scope);
+                                    } else {
+                                        app.execute_dedup(scope);
+                                    }
+                                }
+                                Err(e) => {
+                                    if let Some(ref mut d) = app.dialog {
+                                        d.message = format!("{}!", e);
+                                    }
+                                }
+                            }
+                            return false;
+                        }
+
+                        if dialog_type == DialogType::EncryptKey {
+                            use crate::enc::keys::{self, KeySource};
+                            match KeySource::parse(&input) {
+                                Ok(source) => {
+                                    // A new keyring key is created on first use; confirm it is not a typo
+                                    let is_new = match &source {
+                                        KeySource::Named(name) => keys::credential_dir()
+                                            .map(|dir| !keys::list_keys(&dir).contains(name))
+                                            .unwrap_or(true),
+                                        _ => false,
+                                    };
+                                    // Recipients must all be known public keys
+                                    if let KeySource::Recipients(names) = &source {
+                                        let known = keys::credential_dir()
+                                            .and_then(|dir| crate::enc::recipients::resolve(&dir, names));
+                                        if let Err(e) = known {
+                                            if let Some(ref mut d) = app.dialog {
+                                                d.message = format!("{}!", e);
+                                            }
+                                            return false;
+                                        }
+                                    }
+                                    let confirm = format!("Create new key '{}'? Enter to confirm", input.trim());
+                                    if is_new && app.dialog.as_ref().is_some_and(|d| d.message != confirm) {
+                                        if let Some(ref mut d) = app.dialog {
+                                            d.message = confirm;
+                                        }
+                                        return false;
+                                    }
+                                    app.dialog = None;
+                                    app.select_encrypt_key(source);
+                                }
+                                Err(e) => {
+                                    if let Some(ref mut d) = app.dialog {
+                                        d.message = format!("{}!", e);
+                                    }
+                                }
+                            }
+                            return false;
+                        }
+
+                        if dialog_type == DialogType::Passphrase {
+                            if let Err(e) = app.submit_passphrase(input) {
+                                if let Some(ref mut d) = app.dialog {
+                                    d.message = e;
+                                    d.input.clear();
+                                    d.cursor_pos = 0;
+                                }
+                            }
+                            return false;
+                        }
+
                         // For Tar dialog, check if archive already exists before closing
                         if dialog_type == DialogType::Tar && !input.trim().is_empty() {
                             // Get path before modifying dialog
@@ -2366,10 +2639,11 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                                 return false;
                             }
                             let use_md5 = app.dialog.as_ref().map_or(false, |d| d.use_md5);
+                            let compress = app.dialog.as_ref().is_some_and(|d| d.compress);
                             match trimmed.parse::<u64>() {
                                 Ok(split_size_mb) => {
                                     app.dialog = None;
-                                    app.execute_encrypt(split_size_mb, use_md5);
+                                    app.execute_encrypt(split_size_mb, use_md5, compress);
                                 }
                                 Err(_) => {
                                     if let Some(ref mut d) = app.dialog {
@@ -2381,6 +2655,23 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                             return false;
                         }
 
+                        // For DiffExport, keep the dialog open on error
+                        if dialog_type == DialogType::DiffExport {
+                            if input.trim().is_empty() {
+                                app.dialog = None;
+                                return false;
+                            }
+                            match app.execute_diff_export(&input) {
+                                Ok(()) => app.dialog = None,
+                                Err(e) => {
+                                    if let Some(ref mut d) = app.dialog {
+                                        d.message = e;
+                                    }
+                                }
+                            }
+                            return false;
+                        }
+
                         app.dialog = None;
                         if !input.trim().is_empty() {
                             match dialog_type {
@@ -2395,6 +2686,9 @@ pub fn handle_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers
                         }
                     }
                     KeyCode::Esc => {
+                        if matches!(dialog.dialog_type, DialogType::EncryptKey | DialogType::Passphrase) {
+                            app.pending_crypto = None;
+                        }
                         app.dialog = None;
                     }
                     KeyCode::Backspace => {
@@ -3018,6 +3312,17 @@ fn handle_goto_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifier
                                 }
                             }
                         }
+                        GotoAction::ImportSshConfig => {
+                            let added = crate::services::ssh_config::import_profiles(&mut app.settings.remote_profiles);
+                            app.message = Some(if added.is_empty() {
+                                "No new hosts in ~/.ssh/config".to_string()
+                            } else if let Err(e) = app.settings.save() {
+                                format!("Failed to save settings: {}", e)
+                            } else {
+                                format!("Imported {} host(s) from ~/.ssh/config: {}", added.len(), added.join(", "))
+                            });
+                            app.message_timer = 30;
+                        }
                         GotoAction::BookmarkEdit => {
                             // Edit selected remote entry in RemoteConnect dialog
                             if has_bookmarks {
@@ -3035,6 +3340,7 @@ fn handle_goto_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifier
                                             selected_button: 0,
                                             selection: None,
                                             use_md5: false,
+                                            compress: false,
                                         });
                                     }
                                 } else if let Some(entry) = mixed_entries.get(selected_idx) {
@@ -3055,6 +3361,7 @@ fn handle_goto_dialog_input(app: &mut App, code: KeyCode, modifiers: KeyModifier
                                                 selected_button: 0,
                                                 selection: None,
                                                 use_md5: false,
+                                                compress: false,
                                             });
                                         }
                                     }
@@ -3108,6 +3415,33 @@ fn handle_progress_dialog_input(app: &mut App, code: KeyCode) -> bool {
     false
 }
 
+/// Handle the encrypted files list: scroll, close or verify the groups
+fn handle_enc_list_input(app: &mut App, code: KeyCode) -> bool {
+    let Some(ref mut state) = app.enc_list_state else {
+        app.dialog = None;
+        return false;
+    };
+    match code {
+        KeyCode::Up => state.scroll_offset = state.scroll_offset.saturating_sub(1),
+        KeyCode::Down if state.scroll_offset + 1 < state.lines.len() => state.scroll_offset += 1,
+        KeyCode::PageUp => state.scroll_offset = state.scroll_offset.saturating_sub(10),
+        KeyCode::PageDown => {
+            state.scroll_offset = (state.scroll_offset + 10).min(state.lines.len().saturating_sub(1));
+        }
+        KeyCode::Char('v') | KeyCode::Char('V') => {
+            app.enc_list_state = None;
+            app.dialog = None;
+            app.verify_encrypted();
+        }
+        KeyCode::Enter | KeyCode::Esc => {
+            app.enc_list_state = None;
+            app.dialog = None;
+        }
+        _ => {}
+    }
+    false
+}
+
 /// Handle tar exclude confirmation dialog input
 fn handle_tar_exclude_confirm_input(app: &mut App, code: KeyCode) -> bool {
     if let Some(ref mut dialog) = app.dialog {
@@ -3874,10 +4208,7 @@ fn draw_remote_connect_dialog(frame: &mut Frame, app: &App, area: Rect, theme: &
 
     use super::app::{RemoteField, RemoteAuthType};
     let label_width = 10;
-    let auth_display = match state.auth_type {
-        RemoteAuthType::Password => "Password",
-        RemoteAuthType::KeyFile => "Key File",
-    };
+    let auth_display = state.auth_type.label();
 
     // Available width for value display (inner width - padding - label - ": ")
     let value_max_width = (inner.width as usize).saturating_sub(2 + label_width + 2);
@@ -3967,6 +4298,21 @@ fn draw_remote_connect_dialog(frame: &mut Frame, app: &App, area: Rect, theme: &
         y += 1;
     }
 
+    // Jump hosts
+    {
+        let is_selected = state.selected_field == RemoteField::Jump;
+        let ls = if is_selected { selected_style } else { label_style };
+        let vs = if is_selected { selected_style } else { value_style };
+        let display = if state.jump.is_empty() && !is_selected { "(none)" } else { state.jump.as_str() };
+        let mut spans = vec![Span::styled(format!("{:>width$}: ", "Jump", width = label_width), ls)];
+        spans.extend(build_value_spans(display, is_selected, state.cursor_pos, vs, false));
+        frame.render_widget(
+            Paragraph::new(Line::from(spans)),
+            Rect::new(inner.x + 1, y, inner.width - 2, 1),
+        );
+        y += 1;
+    }
+
     // Auth type field - toggle only, no text cursor
     {
         let is_selected = state.selected_field == RemoteField::AuthType;
@@ -3983,8 +4329,20 @@ fn draw_remote_connect_dialog(frame: &mut Frame, app: &App, area: Rect, theme: &
     }
 
     // Auth-specific fields
-    if state.auth_type == RemoteAuthType::Password {
-        // Password
+    if state.auth_type == RemoteAuthType::Agent {
+        // Nothing to enter; show where the keys come from
+        let agent = match std::env::var("SSH_AUTH_SOCK") {
+            Ok(sock) if !sock.is_empty() => sock,
+            _ => "(SSH_AUTH_SOCK not set)".to_string(),
+        };
+        let mut spans = vec![Span::styled(format!("{:>width$}: ", "Agent", width = label_width), label_style)];
+        spans.extend(build_value_spans(&agent, false, 0, value_style, false));
+        frame.render_widget(
+            Paragraph::new(Line::from(spans)),
+            Rect::new(inner.x + 1, y, inner.width - 2, 1),
+        );
+    } else if state.auth_type != RemoteAuthType::KeyFile {
+        // Password, also answers keyboard-interactive prompts
         let is_selected = state.selected_field == RemoteField::Credential;
         let ls = if is_selected { selected_style } else { label_style };
         let vs = if is_selected { selected_style } else { value_style };
@@ -4050,7 +4408,7 @@ fn draw_remote_connect_dialog(frame: &mut Frame, app: &App, area: Rect, theme: &
 
 /// Handle input for the remote connect dialog
 fn handle_remote_connect_input(app: &mut App, code: KeyCode) -> bool {
-    use super::app::{RemoteField, RemoteAuthType};
+    use super::app::RemoteField;
 
     if app.remote_connect_state.is_none() {
         app.dialog = None;
@@ -4068,6 +4426,9 @@ fn handle_remote_connect_input(app: &mut App, code: KeyCode) -> bool {
                     state.toggle_auth_type();
                     state.cursor_pos = 0;
                 } else {
+                    if state.selected_field == RemoteField::Host {
+                        state.resolve_host_alias();
+                    }
                     state.selected_field = state.next_field();
                     state.cursor_pos = state.active_field_value().len();
                 }
@@ -4075,6 +4436,9 @@ fn handle_remote_connect_input(app: &mut App, code: KeyCode) -> bool {
         }
         KeyCode::Down => {
             if let Some(ref mut state) = app.remote_connect_state {
+                if state.selected_field == RemoteField::Host {
+                    state.resolve_host_alias();
+                }
                 state.selected_field = state.next_field();
                 state.cursor_pos = state.active_field_value().len();
             }
@@ -4097,6 +4461,13 @@ fn handle_remote_connect_input(app: &mut App, code: KeyCode) -> bool {
         }
         KeyCode::Enter => {
             // Attempt connection
+            if let Some(ref mut state) = app.remote_connect_state {
+                state.resolve_host_alias();
+                if let Err(e) = state.resolve_jump_hosts(&app.settings.remote_profiles) {
+                    state.error = Some(e);
+                    return false;
+                }
+            }
             if let Some(ref state) = app.remote_connect_state {
                 if state.host.is_empty() || state.user.is_empty() {
                     if let Some(ref mut s) = app.remote_connect_state {
@@ -4525,6 +4896,7 @@ mod tests {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         };
 
         assert_eq!(dialog.dialog_type, DialogType::Search);
@@ -4550,6 +4922,7 @@ mod tests {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         };
 
         update_path_suggestions(&mut dialog);
@@ -4577,6 +4950,7 @@ mod tests {
             selected_button: 0,
             selection: None,
             use_md5: false,
+            compress: false,
         };
 
         update_path_suggestions(&mut dialog);
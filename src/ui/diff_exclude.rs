@@ -0,0 +1,272 @@
+//! Exclude rules for folder comparison: user glob patterns (gitignore syntax)
+//! and optional `.gitignore` / `.ignore` files found on either local side.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Ignore files honoured when `use_ignore_files` is on, in precedence order
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Exclude settings for one comparison
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffExcludeConfig {
+    /// Glob patterns in gitignore syntax (`target`, `*.o`, `/build`, `logs/`, `!keep.log`)
+    pub patterns: Vec<String>,
+    /// Honour `.gitignore` / `.ignore` files on both sides (also skips `.git`)
+    pub use_ignore_files: bool,
+}
+
+impl DiffExcludeConfig {
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && !self.use_ignore_files
+    }
+
+    /// Short description for status bars, e.g. "3 patterns, .gitignore"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match self.patterns.len() {
+            0 => {}
+            1 => parts.push("1 pattern".to_string()),
+            n => parts.push(format!("{} patterns", n)),
+        }
+        if self.use_ignore_files {
+            parts.push(".gitignore".to_string());
+        }
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Split user input ("target, node_modules *.log") into patterns
+pub fn parse_patterns(input: &str) -> Vec<String> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Join patterns back into the dialog input form
+pub fn format_patterns(patterns: &[String]) -> String {
+    patterns.join(", ")
+}
+
+/// Compiled exclude rules for a left/right pair of roots.
+///
+/// Ignore files are loaded lazily per directory and cached, so each
+/// `.gitignore` is read once per comparison.
+pub struct ExcludeRules {
+    /// Roots whose ignore files are honoured; None for remote sides
+    left_root: Option<PathBuf>,
+    right_root: Option<PathBuf>,
+    patterns: Option<Gitignore>,
+    use_ignore_files: bool,
+    cache: RefCell<HashMap<PathBuf, Option<Gitignore>>>,
+}
+
+impl ExcludeRules {
+    pub fn new(left_root: &Path, right_root: &Path, config: &DiffExcludeConfig) -> Self {
+        let patterns = if config.patterns.is_empty() {
+            None
+        } else {
+            let mut builder = GitignoreBuilder::new("");
+            for pattern in &config.patterns {
+                // Invalid globs are skipped rather than failing the whole comparison
+                let _ = builder.add_line(None, pattern);
+            }
+            builder.build().ok()
+        };
+        Self {
+            left_root: Some(left_root.to_path_buf()),
+            right_root: Some(right_root.to_path_buf()),
+            patterns,
+            use_ignore_files: config.use_ignore_files,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Rules that exclude nothing
+    pub fn none() -> Self {
+        Self::new(Path::new(""), Path::new(""), &DiffExcludeConfig::default())
+    }
+
+    /// Don't read ignore files from the given sides (remote directories aren't on disk)
+    pub fn without_ignore_files(mut self, left: bool, right: bool) -> Self {
+        if left {
+            self.left_root = None;
+        }
+        if right {
+            self.right_root = None;
+        }
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.patterns.is_some() || self.use_ignore_files
+    }
+
+    /// Check whether `relative_path` (relative to the comparison roots) is excluded.
+    /// An entry ignored by either side's ignore files is excluded from both sides.
+    pub fn is_excluded(&self, relative_path: &str, is_dir: bool) -> bool {
+        if !self.is_active() || relative_path.is_empty() {
+            return false;
+        }
+
+        if let Some(ref patterns) = self.patterns {
+            match patterns.matched(Path::new(relative_path), is_dir) {
+                Match::Ignore(_) => return true,
+                // An explicit `!pattern` keeps the entry regardless of ignore files
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        if self.use_ignore_files {
+            let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            if name == ".git" {
+                return true;
+            }
+            return [&self.left_root, &self.right_root]
+                .into_iter()
+                .flatten()
+                .any(|root| self.ignored_by_files(root, relative_path, is_dir));
+        }
+
+        false
+    }
+
+    /// Evaluate ignore files from the deepest directory up to the root;
+    /// the deepest file with a matching rule decides.
+    fn ignored_by_files(&self, root: &Path, relative_path: &str, is_dir: bool) -> bool {
+        let full_path = root.join(relative_path);
+        let mut ancestors: Vec<&str> = vec![""];
+        for (i, c) in relative_path.char_indices() {
+            if c == '/' {
+                ancestors.push(&relative_path[..i]);
+            }
+        }
+
+        for dir_relative in ancestors.iter().rev() {
+            let dir = if dir_relative.is_empty() {
+                root.to_path_buf()
+            } else {
+                root.join(dir_relative)
+            };
+            let mut cache = self.cache.borrow_mut();
+            let matcher = cache.entry(dir.clone()).or_insert_with(|| load_ignore_files(&dir));
+            if let Some(ref gi) = matcher {
+                match gi.matched(&full_path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Build a matcher from the ignore files in `dir`, or None if there are none
+fn load_ignore_files(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file_name in IGNORE_FILE_NAMES {
+        let path = dir.join(file_name);
+        if path.is_file() {
+            found = true;
+            let _ = builder.add(path);
+        }
+    }
+    if !found {
+        return None;
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config(patterns: &[&str], use_ignore_files: bool) -> DiffExcludeConfig {
+        DiffExcludeConfig {
+            patterns: patterns.iter().map(|s| s.to_string()).collect(),
+            use_ignore_files,
+        }
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        assert_eq!(parse_patterns("target, node_modules  *.log,,"), vec!["target", "node_modules", "*.log"]);
+        assert!(parse_patterns("  ").is_empty());
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        let rules = ExcludeRules::new(Path::new("/l"), Path::new("/r"), &config(&["target", "*.o", "/build", "logs/", "!keep.o"], false));
+        assert!(rules.is_excluded("target", true));
+        assert!(rules.is_excluded("crates/a/target", true));
+        assert!(rules.is_excluded("src/main.o", false));
+        assert!(!rules.is_excluded("keep.o", false));
+        assert!(rules.is_excluded("build", true));
+        assert!(!rules.is_excluded("src/build", true));
+        assert!(rules.is_excluded("logs", true));
+        assert!(!rules.is_excluded("logs", false));
+        assert!(!rules.is_excluded("src/main.rs", false));
+    }
+
+    #[test]
+    fn test_ignore_files_on_either_side() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::write(left.path().join(".gitignore"), "*.tmp\n").unwrap();
+        fs::create_dir(right.path().join("sub")).unwrap();
+        fs::write(right.path().join("sub").join(".ignore"), "generated/\n").unwrap();
+
+        let rules = ExcludeRules::new(left.path(), right.path(), &config(&[], true));
+        assert!(rules.is_excluded("a.tmp", false));
+        assert!(rules.is_excluded("sub/deep/x.tmp", false));
+        assert!(rules.is_excluded("sub/generated", true));
+        assert!(!rules.is_excluded("generated", true));
+        assert!(rules.is_excluded(".git", true));
+        assert!(!rules.is_excluded("src/lib.rs", false));
+
+        let off = ExcludeRules::new(left.path(), right.path(), &config(&[], false));
+        assert!(!off.is_excluded("a.tmp", false));
+        assert!(!off.is_excluded(".git", true));
+
+        // A remote left side contributes no ignore files
+        let remote_left = ExcludeRules::new(left.path(), right.path(), &config(&[], true)).without_ignore_files(true, false);
+        assert!(!remote_left.is_excluded("a.tmp", false));
+        assert!(remote_left.is_excluded("sub/generated", true));
+        assert!(remote_left.is_excluded(".git", true));
+    }
+
+    #[test]
+    fn test_nested_negation_overrides_parent() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::write(left.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::create_dir(left.path().join("keep")).unwrap();
+        fs::write(left.path().join("keep").join(".gitignore"), "!important.log\n").unwrap();
+
+        let rules = ExcludeRules::new(left.path(), right.path(), &config(&[], true));
+        assert!(rules.is_excluded("keep/other.log", false));
+        assert!(!rules.is_excluded("keep/important.log", false));
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(config(&[], false).summary(), "none");
+        assert_eq!(config(&["a", "b"], true).summary(), "2 patterns, .gitignore");
+    }
+}
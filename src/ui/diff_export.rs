@@ -0,0 +1,668 @@
+//! Export of diff results: unified patches (applicable with `patch -p1` or
+//! `git apply`) and machine-readable JSON/CSV folder comparison reports.
+
+use std::fs;
+use std::path::Path;
+
+use super::diff_file_view::{compute_lcs, is_binary};
+use super::diff_screen::{collect_one_side_tree, CompareMethod, DiffEntry, DiffFileInfo, DiffState, DiffStatus};
+use super::diff_side::DiffSide;
+
+/// Number of unchanged context lines around each hunk
+const CONTEXT_LINES: usize = 3;
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Export format
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Patch,
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Parse a format name (for CLI argument parsing)
+    pub fn parse(s: &str) -> Option<ExportFormat> {
+        match s.to_lowercase().as_str() {
+            "patch" | "diff" | "unified" => Some(ExportFormat::Patch),
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// Pick the format from an output file extension (.patch/.diff, .json, .csv)
+    pub fn from_path(path: &Path) -> Option<ExportFormat> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "patch" | "diff" => Some(ExportFormat::Patch),
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            ExportFormat::Patch => "patch",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+}
+
+/// Stable status name used in reports
+pub fn status_label(status: DiffStatus) -> &'static str {
+    match status {
+        DiffStatus::Same | DiffStatus::DirSame => "same",
+        DiffStatus::Modified | DiffStatus::DirModified => "modified",
+        DiffStatus::LeftOnly => "left_only",
+        DiffStatus::RightOnly => "right_only",
+    }
+}
+
+fn compare_method_label(method: CompareMethod) -> &'static str {
+    match method {
+        CompareMethod::Content => "content",
+        CompareMethod::ModifiedTime => "modified_time",
+        CompareMethod::ContentAndTime => "content_and_time",
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Entry collection
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Flatten the diff tree into a complete entry list.
+///
+/// The diff screen loads one-side-only directories lazily, and directories
+/// whose counterpart is a file are never descended into. Exports must cover
+/// every file, so those subtrees are walked here (honouring the exclude rules).
+pub fn full_entry_list(state: &DiffState) -> Vec<DiffEntry> {
+    let excludes = state.exclude_rules();
+    let mut result = Vec::with_capacity(state.all_entries.len());
+    for entry in &state.all_entries {
+        result.push(entry.clone());
+
+        let left_dir = entry.left.as_ref().is_some_and(|i| i.is_directory);
+        let right_dir = entry.right.as_ref().is_some_and(|i| i.is_directory);
+
+        if entry.children_not_loaded {
+            let is_left = entry.status == DiffStatus::LeftOnly;
+            let info = if is_left { &entry.left } else { &entry.right };
+            if let Some(info) = info {
+                if !info.is_symlink {
+                    collect_one_side_tree(
+                        state.side(is_left),
+                        &info.full_path,
+                        &entry.relative_path,
+                        entry.depth + 1,
+                        entry.status == DiffStatus::LeftOnly,
+                        &excludes,
+                        &mut result,
+                    );
+                }
+            }
+        } else if entry.left.is_some() && entry.right.is_some() && left_dir != right_dir {
+            // Type mismatch: the directory side only exists on one side
+            let (info, is_left) = if left_dir {
+                (entry.left.as_ref(), true)
+            } else {
+                (entry.right.as_ref(), false)
+            };
+            if let Some(info) = info.filter(|i| !i.is_symlink) {
+                collect_one_side_tree(
+                    state.side(is_left),
+                    &info.full_path,
+                    &entry.relative_path,
+                    entry.depth + 1,
+                    is_left,
+                    &excludes,
+                    &mut result,
+                );
+            }
+        }
+    }
+    result
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Unified diff
+// ═══════════════════════════════════════════════════════════════════════════════
+
+enum Op {
+    Equal(usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Generate a unified diff for one file.
+///
+/// `left`/`right` are the file contents, `None` when the file is absent on that
+/// side (rendered as `/dev/null`). Returns an empty string when there is no
+/// difference. Every file gets a git-style `diff --git` header, so added and
+/// deleted files (empty ones included) are created or removed on apply.
+/// Binary or non-UTF-8 content yields a "Binary files ... differ" line.
+pub fn unified_diff(path: &str, left: Option<&[u8]>, right: Option<&[u8]>) -> String {
+    if left == right {
+        return String::new();
+    }
+
+    let old_name = quote_path("a/", path);
+    let new_name = quote_path("b/", path);
+    let old_label = if left.is_some() { old_name.clone() } else { "/dev/null".to_string() };
+    let new_label = if right.is_some() { new_name.clone() } else { "/dev/null".to_string() };
+
+    let mut out = format!("diff --git {} {}\n", old_name, new_name);
+    if left.is_none() || right.is_none() {
+        if left.is_none() {
+            out.push_str("new file mode 100644\n");
+        } else {
+            out.push_str("deleted file mode 100644\n");
+        }
+    }
+
+    let left_bytes = left.unwrap_or(&[]);
+    let right_bytes = right.unwrap_or(&[]);
+    if left_bytes.is_empty() && right_bytes.is_empty() {
+        // Empty file added or removed: the header alone describes the change
+        return out;
+    }
+
+    let left_text = if is_binary(left_bytes) { None } else { std::str::from_utf8(left_bytes).ok() };
+    let right_text = if is_binary(right_bytes) { None } else { std::str::from_utf8(right_bytes).ok() };
+    let (left_text, right_text) = match (left_text, right_text) {
+        (Some(l), Some(r)) => (l, r),
+        _ => {
+            out.push_str(&format!("Binary files {} and {} differ\n", old_label, new_label));
+            return out;
+        }
+    };
+
+    // Keep line terminators so a missing final newline counts as a change
+    let left_lines: Vec<String> = left_text.split_inclusive('\n').map(|s| s.to_string()).collect();
+    let right_lines: Vec<String> = right_text.split_inclusive('\n').map(|s| s.to_string()).collect();
+
+    let ops = diff_ops(&left_lines, &right_lines);
+
+    // Like git, end names containing spaces with a tab so `patch` keeps them whole
+    let terminated = |label: &str| if label.contains(' ') { format!("{}\t", label) } else { label.to_string() };
+    out.push_str(&format!("--- {}\n", terminated(&old_label)));
+    out.push_str(&format!("+++ {}\n", terminated(&new_label)));
+
+    for (start, stop) in group_hunks(&ops) {
+        // Line positions before the hunk
+        let (mut old_start, mut new_start) = (0usize, 0usize);
+        for op in &ops[..start] {
+            match op {
+                Op::Equal(_) => {
+                    old_start += 1;
+                    new_start += 1;
+                }
+                Op::Delete(_) => old_start += 1,
+                Op::Insert(_) => new_start += 1,
+            }
+        }
+        let old_count = ops[start..stop].iter().filter(|op| !matches!(op, Op::Insert(_))).count();
+        let new_count = ops[start..stop].iter().filter(|op| !matches!(op, Op::Delete(_))).count();
+        let old_pos = if old_count == 0 { old_start } else { old_start + 1 };
+        let new_pos = if new_count == 0 { new_start } else { new_start + 1 };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", old_pos, old_count, new_pos, new_count));
+
+        for op in &ops[start..stop] {
+            let (prefix, line) = match op {
+                Op::Equal(i) => (' ', &left_lines[*i]),
+                Op::Delete(i) => ('-', &left_lines[*i]),
+                Op::Insert(j) => ('+', &right_lines[*j]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    out
+}
+
+/// `prefix` + `path` as git writes it in patch headers: C-quoted when it holds
+/// quotes, backslashes, control or non-ASCII characters
+fn quote_path(prefix: &str, path: &str) -> String {
+    let name = format!("{}{}", prefix, path);
+    if !name.bytes().any(|b| b == b'"' || b == b'\\' || !(0x20..0x7f).contains(&b)) {
+        return name;
+    }
+    let mut quoted = String::from("\"");
+    for b in name.bytes() {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\n' => quoted.push_str("\\n"),
+            0x20..=0x7e => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Turn the LCS matching into a sequence of edit operations
+fn diff_ops(left: &[String], right: &[String]) -> Vec<Op> {
+    let lcs = compute_lcs(left, right);
+    let mut ops = Vec::with_capacity(left.len().max(right.len()));
+    let (mut i, mut j) = (0usize, 0usize);
+    for (li, ri) in lcs {
+        while i < li {
+            ops.push(Op::Delete(i));
+            i += 1;
+        }
+        while j < ri {
+            ops.push(Op::Insert(j));
+            j += 1;
+        }
+        ops.push(Op::Equal(i));
+        i += 1;
+        j += 1;
+    }
+    while i < left.len() {
+        ops.push(Op::Delete(i));
+        i += 1;
+    }
+    while j < right.len() {
+        ops.push(Op::Insert(j));
+        j += 1;
+    }
+    ops
+}
+
+/// Group changes into hunks, returning half-open ranges over `ops`.
+/// Changes separated by at most 2 * CONTEXT_LINES unchanged lines share a hunk.
+fn group_hunks(ops: &[Op]) -> Vec<(usize, usize)> {
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < ops.len() {
+        if matches!(ops[i], Op::Equal(_)) {
+            i += 1;
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let mut last_change = i;
+        let mut k = i + 1;
+        while k < ops.len() {
+            if !matches!(ops[k], Op::Equal(_)) {
+                last_change = k;
+            } else if k - last_change > 2 * CONTEXT_LINES {
+                break;
+            }
+            k += 1;
+        }
+        let stop = (last_change + CONTEXT_LINES + 1).min(ops.len());
+        hunks.push((start, stop));
+        i = stop;
+    }
+    hunks
+}
+
+fn read_side(side: &DiffSide, info: Option<&DiffFileInfo>) -> Result<Option<Vec<u8>>, String> {
+    match info {
+        Some(info) if !info.is_directory => side.read_file(&info.full_path).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Unified patch for a single file pair. Empty paths mean the file is absent on that side.
+pub fn file_patch(path: &str, left_path: &Path, right_path: &Path) -> Result<String, String> {
+    let read = |p: &Path| -> Result<Option<Vec<u8>>, String> {
+        if p.as_os_str().is_empty() || !p.exists() {
+            return Ok(None);
+        }
+        fs::read(p)
+            .map(Some)
+            .map_err(|e| format!("Cannot read {}: {}", p.display(), e))
+    };
+    let left = read(left_path)?;
+    let right = read(right_path)?;
+    Ok(unified_diff(path, left.as_deref(), right.as_deref()))
+}
+
+/// Unified patch covering every differing file of a folder comparison.
+/// Apply it inside the left directory with `patch -p1` or `git apply`.
+pub fn folder_patch(state: &DiffState) -> Result<String, String> {
+    let mut out = String::new();
+    for entry in full_entry_list(state) {
+        if matches!(entry.status, DiffStatus::Same | DiffStatus::DirSame | DiffStatus::DirModified) {
+            continue;
+        }
+        let left = read_side(&state.left_side, entry.left.as_ref())?;
+        let right = read_side(&state.right_side, entry.right.as_ref())?;
+        if left.is_none() && right.is_none() {
+            continue;
+        }
+        out.push_str(&unified_diff(&entry.relative_path, left.as_deref(), right.as_deref()));
+    }
+    Ok(out)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Reports
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn format_time(info: Option<&DiffFileInfo>) -> Option<String> {
+    info.map(|i| i.modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, false))
+}
+
+/// JSON report: roots, summary counts and one record per entry
+pub fn report_json(state: &DiffState) -> String {
+    let entries = full_entry_list(state);
+    let count = |label: &str| entries.iter().filter(|e| status_label(e.status) == label).count();
+
+    let records: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "path": e.relative_path,
+                "status": status_label(e.status),
+                "type": if e.is_directory { "directory" } else { "file" },
+                "left_size": e.left.as_ref().map(|i| i.size),
+                "right_size": e.right.as_ref().map(|i| i.size),
+                "left_modified": format_time(e.left.as_ref()),
+                "right_modified": format_time(e.right.as_ref()),
+            })
+        })
+        .collect();
+
+    let report = serde_json::json!({
+        "left": state.left_side.display_root(),
+        "right": state.right_side.display_root(),
+        "compare_method": compare_method_label(state.compare_method),
+        "exclude": {
+            "patterns": state.excludes.patterns,
+            "ignore_files": state.excludes.use_ignore_files,
+        },
+        "summary": {
+            "total": entries.len(),
+            "same": count("same"),
+            "modified": count("modified"),
+            "left_only": count("left_only"),
+            "right_only": count("right_only"),
+        },
+        "entries": records,
+    });
+
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// CSV report with one row per entry
+pub fn report_csv(state: &DiffState) -> String {
+    let mut out = String::from("path,status,type,left_size,right_size,left_modified,right_modified\n");
+    for e in full_entry_list(state) {
+        let size = |i: Option<&DiffFileInfo>| i.map(|i| i.size.to_string()).unwrap_or_default();
+        let row = [
+            csv_field(&e.relative_path),
+            status_label(e.status).to_string(),
+            if e.is_directory { "directory" } else { "file" }.to_string(),
+            size(e.left.as_ref()),
+            size(e.right.as_ref()),
+            format_time(e.left.as_ref()).unwrap_or_default(),
+            format_time(e.right.as_ref()).unwrap_or_default(),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Render a folder comparison in the requested format
+pub fn render(format: ExportFormat, state: &DiffState) -> Result<String, String> {
+    match format {
+        ExportFormat::Patch => folder_patch(state),
+        ExportFormat::Json => Ok(report_json(state)),
+        ExportFormat::Csv => Ok(report_csv(state)),
+    }
+}
+
+/// Write export content to `path`, refusing to overwrite an existing file
+pub fn write_export(path: &Path, content: &str) -> Result<(), String> {
+    if path.exists() {
+        return Err(format!("'{}' already exists!", path.display()));
+    }
+    fs::write(path, content).map_err(|e| format!("Export failed: {}!", e))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::app::{SortBy, SortOrder};
+    use tempfile::TempDir;
+
+    fn compare(left: &Path, right: &Path) -> DiffState {
+        let mut state = DiffState::new(
+            left.to_path_buf(),
+            right.to_path_buf(),
+            CompareMethod::Content,
+            SortBy::Name,
+            SortOrder::Asc,
+        );
+        state.build_diff_list();
+        state
+    }
+
+    #[test]
+    fn test_unified_diff_modified() {
+        let diff = unified_diff("f.txt", Some(b"a\nb\nc\n"), Some(b"a\nB\nc\n"));
+        assert_eq!(
+            diff,
+            "diff --git a/f.txt b/f.txt\n--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_identical_is_empty() {
+        assert!(unified_diff("f", Some(b"x\n"), Some(b"x\n")).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff_new_and_deleted_file() {
+        let added = unified_diff("n.txt", None, Some(b"one\ntwo\n"));
+        assert_eq!(
+            added,
+            "diff --git a/n.txt b/n.txt\nnew file mode 100644\n--- /dev/null\n+++ b/n.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+        let removed = unified_diff("o.txt", Some(b"x\n"), None);
+        assert_eq!(
+            removed,
+            "diff --git a/o.txt b/o.txt\ndeleted file mode 100644\n--- a/o.txt\n+++ /dev/null\n@@ -1,1 +0,0 @@\n-x\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_empty_file_on_one_side() {
+        assert_eq!(
+            unified_diff("empty", None, Some(b"")),
+            "diff --git a/empty b/empty\nnew file mode 100644\n"
+        );
+        assert_eq!(
+            unified_diff("empty", Some(b""), None),
+            "diff --git a/empty b/empty\ndeleted file mode 100644\n"
+        );
+        assert!(unified_diff("empty", Some(b""), Some(b"")).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff_no_newline_at_eof() {
+        let diff = unified_diff("f", Some(b"a\nb"), Some(b"a\nb\n"));
+        assert!(diff.contains("-b\n\\ No newline at end of file\n+b\n"));
+    }
+
+    #[test]
+    fn test_unified_diff_binary() {
+        let diff = unified_diff("bin", Some(b"\0\x01"), Some(b"\0\x02"));
+        assert_eq!(diff, "diff --git a/bin b/bin\nBinary files a/bin and b/bin differ\n");
+    }
+
+    #[test]
+    fn test_hunks_are_split_by_distant_changes() {
+        let left: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let right: String = (1..=20)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                18 => "eighteen\n".to_string(),
+                _ => format!("{}\n", i),
+            })
+            .collect();
+        let diff = unified_diff("f", Some(left.as_bytes()), Some(right.as_bytes()));
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@"));
+        assert!(diff.contains("@@ -15,6 +15,6 @@"));
+    }
+
+    #[test]
+    fn test_patch_paths_are_quoted_like_git() {
+        let diff = unified_diff("my file.txt", None, Some(b"x\n"));
+        assert!(diff.starts_with("diff --git a/my file.txt b/my file.txt\nnew file mode 100644\n"));
+        assert!(diff.contains("--- /dev/null\n+++ b/my file.txt\t\n"));
+        assert_eq!(quote_path("a/", "say \"hi\".txt"), "\"a/say \\\"hi\\\".txt\"");
+        assert_eq!(quote_path("b/", "caf\u{e9}"), "\"b/caf\\303\\251\"");
+    }
+
+    fn copy_tree(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            let target = to.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy_tree(&entry.path(), &target);
+            } else {
+                fs::copy(entry.path(), &target).unwrap();
+            }
+        }
+    }
+
+    /// Relative path and content of every file below `root`
+    fn tree_files(root: &Path) -> Vec<(String, Vec<u8>)> {
+        fn walk(root: &Path, dir: &Path, out: &mut Vec<(String, Vec<u8>)>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    walk(root, &path, out);
+                } else {
+                    let rel = path.strip_prefix(root).unwrap().display().to_string();
+                    out.push((rel, fs::read(&path).unwrap()));
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(root, root, &mut out);
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn test_folder_patch_applies_with_git() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        let write = |root: &Path, rel: &str, content: &str| {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(left.path(), "same.txt", "s\n");
+        write(right.path(), "same.txt", "s\n");
+        write(left.path(), "mod.txt", "a\nb\nc\n");
+        write(right.path(), "mod.txt", "a\nB\nc\n");
+        write(left.path(), "with space.txt", "old\n");
+        write(right.path(), "with space.txt", "new\n");
+        write(left.path(), "deleted.txt", "gone\n");
+        write(left.path(), "empty-deleted", "");
+        write(right.path(), "empty-added", "");
+        write(right.path(), "added/dir/inner.txt", "hello\n");
+        write(right.path(), "no newline", "tail");
+
+        let patch = folder_patch(&compare(left.path(), right.path())).unwrap();
+
+        let work = TempDir::new().unwrap();
+        copy_tree(left.path(), work.path());
+        let patch_dir = TempDir::new().unwrap();
+        let patch_file = patch_dir.path().join("export.patch");
+        fs::write(&patch_file, &patch).unwrap();
+        let output = std::process::Command::new("git")
+            .arg("apply")
+            .arg(&patch_file)
+            .current_dir(work.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stderr), patch);
+        assert_eq!(tree_files(work.path()), tree_files(right.path()));
+    }
+
+    #[test]
+    fn test_export_format_from_path() {
+        assert_eq!(ExportFormat::from_path(Path::new("/tmp/x.patch")), Some(ExportFormat::Patch));
+        assert_eq!(ExportFormat::from_path(Path::new("x.DIFF")), Some(ExportFormat::Patch));
+        assert_eq!(ExportFormat::from_path(Path::new("x.json")), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_path(Path::new("x.csv")), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_path(Path::new("x.txt")), None);
+        assert_eq!(ExportFormat::parse("JSON"), Some(ExportFormat::Json));
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_folder_reports_cover_unloaded_directories() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::write(left.path().join("same.txt"), "s\n").unwrap();
+        fs::write(right.path().join("same.txt"), "s\n").unwrap();
+        fs::write(left.path().join("mod.txt"), "old\n").unwrap();
+        fs::write(right.path().join("mod.txt"), "new\n").unwrap();
+        fs::create_dir(right.path().join("added")).unwrap();
+        fs::write(right.path().join("added").join("inner.txt"), "hello\n").unwrap();
+        fs::write(right.path().join("empty.txt"), "").unwrap();
+
+        let state = compare(left.path(), right.path());
+
+        let patch = folder_patch(&state).unwrap();
+        assert!(patch.contains("--- a/mod.txt\n+++ b/mod.txt\n"));
+        assert!(patch.contains("--- /dev/null\n+++ b/added/inner.txt\n@@ -0,0 +1,1 @@\n+hello\n"));
+        assert!(patch.contains("diff --git a/empty.txt b/empty.txt\nnew file mode 100644\n"));
+        assert!(!patch.contains("same.txt"));
+
+        let json = report_json(&state);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let paths: Vec<&str> = value["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap())
+            .collect();
+        assert!(paths.contains(&"added/inner.txt"));
+        assert_eq!(value["summary"]["modified"], 1);
+        assert_eq!(value["summary"]["right_only"], 3);
+
+        let csv = report_csv(&state);
+        assert!(csv.starts_with("path,status,type,"));
+        assert!(csv.contains("\nmod.txt,modified,file,4,4,"));
+        assert!(csv.contains("\nadded/inner.txt,right_only,file,,6,"));
+    }
+}
@@ -13,6 +13,8 @@ use ratatui::{
 use unicode_width::UnicodeWidthChar;
 
 use super::app::App;
+use super::diff_hex::{self, HexCell, HexDiff};
+use super::diff_side::LocalCopy;
 use super::theme::Theme;
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -49,6 +51,10 @@ pub struct DiffFileViewState {
     pub file_name: String,
     pub max_scroll: usize,        // max visual row offset
     pub change_visual_offsets: Vec<usize>, // visual row offset for each change_positions entry
+    pub hex: Option<HexDiff>,     // byte alignment, built on first use
+    pub hex_mode: bool,
+    /// Downloads of remote files shown in this view, removed when the view closes
+    pub local_copies: Vec<LocalCopy>,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -56,7 +62,7 @@ pub struct DiffFileViewState {
 // ═══════════════════════════════════════════════════════════════════════════════
 
 /// Check if raw bytes represent a binary file by looking for null bytes in the first 8KB.
-fn is_binary(data: &[u8]) -> bool {
+pub(crate) fn is_binary(data: &[u8]) -> bool {
     let check_len = data.len().min(8192);
     data[..check_len].contains(&0)
 }
@@ -70,7 +76,7 @@ fn is_binary(data: &[u8]) -> bool {
 ///
 /// For files up to ~10000 lines each, uses standard O(n*m) DP.
 /// For larger files, falls back to a simpler sequential comparison.
-fn compute_lcs(left: &[String], right: &[String]) -> Vec<(usize, usize)> {
+pub(crate) fn compute_lcs(left: &[String], right: &[String]) -> Vec<(usize, usize)> {
     let n = left.len();
     let m = right.len();
 
@@ -273,7 +279,7 @@ impl DiffFileViewState {
         let right_is_binary = right_data.as_ref().map_or(false, |d| is_binary(d));
 
         if left_is_binary || right_is_binary {
-            // Binary file: show a single informational line
+            // Binary file: open in hex mode; the text view shows a single informational line
             let diff_lines = vec![DiffLine {
                 left_line_no: None,
                 left_content: Some("Binary file".to_string()),
@@ -294,6 +300,9 @@ impl DiffFileViewState {
                 file_name,
                 max_scroll: 0,
                 change_visual_offsets: Vec::new(),
+                hex: Some(HexDiff::new(left_data.unwrap_or_default(), right_data.unwrap_or_default())),
+                hex_mode: true,
+                local_copies: Vec::new(),
             };
         }
 
@@ -371,6 +380,40 @@ impl DiffFileViewState {
             file_name,
             max_scroll: 0,
             change_visual_offsets: Vec::new(),
+            hex: None,
+            hex_mode: false,
+            local_copies: Vec::new(),
+        }
+    }
+
+    /// Switch between the text and hex views, resetting position
+    pub fn toggle_hex(&mut self) {
+        if !self.hex_mode && self.hex.is_none() {
+            let left = fs::read(&self.left_path).unwrap_or_default();
+            let right = fs::read(&self.right_path).unwrap_or_default();
+            self.hex = Some(HexDiff::new(left, right));
+        }
+        self.hex_mode = !self.hex_mode;
+        self.scroll = 0;
+        self.max_scroll = 0;
+        self.current_change = 0;
+        self.change_visual_offsets.clear();
+    }
+
+    /// Hex alignment when the hex view is active
+    fn active_hex(&self) -> Option<&HexDiff> {
+        if self.hex_mode {
+            self.hex.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Number of change blocks in the active view
+    pub fn change_count(&self) -> usize {
+        match self.active_hex() {
+            Some(hex) => hex.differences.len(),
+            None => self.change_positions.len(),
         }
     }
 }
@@ -379,7 +422,7 @@ impl DiffFileViewState {
 // Drawing
 // ═══════════════════════════════════════════════════════════════════════════════
 
-pub fn draw(frame: &mut Frame, state: &mut DiffFileViewState, area: Rect, theme: &Theme, kb: &crate::keybindings::Keybindings) {
+pub fn draw(frame: &mut Frame, state: &mut DiffFileViewState, area: Rect, theme: &Theme, kb: &crate::keybindings::Keybindings, message: Option<&str>) {
     if area.height < 4 {
         return;
     }
@@ -404,7 +447,11 @@ pub fn draw(frame: &mut Frame, state: &mut DiffFileViewState, area: Rect, theme:
     state.visible_height = content_area.height as usize;
 
     // ─── Header ─────────────────────────────────────────────────────────────
-    let header_text = format!("[FILE DIFF] {}", state.file_name);
+    let header_text = if state.hex_mode {
+        format!("[FILE DIFF] {} (hex)", state.file_name)
+    } else {
+        format!("[FILE DIFF] {}", state.file_name)
+    };
     let header_line = Line::from(Span::styled(
         header_text,
         Style::default()
@@ -415,7 +462,81 @@ pub fn draw(frame: &mut Frame, state: &mut DiffFileViewState, area: Rect, theme:
         .style(Style::default().bg(theme.diff_file_view.bg));
     frame.render_widget(header_paragraph, header_area);
 
-    // ─── Content: split 50:50 horizontal ────────────────────────────────────
+    // ─── Content ───────────────────────────────────────────────────────────
+    if state.active_hex().is_some() {
+        draw_hex_content(frame, state, content_area, theme);
+    } else {
+        draw_text_content(frame, state, content_area, theme);
+    }
+
+    // ─── StatusBar ──────────────────────────────────────────────────────────
+    let changes_count = state.change_count();
+    let current_display = if changes_count > 0 {
+        state.current_change + 1
+    } else {
+        0
+    };
+    let status_text = match (message, state.active_hex()) {
+        (Some(msg), _) => format!(" {}", msg),
+        (None, Some(hex)) => format!(
+            " Bytes: {}/{} Differences: {} [{}/{}]",
+            hex.left.len(),
+            hex.right.len(),
+            changes_count,
+            current_display,
+            changes_count,
+        ),
+        (None, None) => format!(
+            " Lines: {}/{} Changes: {} [{}/{}]",
+            state.left_total_lines,
+            state.right_total_lines,
+            changes_count,
+            current_display,
+            changes_count,
+        ),
+    };
+    let status_line = Line::from(Span::styled(
+        status_text,
+        Style::default()
+            .fg(theme.diff_file_view.status_bar_text)
+            .bg(theme.diff_file_view.status_bar_bg),
+    ));
+    let status_paragraph = Paragraph::new(status_line)
+        .style(Style::default().bg(theme.diff_file_view.status_bar_bg));
+    frame.render_widget(status_paragraph, status_area);
+
+    // ─── FunctionBar (keybindings에서 동적으로) ────────────────────────────
+    use crate::keybindings::DiffFileViewAction;
+    let key_style = Style::default()
+        .fg(theme.diff_file_view.footer_key)
+        .bg(theme.diff_file_view.bg);
+    let text_style = Style::default()
+        .fg(theme.diff_file_view.footer_text)
+        .bg(theme.diff_file_view.bg);
+    let shortcuts: Vec<(String, &str)> = vec![
+        (kb.diff_file_view_first_key(DiffFileViewAction::MoveUp).to_string(), "scroll "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::PageUp).to_string(), "page "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::NextChange).to_string(), "next "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::PrevChange).to_string(), "prev "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::ToggleHex).to_string(), "hex "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::Export).to_string(), "export "),
+        (kb.diff_file_view_first_key(DiffFileViewAction::Close).to_string(), "back"),
+    ];
+    let mut fn_spans = Vec::new();
+    for (key, label) in &shortcuts {
+        fn_spans.push(Span::styled(key.as_str(), key_style));
+        fn_spans.push(Span::styled(":", text_style));
+        fn_spans.push(Span::styled(*label, text_style));
+    }
+    let fn_line = Line::from(fn_spans);
+    let fn_paragraph = Paragraph::new(fn_line)
+        .style(Style::default().bg(theme.diff_file_view.bg));
+    frame.render_widget(fn_paragraph, function_area);
+}
+
+/// Draw the side-by-side line diff
+fn draw_text_content(frame: &mut Frame, state: &mut DiffFileViewState, content_area: Rect, theme: &Theme) {
+    // Split 50:50 horizontal
     let content_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -583,57 +704,145 @@ pub fn draw(frame: &mut Frame, state: &mut DiffFileViewState, area: Rect, theme:
             .position(state.scroll);
         frame.render_stateful_widget(scrollbar, content_area, &mut scrollbar_state);
     }
+}
 
-    // ─── StatusBar ──────────────────────────────────────────────────────────
-    let changes_count = state.change_positions.len();
-    let current_display = if changes_count > 0 {
-        state.current_change + 1
-    } else {
-        0
+/// Draw the aligned hex dump of both files
+fn draw_hex_content(frame: &mut Frame, state: &mut DiffFileViewState, content_area: Rect, theme: &Theme) {
+    let content_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ])
+        .split(content_area);
+
+    let left_area = content_layout[0];
+    let right_area = content_layout[1];
+    let left_inner_width = (left_area.width as usize).saturating_sub(1);
+    let right_width = right_area.width as usize;
+    let visible_lines = state.visible_height;
+
+    let hex = match state.hex.as_ref() {
+        Some(h) => h,
+        None => return,
     };
-    let status_text = format!(
-        " Lines: {}/{} Changes: {} [{}/{}]",
-        state.left_total_lines,
-        state.right_total_lines,
-        changes_count,
-        current_display,
-        changes_count,
-    );
-    let status_line = Line::from(Span::styled(
-        status_text,
-        Style::default()
-            .fg(theme.diff_file_view.status_bar_text)
-            .bg(theme.diff_file_view.status_bar_bg),
-    ));
-    let status_paragraph = Paragraph::new(status_line)
-        .style(Style::default().bg(theme.diff_file_view.status_bar_bg));
-    frame.render_widget(status_paragraph, status_area);
 
-    // ─── FunctionBar (keybindings에서 동적으로) ────────────────────────────
-    use crate::keybindings::DiffFileViewAction;
-    let key_style = Style::default()
-        .fg(theme.diff_file_view.footer_key)
-        .bg(theme.diff_file_view.bg);
-    let text_style = Style::default()
-        .fg(theme.diff_file_view.footer_text)
-        .bg(theme.diff_file_view.bg);
-    let shortcuts: Vec<(String, &str)> = vec![
-        (kb.diff_file_view_first_key(DiffFileViewAction::MoveUp).to_string(), "scroll "),
-        (kb.diff_file_view_first_key(DiffFileViewAction::PageUp).to_string(), "page "),
-        (kb.diff_file_view_first_key(DiffFileViewAction::NextChange).to_string(), "next "),
-        (kb.diff_file_view_first_key(DiffFileViewAction::PrevChange).to_string(), "prev "),
-        (kb.diff_file_view_first_key(DiffFileViewAction::Close).to_string(), "back"),
-    ];
-    let mut fn_spans = Vec::new();
-    for (key, label) in &shortcuts {
-        fn_spans.push(Span::styled(key.as_str(), key_style));
-        fn_spans.push(Span::styled(":", text_style));
-        fn_spans.push(Span::styled(*label, text_style));
+    // Both sides use the same row width; 1 column on the right is left for the scrollbar
+    let offset_digits = hex.offset_digits();
+    let bytes_per_row = diff_hex::bytes_per_row(left_inner_width.min(right_width.saturating_sub(1)), offset_digits);
+    let total_rows = hex.total_cells.div_ceil(bytes_per_row);
+    let max_scroll = total_rows.saturating_sub(visible_lines);
+    let scroll = state.scroll.min(max_scroll);
+    let change_rows: Vec<usize> = hex.differences.iter().map(|&cell| cell / bytes_per_row).collect();
+    let current_change_row = change_rows.get(state.current_change).copied();
+
+    let mut left_lines_display: Vec<Line> = Vec::with_capacity(visible_lines);
+    let mut right_lines_display: Vec<Line> = Vec::with_capacity(visible_lines);
+    for row in scroll..(scroll + visible_lines).min(total_rows) {
+        let cells = hex.cells(row * bytes_per_row, bytes_per_row);
+        let is_current_change = current_change_row == Some(row);
+        left_lines_display.push(Line::from(render_hex_side(
+            &cells, true, bytes_per_row, offset_digits, left_inner_width, theme, is_current_change,
+        )));
+        right_lines_display.push(Line::from(render_hex_side(
+            &cells, false, bytes_per_row, offset_digits, right_width, theme, is_current_change,
+        )));
     }
-    let fn_line = Line::from(fn_spans);
-    let fn_paragraph = Paragraph::new(fn_line)
-        .style(Style::default().bg(theme.diff_file_view.bg));
-    frame.render_widget(fn_paragraph, function_area);
+    while left_lines_display.len() < visible_lines {
+        left_lines_display.push(Line::from(Span::styled(
+            " ".repeat(left_inner_width),
+            Style::default().bg(theme.diff_file_view.bg),
+        )));
+        right_lines_display.push(Line::from(Span::styled(
+            " ".repeat(right_width),
+            Style::default().bg(theme.diff_file_view.bg),
+        )));
+    }
+
+    state.scroll = scroll;
+    state.max_scroll = max_scroll;
+    state.change_visual_offsets = change_rows;
+
+    let left_block = Block::default()
+        .borders(Borders::RIGHT)
+        .border_style(Style::default().fg(theme.diff_file_view.border));
+    let left_inner = left_block.inner(left_area);
+    frame.render_widget(left_block, left_area);
+    frame.render_widget(Paragraph::new(left_lines_display), left_inner);
+    frame.render_widget(Paragraph::new(right_lines_display), right_area);
+
+    if total_rows > visible_lines {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
+        let mut scrollbar_state = ScrollbarState::new(max_scroll).position(scroll);
+        frame.render_stateful_widget(scrollbar, content_area, &mut scrollbar_state);
+    }
+}
+
+/// Render one side of a hex row: offset, hex bytes and ASCII column.
+/// Bytes missing on this side (inserted on the other) are drawn as gaps.
+fn render_hex_side<'a>(
+    cells: &[HexCell],
+    is_left: bool,
+    bytes_per_row: usize,
+    offset_digits: usize,
+    width: usize,
+    theme: &Theme,
+    is_current_change: bool,
+) -> Vec<Span<'a>> {
+    let colors = &theme.diff_file_view;
+    let base_style = Style::default().fg(colors.same_text).bg(colors.bg);
+    let gap_style = Style::default().bg(colors.empty_bg);
+    let line_no_style = Style::default().fg(colors.line_number).bg(colors.bg);
+
+    let side = |cell: &HexCell| if is_left { cell.left } else { cell.right };
+    let other = |cell: &HexCell| if is_left { cell.right } else { cell.left };
+    let byte_style = |cell: &HexCell| match cell.status {
+        DiffLineStatus::Same => base_style,
+        // Bytes inside a modified run can still be equal by chance
+        DiffLineStatus::Modified if side(cell).map(|(_, b)| b) == other(cell).map(|(_, b)| b) => base_style,
+        DiffLineStatus::Modified => Style::default().fg(colors.inline_change_text).bg(colors.inline_change_bg),
+        DiffLineStatus::LeftOnly => Style::default().fg(colors.left_only_text).bg(colors.left_only_bg),
+        DiffLineStatus::RightOnly => Style::default().fg(colors.right_only_text).bg(colors.right_only_bg),
+    };
+
+    let marker = if is_current_change { "\u{25B6}" } else { " " };
+    let mut spans = Vec::with_capacity(bytes_per_row * 2 + 4);
+    match cells.iter().find_map(side) {
+        Some((offset, _)) => spans.push(Span::styled(
+            format!("{}{:0width$x}\u{2502}", marker, offset, width = offset_digits),
+            line_no_style,
+        )),
+        None => spans.push(Span::styled(
+            format!("{}{:width$}\u{2502}", marker, "", width = offset_digits),
+            Style::default().fg(colors.line_number).bg(colors.empty_bg),
+        )),
+    }
+
+    for idx in 0..bytes_per_row {
+        match cells.get(idx) {
+            Some(cell) => match side(cell) {
+                Some((_, byte)) => spans.push(Span::styled(format!(" {:02x}", byte), byte_style(cell))),
+                None => spans.push(Span::styled("   ", gap_style)),
+            },
+            None => spans.push(Span::styled("   ", base_style)),
+        }
+    }
+    spans.push(Span::styled(" \u{2502}", line_no_style));
+    for idx in 0..bytes_per_row {
+        match cells.get(idx) {
+            Some(cell) => match side(cell) {
+                Some((_, byte)) => spans.push(Span::styled(diff_hex::ascii_char(byte).to_string(), byte_style(cell))),
+                None => spans.push(Span::styled(" ", gap_style)),
+            },
+            None => spans.push(Span::styled(" ", base_style)),
+        }
+    }
+
+    let used = 4 + offset_digits + bytes_per_row * 4;
+    if width > used {
+        spans.push(Span::styled(" ".repeat(width - used), base_style));
+    }
+    spans
 }
 
 /// Render a single DiffLine into multiple visual rows of (left_spans, right_spans).
@@ -1083,8 +1292,8 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
                 state.scroll = max_scroll;
             }
             DiffFileViewAction::NextChange => {
-                if !state.change_positions.is_empty() {
-                    if state.current_change + 1 < state.change_positions.len() {
+                if state.change_count() > 0 {
+                    if state.current_change + 1 < state.change_count() {
                         state.current_change += 1;
                     }
                     if state.current_change < state.change_visual_offsets.len() {
@@ -1094,7 +1303,7 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
                 }
             }
             DiffFileViewAction::PrevChange => {
-                if !state.change_positions.is_empty() {
+                if state.change_count() > 0 {
                     if state.current_change > 0 {
                         state.current_change -= 1;
                     }
@@ -1104,6 +1313,12 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
                     }
                 }
             }
+            DiffFileViewAction::ToggleHex => {
+                state.toggle_hex();
+            }
+            DiffFileViewAction::Export => {
+                app.show_diff_export_dialog();
+            }
             DiffFileViewAction::Close => {
                 app.current_screen = super::app::Screen::DiffScreen;
                 app.diff_file_view_state = None;
@@ -1203,4 +1418,40 @@ mod tests {
         let lcs = compute_lcs(&left, &right);
         assert!(lcs.is_empty());
     }
+
+    #[test]
+    fn test_binary_files_open_in_hex_mode() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let left: Vec<u8> = (0..64u8).collect();
+        let mut right = left.clone();
+        right.insert(3, 0xAA);
+        std::fs::write(dir.path().join("a.bin"), &left).unwrap();
+        std::fs::write(dir.path().join("b.bin"), &right).unwrap();
+
+        let mut state = DiffFileViewState::new(
+            dir.path().join("a.bin"),
+            dir.path().join("b.bin"),
+            "a.bin".to_string(),
+        );
+        assert!(state.hex_mode);
+        assert_eq!(state.change_count(), 1);
+
+        let theme = Theme::default();
+        let kb = crate::keybindings::Keybindings::from_config(&Default::default());
+        let mut terminal = Terminal::new(TestBackend::new(160, 10)).unwrap();
+        terminal
+            .draw(|frame| draw(frame, &mut state, frame.area(), &theme, &kb, None))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..160).map(|x| buffer.cell((x, 2)).unwrap().symbol().to_string()).collect();
+        // Second row: the gap opposite the inserted byte keeps both sides aligned
+        assert!(row.contains("0000000f\u{2502} 0f 10"), "{}", row);
+        assert!(row.contains("00000010\u{2502} 0f 10"), "{}", row);
+
+        state.toggle_hex();
+        assert!(!state.hex_mode);
+        assert_eq!(state.change_count(), 0);
+    }
 }
@@ -0,0 +1,364 @@
+//! Byte-level alignment for the hex view of the file diff.
+//!
+//! Both files are walked in lockstep. At a mismatch a small window is searched
+//! for the nearest position where the two sides agree again, so an inserted or
+//! deleted byte shifts the alignment instead of marking the rest of the file as
+//! different.
+
+use super::diff_file_view::DiffLineStatus;
+
+/// Bytes that must match after a mismatch before the sides count as resynchronized
+const RESYNC_LEN: usize = 8;
+/// How far ahead on each side to look for a resync point
+const RESYNC_WINDOW: usize = 32;
+/// Upper bound for the modified run emitted when no resync point is found
+const MAX_SKIP: usize = 4096;
+/// Minimum number of hex digits in the offset column
+const MIN_OFFSET_DIGITS: usize = 8;
+
+/// A run of aligned cells with one status.
+/// `Same` / `Modified` consume bytes on both sides, `LeftOnly` / `RightOnly` on one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexSegment {
+    pub status: DiffLineStatus,
+    /// First aligned cell covered by this segment
+    pub cell: usize,
+    pub left_start: usize,
+    pub right_start: usize,
+    pub len: usize,
+}
+
+/// One aligned column: the byte (with its file offset) on each side, if any
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexCell {
+    pub status: DiffLineStatus,
+    pub left: Option<(usize, u8)>,
+    pub right: Option<(usize, u8)>,
+}
+
+pub struct HexDiff {
+    pub left: Vec<u8>,
+    pub right: Vec<u8>,
+    pub segments: Vec<HexSegment>,
+    /// First cell of each difference block (adjacent non-equal segments merged)
+    pub differences: Vec<usize>,
+    pub total_cells: usize,
+}
+
+impl HexDiff {
+    pub fn new(left: Vec<u8>, right: Vec<u8>) -> Self {
+        let segments = align(&left, &right);
+        let total_cells = segments.last().map_or(0, |s| s.cell + s.len);
+
+        let mut differences = Vec::new();
+        let mut in_difference = false;
+        for seg in &segments {
+            if seg.status == DiffLineStatus::Same {
+                in_difference = false;
+            } else {
+                if !in_difference {
+                    differences.push(seg.cell);
+                }
+                in_difference = true;
+            }
+        }
+
+        Self {
+            left,
+            right,
+            segments,
+            differences,
+            total_cells,
+        }
+    }
+
+    /// Aligned cells in `start..start + count` (clamped to the end of the diff)
+    pub fn cells(&self, start: usize, count: usize) -> Vec<HexCell> {
+        let end = start.saturating_add(count).min(self.total_cells);
+        let mut result = Vec::with_capacity(end.saturating_sub(start));
+        let mut seg_idx = self.segments.partition_point(|s| s.cell + s.len <= start);
+        let mut cell = start;
+
+        while cell < end && seg_idx < self.segments.len() {
+            let seg = &self.segments[seg_idx];
+            if cell >= seg.cell + seg.len {
+                seg_idx += 1;
+                continue;
+            }
+            let k = cell - seg.cell;
+            let left = match seg.status {
+                DiffLineStatus::RightOnly => None,
+                _ => Some((seg.left_start + k, self.left[seg.left_start + k])),
+            };
+            let right = match seg.status {
+                DiffLineStatus::LeftOnly => None,
+                _ => Some((seg.right_start + k, self.right[seg.right_start + k])),
+            };
+            result.push(HexCell { status: seg.status, left, right });
+            cell += 1;
+        }
+        result
+    }
+
+    /// Hex digits needed for the offset column
+    pub fn offset_digits(&self) -> usize {
+        let max_len = self.left.len().max(self.right.len());
+        let mut digits = 1;
+        let mut n = max_len >> 4;
+        while n > 0 {
+            digits += 1;
+            n >>= 4;
+        }
+        digits.max(MIN_OFFSET_DIGITS)
+    }
+}
+
+/// Bytes per row that fit a panel of `width` columns:
+/// marker + offset + `│` + " hh" per byte + " │" + one ASCII column per byte
+pub fn bytes_per_row(width: usize, offset_digits: usize) -> usize {
+    for bytes in [16, 8] {
+        if 4 + offset_digits + bytes * 4 <= width {
+            return bytes;
+        }
+    }
+    4
+}
+
+/// Printable ASCII as-is, everything else as '.'
+pub fn ascii_char(byte: u8) -> char {
+    if (0x20..0x7f).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+/// Align two byte buffers into segments
+fn align(left: &[u8], right: &[u8]) -> Vec<HexSegment> {
+    let mut segments = Vec::new();
+    let mut cell = 0usize;
+    let mut i = 0usize;
+    let mut j = 0usize;
+    // Consecutive failed resync searches; widens the skip so that completely
+    // different files don't cost a window search every few bytes
+    let mut failures = 0u32;
+
+    loop {
+        let run = left[i..].iter().zip(&right[j..]).take_while(|(a, b)| a == b).count();
+        push_segment(&mut segments, &mut cell, DiffLineStatus::Same, i, j, run);
+        i += run;
+        j += run;
+
+        if i == left.len() || j == right.len() {
+            break;
+        }
+
+        match find_resync(left, right, i, j) {
+            Some((a, b)) => {
+                let paired = a.min(b);
+                push_segment(&mut segments, &mut cell, DiffLineStatus::Modified, i, j, paired);
+                push_segment(&mut segments, &mut cell, DiffLineStatus::LeftOnly, i + paired, j + paired, a - paired);
+                push_segment(&mut segments, &mut cell, DiffLineStatus::RightOnly, i + paired, j + paired, b - paired);
+                i += a;
+                j += b;
+                failures = 0;
+            }
+            None => {
+                let skip = (RESYNC_WINDOW << failures.min(7))
+                    .min(MAX_SKIP)
+                    .min(left.len() - i)
+                    .min(right.len() - j);
+                push_segment(&mut segments, &mut cell, DiffLineStatus::Modified, i, j, skip);
+                i += skip;
+                j += skip;
+                failures += 1;
+            }
+        }
+    }
+
+    push_segment(&mut segments, &mut cell, DiffLineStatus::LeftOnly, i, j, left.len() - i);
+    push_segment(&mut segments, &mut cell, DiffLineStatus::RightOnly, left.len(), j, right.len() - j);
+    segments
+}
+
+/// Find the smallest (left skip, right skip) after which both sides match again.
+/// Among equal total skips, balanced splits (substitutions) win over pure
+/// insertions or deletions.
+fn find_resync(left: &[u8], right: &[u8], i: usize, j: usize) -> Option<(usize, usize)> {
+    let max_a = RESYNC_WINDOW.min(left.len() - i);
+    let max_b = RESYNC_WINDOW.min(right.len() - j);
+
+    for d in 1..=(max_a + max_b) {
+        let mid = d / 2;
+        for step in 0..=(2 * d + 1) {
+            // mid, mid - 1, mid + 1, mid - 2, ...
+            let a = if step % 2 == 0 {
+                mid + step / 2
+            } else {
+                match mid.checked_sub(step / 2 + 1) {
+                    Some(a) => a,
+                    None => continue,
+                }
+            };
+            if a > d {
+                continue;
+            }
+            let b = d - a;
+            if a > max_a || b > max_b {
+                continue;
+            }
+            if matches_at(left, right, i + a, j + b) {
+                return Some((a, b));
+            }
+        }
+    }
+    None
+}
+
+/// Check whether both sides agree for `RESYNC_LEN` bytes (or up to both ends)
+fn matches_at(left: &[u8], right: &[u8], i: usize, j: usize) -> bool {
+    let len = RESYNC_LEN.min(left.len() - i).min(right.len() - j);
+    if left[i..i + len] != right[j..j + len] {
+        return false;
+    }
+    len == RESYNC_LEN || (i + len == left.len() && j + len == right.len())
+}
+
+fn push_segment(
+    segments: &mut Vec<HexSegment>,
+    cell: &mut usize,
+    status: DiffLineStatus,
+    left_start: usize,
+    right_start: usize,
+    len: usize,
+) {
+    if len == 0 {
+        return;
+    }
+    match segments.last_mut() {
+        Some(last) if last.status == status => last.len += len,
+        _ => segments.push(HexSegment {
+            status,
+            cell: *cell,
+            left_start,
+            right_start,
+            len,
+        }),
+    }
+    *cell += len;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn firmware(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 13) as u8).collect()
+    }
+
+    fn statuses(diff: &HexDiff) -> Vec<(DiffLineStatus, usize)> {
+        diff.segments.iter().map(|s| (s.status, s.len)).collect()
+    }
+
+    #[test]
+    fn test_identical() {
+        let diff = HexDiff::new(firmware(100), firmware(100));
+        assert_eq!(statuses(&diff), vec![(DiffLineStatus::Same, 100)]);
+        assert!(diff.differences.is_empty());
+    }
+
+    #[test]
+    fn test_inserted_byte_resyncs() {
+        let left = firmware(1000);
+        let mut right = left.clone();
+        right.insert(300, 0xAA);
+
+        let diff = HexDiff::new(left, right);
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                (DiffLineStatus::Same, 300),
+                (DiffLineStatus::RightOnly, 1),
+                (DiffLineStatus::Same, 700),
+            ]
+        );
+        assert_eq!(diff.differences, vec![300]);
+    }
+
+    #[test]
+    fn test_deleted_and_substituted_bytes() {
+        let left = firmware(500);
+        let mut right = left.clone();
+        right[10] ^= 0xFF;
+        right[11] ^= 0xFF;
+        right.drain(200..203);
+
+        let diff = HexDiff::new(left, right);
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                (DiffLineStatus::Same, 10),
+                (DiffLineStatus::Modified, 2),
+                (DiffLineStatus::Same, 188),
+                (DiffLineStatus::LeftOnly, 3),
+                (DiffLineStatus::Same, 297),
+            ]
+        );
+        assert_eq!(diff.differences, vec![10, 200]);
+    }
+
+    #[test]
+    fn test_replaced_run_of_different_length() {
+        let left = firmware(300);
+        let mut right = left[..100].to_vec();
+        right.extend_from_slice(&[0xEE; 5]);
+        right.extend_from_slice(&left[102..]);
+
+        let diff = HexDiff::new(left, right);
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                (DiffLineStatus::Same, 100),
+                (DiffLineStatus::Modified, 2),
+                (DiffLineStatus::RightOnly, 3),
+                (DiffLineStatus::Same, 198),
+            ]
+        );
+        // The modified and right-only runs form a single difference
+        assert_eq!(diff.differences, vec![100]);
+    }
+
+    #[test]
+    fn test_unrelated_files_and_tail() {
+        let left = vec![0u8; 10_000];
+        let right = vec![1u8; 12_000];
+        let diff = HexDiff::new(left, right);
+        assert_eq!(
+            statuses(&diff),
+            vec![(DiffLineStatus::Modified, 10_000), (DiffLineStatus::RightOnly, 2_000)]
+        );
+        assert_eq!(diff.total_cells, 12_000);
+    }
+
+    #[test]
+    fn test_cells_and_offsets() {
+        let diff = HexDiff::new(vec![1, 2, 3, 4], vec![1, 9, 2, 3, 4]);
+        let cells = diff.cells(0, 16);
+        assert_eq!(cells.len(), 5);
+        assert_eq!(cells[1], HexCell { status: DiffLineStatus::RightOnly, left: None, right: Some((1, 9)) });
+        assert_eq!(cells[4].left, Some((3, 4)));
+        assert_eq!(cells[4].right, Some((4, 4)));
+        assert_eq!(diff.cells(3, 16).len(), 2);
+    }
+
+    #[test]
+    fn test_layout_helpers() {
+        assert_eq!(bytes_per_row(80, 8), 16);
+        assert_eq!(bytes_per_row(60, 8), 8);
+        assert_eq!(bytes_per_row(20, 8), 4);
+        assert_eq!(HexDiff::new(vec![0; 16], Vec::new()).offset_digits(), 8);
+        assert_eq!(ascii_char(b'A'), 'A');
+        assert_eq!(ascii_char(0), '.');
+        assert_eq!(ascii_char(0x7f), '.');
+    }
+}
@@ -1,5 +1,5 @@
-use std::collections::HashSet;
-use std::fs::{self, File};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -19,7 +19,10 @@ use ratatui::{
 use unicode_width::UnicodeWidthStr;
 
 use super::app::{App, Screen, SortBy, SortOrder};
+use super::diff_exclude::{DiffExcludeConfig, ExcludeRules};
+use super::diff_side::{DiffSide, LocalCopy};
 use super::theme::Theme;
+use crate::services::remote_transfer::TransferGroup;
 use crate::utils::format::{format_size, safe_suffix};
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -123,9 +126,16 @@ pub fn parse_compare_method(s: &str) -> CompareMethod {
 // Async diff types
 // ═══════════════════════════════════════════════════════════════════════════════
 
-struct DiffCompareResult(Vec<DiffEntry>);
+struct DiffCompareResult {
+    entries: Vec<DiffEntry>,
+    /// Sides handed back with their sessions still open
+    left_side: DiffSide,
+    right_side: DiffSide,
+    error: Option<String>,
+}
 
 enum DiffProgressMsg {
+    Connecting(String),
     Counting(usize),
     Comparing(String, usize, usize),
 }
@@ -137,6 +147,11 @@ enum DiffProgressMsg {
 pub struct DiffState {
     pub left_root: PathBuf,
     pub right_root: PathBuf,
+    /// Where each root lives (local filesystem or a remote panel)
+    pub left_side: DiffSide,
+    pub right_side: DiffSide,
+    /// Set when a remote side could not be reached
+    pub error: Option<String>,
     pub all_entries: Vec<DiffEntry>,
     pub filtered_indices: Vec<usize>,
     pub selected_index: usize,
@@ -149,6 +164,10 @@ pub struct DiffState {
     pub visible_height: usize,
     /// Set of relative_path values for collapsed directories
     pub collapsed_dirs: HashSet<String>,
+    /// Exclude patterns / ignore-file settings for this comparison
+    pub excludes: DiffExcludeConfig,
+    /// Compiled form of `excludes`, used for lazy loading
+    exclude_rules: ExcludeRules,
     // Async comparison fields
     pub is_comparing: bool,
     cancel_flag: Arc<AtomicBool>,
@@ -168,6 +187,9 @@ impl DiffState {
         sort_order: SortOrder,
     ) -> Self {
         Self {
+            left_side: DiffSide::local(left.clone()),
+            right_side: DiffSide::local(right.clone()),
+            error: None,
             left_root: left,
             right_root: right,
             all_entries: Vec::new(),
@@ -181,6 +203,8 @@ impl DiffState {
             selected_files: HashSet::new(),
             visible_height: 0,
             collapsed_dirs: HashSet::new(),
+            excludes: DiffExcludeConfig::default(),
+            exclude_rules: ExcludeRules::none(),
             is_comparing: false,
             cancel_flag: Arc::new(AtomicBool::new(false)),
             receiver: None,
@@ -205,24 +229,48 @@ impl DiffState {
         self.progress_current = String::new();
         self.progress_count = 0;
         self.progress_total = 0;
+        self.error = None;
         self.cancel_flag = Arc::new(AtomicBool::new(false));
+        self.exclude_rules = self.exclude_rules();
 
         let (result_tx, result_rx) = mpsc::channel();
         let (progress_tx, progress_rx) = mpsc::channel();
         self.receiver = Some(result_rx);
         self.progress_receiver = Some(progress_rx);
 
-        let left_root = self.left_root.clone();
-        let right_root = self.right_root.clone();
+        let mut left_side = self.left_side.detach();
+        let mut right_side = self.right_side.detach();
         let compare_method = self.compare_method;
         let sort_by = self.sort_by;
         let sort_order = self.sort_order;
         let cancel_flag = self.cancel_flag.clone();
+        let excludes = self.excludes.clone();
 
         thread::spawn(move || {
+            // Phase 0: Open SFTP sessions for remote sides
+            let mut connected = Ok(());
+            for side in [&mut left_side, &mut right_side] {
+                if connected.is_ok() && side.is_remote() {
+                    let _ = progress_tx.send(DiffProgressMsg::Connecting(side.display_root()));
+                }
+                connected = connected.and_then(|_| side.connect());
+            }
+            if let Err(e) = connected {
+                let _ = result_tx.send(DiffCompareResult {
+                    entries: Vec::new(),
+                    left_side,
+                    right_side,
+                    error: Some(e),
+                });
+                return;
+            }
+
+            let exclude_rules = ExcludeRules::new(&left_side.root, &right_side.root, &excludes)
+                .without_ignore_files(left_side.is_remote(), right_side.is_remote());
+
             // Phase 1: Count total items (with live progress)
             let counting_counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
-            let total = count_entries_recursive(&left_root, &right_root, "", &cancel_flag, &progress_tx, &counting_counter);
+            let total = count_entries_recursive(&left_side, &right_side, "", &exclude_rules, &cancel_flag, &progress_tx, &counting_counter);
             if cancel_flag.load(Ordering::Relaxed) {
                 return;
             }
@@ -231,13 +279,14 @@ impl DiffState {
             let mut entries = Vec::new();
             let counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
             build_recursive_threaded(
-                &left_root,
-                &right_root,
+                &left_side,
+                &right_side,
                 "",
                 0,
                 compare_method,
                 sort_by,
                 sort_order,
+                &exclude_rules,
                 &mut entries,
                 &cancel_flag,
                 &progress_tx,
@@ -246,7 +295,12 @@ impl DiffState {
             );
 
             if !cancel_flag.load(Ordering::Relaxed) {
-                let _ = result_tx.send(DiffCompareResult(entries));
+                let _ = result_tx.send(DiffCompareResult {
+                    entries,
+                    left_side,
+                    right_side,
+                    error: None,
+                });
             }
         });
     }
@@ -262,6 +316,9 @@ impl DiffState {
         if let Some(ref progress_rx) = self.progress_receiver {
             loop {
                 match progress_rx.try_recv() {
+                    Ok(DiffProgressMsg::Connecting(target)) => {
+                        self.progress_current = format!("Connecting to {}", target);
+                    }
                     Ok(DiffProgressMsg::Counting(total)) => {
                         self.progress_total = total;
                     }
@@ -278,8 +335,11 @@ impl DiffState {
         // Check for completion
         if let Some(ref receiver) = self.receiver {
             match receiver.try_recv() {
-                Ok(DiffCompareResult(entries)) => {
-                    self.all_entries = entries;
+                Ok(result) => {
+                    self.all_entries = result.entries;
+                    self.left_side = result.left_side;
+                    self.right_side = result.right_side;
+                    self.error = result.error;
                     // Collapse all directories by default
                     self.collapsed_dirs.clear();
                     for entry in &self.all_entries {
@@ -325,16 +385,21 @@ impl DiffState {
     /// Build the flat diff list by recursively comparing both directory trees (synchronous)
     pub fn build_diff_list(&mut self) {
         self.all_entries.clear();
-        let left_root = self.left_root.clone();
-        let right_root = self.right_root.clone();
+        self.error = None;
+        self.exclude_rules = self.exclude_rules();
+        if let Err(e) = self.left_side.connect().and_then(|_| self.right_side.connect()) {
+            self.error = Some(e);
+            return;
+        }
         build_recursive(
-            &left_root,
-            &right_root,
+            &self.left_side,
+            &self.right_side,
             "",
             0,
             self.compare_method,
             self.sort_by,
             self.sort_order,
+            &self.exclude_rules,
             &mut self.all_entries,
         );
         // Collapse all directories by default
@@ -346,6 +411,21 @@ impl DiffState {
         }
     }
 
+    /// Compile the current exclude settings for this pair of roots
+    pub fn exclude_rules(&self) -> ExcludeRules {
+        ExcludeRules::new(&self.left_root, &self.right_root, &self.excludes)
+            .without_ignore_files(self.left_side.is_remote(), self.right_side.is_remote())
+    }
+
+    /// The side holding the left (`is_left`) or right tree
+    pub fn side(&self, is_left: bool) -> &DiffSide {
+        if is_left {
+            &self.left_side
+        } else {
+            &self.right_side
+        }
+    }
+
     /// Rebuild filtered_indices based on the current filter and collapsed state
     pub fn apply_filter(&mut self) {
         self.filtered_indices.clear();
@@ -650,27 +730,22 @@ impl DiffState {
         }
 
         let is_left = entry.status == DiffStatus::LeftOnly;
-        let root = if is_left {
-            self.left_root.clone()
-        } else {
-            self.right_root.clone()
-        };
         let relative_path = entry.relative_path.clone();
         let parent_depth = entry.depth;
 
         // Load one level of children
-        let dir_path = root.join(&relative_path);
-        let names = read_dir_names(&dir_path);
-
-        let mut sorted_names = names;
-        sort_names_one_side(&mut sorted_names, &dir_path);
+        let side = self.side(is_left);
+        let dir_path = side.root.join(&relative_path);
+        let mut infos: Vec<DiffFileInfo> = read_included_entries(side, &dir_path, &relative_path, &self.exclude_rules)
+            .into_values()
+            .collect();
+        sort_infos_one_side(&mut infos);
 
         let mut children = Vec::new();
-        for name in &sorted_names {
-            let child_relative = format!("{}/{}", relative_path, name);
-            let full_path = dir_path.join(name);
-            let info = make_file_info(&full_path, name);
-            let is_dir = info.as_ref().map_or(false, |i| i.is_directory);
+        for info in infos {
+            let child_relative = format!("{}/{}", relative_path, info.name);
+            let is_dir = info.is_directory;
+            let info = Some(info);
             let status = if is_left {
                 DiffStatus::LeftOnly
             } else {
@@ -812,55 +887,26 @@ impl DiffState {
 // ═══════════════════════════════════════════════════════════════════════════════
 
 fn build_recursive(
-    left_root: &Path,
-    right_root: &Path,
+    left: &DiffSide,
+    right: &DiffSide,
     relative_path: &str,
     depth: usize,
     compare_method: CompareMethod,
     sort_by: SortBy,
     sort_order: SortOrder,
+    excludes: &ExcludeRules,
     entries: &mut Vec<DiffEntry>,
 ) {
-    let left_dir = if relative_path.is_empty() {
-        left_root.to_path_buf()
-    } else {
-        left_root.join(relative_path)
-    };
-    let right_dir = if relative_path.is_empty() {
-        right_root.to_path_buf()
-    } else {
-        right_root.join(relative_path)
-    };
+    let left_dir = side_dir(left, relative_path);
+    let right_dir = side_dir(right, relative_path);
 
     // Read entries from both sides
-    let left_names = read_dir_names(&left_dir);
-    let right_names = read_dir_names(&right_dir);
+    let left_infos = read_included_entries(left, &left_dir, relative_path, excludes);
+    let right_infos = read_included_entries(right, &right_dir, relative_path, excludes);
 
-    // Merge into union of names
-    let mut all_names: Vec<String> = {
-        let mut set: HashSet<String> = HashSet::new();
-        for name in &left_names {
-            set.insert(name.clone());
-        }
-        for name in &right_names {
-            set.insert(name.clone());
-        }
-        set.into_iter().collect()
-    };
-
-    let left_set: HashSet<&str> = left_names.iter().map(|s| s.as_str()).collect();
-    let right_set: HashSet<&str> = right_names.iter().map(|s| s.as_str()).collect();
-
-    // Sort names: directories first, then by sort criteria
-    sort_names(
-        &mut all_names,
-        &left_dir,
-        &right_dir,
-        &left_set,
-        &right_set,
-        sort_by,
-        sort_order,
-    );
+    // Merge into union of names, sorted: directories first, then by sort criteria
+    let mut all_names = union_names(&left_infos, &right_infos);
+    sort_names(&mut all_names, &left_infos, &right_infos, sort_by, sort_order);
 
     for name in &all_names {
         let child_relative = if relative_path.is_empty() {
@@ -869,137 +915,164 @@ fn build_recursive(
             format!("{}/{}", relative_path, name)
         };
 
-        let left_path = left_dir.join(name);
-        let right_path = right_dir.join(name);
-
-        let left_info = make_file_info(&left_path, name);
-        let right_info = make_file_info(&right_path, name);
+        let left_info = left_infos.get(name).cloned();
+        let right_info = right_infos.get(name).cloned();
 
-        let left_exists = left_set.contains(name.as_str());
-        let right_exists = right_set.contains(name.as_str());
-
-        let left_is_dir = left_info.as_ref().map_or(false, |i| i.is_directory);
-        let right_is_dir = right_info.as_ref().map_or(false, |i| i.is_directory);
+        let left_is_dir = left_info.as_ref().is_some_and(|i| i.is_directory);
+        let right_is_dir = right_info.as_ref().is_some_and(|i| i.is_directory);
         let is_directory = left_is_dir || right_is_dir;
 
-        if left_exists && right_exists {
-            // Both sides exist
-            if left_is_dir && right_is_dir {
-                // Both are directories - recurse and check children
-                let child_start = entries.len();
-
-                // Placeholder index for this directory entry
-                let dir_index = entries.len();
-                entries.push(DiffEntry {
-                    relative_path: child_relative.clone(),
-                    left: left_info,
-                    right: right_info,
-                    status: DiffStatus::DirSame, // Temporary, will be updated
-                    is_directory: true,
-                    depth,
-                    children_not_loaded: false,
-                });
-
-                build_recursive(
-                    left_root,
-                    right_root,
-                    &child_relative,
-                    depth + 1,
-                    compare_method,
-                    sort_by,
-                    sort_order,
-                    entries,
-                );
-
-                // Check if any children differ
-                let has_diff = entries[dir_index + 1..].iter().any(|e| {
-                    matches!(
-                        e.status,
-                        DiffStatus::Modified
-                            | DiffStatus::LeftOnly
-                            | DiffStatus::RightOnly
-                            | DiffStatus::DirModified
-                    )
-                });
-
-                entries[dir_index].status = if has_diff {
-                    DiffStatus::DirModified
+        match (left_info, right_info) {
+            (Some(left_info), Some(right_info)) => {
+                // Both sides exist
+                if left_is_dir && right_is_dir {
+                    // Both are directories - recurse and check children
+                    let dir_index = entries.len();
+                    entries.push(DiffEntry {
+                        relative_path: child_relative.clone(),
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: DiffStatus::DirSame, // Temporary, will be updated
+                        is_directory: true,
+                        depth,
+                        children_not_loaded: false,
+                    });
+
+                    build_recursive(
+                        left,
+                        right,
+                        &child_relative,
+                        depth + 1,
+                        compare_method,
+                        sort_by,
+                        sort_order,
+                        excludes,
+                        entries,
+                    );
+
+                    entries[dir_index].status = dir_status(&entries[dir_index + 1..]);
+                } else if !left_is_dir && !right_is_dir {
+                    // Both are files - compare
+                    let same = compare_files(left, right, &left_info, &right_info, compare_method);
+                    entries.push(DiffEntry {
+                        relative_path: child_relative,
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: if same {
+                            DiffStatus::Same
+                        } else {
+                            DiffStatus::Modified
+                        },
+                        is_directory: false,
+                        depth,
+                        children_not_loaded: false,
+                    });
                 } else {
-                    DiffStatus::DirSame
-                };
-            } else if !left_is_dir && !right_is_dir {
-                // Both are files - compare
-                let same = match (left_info.as_ref(), right_info.as_ref()) {
-                    (Some(l), Some(r)) => compare_files(l, r, compare_method),
-                    _ => false, // If either info is None (stat failed), treat as different
+                    // One is dir, one is file - treat as modified (type mismatch)
+                    entries.push(DiffEntry {
+                        relative_path: child_relative,
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: DiffStatus::Modified,
+                        is_directory,
+                        depth,
+                        children_not_loaded: false,
+                    });
+                }
+            }
+            (left_info, right_info) => {
+                // One side only - skip recursion, children loaded lazily on expand
+                let status = if left_info.is_some() {
+                    DiffStatus::LeftOnly
+                } else {
+                    DiffStatus::RightOnly
                 };
                 entries.push(DiffEntry {
                     relative_path: child_relative,
                     left: left_info,
                     right: right_info,
-                    status: if same {
-                        DiffStatus::Same
-                    } else {
-                        DiffStatus::Modified
-                    },
-                    is_directory: false,
-                    depth,
-                    children_not_loaded: false,
-                });
-            } else {
-                // One is dir, one is file - treat as modified (type mismatch)
-                entries.push(DiffEntry {
-                    relative_path: child_relative,
-                    left: left_info,
-                    right: right_info,
-                    status: DiffStatus::Modified,
+                    status,
                     is_directory,
                     depth,
-                    children_not_loaded: false,
+                    children_not_loaded: is_directory,
                 });
             }
-        } else if left_exists {
-            // Left only - skip recursion, children loaded lazily on expand
-            entries.push(DiffEntry {
-                relative_path: child_relative,
-                left: left_info,
-                right: None,
-                status: DiffStatus::LeftOnly,
-                is_directory,
-                depth,
-                children_not_loaded: is_directory,
-            });
+        }
+    }
+}
+
+/// Recursively collect all descendants of a one-side-only directory.
+/// `dir` is the directory on `side`, `relative_path` its path within the comparison.
+/// Symlinked directories are listed but not descended into.
+pub(crate) fn collect_one_side_tree(
+    side: &DiffSide,
+    dir: &Path,
+    relative_path: &str,
+    depth: usize,
+    is_left: bool,
+    excludes: &ExcludeRules,
+    entries: &mut Vec<DiffEntry>,
+) {
+    let mut infos: Vec<DiffFileInfo> = read_included_entries(side, dir, relative_path, excludes)
+        .into_values()
+        .collect();
+    sort_infos_one_side(&mut infos);
+
+    for info in infos {
+        let child_relative = format!("{}/{}", relative_path, info.name);
+        let full_path = info.full_path.clone();
+        let is_dir = info.is_directory;
+        let descend = is_dir && !info.is_symlink;
+        let (left, right, status) = if is_left {
+            (Some(info), None, DiffStatus::LeftOnly)
         } else {
-            // Right only - skip recursion, children loaded lazily on expand
-            entries.push(DiffEntry {
-                relative_path: child_relative,
-                left: None,
-                right: right_info,
-                status: DiffStatus::RightOnly,
-                is_directory,
-                depth,
-                children_not_loaded: is_directory,
-            });
+            (None, Some(info), DiffStatus::RightOnly)
+        };
+
+        entries.push(DiffEntry {
+            relative_path: child_relative.clone(),
+            left,
+            right,
+            status,
+            is_directory: is_dir,
+            depth,
+            children_not_loaded: false,
+        });
+
+        if descend {
+            collect_one_side_tree(side, &full_path, &child_relative, depth + 1, is_left, excludes, entries);
         }
     }
 }
 
+/// Status of a directory present on both sides, given its (already built) descendants
+fn dir_status(children: &[DiffEntry]) -> DiffStatus {
+    let has_diff = children.iter().any(|e| {
+        matches!(
+            e.status,
+            DiffStatus::Modified
+                | DiffStatus::LeftOnly
+                | DiffStatus::RightOnly
+                | DiffStatus::DirModified
+        )
+    });
+    if has_diff {
+        DiffStatus::DirModified
+    } else {
+        DiffStatus::DirSame
+    }
+}
 
 // ═══════════════════════════════════════════════════════════════════════════════
 // Threaded diff builders (async with cancel + progress)
 // ═══════════════════════════════════════════════════════════════════════════════
 
-/// Check if path is a directory using the same logic as make_file_info
-/// (symlink_metadata → metadata fallback), matching build_recursive_threaded behavior
-fn is_dir_via_info(path: &Path) -> bool {
-    make_file_info(path, "").map_or(false, |i| i.is_directory)
-}
-
 /// Count total entries in both directory trees (for progress bar total)
 fn count_entries_recursive(
-    left_root: &Path,
-    right_root: &Path,
+    left: &DiffSide,
+    right: &DiffSide,
     relative_path: &str,
+    excludes: &ExcludeRules,
     cancel_flag: &AtomicBool,
     progress_tx: &Sender<DiffProgressMsg>,
     running_count: &Arc<std::sync::atomic::AtomicUsize>,
@@ -1008,30 +1081,9 @@ fn count_entries_recursive(
         return 0;
     }
 
-    let left_dir = if relative_path.is_empty() {
-        left_root.to_path_buf()
-    } else {
-        left_root.join(relative_path)
-    };
-    let right_dir = if relative_path.is_empty() {
-        right_root.to_path_buf()
-    } else {
-        right_root.join(relative_path)
-    };
-
-    let left_names = read_dir_names(&left_dir);
-    let right_names = read_dir_names(&right_dir);
-
-    let mut all_names: HashSet<String> = HashSet::new();
-    for name in &left_names {
-        all_names.insert(name.clone());
-    }
-    for name in &right_names {
-        all_names.insert(name.clone());
-    }
-
-    let left_set: HashSet<&str> = left_names.iter().map(|s| s.as_str()).collect();
-    let right_set: HashSet<&str> = right_names.iter().map(|s| s.as_str()).collect();
+    let left_infos = read_included_entries(left, &side_dir(left, relative_path), relative_path, excludes);
+    let right_infos = read_included_entries(right, &side_dir(right, relative_path), relative_path, excludes);
+    let all_names = union_names(&left_infos, &right_infos);
 
     let added = all_names.len();
     let new_total = running_count.fetch_add(added, Ordering::Relaxed) + added;
@@ -1043,21 +1095,16 @@ fn count_entries_recursive(
         if cancel_flag.load(Ordering::Relaxed) {
             return count;
         }
-        let left_exists = left_set.contains(name.as_str());
-        let right_exists = right_set.contains(name.as_str());
-
-        let child_relative = if relative_path.is_empty() {
-            name.clone()
-        } else {
-            format!("{}/{}", relative_path, name)
-        };
 
-        if left_exists && right_exists {
-            let left_is_dir = is_dir_via_info(&left_dir.join(name));
-            let right_is_dir = is_dir_via_info(&right_dir.join(name));
-            if left_is_dir && right_is_dir {
-                count += count_entries_recursive(left_root, right_root, &child_relative, cancel_flag, progress_tx, running_count);
-            }
+        let left_is_dir = left_infos.get(name).is_some_and(|i| i.is_directory);
+        let right_is_dir = right_infos.get(name).is_some_and(|i| i.is_directory);
+        if left_is_dir && right_is_dir {
+            let child_relative = if relative_path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", relative_path, name)
+            };
+            count += count_entries_recursive(left, right, &child_relative, excludes, cancel_flag, progress_tx, running_count);
         }
         // One-side-only directories: no recursion needed, just count the directory itself
     }
@@ -1067,13 +1114,14 @@ fn count_entries_recursive(
 
 /// Threaded version of build_recursive with cancel_flag and progress reporting
 fn build_recursive_threaded(
-    left_root: &Path,
-    right_root: &Path,
+    left: &DiffSide,
+    right: &DiffSide,
     relative_path: &str,
     depth: usize,
     compare_method: CompareMethod,
     sort_by: SortBy,
     sort_order: SortOrder,
+    excludes: &ExcludeRules,
     entries: &mut Vec<DiffEntry>,
     cancel_flag: &AtomicBool,
     progress_tx: &Sender<DiffProgressMsg>,
@@ -1084,43 +1132,14 @@ fn build_recursive_threaded(
         return;
     }
 
-    let left_dir = if relative_path.is_empty() {
-        left_root.to_path_buf()
-    } else {
-        left_root.join(relative_path)
-    };
-    let right_dir = if relative_path.is_empty() {
-        right_root.to_path_buf()
-    } else {
-        right_root.join(relative_path)
-    };
+    let left_dir = side_dir(left, relative_path);
+    let right_dir = side_dir(right, relative_path);
 
-    let left_names = read_dir_names(&left_dir);
-    let right_names = read_dir_names(&right_dir);
+    let left_infos = read_included_entries(left, &left_dir, relative_path, excludes);
+    let right_infos = read_included_entries(right, &right_dir, relative_path, excludes);
 
-    let mut all_names: Vec<String> = {
-        let mut set: HashSet<String> = HashSet::new();
-        for name in &left_names {
-            set.insert(name.clone());
-        }
-        for name in &right_names {
-            set.insert(name.clone());
-        }
-        set.into_iter().collect()
-    };
-
-    let left_set: HashSet<&str> = left_names.iter().map(|s| s.as_str()).collect();
-    let right_set: HashSet<&str> = right_names.iter().map(|s| s.as_str()).collect();
-
-    sort_names(
-        &mut all_names,
-        &left_dir,
-        &right_dir,
-        &left_set,
-        &right_set,
-        sort_by,
-        sort_order,
-    );
+    let mut all_names = union_names(&left_infos, &right_infos);
+    sort_names(&mut all_names, &left_infos, &right_infos, sort_by, sort_order);
 
     for name in &all_names {
         if cancel_flag.load(Ordering::Relaxed) {
@@ -1137,113 +1156,88 @@ fn build_recursive_threaded(
         let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
         let _ = progress_tx.send(DiffProgressMsg::Comparing(child_relative.clone(), count, total));
 
-        let left_path = left_dir.join(name);
-        let right_path = right_dir.join(name);
-
-        let left_info = make_file_info(&left_path, name);
-        let right_info = make_file_info(&right_path, name);
+        let left_info = left_infos.get(name).cloned();
+        let right_info = right_infos.get(name).cloned();
 
-        let left_exists = left_set.contains(name.as_str());
-        let right_exists = right_set.contains(name.as_str());
-
-        let left_is_dir = left_info.as_ref().map_or(false, |i| i.is_directory);
-        let right_is_dir = right_info.as_ref().map_or(false, |i| i.is_directory);
+        let left_is_dir = left_info.as_ref().is_some_and(|i| i.is_directory);
+        let right_is_dir = right_info.as_ref().is_some_and(|i| i.is_directory);
         let is_directory = left_is_dir || right_is_dir;
 
-        if left_exists && right_exists {
-            if left_is_dir && right_is_dir {
-                let dir_index = entries.len();
-                entries.push(DiffEntry {
-                    relative_path: child_relative.clone(),
-                    left: left_info,
-                    right: right_info,
-                    status: DiffStatus::DirSame,
-                    is_directory: true,
-                    depth,
-                    children_not_loaded: false,
-                });
-
-                build_recursive_threaded(
-                    left_root,
-                    right_root,
-                    &child_relative,
-                    depth + 1,
-                    compare_method,
-                    sort_by,
-                    sort_order,
-                    entries,
-                    cancel_flag,
-                    progress_tx,
-                    total,
-                    counter,
-                );
-
-                let has_diff = entries[dir_index + 1..].iter().any(|e| {
-                    matches!(
-                        e.status,
-                        DiffStatus::Modified
-                            | DiffStatus::LeftOnly
-                            | DiffStatus::RightOnly
-                            | DiffStatus::DirModified
-                    )
-                });
-
-                entries[dir_index].status = if has_diff {
-                    DiffStatus::DirModified
+        match (left_info, right_info) {
+            (Some(left_info), Some(right_info)) => {
+                if left_is_dir && right_is_dir {
+                    let dir_index = entries.len();
+                    entries.push(DiffEntry {
+                        relative_path: child_relative.clone(),
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: DiffStatus::DirSame,
+                        is_directory: true,
+                        depth,
+                        children_not_loaded: false,
+                    });
+
+                    build_recursive_threaded(
+                        left,
+                        right,
+                        &child_relative,
+                        depth + 1,
+                        compare_method,
+                        sort_by,
+                        sort_order,
+                        excludes,
+                        entries,
+                        cancel_flag,
+                        progress_tx,
+                        total,
+                        counter,
+                    );
+
+                    entries[dir_index].status = dir_status(&entries[dir_index + 1..]);
+                } else if !left_is_dir && !right_is_dir {
+                    let same = compare_files(left, right, &left_info, &right_info, compare_method);
+                    entries.push(DiffEntry {
+                        relative_path: child_relative,
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: if same {
+                            DiffStatus::Same
+                        } else {
+                            DiffStatus::Modified
+                        },
+                        is_directory: false,
+                        depth,
+                        children_not_loaded: false,
+                    });
                 } else {
-                    DiffStatus::DirSame
-                };
-            } else if !left_is_dir && !right_is_dir {
-                let same = match (left_info.as_ref(), right_info.as_ref()) {
-                    (Some(l), Some(r)) => compare_files(l, r, compare_method),
-                    _ => false,
+                    entries.push(DiffEntry {
+                        relative_path: child_relative,
+                        left: Some(left_info),
+                        right: Some(right_info),
+                        status: DiffStatus::Modified,
+                        is_directory,
+                        depth,
+                        children_not_loaded: false,
+                    });
+                }
+            }
+            (left_info, right_info) => {
+                // One side only - skip recursion, children loaded lazily on expand
+                let status = if left_info.is_some() {
+                    DiffStatus::LeftOnly
+                } else {
+                    DiffStatus::RightOnly
                 };
                 entries.push(DiffEntry {
                     relative_path: child_relative,
                     left: left_info,
                     right: right_info,
-                    status: if same {
-                        DiffStatus::Same
-                    } else {
-                        DiffStatus::Modified
-                    },
-                    is_directory: false,
-                    depth,
-                    children_not_loaded: false,
-                });
-            } else {
-                entries.push(DiffEntry {
-                    relative_path: child_relative,
-                    left: left_info,
-                    right: right_info,
-                    status: DiffStatus::Modified,
+                    status,
                     is_directory,
                     depth,
-                    children_not_loaded: false,
+                    children_not_loaded: is_directory,
                 });
             }
-        } else if left_exists {
-            // Left only - skip recursion, children loaded lazily on expand
-            entries.push(DiffEntry {
-                relative_path: child_relative,
-                left: left_info,
-                right: None,
-                status: DiffStatus::LeftOnly,
-                is_directory,
-                depth,
-                children_not_loaded: is_directory,
-            });
-        } else {
-            // Right only - skip recursion, children loaded lazily on expand
-            entries.push(DiffEntry {
-                relative_path: child_relative,
-                left: None,
-                right: right_info,
-                status: DiffStatus::RightOnly,
-                is_directory,
-                depth,
-                children_not_loaded: is_directory,
-            });
         }
     }
 }
@@ -1252,74 +1246,71 @@ fn build_recursive_threaded(
 // Helper functions
 // ═══════════════════════════════════════════════════════════════════════════════
 
-/// Sort names for lazy-loaded one-side directories (directories first, then by name)
-fn sort_names_one_side(names: &mut Vec<String>, dir: &Path) {
-    names.sort_by(|a, b| {
-        let a_is_dir = dir.join(a).is_dir();
-        let b_is_dir = dir.join(b).is_dir();
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.to_lowercase().cmp(&b.to_lowercase()),
-        }
+/// Directory of `relative_path` on one side
+fn side_dir(side: &DiffSide, relative_path: &str) -> PathBuf {
+    if relative_path.is_empty() {
+        side.root.clone()
+    } else {
+        side.root.join(relative_path)
+    }
+}
+
+/// Sort entries for lazy-loaded one-side directories (directories first, then by name)
+fn sort_infos_one_side(infos: &mut [DiffFileInfo]) {
+    infos.sort_by(|a, b| match (a.is_directory, b.is_directory) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
     });
 }
 
-/// Read directory entry names, returning an empty vec on failure
-fn read_dir_names(dir: &Path) -> Vec<String> {
-    match fs::read_dir(dir) {
-        Ok(entries) => entries
-            .filter_map(|e| e.ok())
-            .map(|e| e.file_name().to_string_lossy().to_string())
-            .collect(),
-        Err(_) => Vec::new(),
-    }
+/// List a directory on one side keyed by name, dropping entries matched by the
+/// exclude rules. `relative_path` is the directory's path relative to the comparison roots.
+fn read_included_entries(
+    side: &DiffSide,
+    dir: &Path,
+    relative_path: &str,
+    excludes: &ExcludeRules,
+) -> HashMap<String, DiffFileInfo> {
+    side.list_dir(dir)
+        .into_iter()
+        .filter(|info| {
+            if !excludes.is_active() {
+                return true;
+            }
+            let relative = if relative_path.is_empty() {
+                info.name.clone()
+            } else {
+                format!("{}/{}", relative_path, info.name)
+            };
+            !excludes.is_excluded(&relative, info.is_directory)
+        })
+        .map(|info| (info.name.clone(), info))
+        .collect()
 }
 
-/// Build DiffFileInfo from a path, returning None if the path doesn't exist
-fn make_file_info(path: &Path, name: &str) -> Option<DiffFileInfo> {
-    let metadata = fs::symlink_metadata(path).ok()?;
-    let is_symlink = metadata.file_type().is_symlink();
-    let actual_metadata = if is_symlink {
-        fs::metadata(path).unwrap_or(metadata.clone())
-    } else {
-        metadata.clone()
-    };
-    let is_directory = actual_metadata.is_dir();
-    let size = if is_directory { 0 } else { actual_metadata.len() };
-    let modified = metadata
-        .modified()
-        .ok()
-        .map(DateTime::<Local>::from)
-        .unwrap_or_else(Local::now);
-
-    Some(DiffFileInfo {
-        name: name.to_string(),
-        size,
-        modified,
-        is_directory,
-        is_symlink,
-        full_path: path.to_path_buf(),
-    })
+/// Union of the names present on either side
+fn union_names(left: &HashMap<String, DiffFileInfo>, right: &HashMap<String, DiffFileInfo>) -> Vec<String> {
+    let set: HashSet<&String> = left.keys().chain(right.keys()).collect();
+    set.into_iter().cloned().collect()
 }
 
-/// Sort names: directories first, then by sort_by/sort_order
+/// Sort names: directories first, then by sort_by/sort_order (left side preferred for keys)
 fn sort_names(
-    names: &mut Vec<String>,
-    left_dir: &Path,
-    right_dir: &Path,
-    left_set: &HashSet<&str>,
-    right_set: &HashSet<&str>,
+    names: &mut [String],
+    left: &HashMap<String, DiffFileInfo>,
+    right: &HashMap<String, DiffFileInfo>,
     sort_by: SortBy,
     sort_order: SortOrder,
 ) {
-    names.sort_by(|a, b| {
-        // Determine if each name is a directory (check both sides)
-        let a_is_dir = is_name_dir(a, left_dir, right_dir, left_set, right_set);
-        let b_is_dir = is_name_dir(b, left_dir, right_dir, left_set, right_set);
+    let is_dir = |name: &str| {
+        left.get(name).is_some_and(|i| i.is_directory) || right.get(name).is_some_and(|i| i.is_directory)
+    };
+    let info = |name: &str| left.get(name).or_else(|| right.get(name));
 
+    names.sort_by(|a, b| {
         // Directories first
-        match (a_is_dir, b_is_dir) {
+        match (is_dir(a), is_dir(b)) {
             (true, false) => return std::cmp::Ordering::Less,
             (false, true) => return std::cmp::Ordering::Greater,
             _ => {}
@@ -1328,13 +1319,13 @@ fn sort_names(
         let ord = match sort_by {
             SortBy::Name => a.to_lowercase().cmp(&b.to_lowercase()),
             SortBy::Size => {
-                let a_size = get_name_size(a, left_dir, right_dir, left_set, right_set);
-                let b_size = get_name_size(b, left_dir, right_dir, left_set, right_set);
+                let a_size = info(a).map(|i| i.size).unwrap_or(0);
+                let b_size = info(b).map(|i| i.size).unwrap_or(0);
                 a_size.cmp(&b_size)
             }
             SortBy::Modified => {
-                let a_mod = get_name_modified(a, left_dir, right_dir, left_set, right_set);
-                let b_mod = get_name_modified(b, left_dir, right_dir, left_set, right_set);
+                let a_mod = info(a).map(|i| i.modified);
+                let b_mod = info(b).map(|i| i.modified);
                 a_mod.cmp(&b_mod)
             }
             SortBy::Type => {
@@ -1351,75 +1342,6 @@ fn sort_names(
     });
 }
 
-
-
-fn is_name_dir(
-    name: &str,
-    left_dir: &Path,
-    right_dir: &Path,
-    left_set: &HashSet<&str>,
-    right_set: &HashSet<&str>,
-) -> bool {
-    if left_set.contains(name) {
-        let path = left_dir.join(name);
-        if path.is_dir() {
-            return true;
-        }
-    }
-    if right_set.contains(name) {
-        let path = right_dir.join(name);
-        if path.is_dir() {
-            return true;
-        }
-    }
-    false
-}
-
-fn get_name_size(
-    name: &str,
-    left_dir: &Path,
-    right_dir: &Path,
-    left_set: &HashSet<&str>,
-    right_set: &HashSet<&str>,
-) -> u64 {
-    // Prefer left side for sorting
-    if left_set.contains(name) {
-        if let Ok(m) = fs::metadata(left_dir.join(name)) {
-            return m.len();
-        }
-    }
-    if right_set.contains(name) {
-        if let Ok(m) = fs::metadata(right_dir.join(name)) {
-            return m.len();
-        }
-    }
-    0
-}
-
-fn get_name_modified(
-    name: &str,
-    left_dir: &Path,
-    right_dir: &Path,
-    left_set: &HashSet<&str>,
-    right_set: &HashSet<&str>,
-) -> std::time::SystemTime {
-    if left_set.contains(name) {
-        if let Ok(m) = fs::metadata(left_dir.join(name)) {
-            if let Ok(t) = m.modified() {
-                return t;
-            }
-        }
-    }
-    if right_set.contains(name) {
-        if let Ok(m) = fs::metadata(right_dir.join(name)) {
-            if let Ok(t) = m.modified() {
-                return t;
-            }
-        }
-    }
-    std::time::SystemTime::UNIX_EPOCH
-}
-
 fn get_extension(name: &str) -> String {
     Path::new(name)
         .extension()
@@ -1532,17 +1454,23 @@ fn compare_entries_for_sort(
 // ═══════════════════════════════════════════════════════════════════════════════
 
 /// Compare two files. Returns true if they are considered the same.
-pub fn compare_files(left: &DiffFileInfo, right: &DiffFileInfo, method: CompareMethod) -> bool {
+pub fn compare_files(
+    left_side: &DiffSide,
+    right_side: &DiffSide,
+    left: &DiffFileInfo,
+    right: &DiffFileInfo,
+    method: CompareMethod,
+) -> bool {
     // If both are symlinks, compare their target paths
     if left.is_symlink && right.is_symlink {
-        return fs::read_link(&left.full_path).ok() == fs::read_link(&right.full_path).ok();
+        return left_side.read_link(&left.full_path) == right_side.read_link(&right.full_path);
     }
     match method {
         CompareMethod::Content => {
             if left.size != right.size {
                 return false;
             }
-            byte_compare(&left.full_path, &right.full_path)
+            side_content_equal(left_side, right_side, left, right)
         }
         CompareMethod::ModifiedTime => {
             // Compare truncated to seconds to avoid sub-second differences
@@ -1551,11 +1479,25 @@ pub fn compare_files(left: &DiffFileInfo, right: &DiffFileInfo, method: CompareM
         CompareMethod::ContentAndTime => {
             left.modified.timestamp() == right.modified.timestamp()
                 && left.size == right.size
-                && byte_compare(&left.full_path, &right.full_path)
+                && side_content_equal(left_side, right_side, left, right)
         }
     }
 }
 
+/// Byte comparison of two files that may live on remote sides
+/// (remote files are downloaded to a temporary copy first)
+fn side_content_equal(left_side: &DiffSide, right_side: &DiffSide, left: &DiffFileInfo, right: &DiffFileInfo) -> bool {
+    let left_copy = match left_side.local_copy(&left.full_path) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+    let right_copy = match right_side.local_copy(&right.full_path) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+    byte_compare(left_copy.path(), right_copy.path())
+}
+
 /// Byte-by-byte comparison of two files using buffered 8KB reads.
 /// Returns true if files are identical.
 pub fn byte_compare(path_a: &Path, path_b: &Path) -> bool {
@@ -1604,6 +1546,110 @@ fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> usize {
     filled
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+// Sync
+// ═══════════════════════════════════════════════════════════════════════════════
+
+fn side_info(entry: &DiffEntry, is_left: bool) -> Option<&DiffFileInfo> {
+    if is_left {
+        entry.left.as_ref()
+    } else {
+        entry.right.as_ref()
+    }
+}
+
+/// Plan copying the selected entries (or the current one) towards one side.
+///
+/// Returns (parent relative path, names) groups; "" is the comparison root.
+/// Directories present on both sides contribute their differing descendants,
+/// entries missing on the target are lifted to their topmost missing ancestor
+/// so the copy lands in a directory that exists. Identical entries, entries
+/// absent on the source side and file/directory type mismatches are skipped.
+pub fn plan_sync(state: &DiffState, to_right: bool) -> Vec<(String, Vec<String>)> {
+    let by_path: HashMap<&str, &DiffEntry> = state
+        .all_entries
+        .iter()
+        .map(|e| (e.relative_path.as_str(), e))
+        .collect();
+
+    let roots: Vec<String> = if state.selected_files.is_empty() {
+        state.current_entry().map(|e| e.relative_path.clone()).into_iter().collect()
+    } else {
+        state.selected_files.iter().cloned().collect()
+    };
+
+    let mut picked: BTreeSet<String> = BTreeSet::new();
+    for root in &roots {
+        let prefix = format!("{}/", root);
+        for entry in &state.all_entries {
+            if entry.relative_path != *root && !entry.relative_path.starts_with(&prefix) {
+                continue;
+            }
+            let source = match side_info(entry, to_right) {
+                Some(info) => info,
+                None => continue,
+            };
+            let needed = match side_info(entry, !to_right) {
+                None => true,
+                Some(target) => {
+                    entry.status == DiffStatus::Modified && !source.is_directory && !target.is_directory
+                }
+            };
+            if !needed {
+                continue;
+            }
+
+            // Lift to the topmost ancestor that is missing on the target side
+            let mut path = entry.relative_path.as_str();
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                match by_path.get(parent) {
+                    Some(p) if side_info(p, !to_right).is_none() => path = parent,
+                    _ => break,
+                }
+            }
+            picked.insert(path.to_string());
+        }
+    }
+
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in &picked {
+        // Skip entries already covered by a picked ancestor
+        let mut covered = false;
+        let mut ancestor = path.as_str();
+        while let Some((parent, _)) = ancestor.rsplit_once('/') {
+            if picked.contains(parent) {
+                covered = true;
+                break;
+            }
+            ancestor = parent;
+        }
+        if covered {
+            continue;
+        }
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+        groups.entry(parent.to_string()).or_default().push(name.to_string());
+    }
+    groups.into_iter().collect()
+}
+
+/// The transfers that carry out `plan_sync`: one per source directory, with
+/// paths on the sides' own machines
+pub fn sync_transfer_groups(state: &DiffState, to_right: bool) -> Vec<TransferGroup> {
+    let (source, target) = if to_right {
+        (&state.left_side, &state.right_side)
+    } else {
+        (&state.right_side, &state.left_side)
+    };
+    plan_sync(state, to_right)
+        .into_iter()
+        .map(|(parent, names)| TransferGroup {
+            source_base: source.root.join(&parent).display().to_string(),
+            target_path: target.root.join(&parent).display().to_string(),
+            files: names.into_iter().map(PathBuf::from).collect(),
+        })
+        .collect()
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // Drawing
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -1615,6 +1661,7 @@ pub fn draw(
     area: Rect,
     theme: &Theme,
     kb: &crate::keybindings::Keybindings,
+    message: Option<&str>,
 ) {
     // Layout: Header(1) + ColumnHeader(1) + Content(fill) + StatusBar(1) + FunctionBar(1)
     let layout = Layout::default()
@@ -1705,7 +1752,7 @@ pub fn draw(
     }
 
     // ── Status Bar ──────────────────────────────────────────────────────────
-    draw_status_bar(frame, state, status_area, theme);
+    draw_status_bar(frame, state, status_area, theme, message);
 
     // ── Function Bar ────────────────────────────────────────────────────────
     draw_function_bar(frame, fn_bar_area, theme, kb);
@@ -1728,6 +1775,9 @@ fn draw_comparing_progress(frame: &mut Frame, state: &DiffState, area: Rect, the
         // Phase 1: Counting files — spinner + live count
         let count_text = if state.progress_total > 0 {
             format!("Counting files... ({})", state.progress_total)
+        } else if !state.progress_current.is_empty() {
+            // Still opening an SFTP session
+            format!("{}...", state.progress_current)
         } else {
             "Counting files...".to_string()
         };
@@ -1840,7 +1890,7 @@ fn draw_header(frame: &mut Frame, state: &DiffState, area: Rect, theme: &Theme)
     let max_path_width = (area.width as usize).saturating_sub(12); // "[DIFF] " + " ⟷ "
     let half_width = max_path_width / 2;
 
-    let left_str = state.left_root.display().to_string();
+    let left_str = state.left_side.display_root();
     let left_display = if left_str.width() > half_width {
         let suffix = crate::utils::format::display_width_suffix(&left_str, half_width.saturating_sub(3));
         format!("...{}", suffix)
@@ -1848,7 +1898,7 @@ fn draw_header(frame: &mut Frame, state: &DiffState, area: Rect, theme: &Theme)
         left_str
     };
 
-    let right_str = state.right_root.display().to_string();
+    let right_str = state.right_side.display_root();
     let right_display = if right_str.width() > half_width {
         let suffix = crate::utils::format::display_width_suffix(&right_str, half_width.saturating_sub(3));
         format!("...{}", suffix)
@@ -2118,7 +2168,21 @@ fn get_entry_styles(
     }
 }
 
-fn draw_status_bar(frame: &mut Frame, state: &DiffState, area: Rect, theme: &Theme) {
+fn draw_status_bar(frame: &mut Frame, state: &DiffState, area: Rect, theme: &Theme, message: Option<&str>) {
+    let status_style = Style::default()
+        .fg(theme.diff.status_bar_text)
+        .bg(theme.diff.status_bar_bg);
+
+    // Transient message (e.g. export result) takes over the status bar
+    if let Some(msg) = message {
+        let line = Line::from(vec![Span::styled(
+            format!("{:<width$}", format!(" {}", msg), width = area.width as usize),
+            status_style.add_modifier(Modifier::BOLD),
+        )]);
+        frame.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
     let total = state.all_entries.len();
     let diff_count = state
         .all_entries
@@ -2143,10 +2207,17 @@ fn draw_status_bar(frame: &mut Frame, state: &DiffState, area: Rect, theme: &The
         String::new()
     };
 
+    let exclude_str = if state.excludes.is_empty() {
+        String::new()
+    } else {
+        format!(" | Exclude: {}", state.excludes.summary())
+    };
+
     let status_text = format!(
-        " Filter: {} | Compare: {} | Total: {} Different: {} Left: {} Right: {}{}",
+        " Filter: {} | Compare: {}{} | Total: {} Different: {} Left: {} Right: {}{}",
         state.filter.display_name(),
         state.compare_method.display_name(),
+        exclude_str,
         total,
         diff_count,
         left_count,
@@ -2154,10 +2225,6 @@ fn draw_status_bar(frame: &mut Frame, state: &DiffState, area: Rect, theme: &The
         sel_str,
     );
 
-    let status_style = Style::default()
-        .fg(theme.diff.status_bar_text)
-        .bg(theme.diff.status_bar_bg);
-
     let line = Line::from(vec![Span::styled(
         format!("{:<width$}", status_text, width = area.width as usize),
         status_style,
@@ -2181,6 +2248,17 @@ fn draw_function_bar(frame: &mut Frame, area: Rect, theme: &Theme, kb: &crate::k
         (kb.diff_screen_first_key(DiffScreenAction::SortBySize).to_string(), "ize "),
         (kb.diff_screen_first_key(DiffScreenAction::SortByDate).to_string(), "ate "),
         (kb.diff_screen_first_key(DiffScreenAction::SortByType).to_string(), ":type "),
+        (kb.diff_screen_first_key(DiffScreenAction::EditExcludes).to_string(), ":exclude "),
+        (kb.diff_screen_first_key(DiffScreenAction::ToggleGitignore).to_string(), ":gitignore "),
+        (
+            format!(
+                "{}{}",
+                kb.diff_screen_first_key(DiffScreenAction::SyncToRight),
+                kb.diff_screen_first_key(DiffScreenAction::SyncToLeft)
+            ),
+            ":sync ",
+        ),
+        (kb.diff_screen_first_key(DiffScreenAction::Export).to_string(), ":export "),
         (kb.diff_screen_first_key(DiffScreenAction::Close).to_string(), ":back"),
     ];
 
@@ -2289,6 +2367,21 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
             DiffScreenAction::CollapseAll => {
                 state.collapse();
             }
+            DiffScreenAction::EditExcludes => {
+                app.show_diff_exclude_dialog();
+            }
+            DiffScreenAction::ToggleGitignore => {
+                app.toggle_diff_gitignore();
+            }
+            DiffScreenAction::SyncToRight => {
+                app.show_diff_sync_dialog(true);
+            }
+            DiffScreenAction::SyncToLeft => {
+                app.show_diff_sync_dialog(false);
+            }
+            DiffScreenAction::Export => {
+                app.show_diff_export_dialog();
+            }
             DiffScreenAction::Open => {
                 // Handle Enter: view file diff if current entry is a file
                 handle_enter(app);
@@ -2339,15 +2432,110 @@ fn handle_enter(app: &mut App) {
         return;
     }
 
-    // Need both sides for file diff view
-    let left_path = entry.left.as_ref().map(|f| f.full_path.clone())
-        .unwrap_or_default();
-    let right_path = entry.right.as_ref().map(|f| f.full_path.clone())
-        .unwrap_or_default();
+    // Files on remote sides are downloaded to temporary copies for the view
+    let copies = match app.diff_state.as_ref() {
+        Some(state) => [(&state.left_side, &entry.left), (&state.right_side, &entry.right)]
+            .into_iter()
+            .map(|(side, info)| info.as_ref().map(|i| side.local_copy(&i.full_path)).transpose())
+            .collect::<Result<Vec<_>, String>>(),
+        None => return,
+    };
+    let copies = match copies {
+        Ok(copies) => copies,
+        Err(e) => {
+            app.show_message(&format!("Download failed: {}", e));
+            return;
+        }
+    };
+    let path_of = |copy: Option<&LocalCopy>| copy.map(|c| c.path().to_path_buf()).unwrap_or_default();
+    let left_path = path_of(copies.first().and_then(|c| c.as_ref()));
+    let right_path = path_of(copies.get(1).and_then(|c| c.as_ref()));
 
     // Get file name for display
     let file_name = entry.relative_path.clone();
 
     // Enter file content diff view
     app.enter_diff_file_view(left_path, right_path, file_name);
+    if let Some(ref mut view) = app.diff_file_view_state {
+        view.local_copies = copies.into_iter().flatten().collect();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn compare(left: &Path, right: &Path) -> DiffState {
+        let mut state = DiffState::new(
+            left.to_path_buf(),
+            right.to_path_buf(),
+            CompareMethod::Content,
+            SortBy::Name,
+            SortOrder::Asc,
+        );
+        state.build_diff_list();
+        state.apply_filter();
+        state
+    }
+
+    #[test]
+    fn test_plan_sync_groups_by_parent_and_lifts_missing_dirs() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        for root in [left.path(), right.path()] {
+            fs_write(&root.join("same.txt"), "s");
+            std::fs::create_dir_all(root.join("src")).unwrap();
+        }
+        fs_write(&left.path().join("src/main.rs"), "old");
+        fs_write(&right.path().join("src/main.rs"), "new");
+        fs_write(&left.path().join("src/new/deep/a.rs"), "a");
+        fs_write(&left.path().join("top.txt"), "t");
+        fs_write(&right.path().join("only_right.txt"), "r");
+
+        let mut state = compare(left.path(), right.path());
+        // Select the whole tree: both-side directories expand to their differences
+        state.selected_files = ["src", "same.txt", "top.txt", "only_right.txt"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(
+            plan_sync(&state, true),
+            vec![
+                (String::new(), vec!["top.txt".to_string()]),
+                ("src".to_string(), vec!["main.rs".to_string(), "new".to_string()]),
+            ]
+        );
+        assert_eq!(
+            plan_sync(&state, false),
+            vec![
+                (String::new(), vec!["only_right.txt".to_string()]),
+                ("src".to_string(), vec!["main.rs".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_sync_current_entry() {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs_write(&left.path().join("a.txt"), "a");
+
+        let state = compare(left.path(), right.path());
+        assert_eq!(plan_sync(&state, true), vec![(String::new(), vec!["a.txt".to_string()])]);
+        // Nothing exists on the right to copy back
+        assert!(plan_sync(&state, false).is_empty());
+    }
+
+    fn fs_write(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, content).unwrap();
+    }
 }
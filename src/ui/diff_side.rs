@@ -0,0 +1,522 @@
+//! One side of a folder comparison: a local directory or a directory on a
+//! remote panel, read over SFTP.
+//!
+//! Remote sides carry the panel's profile. The comparison thread opens its own
+//! session (like other background remote operations) and hands it back to the
+//! diff state, which keeps it for lazy loading, file views and exports.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{DateTime, Local};
+
+use super::diff_screen::DiffFileInfo;
+use crate::services::remote::{self, RemoteProfile, SftpFileEntry, SftpSession};
+
+/// Counter for unique temp download names within this process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct DiffSide {
+    pub root: PathBuf,
+    /// Profile of the remote panel, None for a local directory
+    pub remote: Option<RemoteProfile>,
+    session: Option<SftpSession>,
+}
+
+impl DiffSide {
+    pub fn local(root: PathBuf) -> Self {
+        Self {
+            root,
+            remote: None,
+            session: None,
+        }
+    }
+
+    pub fn remote(root: PathBuf, profile: RemoteProfile) -> Self {
+        Self {
+            root,
+            remote: Some(profile),
+            session: None,
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    /// True if both sides point at the same directory on the same machine
+    pub fn same_location(&self, other: &DiffSide) -> bool {
+        let host = |side: &DiffSide| side.remote.as_ref().map(|p| (p.user.clone(), p.host.clone(), p.port));
+        self.root == other.root && host(self) == host(other)
+    }
+
+    /// Move this side (including any open session) out, leaving a disconnected copy behind
+    pub fn detach(&mut self) -> Self {
+        Self {
+            root: self.root.clone(),
+            remote: self.remote.clone(),
+            session: self.session.take(),
+        }
+    }
+
+    /// Open the SFTP session for a remote side if it isn't open yet. No-op for local sides.
+    pub fn connect(&mut self) -> Result<(), String> {
+        let profile = match self.remote {
+            Some(ref p) => p,
+            None => return Ok(()),
+        };
+        if self.session.as_ref().is_some_and(|s| s.is_connected()) {
+            return Ok(());
+        }
+        let session = SftpSession::connect(profile)
+            .map_err(|e| format!("{}: {}", profile.host, e))?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    /// Root as shown to the user: `user@host:/path` for remote sides
+    pub fn display_root(&self) -> String {
+        let path = self.root.display().to_string();
+        match self.remote {
+            Some(ref profile) => remote::format_remote_display(profile, &path),
+            None => path,
+        }
+    }
+
+    /// List a directory with metadata; an unreadable directory yields no entries.
+    /// Directory-ness follows symlinks on both kinds of side.
+    pub fn list_dir(&self, dir: &Path) -> Vec<DiffFileInfo> {
+        if !self.is_remote() {
+            return match fs::read_dir(dir) {
+                Ok(entries) => entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| {
+                        let name = e.file_name().to_string_lossy().to_string();
+                        make_file_info(&dir.join(&name), &name)
+                    })
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+        }
+
+        let session = match self.session {
+            Some(ref s) => s,
+            None => return Vec::new(),
+        };
+        match session.list_dir(&remote_path(dir)) {
+            Ok(entries) => remote_file_infos(dir, entries, |path| session.dir_exists(path)),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Symlink target, None if `path` is not a readable symlink
+    pub fn read_link(&self, path: &Path) -> Option<PathBuf> {
+        if !self.is_remote() {
+            return fs::read_link(path).ok();
+        }
+        self.session.as_ref()?.read_link(&remote_path(path)).ok().map(PathBuf::from)
+    }
+
+    /// Read a whole file
+    pub fn read_file(&self, path: &Path) -> Result<Vec<u8>, String> {
+        let copy = self.local_copy(path)?;
+        fs::read(copy.path()).map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+    }
+
+    /// A local path holding the file's content: the file itself on a local side,
+    /// a temporary download (removed when the copy is dropped) on a remote one.
+    pub fn local_copy(&self, path: &Path) -> Result<LocalCopy, String> {
+        if !self.is_remote() {
+            return Ok(LocalCopy {
+                path: path.to_path_buf(),
+                temporary: false,
+            });
+        }
+        let session = self.session.as_ref().ok_or("Not connected")?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp_dir = dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(".cokacdir")
+            .join("tmp")
+            .join("diff");
+        fs::create_dir_all(&temp_dir).map_err(|e| format!("Cannot create tmp dir: {}", e))?;
+        let local = temp_dir.join(format!(
+            "{}_{}_{}",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+            name
+        ));
+        let copy = LocalCopy {
+            path: local,
+            temporary: true,
+        };
+        session.download_file(&remote_path(path), &copy.path.display().to_string())?;
+        Ok(copy)
+    }
+}
+
+/// Local file path handed out by `DiffSide::local_copy`
+pub struct LocalCopy {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl LocalCopy {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LocalCopy {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// DiffFileInfo for the SFTP entries of `dir`. `dir_exists` tells whether a
+/// remote path is a directory, to follow symlinks like local sides do.
+fn remote_file_infos(
+    dir: &Path,
+    entries: Vec<SftpFileEntry>,
+    dir_exists: impl Fn(&str) -> bool,
+) -> Vec<DiffFileInfo> {
+    entries
+        .into_iter()
+        .map(|e| {
+            let full_path = dir.join(&e.name);
+            let is_directory = e.is_directory || (e.is_symlink && dir_exists(&remote_path(&full_path)));
+            DiffFileInfo {
+                name: e.name,
+                size: if is_directory { 0 } else { e.size },
+                modified: e.modified,
+                is_directory,
+                is_symlink: e.is_symlink,
+                full_path,
+            }
+        })
+        .collect()
+}
+
+/// Remote paths are always '/'-separated strings
+fn remote_path(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Build DiffFileInfo from a local path, returning None if the path doesn't exist
+pub(crate) fn make_file_info(path: &Path, name: &str) -> Option<DiffFileInfo> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    let is_symlink = metadata.file_type().is_symlink();
+    let actual_metadata = if is_symlink {
+        fs::metadata(path).unwrap_or(metadata.clone())
+    } else {
+        metadata.clone()
+    };
+    let is_directory = actual_metadata.is_dir();
+    let size = if is_directory { 0 } else { actual_metadata.len() };
+    let modified = metadata
+        .modified()
+        .ok()
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(Local::now);
+
+    Some(DiffFileInfo {
+        name: name.to_string(),
+        size,
+        modified,
+        is_directory,
+        is_symlink,
+        full_path: path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::remote::RemoteAuth;
+    use crate::ui::app::{SortBy, SortOrder};
+    use crate::ui::diff_screen::{sync_transfer_groups, CompareMethod, DiffState, DiffStatus};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::{Child, Command};
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    fn profile(user: &str, port: u16, key: &Path) -> RemoteProfile {
+        RemoteProfile {
+            name: "test".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            user: user.to_string(),
+            auth: RemoteAuth::KeyFile {
+                path: key.display().to_string(),
+                passphrase: None,
+                certificate: None,
+            },
+            default_path: String::new(),
+            jump_hosts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_local_side_listing() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("file.txt"), "hello").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("sub"), dir.path().join("link")).unwrap();
+
+        let side = DiffSide::local(dir.path().to_path_buf());
+        let mut infos = side.list_dir(dir.path());
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let file = infos.iter().find(|i| i.name == "file.txt").unwrap();
+        assert_eq!(file.size, 5);
+        assert!(!file.is_directory);
+        assert!(infos.iter().find(|i| i.name == "sub").unwrap().is_directory);
+        #[cfg(unix)]
+        {
+            let link = infos.iter().find(|i| i.name == "link").unwrap();
+            assert!(link.is_directory && link.is_symlink);
+            assert_eq!(side.read_link(&link.full_path), Some(dir.path().join("sub")));
+        }
+
+        // Local copies are the file itself and are never removed
+        let path = dir.path().join("file.txt");
+        drop(side.local_copy(&path).unwrap());
+        assert_eq!(side.read_file(&path).unwrap(), b"hello");
+        assert!(side.list_dir(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_same_location() {
+        let key = PathBuf::from("/nonexistent");
+        let local = DiffSide::local(PathBuf::from("/srv/data"));
+        let remote = DiffSide::remote(PathBuf::from("/srv/data"), profile("me", 22, &key));
+        let other_port = DiffSide::remote(PathBuf::from("/srv/data"), profile("me", 2222, &key));
+
+        assert!(local.same_location(&DiffSide::local(PathBuf::from("/srv/data"))));
+        assert!(!local.same_location(&remote));
+        assert!(remote.same_location(&DiffSide::remote(PathBuf::from("/srv/data"), profile("me", 22, &key))));
+        assert!(!remote.same_location(&other_port));
+        assert_eq!(remote.display_root(), remote::format_remote_display(&profile("me", 22, &key), "/srv/data"));
+    }
+
+    #[test]
+    fn test_remote_listing_and_sync_without_sshd() {
+        let entry = |name: &str, is_directory: bool, is_symlink: bool| SftpFileEntry {
+            name: name.to_string(),
+            is_directory,
+            is_symlink,
+            size: 42,
+            modified: Local::now(),
+            permissions: String::new(),
+        };
+        let dir = Path::new("/srv/data");
+        let infos = remote_file_infos(
+            dir,
+            vec![
+                entry("file.txt", false, false),
+                entry("sub", true, false),
+                entry("dir-link", false, true),
+                entry("file-link", false, true),
+            ],
+            |path| path == "/srv/data/dir-link",
+        );
+        let info = |name: &str| infos.iter().find(|i| i.name == name).unwrap();
+        assert_eq!((info("file.txt").size, info("file.txt").is_directory), (42, false));
+        assert_eq!(info("file.txt").full_path, dir.join("file.txt"));
+        // Directories report no size, symlinks to directories count as directories
+        assert_eq!((info("sub").size, info("sub").is_directory), (0, true));
+        assert!(info("dir-link").is_directory && info("dir-link").is_symlink);
+        assert!(!info("file-link").is_directory && info("file-link").is_symlink);
+
+        // Syncing towards a remote side copies into the remote root
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::create_dir_all(left.path().join("docs/new")).unwrap();
+        fs::create_dir(right.path().join("docs")).unwrap();
+        fs::write(left.path().join("docs/new/a.txt"), "a").unwrap();
+        fs::write(left.path().join("top.txt"), "t").unwrap();
+        let mut state = DiffState::new(
+            left.path().to_path_buf(),
+            right.path().to_path_buf(),
+            CompareMethod::Content,
+            SortBy::Name,
+            SortOrder::Asc,
+        );
+        state.build_diff_list();
+        let remote_root = PathBuf::from("/srv/data");
+        state.right_side = DiffSide::remote(remote_root.clone(), profile("me", 22, Path::new("/nonexistent")));
+        state.selected_files = ["docs", "top.txt"].iter().map(|s| s.to_string()).collect();
+
+        let groups = sync_transfer_groups(&state, true);
+        let summary: Vec<(String, String, Vec<PathBuf>)> = groups
+            .into_iter()
+            .map(|g| (g.source_base, g.target_path, g.files))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    left.path().join("").display().to_string(),
+                    remote_root.join("").display().to_string(),
+                    vec![PathBuf::from("top.txt")],
+                ),
+                (
+                    left.path().join("docs").display().to_string(),
+                    remote_root.join("docs").display().to_string(),
+                    vec![PathBuf::from("new")],
+                ),
+            ]
+        );
+        // Nothing on the remote side is missing locally
+        assert!(sync_transfer_groups(&state, false).is_empty());
+    }
+
+    /// Throwaway sshd on a free local port, killed on drop
+    struct TestSshd {
+        child: Child,
+        _dir: TempDir,
+        key: PathBuf,
+        known_hosts: PathBuf,
+        port: u16,
+    }
+
+    impl Drop for TestSshd {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    /// Start sshd for the current user with a fresh key pair.
+    /// Returns None when sshd/ssh-keygen are not installed.
+    fn start_sshd() -> Option<TestSshd> {
+        let sshd = ["/usr/sbin/sshd", "/usr/bin/sshd", "/usr/local/sbin/sshd"]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|p| p.exists())?;
+        let dir = TempDir::new().ok()?;
+        let host_key = dir.path().join("host_key");
+        let user_key = dir.path().join("user_key");
+        for key in [&host_key, &user_key] {
+            let status = Command::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+                .arg(key)
+                .status()
+                .ok()?;
+            if !status.success() {
+                return None;
+            }
+        }
+        let authorized = dir.path().join("authorized_keys");
+        fs::copy(user_key.with_extension("pub"), &authorized).ok()?;
+
+        let port = TcpListener::bind("127.0.0.1:0").ok()?.local_addr().ok()?.port();
+        // Host key checking refuses unknown servers; vouch for this one
+        let host_pub = fs::read_to_string(host_key.with_extension("pub")).ok()?;
+        let known_hosts = dir.path().join("known_hosts");
+        fs::write(&known_hosts, format!("[127.0.0.1]:{} {}", port, host_pub)).ok()?;
+
+        let config = dir.path().join("sshd_config");
+        fs::write(
+            &config,
+            format!(
+                "ListenAddress 127.0.0.1\nPort {}\nHostKey {}\nAuthorizedKeysFile {}\nPidFile {}\n\
+                 StrictModes no\nUsePAM no\nPasswordAuthentication no\nSubsystem sftp internal-sftp\n",
+                port,
+                host_key.display(),
+                authorized.display(),
+                dir.path().join("sshd.pid").display()
+            ),
+        )
+        .ok()?;
+
+        let child = Command::new(sshd).arg("-D").arg("-f").arg(&config).spawn().ok()?;
+        let server = TestSshd {
+            child,
+            _dir: dir,
+            key: user_key,
+            known_hosts,
+            port,
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            if Instant::now() > deadline {
+                return None;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Some(server)
+    }
+
+    fn current_user() -> Option<String> {
+        let out = Command::new("id").arg("-un").output().ok()?;
+        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    // Run with `cargo test -- --ignored` on a machine with sshd
+    #[test]
+    #[ignore = "needs sshd"]
+    fn test_compare_with_remote_side_over_sftp() {
+        let server = start_sshd().expect("sshd and ssh-keygen are needed for this test");
+        let user = current_user().unwrap();
+
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::write(left.path().join("same.txt"), "same\n").unwrap();
+        fs::write(right.path().join("same.txt"), "same\n").unwrap();
+        fs::write(left.path().join("mod.txt"), "old\n").unwrap();
+        fs::write(right.path().join("mod.txt"), "new\n").unwrap();
+        fs::create_dir(right.path().join("added")).unwrap();
+        fs::write(right.path().join("added").join("inner.txt"), "x").unwrap();
+
+        let mut state = DiffState::new(
+            left.path().to_path_buf(),
+            right.path().to_path_buf(),
+            CompareMethod::Content,
+            SortBy::Name,
+            SortOrder::Asc,
+        );
+        let profile = profile(&user, server.port, &server.key);
+        let mut side = DiffSide::remote(right.path().to_path_buf(), profile.clone());
+        side.session = Some(SftpSession::connect_with_known_hosts(&profile, std::slice::from_ref(&server.known_hosts)).unwrap());
+        state.right_side = side;
+        state.build_diff_list();
+        assert_eq!(state.error, None);
+
+        let status = |path: &str| state.all_entries.iter().find(|e| e.relative_path == path).map(|e| e.status);
+        assert_eq!(status("same.txt"), Some(DiffStatus::Same));
+        assert_eq!(status("mod.txt"), Some(DiffStatus::Modified));
+        assert_eq!(status("added"), Some(DiffStatus::RightOnly));
+
+        // Remote contents are readable for the file view and exports
+        let copy = state.right_side.local_copy(&right.path().join("mod.txt")).unwrap();
+        assert_eq!(fs::read(copy.path()).unwrap(), b"new\n");
+        let temp = copy.path().to_path_buf();
+        drop(copy);
+        assert!(!temp.exists());
+    }
+
+    #[test]
+    fn test_unreachable_remote_side_reports_error() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let dir = TempDir::new().unwrap();
+        let mut state = DiffState::new(
+            dir.path().to_path_buf(),
+            PathBuf::from("/srv"),
+            CompareMethod::Content,
+            SortBy::Name,
+            SortOrder::Asc,
+        );
+        state.right_side = DiffSide::remote(PathBuf::from("/srv"), profile("me", port, Path::new("/nonexistent")));
+        state.build_diff_list();
+        assert!(state.error.as_deref().is_some_and(|e| e.starts_with("127.0.0.1: ")));
+        assert!(state.all_entries.is_empty());
+    }
+}
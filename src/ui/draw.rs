@@ -99,12 +99,12 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         }
         Screen::DiffScreen => {
             if let Some(ref mut state) = app.diff_state {
-                diff_screen::draw(frame, state, area, &theme, &app.keybindings);
+                diff_screen::draw(frame, state, area, &theme, &app.keybindings, app.message.as_deref());
             }
         }
         Screen::DiffFileView => {
             if let Some(ref mut state) = app.diff_file_view_state {
-                diff_file_view::draw(frame, state, area, &theme, &app.keybindings);
+                diff_file_view::draw(frame, state, area, &theme, &app.keybindings, app.message.as_deref());
             }
         }
         Screen::GitScreen => {
@@ -335,6 +335,7 @@ fn draw_function_bar(frame: &mut Frame, app: &App, area: Rect, theme: &Theme) {
         (PanelAction::ToggleBookmark, "bmk "),
         (PanelAction::GitScreen, "git "),
         (PanelAction::GitLogDiff, "glog "),
+        (PanelAction::GitFileHistory, "hist "),
         (PanelAction::StartDiff, "diff "),
         (PanelAction::AddPanel, "+pan "),
         (PanelAction::ClosePanel, "-pan "),
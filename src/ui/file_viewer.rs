@@ -8,11 +8,12 @@ use ratatui::{
 };
 use regex::Regex;
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use unicode_width::UnicodeWidthChar;
 
 use super::{
     app::{App, Screen},
+    git_blame::{blame_age, relative_date, BlameAge, BlameView},
     syntax::{Language, SyntaxHighlighter},
     theme::Theme,
 };
@@ -76,8 +77,15 @@ pub struct ViewerState {
 
     // 파일 정보
     pub file_size: u64,
+    /// Contents were decrypted to memory; `file_path` does not hold them
+    pub in_memory: bool,
     pub total_lines: usize,
 
+    // Git blame
+    pub blame: Option<BlameView>,
+    /// One-shot footer message, cleared on the next key
+    pub status_message: Option<String>,
+
     // 화면 크기 (렌더링 시 업데이트)
     pub visible_height: usize,
 }
@@ -112,7 +120,10 @@ impl ViewerState {
             encoding: "UTF-8".to_string(),
             is_binary: false,
             file_size: 0,
+            in_memory: false,
             total_lines: 0,
+            blame: None,
+            status_message: None,
             visible_height: 20, // 기본값, 렌더링 시 업데이트됨
         }
     }
@@ -127,18 +138,10 @@ impl ViewerState {
     }
 
     /// Maximum file size for viewing (100MB)
-    const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+    pub const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
 
     /// 파일 로드
     pub fn load_file(&mut self, path: &PathBuf) -> Result<(), String> {
-        self.file_path = path.clone();
-        self.scroll = 0;
-        self.horizontal_scroll = 0;
-        self.bookmarks.clear();
-        self.search_term.clear();
-        self.match_lines.clear();
-        self.match_positions.clear();
-
         // Check file size before loading to prevent memory exhaustion
         let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
         if metadata.len() > Self::MAX_FILE_SIZE {
@@ -151,6 +154,26 @@ impl ViewerState {
 
         // 파일 읽기
         let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        self.load_bytes(path, bytes);
+        self.in_memory = false;
+        Ok(())
+    }
+
+    /// Show a file decrypted to memory; `path` only names it
+    pub fn load_memory(&mut self, path: &Path, bytes: Vec<u8>) {
+        self.load_bytes(path, bytes);
+        self.in_memory = true;
+    }
+
+    fn load_bytes(&mut self, path: &Path, bytes: Vec<u8>) {
+        self.file_path = path.to_path_buf();
+        self.scroll = 0;
+        self.horizontal_scroll = 0;
+        self.bookmarks.clear();
+        self.search_term.clear();
+        self.match_lines.clear();
+        self.match_positions.clear();
+        self.blame = None;
         self.file_size = bytes.len() as u64;
 
         // 바이너리 파일 감지
@@ -188,8 +211,6 @@ impl ViewerState {
         if !self.is_binary {
             self.highlighter = Some(SyntaxHighlighter::new(self.language, self.syntax_colors));
         }
-
-        Ok(())
     }
 
     /// 바이너리 파일 감지
@@ -414,6 +435,7 @@ impl ViewerState {
             return; // 바이너리 파일은 항상 헥스 모드
         }
 
+        self.blame = None;
         match self.mode {
             ViewerMode::Text => {
                 self.mode = ViewerMode::Hex;
@@ -428,6 +450,62 @@ impl ViewerState {
         }
         self.scroll = 0;
     }
+
+    /// Blame 모드 토글
+    pub fn toggle_blame(&mut self) {
+        if self.blame.take().is_some() {
+            return;
+        }
+        if self.mode != ViewerMode::Text {
+            self.status_message = Some("Blame is only available in text mode".to_string());
+            return;
+        }
+        if self.in_memory {
+            self.status_message = Some("Blame is not available for decrypted files".to_string());
+            return;
+        }
+        match BlameView::open(&self.file_path) {
+            Ok(mut view) => {
+                // 화면 상단 줄에서 시작
+                view.cursor = self.scroll.min(view.blame.lines.len().saturating_sub(1));
+                self.blame = Some(view);
+            }
+            Err(e) => {
+                self.status_message = Some(e.lines().next().unwrap_or("Blame failed").to_string());
+            }
+        }
+    }
+
+    /// 선택된 줄을 마지막으로 바꾼 커밋의 부모 시점으로 다시 blame
+    pub fn blame_parent(&mut self) {
+        let result = match self.blame {
+            Some(ref view) => view.parent(),
+            None => return,
+        };
+        match result {
+            Ok(view) => {
+                self.blame = Some(view);
+                self.move_blame_cursor(0);
+            }
+            Err(e) => {
+                self.status_message = Some(e.lines().next().unwrap_or("Blame failed").to_string());
+            }
+        }
+    }
+
+    /// Blame 커서 이동 (화면에 보이도록 스크롤 조정)
+    pub fn move_blame_cursor(&mut self, delta: isize) {
+        let visible = self.visible_height.max(1);
+        if let Some(ref mut view) = self.blame {
+            let max = view.blame.lines.len().saturating_sub(1) as isize;
+            view.cursor = (view.cursor as isize + delta).clamp(0, max.max(0)) as usize;
+            if view.cursor < self.scroll {
+                self.scroll = view.cursor;
+            } else if view.cursor >= self.scroll + visible {
+                self.scroll = view.cursor + 1 - visible;
+            }
+        }
+    }
 }
 
 pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Theme, kb: &crate::keybindings::Keybindings) {
@@ -447,7 +525,10 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
     state.visible_height = visible_lines;
 
     // Header
-    let total_lines = state.lines.len();
+    let total_lines = match state.blame {
+        Some(ref view) => view.blame.lines.len(),
+        None => state.lines.len(),
+    };
     let end_line = (state.scroll + visible_lines).min(total_lines);
     let percentage = if total_lines > 0 {
         ((end_line as f32 / total_lines as f32) * 100.0) as u32
@@ -484,6 +565,13 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
         } else {
             Span::raw("")
         },
+        match state.blame {
+            Some(ref view) => Span::styled(
+                format!(" Blame: {} @ {}", view.path, view.rev_label()),
+                Style::default().fg(theme.viewer.blame_recent).add_modifier(Modifier::BOLD),
+            ),
+            None => Span::raw(""),
+        },
     ]);
     frame.render_widget(
         Paragraph::new(header).style(theme.status_bar_style()),
@@ -504,8 +592,11 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
         }
     }
 
-    // Word wrap 모드일 경우 표시할 줄들을 미리 계산
-    if state.word_wrap {
+    // Blame 모드: 거터 + 커밋 시점의 줄 내용 (word wrap 없음)
+    if state.blame.is_some() {
+        draw_blame_lines(frame, state, inner, content_height, theme);
+    } else if state.word_wrap {
+        // Word wrap 모드일 경우 표시할 줄들을 미리 계산
         // wrapped 줄 목록 생성: (원본 줄 번호, 원본 줄 참조, 줄 내용, 첫 줄 여부)
         let mut wrapped_lines: Vec<(usize, String, bool)> = Vec::new();
 
@@ -650,51 +741,7 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
             };
 
             // 수평 스크롤 적용 (display width 기준, span별 개별 처리)
-            let final_spans = if state.horizontal_scroll > 0 {
-                let mut result_spans: Vec<Span> = Vec::new();
-                let mut remaining_skip = state.horizontal_scroll;
-                let mut total_rendered_width = 0usize;
-
-                for span in &content_spans {
-                    if total_rendered_width >= content_width {
-                        break;
-                    }
-                    let span_text = &span.content;
-                    let mut visible_text = String::new();
-
-                    for c in span_text.chars() {
-                        let cw = c.width().unwrap_or(1);
-                        if remaining_skip > 0 {
-                            if cw <= remaining_skip {
-                                remaining_skip -= cw;
-                            } else {
-                                // 전각 문자가 스크롤 경계에 걸림 — 공백으로 대체
-                                visible_text.push(' ');
-                                total_rendered_width += 1;
-                                remaining_skip = 0;
-                            }
-                            continue;
-                        }
-                        if total_rendered_width + cw > content_width {
-                            // 전각 문자가 우측 경계에 걸리면 공백 패딩
-                            if total_rendered_width + 1 <= content_width {
-                                visible_text.push(' ');
-                                total_rendered_width += 1;
-                            }
-                            break;
-                        }
-                        visible_text.push(c);
-                        total_rendered_width += cw;
-                    }
-
-                    if !visible_text.is_empty() {
-                        result_spans.push(Span::styled(visible_text, span.style));
-                    }
-                }
-                result_spans
-            } else {
-                content_spans
-            };
+            let final_spans = clip_spans(content_spans, state.horizontal_scroll, content_width);
 
             let mut spans = vec![line_num_span];
             spans.extend(final_spans);
@@ -810,6 +857,40 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
             Paragraph::new(Line::from(spans)).style(theme.status_bar_style()),
             Rect::new(inner.x, footer_y, inner.width, 1),
         );
+    } else if let Some(ref msg) = state.status_message {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                format!(" {}", msg),
+                Style::default().fg(theme.viewer.footer_text).add_modifier(Modifier::BOLD),
+            ))
+            .style(theme.status_bar_style()),
+            Rect::new(inner.x, footer_y, inner.width, 1),
+        );
+    } else if let Some(ref view) = state.blame {
+        // Blame 모드: 커서 줄의 커밋 요약 + 단축키
+        use crate::keybindings::ViewerAction;
+        let mut footer_spans = Vec::new();
+        if let Some(commit) = view.current_commit() {
+            footer_spans.push(Span::styled(
+                format!("{} ", commit.summary),
+                Style::default().fg(theme.viewer.blame_recent),
+            ));
+        }
+        let shortcuts: Vec<(String, &str)> = vec![
+            (kb.viewer_first_key(ViewerAction::BlameShowCommit).to_string(), "commit "),
+            (kb.viewer_first_key(ViewerAction::BlameParent).to_string(), "parent "),
+            (kb.viewer_first_key(ViewerAction::ToggleBlame).to_string(), "blame off "),
+            (kb.viewer_first_key(ViewerAction::Quit).to_string(), "quit"),
+        ];
+        for (key, rest) in &shortcuts {
+            footer_spans.push(Span::styled(key.clone(), theme.header_style()));
+            footer_spans.push(Span::styled(":", theme.dim_style()));
+            footer_spans.push(Span::styled(*rest, theme.dim_style()));
+        }
+        frame.render_widget(
+            Paragraph::new(Line::from(footer_spans)).style(theme.status_bar_style()),
+            Rect::new(inner.x, footer_y, inner.width, 1),
+        );
     } else {
         let wrap_indicator = if state.word_wrap { "Wrap " } else { "" };
 
@@ -830,6 +911,7 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
             (vkb.viewer_first_key(ViewerAction::Edit).to_string(), "edit "),
             (vkb.viewer_first_key(ViewerAction::ToggleWrap).to_string(), "wrap "),
             (vkb.viewer_first_key(ViewerAction::ToggleHex).to_string(), "hex "),
+            (vkb.viewer_first_key(ViewerAction::ToggleBlame).to_string(), "blame "),
             (vkb.viewer_first_key(ViewerAction::ToggleBookmark).to_string(), "bmark"),
         ];
 
@@ -847,6 +929,132 @@ pub fn draw(frame: &mut Frame, state: &mut ViewerState, area: Rect, theme: &Them
     }
 }
 
+/// 수평 스크롤 적용: 앞쪽 `skip` 칸을 건너뛰고 `width` 칸까지만 남김
+fn clip_spans(content_spans: Vec<Span<'static>>, skip: usize, width: usize) -> Vec<Span<'static>> {
+    if skip == 0 {
+        return content_spans;
+    }
+    let mut result_spans: Vec<Span> = Vec::new();
+    let mut remaining_skip = skip;
+    let mut total_rendered_width = 0usize;
+
+    for span in &content_spans {
+        if total_rendered_width >= width {
+            break;
+        }
+        let span_text = &span.content;
+        let mut visible_text = String::new();
+
+        for c in span_text.chars() {
+            let cw = c.width().unwrap_or(1);
+            if remaining_skip > 0 {
+                if cw <= remaining_skip {
+                    remaining_skip -= cw;
+                } else {
+                    // 전각 문자가 스크롤 경계에 걸림 — 공백으로 대체
+                    visible_text.push(' ');
+                    total_rendered_width += 1;
+                    remaining_skip = 0;
+                }
+                continue;
+            }
+            if total_rendered_width + cw > width {
+                // 전각 문자가 우측 경계에 걸리면 공백 패딩
+                if total_rendered_width < width {
+                    visible_text.push(' ');
+                    total_rendered_width += 1;
+                }
+                break;
+            }
+            visible_text.push(c);
+            total_rendered_width += cw;
+        }
+
+        if !visible_text.is_empty() {
+            result_spans.push(Span::styled(visible_text, span.style));
+        }
+    }
+    result_spans
+}
+
+/// Blame 거터 폭: 해시(7) + 작성자(12) + 날짜(4) + 공백
+const BLAME_GUTTER_WIDTH: usize = 7 + 1 + 12 + 1 + 4 + 1;
+
+/// Blame 모드 본문 렌더링
+fn draw_blame_lines(frame: &mut Frame, state: &ViewerState, inner: Rect, content_height: usize, theme: &Theme) {
+    let view = match state.blame {
+        Some(ref v) => v,
+        None => return,
+    };
+    let now = chrono::Local::now().timestamp();
+    let content_width = (inner.width as usize).saturating_sub(5 + BLAME_GUTTER_WIDTH);
+
+    // 하이라이터 상태를 스크롤 위치까지 진행
+    let mut highlighter = state.highlighter;
+    if let Some(ref mut hl) = highlighter {
+        hl.reset();
+        for line in view.blame.lines.iter().take(state.scroll) {
+            hl.tokenize_line(&line.content);
+        }
+    }
+
+    for (i, line) in view.blame.lines.iter().enumerate().skip(state.scroll).take(content_height) {
+        let commit = &view.blame.commits[line.commit];
+        let is_cursor = i == view.cursor;
+        let cursor_bg = if is_cursor { Some(theme.viewer.blame_cursor_bg) } else { None };
+        let with_bg = |style: Style| match cursor_bg {
+            Some(bg) => style.bg(bg),
+            None => style,
+        };
+
+        // 같은 커밋이 연속되면 첫 줄(또는 화면 첫 줄)에만 표시
+        let group_start = i == state.scroll
+            || view.blame.lines.get(i.wrapping_sub(1)).map(|p| p.commit) != Some(line.commit);
+        let gutter = if group_start || is_cursor {
+            let author: String = commit.author.chars().take(12).collect();
+            format!(
+                "{} {:<12} {:>4} ",
+                commit.short_hash(),
+                author,
+                relative_date(commit.time, now)
+            )
+        } else {
+            " ".repeat(BLAME_GUTTER_WIDTH)
+        };
+        let age_color = if commit.is_uncommitted() {
+            theme.viewer.blame_recent
+        } else {
+            match blame_age(commit.time, now) {
+                BlameAge::Recent => theme.viewer.blame_recent,
+                BlameAge::Medium => theme.viewer.blame_medium,
+                BlameAge::Old => theme.viewer.blame_old,
+            }
+        };
+
+        let text = line.content.replace('\t', "    ");
+        let base_style = with_bg(theme.normal_style());
+        let content_spans = match highlighter {
+            Some(ref mut hl) => render_syntax_highlighted_line(&text, hl, base_style),
+            None => vec![Span::styled(text, base_style)],
+        };
+
+        let mut spans = vec![
+            Span::styled(gutter, with_bg(Style::default().fg(age_color))),
+            Span::styled(format!("{:4} ", i + 1), with_bg(Style::default().fg(theme.viewer.line_number))),
+        ];
+        spans.extend(clip_spans(content_spans, state.horizontal_scroll, content_width));
+
+        let mut paragraph = Paragraph::new(Line::from(spans));
+        if is_cursor {
+            paragraph = paragraph.style(Style::default().bg(theme.viewer.blame_cursor_bg));
+        }
+        frame.render_widget(
+            paragraph,
+            Rect::new(inner.x, inner.y + 1 + (i - state.scroll) as u16, inner.width, 1),
+        );
+    }
+}
+
 /// 헥스 라인 렌더링
 fn render_hex_line(line: &str, theme: &Theme) -> Vec<Span<'static>> {
     // 헥스 뷰: offset | hex bytes | ascii
@@ -1377,16 +1585,37 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
     }
 
     let visible_lines = state.visible_height;
+    state.status_message = None;
 
     use crate::keybindings::ViewerAction;
     if let Some(action) = app.keybindings.viewer_action(code, modifiers) {
+        // Blame 모드에서는 이동 키가 선택 줄을 움직임
+        if let Some(ref view) = state.blame {
+            let page = visible_lines.max(1) as isize;
+            let total = view.blame.lines.len() as isize;
+            let delta = match action {
+                ViewerAction::ScrollUp => Some(-1),
+                ViewerAction::ScrollDown => Some(1),
+                ViewerAction::PageUp => Some(-page),
+                ViewerAction::PageDown => Some(page),
+                ViewerAction::GoTop => Some(-total),
+                ViewerAction::GoBottom => Some(total),
+                _ => None,
+            };
+            if let Some(delta) = delta {
+                state.move_blame_cursor(delta);
+                return;
+            }
+        }
         match action {
             ViewerAction::Quit => {
                 app.current_screen = Screen::FilePanel;
             }
             ViewerAction::Edit => {
-                if let Some(ref viewer_state) = app.viewer_state {
-                    if !viewer_state.is_binary {
+                if let Some(ref mut viewer_state) = app.viewer_state {
+                    if viewer_state.in_memory {
+                        viewer_state.status_message = Some("Extract the file to edit it".to_string());
+                    } else if !viewer_state.is_binary {
                         let path = viewer_state.file_path.clone();
                         let viewer_scroll = viewer_state.scroll;
                         let mut editor = super::file_editor::EditorState::new();
@@ -1453,6 +1682,25 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
                 state.goto_mode = true;
                 state.goto_input.clear();
             }
+            ViewerAction::ToggleBlame => {
+                state.toggle_blame();
+            }
+            ViewerAction::BlameParent => {
+                state.blame_parent();
+            }
+            ViewerAction::BlameShowCommit => {
+                let target = match state.blame {
+                    Some(ref view) => view
+                        .current_commit()
+                        .filter(|c| !c.is_uncommitted())
+                        .map(|c| (view.repo_root.clone(), c.hash.clone())),
+                    None => return,
+                };
+                match target {
+                    Some((repo_root, hash)) => app.show_git_commit(repo_root, &hash),
+                    None => state.status_message = Some("Line is not committed yet".to_string()),
+                }
+            }
         }
     }
 }
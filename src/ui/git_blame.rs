@@ -0,0 +1,328 @@
+//! `git blame --porcelain` parsing for the file viewer's blame mode.
+//!
+//! A blame is taken either of the working tree file (uncommitted lines show up
+//! with an all-zero hash) or of the file at a given revision, which is how the
+//! viewer walks back to the parent of the commit that last touched a line.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::git_screen::git_cmd_public as git_cmd;
+
+#[derive(Debug, Clone)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub author: String,
+    /// Author time (unix seconds)
+    pub time: i64,
+    pub summary: String,
+    /// Parent revision and the file's path there (absent for root commits)
+    pub previous: Option<(String, String)>,
+}
+
+impl BlameCommit {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    pub fn is_uncommitted(&self) -> bool {
+        self.hash.chars().all(|c| c == '0')
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    /// Index into `Blame::commits`
+    pub commit: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Blame {
+    pub commits: Vec<BlameCommit>,
+    pub lines: Vec<BlameLine>,
+}
+
+impl Blame {
+    /// Parse `git blame --porcelain` output
+    pub fn parse(output: &str) -> Result<Self, String> {
+        let mut blame = Blame::default();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut current: Option<usize> = None;
+
+        for line in output.lines() {
+            if let Some(content) = line.strip_prefix('\t') {
+                let commit = current.ok_or("Malformed blame output")?;
+                blame.lines.push(BlameLine {
+                    commit,
+                    content: content.to_string(),
+                });
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if key.len() == 40 && key.chars().all(|c| c.is_ascii_hexdigit()) {
+                // "<hash> <orig line> <final line> [<group size>]"
+                let next = blame.commits.len();
+                let idx = *index.entry(key.to_string()).or_insert(next);
+                if idx == next {
+                    blame.commits.push(BlameCommit {
+                        hash: key.to_string(),
+                        author: String::new(),
+                        time: 0,
+                        summary: String::new(),
+                        previous: None,
+                    });
+                }
+                current = Some(idx);
+                continue;
+            }
+
+            let commit = match current {
+                Some(i) => &mut blame.commits[i],
+                None => continue,
+            };
+            match key {
+                "author" => commit.author = value.to_string(),
+                "author-time" => commit.time = value.parse().unwrap_or(0),
+                "summary" => commit.summary = value.to_string(),
+                "previous" => {
+                    if let Some((rev, path)) = value.split_once(' ') {
+                        commit.previous = Some((rev.to_string(), path.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(blame)
+    }
+
+    /// Blame `path` (relative to `repo_root`) at `rev`, or the working tree file
+    pub fn run(repo_root: &Path, path: &str, rev: Option<&str>) -> Result<Self, String> {
+        let mut cmd = git_cmd(repo_root);
+        cmd.args(["blame", "--porcelain"]);
+        if let Some(rev) = rev {
+            if rev.starts_with('-') {
+                return Err("Invalid revision".to_string());
+            }
+            cmd.arg(rev);
+        }
+        cmd.args(["--", path]);
+
+        let output = cmd.output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    pub fn commit_of(&self, line: usize) -> Option<&BlameCommit> {
+        self.lines.get(line).map(|l| &self.commits[l.commit])
+    }
+}
+
+/// Age bucket used to shade the blame gutter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlameAge {
+    Recent,
+    Medium,
+    Old,
+}
+
+const DAY: i64 = 24 * 60 * 60;
+
+pub fn blame_age(time: i64, now: i64) -> BlameAge {
+    let age = now - time;
+    if age < 30 * DAY {
+        BlameAge::Recent
+    } else if age < 365 * DAY {
+        BlameAge::Medium
+    } else {
+        BlameAge::Old
+    }
+}
+
+/// Compact relative date for the gutter: "5m", "3h", "12d", "4mo", "2y"
+pub fn relative_date(time: i64, now: i64) -> String {
+    let age = (now - time).max(0);
+    if age < 60 * 60 {
+        format!("{}m", age / 60)
+    } else if age < DAY {
+        format!("{}h", age / (60 * 60))
+    } else if age < 60 * DAY {
+        format!("{}d", age / DAY)
+    } else if age < 365 * DAY {
+        format!("{}mo", age / (30 * DAY))
+    } else {
+        format!("{}y", age / (365 * DAY))
+    }
+}
+
+/// Blame state of the file viewer
+#[derive(Debug, Clone)]
+pub struct BlameView {
+    pub repo_root: PathBuf,
+    /// Path relative to the repo root at `rev`
+    pub path: String,
+    /// None: working tree file
+    pub rev: Option<String>,
+    pub blame: Blame,
+    /// Selected line
+    pub cursor: usize,
+}
+
+impl BlameView {
+    /// Blame the working tree version of a tracked file
+    pub fn open(file: &Path) -> Result<Self, String> {
+        let dir = file.parent().ok_or("Invalid path")?;
+        let repo_root = super::git_screen::get_repo_root(dir).ok_or("Not in a git repository")?;
+
+        // Repo-relative name; also fails for untracked files
+        let output = git_cmd(dir)
+            .args(["ls-files", "--full-name", "--error-unmatch", "--"])
+            .arg(file)
+            .output()
+            .map_err(|e| e.to_string())?;
+        let path = String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or("").to_string();
+        if !output.status.success() || path.is_empty() {
+            return Err("File is not tracked by git".to_string());
+        }
+
+        let blame = Blame::run(&repo_root, &path, None)?;
+        Ok(Self {
+            repo_root,
+            path,
+            rev: None,
+            blame,
+            cursor: 0,
+        })
+    }
+
+    /// Re-blame at the parent of the commit that last changed the selected line
+    pub fn parent(&self) -> Result<Self, String> {
+        let commit = self.blame.commit_of(self.cursor).ok_or("No line selected")?;
+        if commit.is_uncommitted() {
+            return Err("Line is not committed yet".to_string());
+        }
+        let (rev, path) = commit.previous.clone().ok_or("Commit has no parent")?;
+        let blame = Blame::run(&self.repo_root, &path, Some(&rev))?;
+        let cursor = self.cursor.min(blame.lines.len().saturating_sub(1));
+        Ok(Self {
+            repo_root: self.repo_root.clone(),
+            path,
+            rev: Some(rev),
+            blame,
+            cursor,
+        })
+    }
+
+    pub fn current_commit(&self) -> Option<&BlameCommit> {
+        self.blame.commit_of(self.cursor)
+    }
+
+    /// "working tree" or the short revision being blamed
+    pub fn rev_label(&self) -> String {
+        match self.rev {
+            Some(ref rev) => rev[..rev.len().min(7)].to_string(),
+            None => "working tree".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SAMPLE: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-time 1700000000
+summary first
+filename a.txt
+\tone
+1111111111111111111111111111111111111111 2 2
+\ttwo
+2222222222222222222222222222222222222222 3 3 1
+author Bob
+author-time 1710000000
+summary second
+previous 1111111111111111111111111111111111111111 old.txt
+filename a.txt
+\tthree
+";
+
+    #[test]
+    fn test_parse_porcelain() {
+        let blame = Blame::parse(SAMPLE).unwrap();
+        assert_eq!(blame.commits.len(), 2);
+        assert_eq!(blame.lines.len(), 3);
+        assert_eq!(blame.lines[1].content, "two");
+        assert_eq!(blame.commit_of(1).unwrap().author, "Alice");
+        let second = blame.commit_of(2).unwrap();
+        assert_eq!(second.short_hash(), "2222222");
+        assert_eq!(second.time, 1710000000);
+        assert_eq!(
+            second.previous,
+            Some(("1111111111111111111111111111111111111111".to_string(), "old.txt".to_string()))
+        );
+        assert!(blame.commit_of(0).unwrap().previous.is_none());
+    }
+
+    #[test]
+    fn test_age_and_relative_date() {
+        let now = 1_000_000_000;
+        assert_eq!(blame_age(now - DAY, now), BlameAge::Recent);
+        assert_eq!(blame_age(now - 100 * DAY, now), BlameAge::Medium);
+        assert_eq!(blame_age(now - 400 * DAY, now), BlameAge::Old);
+        assert_eq!(relative_date(now - 120, now), "2m");
+        assert_eq!(relative_date(now - 3 * 3600, now), "3h");
+        assert_eq!(relative_date(now - 12 * DAY, now), "12d");
+        assert_eq!(relative_date(now - 90 * DAY, now), "3mo");
+        assert_eq!(relative_date(now - 800 * DAY, now), "2y");
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let ok = git_cmd(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .success();
+        assert!(ok, "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_open_and_parent() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        git(dir, &["init", "-q"]);
+        fs::write(dir.join("f.txt"), "a\nb\n").unwrap();
+        git(dir, &["add", "f.txt"]);
+        git(dir, &["commit", "-q", "-m", "first"]);
+        fs::write(dir.join("f.txt"), "a\nB\n").unwrap();
+        git(dir, &["commit", "-q", "-am", "second"]);
+        fs::write(dir.join("f.txt"), "a\nB\nc\n").unwrap();
+
+        let mut view = BlameView::open(&dir.join("f.txt")).unwrap();
+        assert_eq!(view.path, "f.txt");
+        assert_eq!(view.blame.lines.len(), 3);
+        assert!(view.blame.commit_of(2).unwrap().is_uncommitted());
+        assert_eq!(view.blame.commit_of(1).unwrap().summary, "second");
+
+        view.cursor = 2;
+        assert!(view.parent().is_err());
+
+        view.cursor = 1;
+        let parent = view.parent().unwrap();
+        assert_eq!(parent.blame.lines[1].content, "b");
+        assert_eq!(parent.current_commit().unwrap().summary, "first");
+        // Root commit has no parent to go to
+        assert!(parent.parent().is_err());
+
+        fs::write(dir.join("untracked.txt"), "x\n").unwrap();
+        assert!(BlameView::open(&dir.join("untracked.txt")).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Parsed single-file unified diff with partial patch building for the git
+//! screen's hunk and line staging.
+//!
+//! A partial patch keeps the selected change lines and neutralises the rest so
+//! that it still applies to the side git will patch: for a forward apply
+//! (staging) unselected removals become context and unselected additions are
+//! dropped; for a reverse apply (unstaging, discarding) it is the other way
+//! round.
+
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchLineKind {
+    /// `diff --git`, `index`, `---`, `+++` and similar file header lines
+    Header,
+    /// `@@ -a,b +c,d @@`
+    HunkHeader,
+    Context,
+    Added,
+    Removed,
+    /// `\ No newline at end of file`, belongs to the line before it
+    NoNewline,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchLine {
+    pub kind: PatchLineKind,
+    pub text: String,
+    /// Hunk this line belongs to (None for file header lines)
+    pub hunk: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    /// Text after the second `@@` (usually the enclosing function)
+    pub section: String,
+    /// Index of the hunk header in `lines`
+    pub header_line: usize,
+    /// One past the last line of the hunk in `lines`
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilePatch {
+    pub lines: Vec<PatchLine>,
+    pub hunks: Vec<Hunk>,
+    /// Binary diffs cannot be split
+    pub binary: bool,
+}
+
+impl FilePatch {
+    /// Parse the output of `git diff -- <file>` (a single file)
+    pub fn parse(diff: &str) -> Self {
+        let mut patch = FilePatch::default();
+
+        for text in diff.lines() {
+            let idx = patch.lines.len();
+            if let Some(hunk) = parse_hunk_header(text, idx) {
+                if let Some(last) = patch.hunks.last_mut() {
+                    last.end_line = idx;
+                }
+                patch.hunks.push(hunk);
+                patch.lines.push(PatchLine {
+                    kind: PatchLineKind::HunkHeader,
+                    text: text.to_string(),
+                    hunk: Some(patch.hunks.len() - 1),
+                });
+                continue;
+            }
+
+            let hunk = patch.hunks.len().checked_sub(1);
+            let kind = match hunk {
+                None => {
+                    if text.starts_with("Binary files ") || text == "GIT binary patch" {
+                        patch.binary = true;
+                    }
+                    PatchLineKind::Header
+                }
+                Some(_) => match text.as_bytes().first() {
+                    Some(b'+') => PatchLineKind::Added,
+                    Some(b'-') => PatchLineKind::Removed,
+                    Some(b'\\') => PatchLineKind::NoNewline,
+                    _ => PatchLineKind::Context,
+                },
+            };
+            patch.lines.push(PatchLine {
+                kind,
+                text: text.to_string(),
+                hunk: if kind == PatchLineKind::Header { None } else { hunk },
+            });
+        }
+        if let Some(last) = patch.hunks.last_mut() {
+            last.end_line = patch.lines.len();
+        }
+        patch
+    }
+
+    /// True for added or removed lines, the only lines that can be selected
+    pub fn is_change(&self, idx: usize) -> bool {
+        self.lines
+            .get(idx)
+            .is_some_and(|l| matches!(l.kind, PatchLineKind::Added | PatchLineKind::Removed))
+    }
+
+    /// Change lines of one hunk
+    pub fn hunk_changes(&self, hunk: usize) -> Vec<usize> {
+        match self.hunks.get(hunk) {
+            Some(h) => (h.header_line + 1..h.end_line).filter(|&i| self.is_change(i)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Every change line in the file
+    pub fn all_changes(&self) -> Vec<usize> {
+        (0..self.lines.len()).filter(|&i| self.is_change(i)).collect()
+    }
+
+    /// Build a patch containing only the `selected` change lines.
+    ///
+    /// `reverse` builds a patch meant for `git apply --reverse` (the new side
+    /// of this diff is what exists on disk / in the index). Returns None when
+    /// nothing applicable is selected or the diff is binary.
+    pub fn build(&self, selected: &BTreeSet<usize>, reverse: bool) -> Option<String> {
+        if self.binary {
+            return None;
+        }
+        let all = self.all_changes();
+        if !all.iter().any(|i| selected.contains(i)) {
+            return None;
+        }
+        let whole_file = all.iter().all(|i| selected.contains(i));
+
+        let mut out = String::new();
+        for line in self.lines.iter().filter(|l| l.kind == PatchLineKind::Header) {
+            if whole_file {
+                out.push_str(&line.text);
+                out.push('\n');
+                continue;
+            }
+            // Part of a created or deleted file: the file exists on the side being
+            // patched, so describe it as a plain modification
+            if line.text.starts_with("new file mode ") || line.text.starts_with("deleted file mode ") {
+                continue;
+            }
+            let text = match line.text.as_str() {
+                "--- /dev/null" => format!("--- a/{}", self.path().unwrap_or_default()),
+                "+++ /dev/null" => format!("+++ b/{}", self.path().unwrap_or_default()),
+                other => other.to_string(),
+            };
+            out.push_str(&text);
+            out.push('\n');
+        }
+
+        // Difference between new and old line counts of the hunks emitted so far
+        let mut delta: isize = 0;
+        for hunk in &self.hunks {
+            let mut body = String::new();
+            let mut old_count = 0usize;
+            let mut new_count = 0usize;
+            let mut any_selected = false;
+            let mut previous_kept = false;
+
+            for idx in hunk.header_line + 1..hunk.end_line {
+                let line = &self.lines[idx];
+                match line.kind {
+                    PatchLineKind::Context => {
+                        push_line(&mut body, &line.text);
+                        old_count += 1;
+                        new_count += 1;
+                        previous_kept = true;
+                    }
+                    PatchLineKind::Added | PatchLineKind::Removed => {
+                        let added = line.kind == PatchLineKind::Added;
+                        if selected.contains(&idx) {
+                            push_line(&mut body, &line.text);
+                            if added {
+                                new_count += 1;
+                            } else {
+                                old_count += 1;
+                            }
+                            any_selected = true;
+                            previous_kept = true;
+                        } else if added == reverse {
+                            // Present on the side being patched: keep as context
+                            body.push(' ');
+                            push_line(&mut body, &line.text[1..]);
+                            old_count += 1;
+                            new_count += 1;
+                            previous_kept = true;
+                        } else {
+                            previous_kept = false;
+                        }
+                    }
+                    PatchLineKind::NoNewline => {
+                        if previous_kept {
+                            push_line(&mut body, &line.text);
+                        }
+                    }
+                    PatchLineKind::Header | PatchLineKind::HunkHeader => {}
+                }
+            }
+            if !any_selected {
+                continue;
+            }
+
+            // Git writes the line before the hunk as start when a side is empty
+            let start_of = |before: isize, count: usize| (before + (count > 0) as isize).max(0);
+            let (old_start, new_start) = if reverse {
+                let before = line_before(hunk.new_start, hunk.new_count);
+                (start_of(before - delta, old_count), start_of(before, new_count))
+            } else {
+                let before = line_before(hunk.old_start, hunk.old_count);
+                (start_of(before, old_count), start_of(before + delta, new_count))
+            };
+            delta += new_count as isize - old_count as isize;
+
+            out.push_str(&format!("@@ -{},{} +{},{} @@", old_start, old_count, new_start, new_count));
+            if !hunk.section.is_empty() {
+                out.push(' ');
+                out.push_str(&hunk.section);
+            }
+            out.push('\n');
+            out.push_str(&body);
+        }
+        Some(out)
+    }
+
+    /// File path from the `+++ b/...` (or `--- a/...`) header
+    fn path(&self) -> Option<String> {
+        self.lines
+            .iter()
+            .filter(|l| l.kind == PatchLineKind::Header)
+            .find_map(|l| {
+                l.text
+                    .strip_prefix("+++ b/")
+                    .or_else(|| l.text.strip_prefix("--- a/"))
+                    .map(|p| p.to_string())
+            })
+    }
+}
+
+fn push_line(out: &mut String, text: &str) {
+    out.push_str(text);
+    out.push('\n');
+}
+
+fn line_before(start: usize, count: usize) -> isize {
+    if count == 0 {
+        start as isize
+    } else {
+        start as isize - 1
+    }
+}
+
+/// Parse `@@ -a[,b] +c[,d] @@ section`
+fn parse_hunk_header(text: &str, line: usize) -> Option<Hunk> {
+    let rest = text.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((s, c)) => Some((s.parse().ok()?, c.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old)?;
+    let (new_start, new_count) = range(new)?;
+    Some(Hunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        section: section.trim_start().to_string(),
+        header_line: line,
+        end_line: line + 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/f.txt b/f.txt
+index 1111111..2222222 100644
+--- a/f.txt
++++ b/f.txt
+@@ -1,4 +1,4 @@ fn main
+ a
+-b
++B
+ c
+ d
+@@ -10,3 +10,4 @@
+ x
++y1
++y2
+-z
+";
+
+    fn select(lines: &[usize]) -> BTreeSet<usize> {
+        lines.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let patch = FilePatch::parse(DIFF);
+        assert_eq!(patch.hunks.len(), 2);
+        assert_eq!(patch.hunks[0].section, "fn main");
+        assert_eq!((patch.hunks[1].old_start, patch.hunks[1].new_count), (10, 4));
+        assert_eq!(patch.hunk_changes(0), vec![6, 7]);
+        assert_eq!(patch.hunk_changes(1), vec![12, 13, 14]);
+        assert!(!patch.is_change(5));
+        assert!(!patch.binary);
+        assert!(FilePatch::parse("diff --git a/x b/x\nBinary files a/x and b/x differ\n").binary);
+    }
+
+    #[test]
+    fn test_build_whole_hunk_forward() {
+        let patch = FilePatch::parse(DIFF);
+        let built = patch.build(&select(&patch.hunk_changes(1)), false).unwrap();
+        assert_eq!(
+            built,
+            "diff --git a/f.txt b/f.txt\nindex 1111111..2222222 100644\n--- a/f.txt\n+++ b/f.txt\n\
+             @@ -10,2 +10,3 @@\n x\n+y1\n+y2\n-z\n"
+        );
+    }
+
+    #[test]
+    fn test_build_single_lines() {
+        let patch = FilePatch::parse(DIFF);
+        // Stage only "+y2": "-z" becomes context, "+y1" is dropped
+        let forward = patch.build(&select(&[13]), false).unwrap();
+        assert!(forward.ends_with("@@ -10,2 +10,3 @@\n x\n+y2\n z\n"));
+
+        // Unstage only "+y2": "+y1" stays as context, "-z" is dropped
+        let reverse = patch.build(&select(&[13]), true).unwrap();
+        assert!(reverse.ends_with("@@ -10,2 +10,3 @@\n x\n y1\n+y2\n"));
+
+        // Both hunks: the second hunk's new start shifts by the first hunk's delta
+        let both = patch.build(&select(&[7, 12]), false).unwrap();
+        assert!(both.contains("@@ -1,4 +1,5 @@ fn main\n a\n b\n+B\n c\n d\n"));
+        assert!(both.ends_with("@@ -10,2 +11,3 @@\n x\n+y1\n z\n"));
+
+        assert!(patch.build(&select(&[0, 5]), false).is_none());
+    }
+
+    #[test]
+    fn test_partial_new_file_drops_creation_header() {
+        let diff = "\
+diff --git a/n.txt b/n.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/n.txt
+@@ -0,0 +1,2 @@
++one
++two
+";
+        let patch = FilePatch::parse(diff);
+        let reverse = patch.build(&select(&[7]), true).unwrap();
+        assert_eq!(
+            reverse,
+            "diff --git a/n.txt b/n.txt\nindex 0000000..3333333\n--- a/n.txt\n+++ b/n.txt\n\
+             @@ -1,1 +1,2 @@\n one\n+two\n"
+        );
+        // Whole file keeps the original headers
+        assert!(patch.build(&select(&[6, 7]), true).unwrap().contains("new file mode 100644"));
+    }
+
+    #[test]
+    fn test_no_newline_marker_follows_its_line() {
+        let diff = "\
+--- a/f
++++ b/f
+@@ -1 +1 @@
+-old
+\\ No newline at end of file
++new
+\\ No newline at end of file
+";
+        let patch = FilePatch::parse(diff);
+        assert_eq!(patch.lines[4].kind, PatchLineKind::NoNewline);
+        // Unstage only the addition: the removal and its marker are dropped
+        let reverse = patch.build(&select(&[5]), true).unwrap();
+        assert!(reverse.ends_with("@@ -0,0 +1,1 @@\n+new\n\\ No newline at end of file\n"));
+        // Stage only the removal: the addition and its marker are dropped
+        let removal = patch.build(&select(&[3]), false).unwrap();
+        assert!(removal.ends_with("@@ -1,1 +0,0 @@\n-old\n\\ No newline at end of file\n"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Fetch / pull / push for the git screen.
+//!
+//! The git process runs on a background thread with `--progress`; its stderr is
+//! split on `\r` and `\n` so the in-place progress updates git writes for a
+//! terminal arrive as separate lines. Credential prompts are disabled, so a
+//! remote that needs a password fails instead of waiting for input nobody sees.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// Remote operation of the git screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteCommand {
+    Fetch,
+    Pull { rebase: bool },
+    Push {
+        /// (remote, branch) to push to and record as upstream
+        set_upstream: Option<(String, String)>,
+        force_with_lease: bool,
+    },
+}
+
+impl RemoteCommand {
+    pub fn label(&self) -> String {
+        match self {
+            RemoteCommand::Fetch => "Fetch".to_string(),
+            RemoteCommand::Pull { rebase: false } => "Pull".to_string(),
+            RemoteCommand::Pull { rebase: true } => "Pull (rebase)".to_string(),
+            RemoteCommand::Push { force_with_lease: true, .. } => "Force push".to_string(),
+            RemoteCommand::Push { set_upstream: Some((remote, branch)), .. } => {
+                format!("Push to {}/{}", remote, branch)
+            }
+            RemoteCommand::Push { .. } => "Push".to_string(),
+        }
+    }
+
+    pub fn args(&self) -> Vec<String> {
+        let mut args: Vec<String> = match self {
+            RemoteCommand::Fetch => vec!["fetch".into(), "--all".into(), "--prune".into()],
+            RemoteCommand::Pull { rebase } => vec![
+                "pull".into(),
+                if *rebase { "--rebase".into() } else { "--no-rebase".into() },
+            ],
+            RemoteCommand::Push { set_upstream, force_with_lease } => {
+                let mut args = vec!["push".to_string()];
+                if *force_with_lease {
+                    args.push("--force-with-lease".into());
+                }
+                if let Some((remote, branch)) = set_upstream {
+                    args.push("--set-upstream".into());
+                    args.push(remote.clone());
+                    args.push(branch.clone());
+                }
+                args
+            }
+        };
+        args.insert(1, "--progress".into());
+        args
+    }
+}
+
+/// Parse a git progress line such as "Receiving objects:  45% (9/20)"
+/// into its phase and percentage.
+pub fn parse_progress(line: &str) -> Option<(String, u8)> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(": ")?;
+    let rest = rest.trim_start();
+    let digits_end = rest.find(|c: char| !c.is_ascii_digit())?;
+    if digits_end == 0 || !rest[digits_end..].starts_with('%') {
+        return None;
+    }
+    let percent: u8 = rest[..digits_end].parse().ok()?;
+    Some((phase.trim().to_string(), percent.min(100)))
+}
+
+/// Turn git's stderr into a short error message, calling out failed authentication
+pub fn describe_remote_error(stderr: &str) -> String {
+    const AUTH_MARKERS: [&str; 6] = [
+        "Authentication failed",
+        "Permission denied",
+        "could not read Username",
+        "could not read Password",
+        "terminal prompts disabled",
+        "Host key verification failed",
+    ];
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && parse_progress(l).is_none())
+        .collect();
+
+    if let Some(line) = lines.iter().find(|l| AUTH_MARKERS.iter().any(|m| l.contains(m))) {
+        return format!("Authentication failed: {}", line.trim_start_matches("fatal: "));
+    }
+    // "! [rejected] main -> main (fetch first)" says more than the trailing hints
+    if let Some(line) = lines.iter().find(|l| l.starts_with("! [")) {
+        return format!("Rejected: {}", line.trim_start_matches("! "));
+    }
+    lines
+        .iter()
+        .find(|l| l.starts_with("fatal:") || l.starts_with("error:"))
+        .or(lines.first())
+        .map(|l| l.to_string())
+        .unwrap_or_else(|| "Remote operation failed".to_string())
+}
+
+enum RemoteEvent {
+    Line(String),
+    Progress(String, u8),
+    Done(Result<(), String>),
+}
+
+/// A running (or finished) remote operation and its output
+pub struct RemoteOp {
+    pub command: RemoteCommand,
+    /// Output lines, progress updates excluded
+    pub lines: Vec<String>,
+    /// Current progress phase and percentage
+    pub progress: Option<(String, u8)>,
+    /// Set once the process has exited
+    pub result: Option<Result<(), String>>,
+    receiver: Receiver<RemoteEvent>,
+}
+
+impl RemoteOp {
+    /// Spawn `cmd` (a git command already pointed at the repository) with the
+    /// arguments of `command`
+    pub fn start(mut cmd: Command, command: RemoteCommand) -> Self {
+        cmd.args(command.args())
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_EDITOR", "true")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // Never let ssh ask for a password or host key confirmation
+        if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+            cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let result = run(cmd, &tx);
+            let _ = tx.send(RemoteEvent::Done(result));
+        });
+
+        Self {
+            command,
+            lines: Vec::new(),
+            progress: None,
+            result: None,
+            receiver: rx,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.result.is_none()
+    }
+
+    /// Take in pending output. Returns true when the operation just finished.
+    pub fn poll(&mut self) -> bool {
+        if self.result.is_some() {
+            return false;
+        }
+        loop {
+            match self.receiver.try_recv() {
+                Ok(RemoteEvent::Line(line)) => self.lines.push(line),
+                Ok(RemoteEvent::Progress(phase, percent)) => self.progress = Some((phase, percent)),
+                Ok(RemoteEvent::Done(result)) => {
+                    self.result = Some(result);
+                    return true;
+                }
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.result = Some(Err("Remote operation ended unexpectedly".to_string()));
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+fn run(mut cmd: Command, tx: &Sender<RemoteEvent>) -> Result<(), String> {
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+
+    // Merge / rebase output of a pull goes to stdout
+    let stdout = child.stdout.take();
+    let stdout_tx = tx.clone();
+    let stdout_thread = thread::spawn(move || {
+        if let Some(stdout) = stdout {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if !line.trim().is_empty() {
+                    let _ = stdout_tx.send(RemoteEvent::Line(line));
+                }
+            }
+        }
+    });
+
+    let mut stderr_text = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let mut buf = [0u8; 4096];
+        let mut pending: Vec<u8> = Vec::new();
+        loop {
+            let n = match stderr.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            pending.extend_from_slice(&buf[..n]);
+            while let Some(pos) = pending.iter().position(|&b| b == b'\r' || b == b'\n') {
+                let segment: Vec<u8> = pending.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&segment[..segment.len() - 1]).trim_end().to_string();
+                send_stderr_line(tx, &line, &mut stderr_text);
+            }
+        }
+        if !pending.is_empty() {
+            let line = String::from_utf8_lossy(&pending).trim_end().to_string();
+            send_stderr_line(tx, &line, &mut stderr_text);
+        }
+    }
+
+    let _ = stdout_thread.join();
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(describe_remote_error(&stderr_text))
+    }
+}
+
+fn send_stderr_line(tx: &Sender<RemoteEvent>, line: &str, stderr_text: &mut String) {
+    if line.is_empty() {
+        return;
+    }
+    match parse_progress(line) {
+        Some((phase, percent)) => {
+            // Keep only the final "..., done." line of each phase in the log
+            if line.ends_with("done.") {
+                let _ = tx.send(RemoteEvent::Line(line.to_string()));
+            }
+            let _ = tx.send(RemoteEvent::Progress(phase, percent));
+        }
+        None => {
+            stderr_text.push_str(line);
+            stderr_text.push('\n');
+            let _ = tx.send(RemoteEvent::Line(line.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(parse_progress("Receiving objects:  45% (9/20)"), Some(("Receiving objects".to_string(), 45)));
+        assert_eq!(
+            parse_progress("remote: Counting objects: 100% (3/3), done."),
+            Some(("Counting objects".to_string(), 100))
+        );
+        assert_eq!(parse_progress("To /tmp/origin.git"), None);
+        assert_eq!(parse_progress("error: failed to push some refs"), None);
+        assert_eq!(parse_progress("hint: 50 things"), None);
+    }
+
+    #[test]
+    fn test_describe_remote_error() {
+        let auth = "Cloning...\nfatal: could not read Username for 'https://example.com': terminal prompts disabled\n";
+        assert!(describe_remote_error(auth).starts_with("Authentication failed: could not read Username"));
+        let ssh = "git@example.com: Permission denied (publickey).\nfatal: Could not read from remote repository.\n";
+        assert!(describe_remote_error(ssh).starts_with("Authentication failed"));
+        let rejected = "To /tmp/o.git\n ! [rejected]        main -> main (fetch first)\nerror: failed to push some refs to '/tmp/o.git'\nhint: Updates were rejected\n";
+        assert_eq!(describe_remote_error(rejected), "Rejected: [rejected]        main -> main (fetch first)");
+        assert_eq!(describe_remote_error("fatal: 'nope' does not appear to be a git repository\n"), "fatal: 'nope' does not appear to be a git repository");
+        assert_eq!(describe_remote_error(""), "Remote operation failed");
+    }
+
+    #[test]
+    fn test_command_args() {
+        assert_eq!(RemoteCommand::Fetch.args(), vec!["fetch", "--progress", "--all", "--prune"]);
+        assert_eq!(RemoteCommand::Pull { rebase: true }.args(), vec!["pull", "--progress", "--rebase"]);
+        let push = RemoteCommand::Push {
+            set_upstream: Some(("origin".to_string(), "topic".to_string())),
+            force_with_lease: true,
+        };
+        assert_eq!(
+            push.args(),
+            vec!["push", "--progress", "--force-with-lease", "--set-upstream", "origin", "topic"]
+        );
+    }
+}
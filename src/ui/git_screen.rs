@@ -1,5 +1,7 @@
+use std::collections::BTreeSet;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::{
@@ -12,6 +14,8 @@ use ratatui::{
 use unicode_width::UnicodeWidthStr;
 
 use super::app::{App, Screen};
+use super::git_patch::{FilePatch, PatchLineKind};
+use super::git_remote::{RemoteCommand, RemoteOp};
 use super::theme::Theme;
 use crate::utils::format::{truncate_to_display_width, pad_to_display_width};
 
@@ -24,6 +28,9 @@ pub enum GitTab {
     Commit,
     Log,
     Branch,
+    Stash,
+    Tags,
+    Worktrees,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +48,148 @@ pub struct GitLogEntry {
     pub author: String,
     pub date: String,
     pub refs: String,
+    /// Abbreviated parent hashes
+    pub parents: Vec<String>,
+    /// ASCII graph cell for this row ("*", "|*", "*/", ...); empty when filtered
+    pub graph: String,
+}
+
+/// Filters of the log tab. Written as a query like
+/// `author:kim msg:"fix.*crash" since:2024-01-01 path:src file:README.md`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    pub author: Option<String>,
+    /// Extended regex matched against the commit message
+    pub message: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub path: Option<String>,
+    /// Follow renames of `path` (history of a single file)
+    pub follow: bool,
+    /// Log of all refs instead of the current branch only
+    pub all_refs: bool,
+}
+
+impl LogFilter {
+    /// Parse a filter query; words without a known `key:` prefix match the message
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut filter = LogFilter::default();
+        let mut words = Vec::new();
+        for token in split_query(query)? {
+            let (key, value) = match token.split_once(':') {
+                Some((k, v)) => (k.to_lowercase(), v.to_string()),
+                None => (String::new(), token.clone()),
+            };
+            let slot = match key.as_str() {
+                "author" | "a" => &mut filter.author,
+                "msg" | "m" => &mut filter.message,
+                "since" | "after" => &mut filter.since,
+                "until" | "before" => &mut filter.until,
+                "path" | "p" => &mut filter.path,
+                "file" | "f" => {
+                    filter.follow = true;
+                    &mut filter.path
+                }
+                _ => {
+                    words.push(token);
+                    continue;
+                }
+            };
+            if value.is_empty() {
+                return Err(format!("Missing value for {}:", key));
+            }
+            *slot = Some(value);
+        }
+        if !words.is_empty() {
+            let text = words.join(" ");
+            filter.message = Some(match filter.message.take() {
+                Some(m) => format!("{} {}", m, text),
+                None => text,
+            });
+        }
+        Ok(filter)
+    }
+
+    /// Query text that parses back into this filter (all_refs aside)
+    pub fn to_query(&self) -> String {
+        let quote = |v: &str| if v.contains(' ') { format!("\"{}\"", v) } else { v.to_string() };
+        let mut parts = Vec::new();
+        for (key, value) in [
+            ("author", &self.author),
+            ("msg", &self.message),
+            ("since", &self.since),
+            ("until", &self.until),
+            (if self.follow { "file" } else { "path" }, &self.path),
+        ] {
+            if let Some(v) = value {
+                parts.push(format!("{}:{}", key, quote(v)));
+            }
+        }
+        parts.join(" ")
+    }
+
+    /// Whether the log is limited to some of the commits (the graph is only drawn for full logs)
+    pub fn is_active(&self) -> bool {
+        self.author.is_some()
+            || self.message.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.path.is_some()
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.all_refs {
+            args.push("--all".to_string());
+        }
+        if let Some(ref author) = self.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(ref message) = self.message {
+            args.push("--extended-regexp".to_string());
+            args.push("--regexp-ignore-case".to_string());
+            args.push(format!("--grep={}", message));
+        }
+        if let Some(ref since) = self.since {
+            args.push(format!("--since={}", since));
+        }
+        if let Some(ref until) = self.until {
+            args.push(format!("--until={}", until));
+        }
+        if let Some(ref path) = self.path {
+            if self.follow {
+                args.push("--follow".to_string());
+            }
+            args.push("--".to_string());
+            args.push(path.clone());
+        }
+        args
+    }
+}
+
+/// Split on whitespace, keeping double-quoted parts together
+fn split_query(query: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quote".to_string());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
 }
 
 #[derive(Debug, Clone)]
@@ -48,24 +197,220 @@ pub struct GitBranchEntry {
     pub name: String,
     pub is_current: bool,
     pub is_remote: bool,
+    /// Tracked remote branch, e.g. "origin/main"
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// The upstream branch no longer exists on the remote
+    pub upstream_gone: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GitStashEntry {
+    /// Reflog selector, e.g. "stash@{0}"
+    pub name: String,
+    pub message: String,
+    pub date: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GitTagEntry {
+    pub name: String,
+    /// Short hash of the tagged commit
+    pub commit: String,
+    pub annotated: bool,
+    /// Tag message subject (annotated) or commit subject (lightweight)
+    pub subject: String,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitWorktreeEntry {
+    pub path: PathBuf,
+    /// Short hash of the checked out commit
+    pub head: String,
+    /// Checked out branch; None when detached (or bare)
+    pub branch: Option<String>,
+    pub is_main: bool,
+    /// The worktree this screen was opened in
+    pub is_current: bool,
+    pub bare: bool,
+    pub locked: bool,
+    pub prunable: bool,
+}
+
+/// Which changes `git stash push` takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StashMode {
+    Tracked,
+    IncludeUntracked,
+    StagedOnly,
+}
+
+/// History-integrating operation that can stop on conflicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+
+impl GitOperation {
+    pub fn label(self) -> &'static str {
+        match self {
+            GitOperation::Merge => "Merge",
+            GitOperation::Rebase => "Rebase",
+            GitOperation::CherryPick => "Cherry-pick",
+            GitOperation::Revert => "Revert",
+        }
+    }
+
+    fn subcommand(self) -> &'static str {
+        match self {
+            GitOperation::Merge => "merge",
+            GitOperation::Rebase => "rebase",
+            GitOperation::CherryPick => "cherry-pick",
+            GitOperation::Revert => "revert",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum InputMode {
     BranchCreate,
     CommitAmend,
+    StashPush(StashMode),
+    StashBranch(String), // stash name
+    LogFilter,
+    /// Tag name; `target` None tags HEAD
+    TagCreate { target: Option<String>, annotated: bool },
+    /// Message of an annotated tag
+    TagMessage { name: String, target: Option<String> },
+    /// "<path> [<branch>]"
+    WorktreeAdd,
 }
 
 #[derive(Debug, Clone)]
 pub enum ConfirmAction {
     BranchDelete(String),
     RestoreToCommit(String), // hash
+    DiscardLines(usize),     // number of selected change lines
+    StashApply(String),      // stash name
+    StashPop(String),
+    StashDrop(String),
+    Merge(String),      // branch
+    Rebase(String),     // upstream branch
+    CherryPick(String), // hash
+    Revert(String),     // hash
+    AbortOperation(GitOperation),
+    Push(RemoteCommand),
+    TagDelete(String),
+    WorktreeRemove { path: PathBuf, force: bool },
+}
+
+/// What to do with the selected lines of a stage view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageAction {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+/// Hunk / line staging view of one file's diff in the commit tab
+pub struct StageView {
+    pub file: String,
+    /// Showing the staged (HEAD → index) diff rather than the unstaged one
+    pub staged: bool,
+    pub patch: FilePatch,
+    /// Cursor line (index into patch.lines)
+    pub cursor: usize,
+    /// Selected change lines
+    pub selected: BTreeSet<usize>,
+}
+
+impl StageView {
+    pub fn new(file: String, staged: bool, diff: &str) -> Self {
+        let patch = FilePatch::parse(diff);
+        let cursor = patch.all_changes().first().copied().unwrap_or(0);
+        Self {
+            file,
+            staged,
+            patch,
+            cursor,
+            selected: BTreeSet::new(),
+        }
+    }
+
+    pub fn move_cursor(&mut self, delta: isize) {
+        let max = self.patch.lines.len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize + delta).clamp(0, max.max(0)) as usize;
+    }
+
+    /// Jump to the first change of the next (`forward`) or previous hunk
+    pub fn jump_hunk(&mut self, forward: bool) {
+        let current = self.current_hunk();
+        let target = match (current, forward) {
+            (Some(h), true) => h + 1,
+            (Some(h), false) => match h.checked_sub(1) {
+                Some(h) => h,
+                None => return,
+            },
+            (None, true) => 0,
+            (None, false) => return,
+        };
+        if let Some(hunk) = self.patch.hunks.get(target) {
+            self.cursor = self.patch.hunk_changes(target).first().copied().unwrap_or(hunk.header_line);
+        }
+    }
+
+    pub fn current_hunk(&self) -> Option<usize> {
+        self.patch.lines.get(self.cursor).and_then(|l| l.hunk)
+    }
+
+    pub fn toggle_line(&mut self) {
+        if !self.patch.is_change(self.cursor) {
+            return;
+        }
+        if !self.selected.remove(&self.cursor) {
+            self.selected.insert(self.cursor);
+        }
+    }
+
+    /// Select the current hunk, or deselect it if it is fully selected
+    pub fn toggle_hunk(&mut self) {
+        let lines = match self.current_hunk() {
+            Some(h) => self.patch.hunk_changes(h),
+            None => return,
+        };
+        if lines.iter().all(|l| self.selected.contains(l)) {
+            for l in &lines {
+                self.selected.remove(l);
+            }
+        } else {
+            self.selected.extend(lines);
+        }
+    }
+
+    /// Lines an action applies to: the selection, or the current hunk when nothing is selected
+    pub fn target_lines(&self) -> BTreeSet<usize> {
+        if !self.selected.is_empty() {
+            return self.selected.clone();
+        }
+        match self.current_hunk() {
+            Some(h) => self.patch.hunk_changes(h).into_iter().collect(),
+            None => BTreeSet::new(),
+        }
+    }
 }
 
 pub struct GitScreenState {
     pub repo_path: PathBuf,
     pub current_tab: GitTab,
     pub branch_name: String,
+    /// Merge / rebase / cherry-pick / revert stopped on conflicts; the commit
+    /// tab then lists the conflicted files
+    pub operation: Option<GitOperation>,
 
     // Status tab
     pub status_files: Vec<GitFileEntry>,
@@ -84,12 +429,35 @@ pub struct GitScreenState {
     pub log_scroll: usize,
     pub log_detail: Option<String>,
     pub log_detail_scroll: usize,
+    pub log_filter: LogFilter,
+    /// Hunk / line staging state while a file diff is open in the commit tab
+    pub stage_view: Option<StageView>,
 
     // Branch tab
     pub branches: Vec<GitBranchEntry>,
     pub branch_selected: usize,
     pub branch_scroll: usize,
 
+    // Stash tab
+    pub stashes: Vec<GitStashEntry>,
+    pub stash_selected: usize,
+    pub stash_scroll: usize,
+
+    // Tags tab
+    pub tags: Vec<GitTagEntry>,
+    pub tag_selected: usize,
+    pub tag_scroll: usize,
+
+    // Worktrees tab
+    pub worktrees: Vec<GitWorktreeEntry>,
+    pub worktree_selected: usize,
+    pub worktree_scroll: usize,
+    /// Worktree the user asked to open in a new panel; picked up by `handle_input`
+    pub open_worktree: Option<PathBuf>,
+
+    /// Running or just finished fetch / pull / push, shown in the progress area
+    pub remote_op: Option<RemoteOp>,
+
     // Dialog
     pub input_mode: Option<InputMode>,
     pub input_buffer: String,
@@ -102,15 +470,25 @@ pub struct GitScreenState {
 impl GitScreenState {
     pub fn new(repo_path: PathBuf) -> Self {
         stage_all(&repo_path);
+        Self::load(repo_path)
+    }
+
+    /// Load repository state without touching the index
+    pub fn load(repo_path: PathBuf) -> Self {
         let branch_name = get_current_branch(&repo_path);
+        let operation = get_operation(&repo_path);
         let status_files = get_status(&repo_path);
         let log_entries = get_log(&repo_path, 200);
         let branches = get_branches(&repo_path);
+        let stashes = get_stashes(&repo_path);
+        let tags = get_tags(&repo_path);
+        let worktrees = get_worktrees(&repo_path);
 
         Self {
             repo_path,
             current_tab: GitTab::Commit,
             branch_name,
+            operation,
             status_files,
             status_selected: 0,
             status_scroll: 0,
@@ -123,9 +501,22 @@ impl GitScreenState {
             log_scroll: 0,
             log_detail: None,
             log_detail_scroll: 0,
+            log_filter: LogFilter::default(),
+            stage_view: None,
             branches,
             branch_selected: 0,
             branch_scroll: 0,
+            stashes,
+            stash_selected: 0,
+            stash_scroll: 0,
+            tags,
+            tag_selected: 0,
+            tag_scroll: 0,
+            worktrees,
+            worktree_selected: 0,
+            worktree_scroll: 0,
+            open_worktree: None,
+            remote_op: None,
             input_mode: None,
             input_buffer: String::new(),
             confirm_action: None,
@@ -135,8 +526,19 @@ impl GitScreenState {
         }
     }
 
+    /// Show a commit's diff in the log tab
+    pub fn open_commit(&mut self, hash: &str) {
+        self.current_tab = GitTab::Log;
+        if let Some(i) = self.log_entries.iter().position(|e| hash.starts_with(&e.hash)) {
+            self.log_selected = i;
+        }
+        self.log_detail = Some(get_commit_diff(&self.repo_path, hash));
+        self.log_detail_scroll = 0;
+    }
+
     fn refresh_status(&mut self) {
         self.branch_name = get_current_branch(&self.repo_path);
+        self.operation = get_operation(&self.repo_path);
         self.status_files = get_status(&self.repo_path);
         let len = self.status_files.len();
         if self.status_selected >= len {
@@ -147,22 +549,194 @@ impl GitScreenState {
         }
     }
 
-    fn refresh_all(&mut self) {
-        self.refresh_status();
-        self.log_entries = get_log(&self.repo_path, 200);
-        self.branches = get_branches(&self.repo_path);
+    /// Start a fetch / pull / push in the background
+    fn start_remote(&mut self, command: RemoteCommand) {
+        if self.remote_op.as_ref().is_some_and(|op| op.is_running()) {
+            self.show_msg("A remote operation is already running");
+            return;
+        }
+        if self.operation.is_some() && matches!(command, RemoteCommand::Pull { .. }) {
+            self.show_msg("Finish or abort the current operation first");
+            return;
+        }
+        // A pull can create merge commits, so it needs an identity
+        self.remote_op = Some(RemoteOp::start(git_commit_cmd(&self.repo_path), command));
+    }
+
+    /// Take in output of the running remote operation; refresh when it finishes
+    pub fn poll_remote_op(&mut self) {
+        let finished = match self.remote_op {
+            Some(ref mut op) => op.poll(),
+            None => false,
+        };
+        if !finished {
+            return;
+        }
+        let (label, result) = match self.remote_op {
+            Some(ref op) => (op.command.label(), op.result.clone().unwrap_or(Ok(()))),
+            None => return,
+        };
+        // A pull may stop on conflicts like a merge or rebase does
+        finish_operation(self, result, &format!("{} completed", label));
+    }
+
+    /// Push the current branch; asks first when setting an upstream or forcing
+    fn request_push(&mut self, force_with_lease: bool) {
+        let branch = match self.branches.iter().find(|b| b.is_current) {
+            Some(b) => b.clone(),
+            None => {
+                self.show_msg("Not on a branch");
+                return;
+            }
+        };
+        let set_upstream = if branch.upstream.is_none() || branch.upstream_gone {
+            match default_remote(&self.repo_path) {
+                Some(remote) => Some((remote, branch.name.clone())),
+                None => {
+                    self.show_msg("No remote configured");
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        let command = RemoteCommand::Push { set_upstream, force_with_lease };
+        if force_with_lease || matches!(command, RemoteCommand::Push { set_upstream: Some(_), .. }) {
+            self.confirm_action = Some(ConfirmAction::Push(command));
+            self.confirm_selected_button = 1;
+        } else {
+            self.start_remote(command);
+        }
+    }
+
+    /// Open the log limited to one repo-relative path, following renames for files
+    pub fn open_file_history(&mut self, path: &str, follow: bool) {
+        self.current_tab = GitTab::Log;
+        self.log_filter = LogFilter {
+            path: Some(path.to_string()),
+            follow,
+            ..Default::default()
+        };
+        self.log_selected = 0;
+        self.reload_log();
+    }
+
+    /// Reload the log with the current filter
+    fn reload_log(&mut self) {
+        match get_log_filtered(&self.repo_path, 200, &self.log_filter) {
+            Ok(entries) => self.log_entries = entries,
+            Err(e) => {
+                self.log_entries.clear();
+                let short_err = e.lines().next().unwrap_or("git log failed").to_string();
+                self.show_msg(&short_err);
+            }
+        }
         if self.log_selected >= self.log_entries.len() {
             self.log_selected = self.log_entries.len().saturating_sub(1);
         }
+    }
+
+    fn refresh_all(&mut self) {
+        self.refresh_status();
+        self.reload_log();
+        self.branches = get_branches(&self.repo_path);
         if self.branch_selected >= self.branches.len() {
             self.branch_selected = self.branches.len().saturating_sub(1);
         }
+        self.stashes = get_stashes(&self.repo_path);
+        if self.stash_selected >= self.stashes.len() {
+            self.stash_selected = self.stashes.len().saturating_sub(1);
+        }
+        self.tags = get_tags(&self.repo_path);
+        if self.tag_selected >= self.tags.len() {
+            self.tag_selected = self.tags.len().saturating_sub(1);
+        }
+        self.worktrees = get_worktrees(&self.repo_path);
+        if self.worktree_selected >= self.worktrees.len() {
+            self.worktree_selected = self.worktrees.len().saturating_sub(1);
+        }
     }
 
     fn show_msg(&mut self, msg: &str) {
         self.message = Some(msg.to_string());
         self.message_timer = 4;
     }
+
+    /// Open the staging view for a file, preferring the `staged` side and
+    /// falling back to the other one. Returns false if the file has no diff.
+    fn open_stage_view(&mut self, file: &str, staged: bool) -> bool {
+        for side in [staged, !staged] {
+            let diff = get_file_diff(&self.repo_path, file, side);
+            if !diff.is_empty() {
+                self.stage_view = Some(StageView::new(file.to_string(), side, &diff));
+                self.log_detail = Some(diff);
+                self.log_detail_scroll = 0;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn close_stage_view(&mut self) {
+        self.stage_view = None;
+        self.log_detail = None;
+        self.log_detail_scroll = 0;
+    }
+
+    /// Show the other side (staged / unstaged) of the open file
+    fn switch_stage_side(&mut self) {
+        let (file, staged) = match self.stage_view {
+            Some(ref v) => (v.file.clone(), v.staged),
+            None => return,
+        };
+        let diff = get_file_diff(&self.repo_path, &file, !staged);
+        if diff.is_empty() {
+            self.show_msg(if staged { "No unstaged changes" } else { "No staged changes" });
+            return;
+        }
+        self.stage_view = Some(StageView::new(file, !staged, &diff));
+        self.log_detail = Some(diff);
+        self.log_detail_scroll = 0;
+    }
+
+    /// Apply a stage action to the selected lines (or current hunk) and reload the view
+    fn apply_stage_action(&mut self, action: StageAction) {
+        let view = match self.stage_view {
+            Some(ref v) => v,
+            None => return,
+        };
+        let lines = view.target_lines();
+        if lines.is_empty() {
+            self.show_msg("Nothing selected");
+            return;
+        }
+        let (file, staged, cursor) = (view.file.clone(), view.staged, view.cursor);
+        let result = apply_lines(&self.repo_path, &view.patch, &lines, staged, action);
+        match result {
+            Ok(()) => {
+                let done = match action {
+                    StageAction::Stage => "Staged",
+                    StageAction::Unstage => "Unstaged",
+                    StageAction::Discard => "Discarded",
+                };
+                self.refresh_status();
+                if self.open_stage_view(&file, staged) {
+                    if let Some(ref mut v) = self.stage_view {
+                        if v.staged == staged {
+                            v.cursor = cursor.min(v.patch.lines.len().saturating_sub(1));
+                        }
+                    }
+                } else {
+                    self.close_stage_view();
+                }
+                self.show_msg(&format!("{} {} line(s)", done, lines.len()));
+            }
+            Err(e) => {
+                let short_err = e.lines().next().unwrap_or("git apply failed").to_string();
+                self.show_msg(&short_err);
+            }
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -298,37 +872,123 @@ fn get_status(path: &Path) -> Vec<GitFileEntry> {
 }
 
 fn get_log(path: &Path, count: usize) -> Vec<GitLogEntry> {
+    get_log_filtered(path, count, &LogFilter::default()).unwrap_or_default()
+}
+
+fn get_log_filtered(path: &Path, count: usize, filter: &LogFilter) -> Result<Vec<GitLogEntry>, String> {
     let count_str = count.to_string();
     let output = git_cmd(path)
         .args([
             "log",
-            "--format=%h|%s|%an|%ar|%D",
+            "--topo-order",
+            "--format=%h%x1f%p%x1f%an%x1f%ar%x1f%D%x1f%s",
             "-n", &count_str,
         ])
-        .output();
+        .args(filter.args())
+        .output()
+        .map_err(|e| e.to_string())?;
 
-    let output = match output {
-        Ok(o) if o.status.success() => o,
-        _ => return Vec::new(),
-    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        // A repository without commits has an empty log
+        if stderr.contains("does not have any commits") {
+            return Ok(Vec::new());
+        }
+        return Err(stderr);
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut entries = Vec::new();
+    let mut entries = parse_log(&String::from_utf8_lossy(&output.stdout));
+    if !filter.is_active() {
+        build_graph(&mut entries);
+    }
+    Ok(entries)
+}
 
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.splitn(5, '|').collect();
-        if parts.len() >= 4 {
-            entries.push(GitLogEntry {
+fn parse_log(stdout: &str) -> Vec<GitLogEntry> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(6, '\x1f').collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            Some(GitLogEntry {
                 hash: parts[0].to_string(),
-                message: parts.get(1).unwrap_or(&"").to_string(),
-                author: parts.get(2).unwrap_or(&"").to_string(),
-                date: parts.get(3).unwrap_or(&"").to_string(),
-                refs: parts.get(4).unwrap_or(&"").to_string(),
-            });
+                parents: parts[1].split_whitespace().map(String::from).collect(),
+                author: parts[2].to_string(),
+                date: parts[3].to_string(),
+                refs: parts[4].to_string(),
+                message: parts[5].to_string(),
+                graph: String::new(),
+            })
+        })
+        .collect()
+}
+
+/// Assign each commit a lane and draw one graph cell per lane:
+/// `*` the commit, `|` a lane passing by, `\` / `/` a lane opened for a merge
+/// parent or joining the commit. Entries must be in topological order.
+fn build_graph(entries: &mut [GitLogEntry]) {
+    // Hash each lane is waiting for
+    let mut lanes: Vec<Option<String>> = Vec::new();
+
+    for entry in entries.iter_mut() {
+        let col = match lanes.iter().position(|l| l.as_deref() == Some(entry.hash.as_str())) {
+            Some(col) => col,
+            None => match lanes.iter().position(|l| l.is_none()) {
+                Some(free) => free,
+                None => {
+                    lanes.push(None);
+                    lanes.len() - 1
+                }
+            },
+        };
+
+        let mut cells: Vec<char> = lanes.iter().map(|l| if l.is_some() { '|' } else { ' ' }).collect();
+        cells[col] = '*';
+
+        // Other lanes waiting for this commit end here
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            if i != col && lane.as_deref() == Some(entry.hash.as_str()) {
+                *lane = None;
+                cells[i] = '/';
+            }
+        }
+
+        let mut parents = entry.parents.iter();
+        lanes[col] = parents.next().cloned();
+        for parent in parents {
+            if lanes.iter().any(|l| l.as_deref() == Some(parent.as_str())) {
+                continue;
+            }
+            let slot = match lanes.iter().position(|l| l.is_none()) {
+                Some(free) => free,
+                None => {
+                    lanes.push(None);
+                    cells.push(' ');
+                    lanes.len() - 1
+                }
+            };
+            lanes[slot] = Some(parent.clone());
+            cells[slot] = if slot > col { '\\' } else { '/' };
+        }
+
+        while lanes.last().is_some_and(|l| l.is_none()) {
+            lanes.pop();
         }
+        entry.graph = cells.into_iter().collect::<String>().trim_end().to_string();
     }
+}
 
-    entries
+/// Path of `name` in `dir` relative to the repository root
+pub fn repo_relative_path(dir: &Path, name: &str) -> Option<String> {
+    let prefix = git_cmd(dir)
+        .args(["rev-parse", "--show-prefix"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())?;
+    Some(format!("{}{}", prefix, name))
 }
 
 fn get_commit_diff(path: &Path, hash: &str) -> String {
@@ -347,7 +1007,8 @@ fn get_commit_diff(path: &Path, hash: &str) -> String {
 
 fn get_file_diff(path: &Path, file: &str, staged: bool) -> String {
     let mut cmd = git_cmd(path);
-    cmd.arg("diff");
+    // Plain patch output regardless of user config, so hunks can be re-applied
+    cmd.args(["diff", "--no-color", "--no-ext-diff", "--src-prefix=a/", "--dst-prefix=b/"]);
     if staged {
         cmd.arg("--cached");
     }
@@ -364,7 +1025,7 @@ fn get_branches(path: &Path) -> Vec<GitBranchEntry> {
     let output = git_cmd(path)
         .args([
             "branch",
-            "--format=%(HEAD)|%(refname:short)|%(objectname:short)|%(refname)",
+            "--format=%(HEAD)|%(refname:short)|%(objectname:short)|%(refname)|%(upstream:short)|%(upstream:track,nobracket)",
         ])
         .output();
 
@@ -377,16 +1038,22 @@ fn get_branches(path: &Path) -> Vec<GitBranchEntry> {
     let mut entries = Vec::new();
 
     for line in stdout.lines() {
-        let parts: Vec<&str> = line.splitn(4, '|').collect();
+        let parts: Vec<&str> = line.splitn(6, '|').collect();
         if parts.len() >= 2 {
             let is_current = parts[0] == "*";
             let name = parts[1].to_string();
             let is_remote = parts.get(3).is_some_and(|r| r.starts_with("refs/remotes/"));
+            let upstream = parts.get(4).filter(|u| !u.is_empty()).map(|u| u.to_string());
+            let (ahead, behind, upstream_gone) = parse_track(parts.get(5).unwrap_or(&""));
 
             entries.push(GitBranchEntry {
                 name,
                 is_current,
                 is_remote,
+                upstream,
+                ahead,
+                behind,
+                upstream_gone,
             });
         }
     }
@@ -394,6 +1061,32 @@ fn get_branches(path: &Path) -> Vec<GitBranchEntry> {
     entries
 }
 
+/// Parse `%(upstream:track,nobracket)`: "ahead 1, behind 2", "behind 3", "gone" or ""
+fn parse_track(track: &str) -> (u32, u32, bool) {
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+    (ahead, behind, track == "gone")
+}
+
+/// Remote to push a branch without upstream to: "origin", or the only / first remote
+fn default_remote(path: &Path) -> Option<String> {
+    let output = git_cmd(path).arg("remote").output().ok().filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let remotes: Vec<&str> = stdout.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    remotes
+        .iter()
+        .find(|r| **r == "origin")
+        .or(remotes.first())
+        .map(|r| r.to_string())
+}
+
 fn stage_all(path: &Path) {
     let _ = git_cmd(path)
         .args(["add", "-A"])
@@ -426,6 +1119,54 @@ fn unstage_file(path: &Path, file: &str) -> Result<(), String> {
     }
 }
 
+/// Feed a patch to `git apply` on stdin
+fn apply_patch(path: &Path, patch: &str, args: &[&str]) -> Result<(), String> {
+    let mut child = git_cmd(path)
+        .arg("apply")
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Stage, unstage or discard the given change lines of a file diff.
+/// `staged` tells which diff the lines come from (index or working tree).
+fn apply_lines(
+    path: &Path,
+    patch: &FilePatch,
+    lines: &BTreeSet<usize>,
+    staged: bool,
+    action: StageAction,
+) -> Result<(), String> {
+    let (reverse, args): (bool, &[&str]) = match (action, staged) {
+        (StageAction::Stage, false) => (false, &["--cached"]),
+        (StageAction::Unstage, true) => (true, &["--cached", "--reverse"]),
+        (StageAction::Discard, false) => (true, &["--reverse"]),
+        (StageAction::Stage, true) => return Err("Already staged".to_string()),
+        (StageAction::Unstage, false) => return Err("Not staged".to_string()),
+        (StageAction::Discard, true) => return Err("Unstage before discarding".to_string()),
+    };
+    if patch.binary {
+        return Err("Binary files can only be staged as a whole".to_string());
+    }
+    let partial = patch.build(lines, reverse).ok_or("Nothing selected")?;
+    apply_patch(path, &partial, args)
+}
+
 fn do_commit(path: &Path, message: &str) -> Result<String, String> {
     let output = git_commit_cmd(path)
         .args(["commit", "-m", message])
@@ -520,29 +1261,476 @@ fn restore_to_commit(path: &Path, hash: &str) -> Result<String, String> {
     }
 }
 
-/// Find which commit hash the current index tree matches.
-/// Always returns a commit hash — falls back to HEAD if no exact match.
-fn get_index_matching_commit(path: &Path) -> Option<String> {
-    // Get current index tree hash
-    let index_tree = git_cmd(path)
-        .args(["write-tree"])
+fn get_stashes(path: &Path) -> Vec<GitStashEntry> {
+    git_cmd(path)
+        .args(["stash", "list", "--format=%gd|%cr|%gs"])
         .output()
         .ok()
         .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())?;
+        .map(|o| parse_stash_list(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
 
-    // Search commits for matching tree
-    let output = git_cmd(path)
-        .args(["log", "--format=%h %T", "-n", "200"])
-        .output()
-        .ok()
-        .filter(|o| o.status.success())?;
+fn parse_stash_list(stdout: &str) -> Vec<GitStashEntry> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(3, '|').collect();
+            if parts.len() < 3 {
+                return None;
+            }
+            Some(GitStashEntry {
+                name: parts[0].to_string(),
+                date: parts[1].to_string(),
+                message: parts[2].to_string(),
+            })
+        })
+        .collect()
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        let mut parts = line.splitn(2, ' ');
-        let hash = parts.next()?;
-        let tree = parts.next()?;
+/// Validate a "stash@{N}" selector to prevent option injection
+fn is_valid_stash_name(name: &str) -> bool {
+    name.strip_prefix("stash@{")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn get_stash_diff(path: &Path, name: &str) -> String {
+    if !is_valid_stash_name(name) {
+        return String::new();
+    }
+    // --include-untracked needs git 2.32+; fall back to tracked changes only
+    for args in [
+        &["stash", "show", "--stat", "--patch", "--include-untracked", name][..],
+        &["stash", "show", "--stat", "--patch", name][..],
+    ] {
+        let output = git_cmd(path).args(args).output();
+        if let Ok(o) = output {
+            if o.status.success() {
+                return String::from_utf8_lossy(&o.stdout).to_string();
+            }
+        }
+    }
+    String::new()
+}
+
+fn stash_push(path: &Path, message: &str, mode: StashMode) -> Result<String, String> {
+    // Stashing creates commits, so it needs an identity like commit does
+    let mut cmd = git_commit_cmd(path);
+    cmd.args(["stash", "push"]);
+    match mode {
+        StashMode::Tracked => {}
+        StashMode::IncludeUntracked => {
+            cmd.arg("--include-untracked");
+        }
+        StashMode::StagedOnly => {
+            cmd.arg("--staged");
+        }
+    }
+    if !message.is_empty() {
+        cmd.args(["-m", message]);
+    }
+    let output = cmd.output().map_err(|e| e.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    // git exits 0 but stashes nothing when the tree is clean
+    if stdout.starts_with("No local changes") {
+        return Err(stdout);
+    }
+    Ok(stdout)
+}
+
+/// Run `git stash <subcommand> [args..] <name>`
+fn stash_cmd(path: &Path, subcommand: &str, args: &[&str], name: &str) -> Result<(), String> {
+    if !is_valid_stash_name(name) {
+        return Err("Invalid stash".to_string());
+    }
+    let output = git_cmd(path)
+        .args(["stash", subcommand])
+        .args(args)
+        .arg(name)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.is_empty() {
+            // Conflicts are reported on stdout
+            Err(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            Err(stderr)
+        }
+    }
+}
+
+fn stash_branch(path: &Path, branch: &str, name: &str) -> Result<(), String> {
+    if branch.contains("..") || branch.contains('~') || branch.starts_with('-') || branch.contains(' ') {
+        return Err("Invalid branch name".to_string());
+    }
+    stash_cmd(path, "branch", &[branch], name)
+}
+
+fn is_valid_branch_name(name: &str) -> bool {
+    !(name.is_empty() || name.contains("..") || name.contains('~') || name.starts_with('-') || name.contains(' '))
+}
+
+/// Unmerged path in `git status --porcelain` (DD, AU, UD, UA, DU, AA, UU)
+fn is_conflicted(entry: &GitFileEntry) -> bool {
+    matches!(
+        (entry.index_status, entry.worktree_status),
+        ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D')
+    )
+}
+
+/// Detect an operation stopped in the middle (usually on conflicts)
+fn get_operation(path: &Path) -> Option<GitOperation> {
+    let git_dir = git_cmd(path)
+        .args(["rev-parse", "--absolute-git-dir"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim().to_string()))?;
+
+    // A conflicting pick during a rebase is part of the rebase, so check it first
+    [
+        ("rebase-merge", GitOperation::Rebase),
+        ("rebase-apply", GitOperation::Rebase),
+        ("MERGE_HEAD", GitOperation::Merge),
+        ("CHERRY_PICK_HEAD", GitOperation::CherryPick),
+        ("REVERT_HEAD", GitOperation::Revert),
+    ]
+    .into_iter()
+    .find(|(marker, _)| git_dir.join(marker).exists())
+    .map(|(_, op)| op)
+}
+
+/// Run an operation that may create commits without ever opening an editor
+fn run_operation(path: &Path, args: &[&str]) -> Result<(), String> {
+    let output = git_commit_cmd(path)
+        .env("GIT_EDITOR", "true")
+        .args(args)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.is_empty() {
+            // Merge conflicts are reported on stdout
+            Err(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            Err(stderr)
+        }
+    }
+}
+
+fn merge_branch(path: &Path, branch: &str) -> Result<(), String> {
+    if !is_valid_branch_name(branch) {
+        return Err("Invalid branch name".to_string());
+    }
+    run_operation(path, &["merge", "--no-edit", branch])
+}
+
+fn rebase_onto(path: &Path, branch: &str) -> Result<(), String> {
+    if !is_valid_branch_name(branch) {
+        return Err("Invalid branch name".to_string());
+    }
+    run_operation(path, &["rebase", branch])
+}
+
+fn cherry_pick_commit(path: &Path, hash: &str) -> Result<(), String> {
+    if !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("Invalid commit hash".to_string());
+    }
+    run_operation(path, &["cherry-pick", hash])
+}
+
+fn revert_commit(path: &Path, hash: &str) -> Result<(), String> {
+    if !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("Invalid commit hash".to_string());
+    }
+    run_operation(path, &["revert", "--no-edit", hash])
+}
+
+/// `git <operation> --continue | --skip | --abort`
+fn operation_step(path: &Path, op: GitOperation, step: &str) -> Result<(), String> {
+    if op == GitOperation::Merge && step == "--skip" {
+        return Err("A merge cannot be skipped".to_string());
+    }
+    run_operation(path, &[op.subcommand(), step])
+}
+
+/// Resolve a conflicted file with one side ("--ours" / "--theirs") and mark it resolved.
+/// When that side deleted the file (delete/modify conflicts), the file is removed.
+fn resolve_with(path: &Path, file: &str, side: &str) -> Result<(), String> {
+    // Index stage 2 holds "ours", stage 3 "theirs"; a missing stage means that side deleted it
+    let stage = if side == "--ours" { "2" } else { "3" };
+    let stages = git_cmd(path)
+        .args(["ls-files", "-u", "-z", "--", file])
+        .output()
+        .map_err(|e| e.to_string())?;
+    let deleted = !String::from_utf8_lossy(&stages.stdout)
+        .split('\0')
+        .filter_map(|entry| entry.split_whitespace().nth(2))
+        .any(|s| s == stage);
+
+    let output = if deleted {
+        git_cmd(path).args(["rm", "-q", "--", file]).output()
+    } else {
+        git_cmd(path).args(["checkout", side, "--", file]).output()
+    }
+    .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    if deleted {
+        return Ok(());
+    }
+    stage_file(path, file)
+}
+
+fn get_tags(path: &Path) -> Vec<GitTagEntry> {
+    git_cmd(path)
+        .args([
+            "for-each-ref",
+            "refs/tags",
+            "--sort=-creatordate",
+            "--format=%(refname:short)%1f%(objecttype)%1f%(objectname:short)%1f%(*objectname:short)%1f%(creatordate:relative)%1f%(contents:subject)",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| parse_tags(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
+
+fn parse_tags(stdout: &str) -> Vec<GitTagEntry> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(6, '\x1f').collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            let annotated = parts[1] == "tag";
+            // Annotated tags point at a tag object; the commit is the peeled object
+            let commit = if annotated && !parts[3].is_empty() { parts[3] } else { parts[2] };
+            Some(GitTagEntry {
+                name: parts[0].to_string(),
+                commit: commit.to_string(),
+                annotated,
+                date: parts[4].to_string(),
+                subject: parts[5].to_string(),
+            })
+        })
+        .collect()
+}
+
+fn is_valid_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Create a tag at `target` (HEAD when None); annotated when `message` is given
+fn create_tag(path: &Path, name: &str, target: Option<&str>, message: Option<&str>) -> Result<(), String> {
+    if !is_valid_branch_name(name) {
+        return Err("Invalid tag name".to_string());
+    }
+    if target.is_some_and(|t| !is_valid_hash(t)) {
+        return Err("Invalid commit hash".to_string());
+    }
+    // Annotated tags record a tagger, so they need an identity like commits
+    let mut cmd = git_commit_cmd(path);
+    cmd.arg("tag");
+    if let Some(message) = message {
+        cmd.args(["-a", "-m", message]);
+    }
+    cmd.arg(name);
+    if let Some(target) = target {
+        cmd.arg(target);
+    }
+    let output = cmd.output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn delete_tag(path: &Path, name: &str) -> Result<(), String> {
+    if !is_valid_branch_name(name) {
+        return Err("Invalid tag name".to_string());
+    }
+    let output = git_cmd(path)
+        .args(["tag", "-d", name])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Check out a tag (detached HEAD)
+fn checkout_tag(path: &Path, name: &str) -> Result<(), String> {
+    if !is_valid_branch_name(name) {
+        return Err("Invalid tag name".to_string());
+    }
+    let output = git_cmd(path)
+        .args(["checkout", "--detach", &format!("refs/tags/{}", name)])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn get_tag_detail(path: &Path, name: &str) -> String {
+    if !is_valid_branch_name(name) {
+        return String::new();
+    }
+    git_cmd(path)
+        .args(["show", "--stat", "--patch", &format!("refs/tags/{}", name)])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+}
+
+fn get_worktrees(path: &Path) -> Vec<GitWorktreeEntry> {
+    let output = match git_cmd(path).args(["worktree", "list", "--porcelain"]).output() {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+    let mut worktrees = parse_worktrees(&String::from_utf8_lossy(&output.stdout));
+
+    // Mark the worktree this screen belongs to
+    if let Some(root) = get_repo_root(path).and_then(|r| r.canonicalize().ok()) {
+        for wt in worktrees.iter_mut() {
+            wt.is_current = wt.path.canonicalize().is_ok_and(|p| p == root);
+        }
+    }
+    worktrees
+}
+
+/// Parse `git worktree list --porcelain`; the first entry is the main worktree
+fn parse_worktrees(stdout: &str) -> Vec<GitWorktreeEntry> {
+    let mut worktrees: Vec<GitWorktreeEntry> = Vec::new();
+    for line in stdout.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key == "worktree" {
+            worktrees.push(GitWorktreeEntry {
+                path: PathBuf::from(value),
+                head: String::new(),
+                branch: None,
+                is_main: worktrees.is_empty(),
+                is_current: false,
+                bare: false,
+                locked: false,
+                prunable: false,
+            });
+            continue;
+        }
+        let wt = match worktrees.last_mut() {
+            Some(wt) => wt,
+            None => continue,
+        };
+        match key {
+            "HEAD" => wt.head = value[..value.len().min(7)].to_string(),
+            "branch" => wt.branch = Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string()),
+            "bare" => wt.bare = true,
+            "locked" => wt.locked = true,
+            "prunable" => wt.prunable = true,
+            _ => {}
+        }
+    }
+    worktrees
+}
+
+/// `git worktree add <path> [<branch>]`; a branch that doesn't exist yet is created
+fn add_worktree(path: &Path, dir: &str, branch: Option<&str>) -> Result<(), String> {
+    if dir.is_empty() || dir.starts_with('-') {
+        return Err("Invalid worktree path".to_string());
+    }
+    let mut cmd = git_cmd(path);
+    cmd.args(["worktree", "add"]);
+    match branch {
+        Some(branch) if !is_valid_branch_name(branch) => return Err("Invalid branch name".to_string()),
+        Some(branch) => {
+            let exists = git_cmd(path)
+                .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)])
+                .output()
+                .is_ok_and(|o| o.status.success());
+            if exists {
+                cmd.args([dir, branch]);
+            } else {
+                cmd.args(["-b", branch, dir]);
+            }
+        }
+        None => {
+            cmd.arg(dir);
+        }
+    }
+    let output = cmd.output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn remove_worktree(path: &Path, worktree: &Path, force: bool) -> Result<(), String> {
+    let mut cmd = git_cmd(path);
+    cmd.args(["worktree", "remove"]);
+    if force {
+        cmd.arg("--force");
+    }
+    let output = cmd.arg(worktree).output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Find which commit hash the current index tree matches.
+/// Always returns a commit hash — falls back to HEAD if no exact match.
+fn get_index_matching_commit(path: &Path) -> Option<String> {
+    // Get current index tree hash
+    let index_tree = git_cmd(path)
+        .args(["write-tree"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())?;
+
+    // Search commits for matching tree
+    let output = git_cmd(path)
+        .args(["log", "--format=%h %T", "-n", "200"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let mut parts = line.splitn(2, ' ');
+        let hash = parts.next()?;
+        let tree = parts.next()?;
         if tree == index_tree {
             return Some(hash.to_string());
         }
@@ -580,7 +1768,16 @@ pub fn draw(
 
     draw_header(frame, state, layout[0], colors);
     draw_tab_bar(frame, state, layout[1], colors);
-    draw_content(frame, state, layout[2], colors);
+    if state.remote_op.is_some() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(7)])
+            .split(layout[2]);
+        draw_content(frame, state, chunks[0], colors);
+        draw_remote_progress(frame, state, chunks[1], colors);
+    } else {
+        draw_content(frame, state, layout[2], colors);
+    }
     draw_footer(frame, state, layout[3], colors);
 
     // Draw input dialog overlay
@@ -602,6 +1799,72 @@ pub fn draw(
     }
 }
 
+/// Output and progress of a fetch / pull / push
+fn draw_remote_progress(
+    frame: &mut Frame,
+    state: &GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    let op = match state.remote_op {
+        Some(ref op) => op,
+        None => return,
+    };
+    let (status, status_color) = match op.result {
+        None => ("running", colors.header_branch),
+        Some(Ok(())) => ("done", colors.file_staged),
+        Some(Err(_)) => ("failed", colors.file_deleted),
+    };
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(colors.border))
+        .title(Line::from(vec![
+            Span::styled(format!(" {} ", op.command.label()), Style::default().fg(colors.tab_active).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("{} ", status), Style::default().fg(status_color)),
+        ]));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    if inner.height == 0 {
+        return;
+    }
+
+    let width = inner.width as usize;
+    let mut lines = Vec::new();
+
+    // Progress bar of the current phase while running; the error once failed
+    let last_row = match (&op.result, &op.progress) {
+        (Some(Err(e)), _) => Some(Line::from(Span::styled(
+            truncate_to_display_width(&format!(" {}", e), width),
+            Style::default().fg(colors.file_deleted).add_modifier(Modifier::BOLD),
+        ))),
+        (None, Some((phase, percent))) => {
+            let label = format!(" {} {:>3}% ", phase, percent);
+            let bar_width = width.saturating_sub(label.width() + 3);
+            let filled = bar_width * *percent as usize / 100;
+            Some(Line::from(vec![
+                Span::styled(label, Style::default().fg(colors.log_message)),
+                Span::styled("[", Style::default().fg(colors.footer_text)),
+                Span::styled("#".repeat(filled), Style::default().fg(colors.file_staged)),
+                Span::styled(" ".repeat(bar_width - filled), Style::default()),
+                Span::styled("]", Style::default().fg(colors.footer_text)),
+            ]))
+        }
+        _ => None,
+    };
+
+    let log_rows = inner.height as usize - usize::from(last_row.is_some());
+    for line in op.lines.iter().skip(op.lines.len().saturating_sub(log_rows)) {
+        lines.push(Line::from(Span::styled(
+            truncate_to_display_width(&format!(" {}", line), width),
+            Style::default().fg(colors.footer_text),
+        )));
+    }
+    if let Some(row) = last_row {
+        lines.push(row);
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
 fn draw_header(
     frame: &mut Frame,
     state: &GitScreenState,
@@ -613,19 +1876,26 @@ fn draw_header(
 
     // " [branch] path" — truncate path to fit remaining width
     let prefix = format!(" [{}] ", state.branch_name);
-    let prefix_w = UnicodeWidthStr::width(prefix.as_str());
+    let operation = state.operation.map(|op| format!("{} in progress ", op.label().to_uppercase()));
+    let prefix_w = UnicodeWidthStr::width(prefix.as_str()) + operation.as_ref().map_or(0, |o| o.width());
     let path_max = max_w.saturating_sub(prefix_w);
     let truncated_path = truncate_to_display_width(&path_str, path_max);
 
-    let spans = vec![
+    let mut spans = vec![
         Span::styled(" [", Style::default().fg(colors.header_path)),
         Span::styled(
             &state.branch_name,
             Style::default().fg(colors.header_branch).add_modifier(Modifier::BOLD),
         ),
         Span::styled("] ", Style::default().fg(colors.header_path)),
-        Span::styled(truncated_path, Style::default().fg(colors.header_path)),
     ];
+    if let Some(operation) = operation {
+        spans.push(Span::styled(
+            operation,
+            Style::default().fg(colors.file_deleted).add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.push(Span::styled(truncated_path, Style::default().fg(colors.header_path)));
     frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
@@ -639,6 +1909,9 @@ fn draw_tab_bar(
         (GitTab::Commit, "1:Commit"),
         (GitTab::Log, "2:Log"),
         (GitTab::Branch, "3:Branch"),
+        (GitTab::Stash, "4:Stash"),
+        (GitTab::Tags, "5:Tags"),
+        (GitTab::Worktrees, "6:Worktrees"),
     ];
 
     let bg_style = Style::default().bg(colors.tab_bar_bg);
@@ -671,11 +1944,16 @@ fn draw_content(
         GitTab::Commit => draw_commit_tab(frame, state, area, colors),
         GitTab::Log => draw_log_tab(frame, state, area, colors),
         GitTab::Branch => draw_branch_tab(frame, state, area, colors),
+        GitTab::Stash => draw_stash_tab(frame, state, area, colors),
+        GitTab::Tags => draw_tags_tab(frame, state, area, colors),
+        GitTab::Worktrees => draw_worktrees_tab(frame, state, area, colors),
     }
 }
 
 fn file_status_style(entry: &GitFileEntry, colors: &super::theme::GitScreenColors) -> Style {
-    if entry.staged {
+    if is_conflicted(entry) {
+        Style::default().fg(colors.file_deleted).add_modifier(Modifier::BOLD)
+    } else if entry.staged {
         Style::default().fg(colors.file_staged)
     } else if entry.index_status == '?' {
         Style::default().fg(colors.file_untracked)
@@ -687,7 +1965,9 @@ fn file_status_style(entry: &GitFileEntry, colors: &super::theme::GitScreenColor
 }
 
 fn file_status_char(entry: &GitFileEntry) -> &str {
-    if entry.staged {
+    if is_conflicted(entry) {
+        "U"
+    } else if entry.staged {
         match entry.index_status {
             'A' => "A",
             'D' => "D",
@@ -711,10 +1991,18 @@ fn draw_commit_tab(
     colors: &super::theme::GitScreenColors,
 ) {
     // If showing a diff detail, render it over the full area
+    if state.stage_view.is_some() {
+        draw_stage_view(frame, state, area, colors);
+        return;
+    }
     if state.log_detail.is_some() {
         draw_diff_detail(frame, state, area, colors, false);
         return;
     }
+    if let Some(op) = state.operation {
+        draw_conflict_view(frame, state, area, colors, op);
+        return;
+    }
 
     // Split: file list (top) + message input (bottom 3 lines)
     let chunks = Layout::default()
@@ -750,7 +2038,12 @@ fn draw_commit_tab(
         for (i, entry) in state.status_files.iter().enumerate().skip(state.commit_scroll).take(visible_height) {
             let is_selected = !state.commit_input_active && i == state.commit_selected;
             let status_char = file_status_char(entry);
-            let prefix = if entry.staged { "+" } else { " " };
+            // "~": staged with further unstaged changes (partially staged)
+            let prefix = match (entry.staged, entry.worktree_status) {
+                (true, ' ') => "+",
+                (true, _) => "~",
+                _ => " ",
+            };
             let text = format!(" {}[{}] {}", prefix, status_char, entry.path);
 
             let style = if is_selected {
@@ -818,20 +2111,97 @@ fn draw_commit_tab(
     }
 }
 
-fn draw_log_tab(
+/// File list of a merge / rebase / cherry-pick / revert stopped on conflicts
+fn draw_conflict_view(
     frame: &mut Frame,
     state: &mut GitScreenState,
     area: Rect,
     colors: &super::theme::GitScreenColors,
+    op: GitOperation,
 ) {
-    if state.log_entries.is_empty() {
-        let msg = Paragraph::new(Line::from(Span::styled(
-            "  No commits",
-            Style::default().fg(colors.footer_text),
-        )));
-        frame.render_widget(msg, area);
-        return;
-    }
+    let title_area = Rect::new(area.x, area.y, area.width, 1.min(area.height));
+    let list_area = Rect::new(area.x, area.y + 1, area.width, area.height.saturating_sub(1));
+    let visible_height = list_area.height as usize;
+
+    let conflicts = state.status_files.iter().filter(|f| is_conflicted(f)).count();
+    let title = if conflicts > 0 {
+        format!(" {} in progress: {} conflicted file(s)", op.label(), conflicts)
+    } else {
+        format!(" {} in progress: all conflicts resolved, press c to continue", op.label())
+    };
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            truncate_to_display_width(&title, area.width as usize),
+            Style::default().fg(colors.header_branch).add_modifier(Modifier::BOLD),
+        )),
+        title_area,
+    );
+
+    if state.commit_selected < state.commit_scroll {
+        state.commit_scroll = state.commit_selected;
+    }
+    if visible_height > 0 && state.commit_selected >= state.commit_scroll + visible_height {
+        state.commit_scroll = state.commit_selected + 1 - visible_height;
+    }
+
+    let mut lines = Vec::new();
+    for (i, entry) in state.status_files.iter().enumerate().skip(state.commit_scroll).take(visible_height) {
+        let text = format!("  [{}] {}", file_status_char(entry), entry.path);
+        let style = if i == state.commit_selected {
+            Style::default().fg(colors.selected_text).bg(colors.selected_bg)
+        } else {
+            file_status_style(entry, colors)
+        };
+        lines.push(Line::from(Span::styled(pad_to_display_width(&text, list_area.width as usize), style)));
+    }
+    frame.render_widget(Paragraph::new(lines), list_area);
+
+    if state.status_files.len() > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(state.status_files.len())
+            .position(state.commit_scroll);
+        let scrollbar_area = Rect::new(list_area.x + list_area.width.saturating_sub(1), list_area.y, 1, list_area.height);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn draw_log_tab(
+    frame: &mut Frame,
+    state: &mut GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    // Active filter summary on the first row
+    let area = if state.log_filter.is_active() || state.log_filter.all_refs {
+        let mut summary = state.log_filter.to_query();
+        if state.log_filter.all_refs {
+            summary = format!("all refs {}", summary);
+        }
+        let text = format!(" Filter: {}  ({} commits)", summary.trim(), state.log_entries.len());
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                truncate_to_display_width(&text, area.width as usize),
+                Style::default().fg(colors.header_branch).add_modifier(Modifier::BOLD),
+            )),
+            Rect::new(area.x, area.y, area.width, 1.min(area.height)),
+        );
+        Rect::new(area.x, area.y + 1, area.width, area.height.saturating_sub(1))
+    } else {
+        area
+    };
+
+    if state.log_entries.is_empty() {
+        let text = if state.log_filter.is_active() { "  No commits match the filter" } else { "  No commits" };
+        let msg = Paragraph::new(Line::from(Span::styled(
+            text,
+            Style::default().fg(colors.footer_text),
+        )));
+        frame.render_widget(msg, area);
+        return;
+    }
 
     // If detail view is open, split view
     if state.log_detail.is_some() {
@@ -871,13 +2241,23 @@ fn draw_log_list(
     // Detect if files have been restored to a different commit
     let restored = get_index_matching_commit(&state.repo_path);
 
+    // Graph column as wide as the widest visible graph
+    let graph_width = state.log_entries.iter()
+        .skip(state.log_scroll)
+        .take(visible_height)
+        .map(|e| e.graph.len())
+        .max()
+        .unwrap_or(0);
+
     for (i, entry) in state.log_entries.iter().enumerate().skip(state.log_scroll).take(visible_height) {
         let is_selected = i == state.log_selected;
         let is_restored = restored.as_deref() == Some(&entry.hash);
         let marker = if is_restored { ">" } else { " " };
+        let graph = if graph_width > 0 { format!("{:width$} ", entry.graph, width = graph_width) } else { String::new() };
+        let refs = if entry.refs.is_empty() { String::new() } else { format!("({}) ", entry.refs) };
 
         if is_selected {
-            let text = format!("{}{} {} ({}, {})", marker, entry.hash, entry.message, entry.author, entry.date);
+            let text = format!("{}{}{} {}{} ({}, {})", marker, graph, entry.hash, refs, entry.message, entry.author, entry.date);
             let display = pad_to_display_width(&text, max_width);
             lines.push(Line::from(Span::styled(
                 display,
@@ -889,10 +2269,12 @@ fn draw_log_list(
             } else {
                 Style::default()
             };
-            let mut spans = vec![
+            let spans = vec![
                 Span::styled(marker, marker_style),
+                Span::styled(graph, Style::default().fg(colors.diff_header)),
                 Span::styled(&entry.hash, Style::default().fg(colors.log_hash)),
                 Span::styled(" ", Style::default()),
+                Span::styled(refs, Style::default().fg(colors.branch_current).add_modifier(Modifier::BOLD)),
                 Span::styled(&entry.message, Style::default().fg(colors.log_message)),
                 Span::styled(" (", Style::default().fg(colors.log_date)),
                 Span::styled(&entry.author, Style::default().fg(colors.log_author)),
@@ -981,6 +2363,81 @@ fn draw_diff_detail(
     }
 }
 
+fn draw_stage_view(
+    frame: &mut Frame,
+    state: &mut GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    let view = match state.stage_view {
+        Some(ref v) => v,
+        None => return,
+    };
+
+    // Title row + diff lines
+    let title_area = Rect::new(area.x, area.y, area.width, 1.min(area.height));
+    let body_area = Rect::new(area.x, area.y + 1, area.width, area.height.saturating_sub(1));
+    let visible_height = body_area.height as usize;
+
+    let side = if view.staged { "Staged" } else { "Unstaged" };
+    let title = format!(
+        " {} changes: {}  ({} line(s) selected)",
+        side,
+        view.file,
+        view.selected.len()
+    );
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            truncate_to_display_width(&title, area.width as usize),
+            Style::default().fg(colors.header_branch).add_modifier(Modifier::BOLD),
+        )),
+        title_area,
+    );
+
+    // Keep the cursor visible
+    if view.cursor < state.log_detail_scroll {
+        state.log_detail_scroll = view.cursor;
+    } else if visible_height > 0 && view.cursor >= state.log_detail_scroll + visible_height {
+        state.log_detail_scroll = view.cursor + 1 - visible_height;
+    }
+
+    let max_width = area.width as usize;
+    let mut lines = Vec::new();
+    for (i, line) in view.patch.lines.iter().enumerate().skip(state.log_detail_scroll).take(visible_height) {
+        let marker = if view.selected.contains(&i) { "*" } else { " " };
+        let text = format!("{}{}", marker, line.text);
+
+        let style = if i == view.cursor {
+            Style::default().fg(colors.selected_text).bg(colors.selected_bg)
+        } else {
+            match line.kind {
+                PatchLineKind::Added => Style::default().fg(colors.diff_add),
+                PatchLineKind::Removed => Style::default().fg(colors.diff_remove),
+                PatchLineKind::Header | PatchLineKind::HunkHeader => {
+                    Style::default().fg(colors.diff_header).add_modifier(Modifier::BOLD)
+                }
+                PatchLineKind::Context | PatchLineKind::NoNewline => Style::default().fg(colors.log_message),
+            }
+        };
+        let style = if view.selected.contains(&i) { style.add_modifier(Modifier::BOLD) } else { style };
+
+        lines.push(Line::from(Span::styled(pad_to_display_width(&text, max_width), style)));
+    }
+    frame.render_widget(Paragraph::new(lines), body_area);
+
+    // Scrollbar
+    let total = view.patch.lines.len();
+    if total > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(total).position(state.log_detail_scroll);
+        let scrollbar_area = Rect::new(body_area.x + body_area.width.saturating_sub(1), body_area.y, 1, body_area.height);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            &mut scrollbar_state,
+        );
+    }
+}
+
 fn draw_branch_tab(
     frame: &mut Frame,
     state: &mut GitScreenState,
@@ -1008,14 +2465,37 @@ fn draw_branch_tab(
     let mut lines = Vec::new();
     let max_width = area.width as usize;
 
+    let name_width = state.branches.iter().map(|b| b.name.width()).max().unwrap_or(0);
+
     for (i, branch) in state.branches.iter().enumerate().skip(state.branch_scroll).take(visible_height) {
         let is_selected = i == state.branch_selected;
         let prefix = if branch.is_current { "* " } else { "  " };
-        let text = format!(" {}{}", prefix, branch.name);
+        let name = format!(" {}{}", prefix, pad_to_display_width(&branch.name, name_width));
 
-        let style = if is_selected {
-            Style::default().fg(colors.selected_text).bg(colors.selected_bg)
-        } else if branch.is_current {
+        // Ahead / behind of the upstream
+        let mut track = String::new();
+        if branch.ahead > 0 {
+            track.push_str(&format!(" \u{2191}{}", branch.ahead));
+        }
+        if branch.behind > 0 {
+            track.push_str(&format!(" \u{2193}{}", branch.behind));
+        }
+        let upstream = match branch.upstream {
+            Some(ref u) if branch.upstream_gone => format!("  {} [gone]", u),
+            Some(ref u) => format!("  {}", u),
+            None => String::new(),
+        };
+
+        if is_selected {
+            let text = format!("{}{}{}", name, track, upstream);
+            lines.push(Line::from(Span::styled(
+                pad_to_display_width(&text, max_width),
+                Style::default().fg(colors.selected_text).bg(colors.selected_bg),
+            )));
+            continue;
+        }
+
+        let style = if branch.is_current {
             Style::default().fg(colors.branch_current).add_modifier(Modifier::BOLD)
         } else if branch.is_remote {
             Style::default().fg(colors.footer_text).add_modifier(Modifier::DIM)
@@ -1023,9 +2503,11 @@ fn draw_branch_tab(
             Style::default().fg(colors.branch_normal)
         };
 
-        let display = pad_to_display_width(&text, max_width);
-
-        lines.push(Line::from(Span::styled(display, style)));
+        lines.push(Line::from(vec![
+            Span::styled(name, style),
+            Span::styled(track, Style::default().fg(colors.file_modified)),
+            Span::styled(upstream, Style::default().fg(colors.footer_text)),
+        ]));
     }
 
     frame.render_widget(Paragraph::new(lines), area);
@@ -1043,302 +2525,1133 @@ fn draw_branch_tab(
     }
 }
 
-fn draw_footer(
+fn draw_stash_tab(
     frame: &mut Frame,
-    state: &GitScreenState,
+    state: &mut GitScreenState,
     area: Rect,
     colors: &super::theme::GitScreenColors,
 ) {
-    // Show message if present
-    if let Some(ref msg) = state.message {
-        let raw = format!(" {} ", msg);
-        let display = truncate_to_display_width(&raw, area.width as usize);
-        let message = Paragraph::new(Span::styled(
-            display,
-            Style::default().fg(colors.footer_text).add_modifier(Modifier::BOLD),
-        ));
-        frame.render_widget(message, area);
+    if state.stashes.is_empty() {
+        let msg = Paragraph::new(Line::from(Span::styled(
+            "  No stashes",
+            Style::default().fg(colors.footer_text),
+        )));
+        frame.render_widget(msg, area);
         return;
     }
 
-    let shortcuts: Vec<(&str, &str)> = match state.current_tab {
-        GitTab::Commit => {
-            if state.commit_input_active {
-                vec![
-                    ("Enter", "commit "),
-                    ("Tab", "files "),
-                    ("Esc", "cancel"),
-                ]
-            } else if state.log_detail.is_some() {
-                vec![
-                    ("\u{2191}\u{2193}", "nav "),
-                    ("PgUp/Dn", "scroll "),
-                    ("Esc", "close"),
-                ]
-            } else {
-                vec![
-                    ("\u{2191}\u{2193}", "nav "),
-                    ("Spc", "stage "),
-                    ("Enter", "diff "),
-                    ("^a", "ll "),
-                    ("Tab", "msg "),
-                    ("a", "mend "),
-                    ("\u{2190}\u{2192}", "tab "),
-                    ("Esc", "back"),
-                ]
-            }
-        }
-        GitTab::Log => {
-            if state.log_detail.is_some() {
-                vec![
-                    ("\u{2191}\u{2193}", "nav "),
-                    ("\u{2190}\u{2192}", "commit "),
-                    ("PgUp/Dn", "scroll "),
-                    ("Esc", "close"),
-                ]
-            } else {
-                vec![
-                    ("\u{2191}\u{2193}", "nav "),
-                    ("Enter", "detail "),
-                    ("r", "estore "),
-                    ("\u{2190}\u{2192}", "tab "),
-                    ("Esc", "back"),
-                ]
-            }
-        }
-        GitTab::Branch => vec![
-            ("\u{2191}\u{2193}", "nav "),
-            ("Enter", "checkout "),
-            ("c", "heckout "),
-            ("n", "ew "),
-            ("x", "del "),
-            ("\u{2190}\u{2192}", "tab "),
-            ("Esc", "back"),
-        ],
-    };
+    // If a stash diff is open, split view
+    if state.log_detail.is_some() {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Percentage(65),
+            ])
+            .split(area);
 
-    let mut spans = Vec::new();
-    for (key, rest) in &shortcuts {
-        spans.push(Span::styled(*key, Style::default().fg(colors.footer_key)));
-        spans.push(Span::styled(":", Style::default().fg(colors.footer_text)));
-        spans.push(Span::styled(*rest, Style::default().fg(colors.footer_text)));
+        draw_stash_list(frame, state, chunks[0], colors);
+        draw_diff_detail(frame, state, chunks[1], colors, true);
+    } else {
+        draw_stash_list(frame, state, area, colors);
     }
-
-    frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
-fn draw_input_dialog(
+fn draw_stash_list(
     frame: &mut Frame,
-    state: &GitScreenState,
+    state: &mut GitScreenState,
     area: Rect,
     colors: &super::theme::GitScreenColors,
 ) {
-    let title = match &state.input_mode {
-        Some(InputMode::BranchCreate) => " New Branch Name ",
-        Some(InputMode::CommitAmend) => " Amend Message ",
-        None => return,
-    };
+    let visible_height = area.height as usize;
 
-    let width = 50u16.min(area.width.saturating_sub(4));
-    let height = 3u16;
-    let x = area.x + (area.width.saturating_sub(width)) / 2;
-    let y = area.y + (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    if state.stash_selected < state.stash_scroll {
+        state.stash_scroll = state.stash_selected;
+    }
+    if state.stash_selected >= state.stash_scroll + visible_height {
+        state.stash_scroll = state.stash_selected - visible_height + 1;
+    }
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(colors.tab_active))
-        .title(title)
-        .style(Style::default().bg(colors.bg));
+    let mut lines = Vec::new();
+    let max_width = area.width as usize;
 
-    let dialog_inner_width = width.saturating_sub(2) as usize; // border 2칸 제외
-    let display_buf = truncate_to_display_width(&state.input_buffer, dialog_inner_width);
-    let input = Paragraph::new(Span::styled(
-        display_buf,
-        Style::default().fg(colors.commit_input_text),
-    ))
-    .block(block);
+    for (i, entry) in state.stashes.iter().enumerate().skip(state.stash_scroll).take(visible_height) {
+        if i == state.stash_selected {
+            let text = format!(" {} {} ({})", entry.name, entry.message, entry.date);
+            lines.push(Line::from(Span::styled(
+                pad_to_display_width(&text, max_width),
+                Style::default().fg(colors.selected_text).bg(colors.selected_bg),
+            )));
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled(" ", Style::default()),
+                Span::styled(&entry.name, Style::default().fg(colors.log_hash)),
+                Span::styled(" ", Style::default()),
+                Span::styled(&entry.message, Style::default().fg(colors.log_message)),
+                Span::styled(" (", Style::default().fg(colors.log_date)),
+                Span::styled(&entry.date, Style::default().fg(colors.log_date)),
+                Span::styled(")", Style::default().fg(colors.log_date)),
+            ]));
+        }
+    }
 
-    frame.render_widget(Clear, dialog_area);
-    frame.render_widget(input, dialog_area);
+    frame.render_widget(Paragraph::new(lines), area);
 
-    // Cursor
-    let cursor_x = dialog_area.x + 1 + UnicodeWidthStr::width(state.input_buffer.as_str()) as u16;
-    let cursor_y = dialog_area.y + 1;
-    if cursor_x < dialog_area.x + dialog_area.width - 1 {
-        frame.set_cursor_position((cursor_x, cursor_y));
+    // Scrollbar
+    if state.stashes.len() > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(state.stashes.len())
+            .position(state.stash_scroll);
+        let scrollbar_area = Rect::new(area.x + area.width.saturating_sub(1), area.y, 1, area.height);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            &mut scrollbar_state,
+        );
     }
 }
 
-fn draw_confirm_dialog(
+fn draw_tags_tab(
     frame: &mut Frame,
-    state: &GitScreenState,
+    state: &mut GitScreenState,
     area: Rect,
-    theme: &super::theme::Theme,
+    colors: &super::theme::GitScreenColors,
 ) {
-    let (msg, title) = match &state.confirm_action {
-        Some(ConfirmAction::BranchDelete(name)) => (format!("Delete branch '{}'?", name), " Delete "),
-        Some(ConfirmAction::RestoreToCommit(hash)) => (format!("Restore files to {}?", hash), " Restore "),
-        None => return,
-    };
-    let cd = &theme.confirm_dialog;
+    if state.tags.is_empty() {
+        let msg = Paragraph::new(Line::from(Span::styled(
+            "  No tags",
+            Style::default().fg(colors.footer_text),
+        )));
+        frame.render_widget(msg, area);
+        return;
+    }
 
-    let width = 50u16.min(area.width.saturating_sub(4));
-    let height = 6u16;
-    let x = area.x + (area.width.saturating_sub(width)) / 2;
-    let y = area.y + (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    // If a tag is open, split view
+    if state.log_detail.is_some() {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Percentage(65),
+            ])
+            .split(area);
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(cd.border))
-        .title(title)
-        .title_style(Style::default().fg(cd.title).add_modifier(Modifier::BOLD))
-        .style(Style::default().bg(cd.bg));
+        draw_tag_list(frame, state, chunks[0], colors);
+        draw_diff_detail(frame, state, chunks[1], colors, true);
+    } else {
+        draw_tag_list(frame, state, area, colors);
+    }
+}
 
-    let inner = block.inner(dialog_area);
-    frame.render_widget(Clear, dialog_area);
-    frame.render_widget(block, dialog_area);
+fn draw_tag_list(
+    frame: &mut Frame,
+    state: &mut GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    let visible_height = area.height as usize;
 
-    // Message
-    let message_area = Rect::new(inner.x + 1, inner.y + 1, inner.width - 2, 1);
-    frame.render_widget(
-        Paragraph::new(msg)
-            .style(Style::default().fg(cd.message_text))
-            .alignment(ratatui::layout::Alignment::Center),
-        message_area,
-    );
+    if state.tag_selected < state.tag_scroll {
+        state.tag_scroll = state.tag_selected;
+    }
+    if state.tag_selected >= state.tag_scroll + visible_height {
+        state.tag_scroll = state.tag_selected - visible_height + 1;
+    }
 
-    // Buttons
-    let selected_style = Style::default()
-        .fg(cd.button_selected_text)
-        .bg(cd.button_selected_bg);
-    let normal_style = Style::default().fg(cd.button_text);
+    let mut lines = Vec::new();
+    let max_width = area.width as usize;
 
-    let yes_style = if state.confirm_selected_button == 0 { selected_style } else { normal_style };
-    let no_style = if state.confirm_selected_button == 1 { selected_style } else { normal_style };
+    for (i, entry) in state.tags.iter().enumerate().skip(state.tag_scroll).take(visible_height) {
+        // Annotated tags are marked with '@'
+        let kind = if entry.annotated { "@" } else { " " };
+        if i == state.tag_selected {
+            let text = format!(" {}{} {} {} ({})", kind, entry.name, entry.commit, entry.subject, entry.date);
+            lines.push(Line::from(Span::styled(
+                pad_to_display_width(&text, max_width),
+                Style::default().fg(colors.selected_text).bg(colors.selected_bg),
+            )));
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {}", kind), Style::default().fg(colors.log_author)),
+                Span::styled(&entry.name, Style::default().fg(colors.branch_current).add_modifier(Modifier::BOLD)),
+                Span::styled(" ", Style::default()),
+                Span::styled(&entry.commit, Style::default().fg(colors.log_hash)),
+                Span::styled(" ", Style::default()),
+                Span::styled(&entry.subject, Style::default().fg(colors.log_message)),
+                Span::styled(" (", Style::default().fg(colors.log_date)),
+                Span::styled(&entry.date, Style::default().fg(colors.log_date)),
+                Span::styled(")", Style::default().fg(colors.log_date)),
+            ]));
+        }
+    }
 
-    let buttons = Line::from(vec![
-        Span::styled("  ", Style::default()),
-        Span::styled(" Yes ", yes_style),
-        Span::styled("    ", Style::default()),
-        Span::styled(" No ", no_style),
-        Span::styled("  ", Style::default()),
-    ]);
-    let button_area = Rect::new(inner.x + 1, inner.y + inner.height - 2, inner.width - 2, 1);
-    frame.render_widget(
-        Paragraph::new(buttons).alignment(ratatui::layout::Alignment::Center),
-        button_area,
-    );
-}
+    frame.render_widget(Paragraph::new(lines), area);
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// 입력 처리
-// ═══════════════════════════════════════════════════════════════════════════════
+    // Scrollbar
+    if state.tags.len() > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(state.tags.len())
+            .position(state.tag_scroll);
+        let scrollbar_area = Rect::new(area.x + area.width.saturating_sub(1), area.y, 1, area.height);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            &mut scrollbar_state,
+        );
+    }
+}
 
-pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
-    if app.git_screen_state.is_none() {
+fn draw_worktrees_tab(
+    frame: &mut Frame,
+    state: &mut GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    if state.worktrees.is_empty() {
+        let msg = Paragraph::new(Line::from(Span::styled(
+            "  No worktrees",
+            Style::default().fg(colors.footer_text),
+        )));
+        frame.render_widget(msg, area);
         return;
     }
 
-    // Check if we need to close - handle before borrowing state
-    {
-        let state = app.git_screen_state.as_ref().unwrap();
-        let should_close = code == KeyCode::Esc
-            && state.confirm_action.is_none()
-            && state.input_mode.is_none()
-            && !state.commit_input_active
-            && state.log_detail.is_none();
+    let visible_height = area.height as usize;
 
-        if should_close {
-            app.current_screen = Screen::FilePanel;
-            app.git_screen_state = None;
-            app.refresh_panels();
-            return;
-        }
+    if state.worktree_selected < state.worktree_scroll {
+        state.worktree_scroll = state.worktree_selected;
+    }
+    if state.worktree_selected >= state.worktree_scroll + visible_height {
+        state.worktree_scroll = state.worktree_selected - visible_height + 1;
     }
 
-    let state = app.git_screen_state.as_mut().unwrap();
+    let mut lines = Vec::new();
+    let max_width = area.width as usize;
 
-    // Handle confirm dialog first
-    if state.confirm_action.is_some() {
-        handle_confirm_input(state, code);
-        return;
-    }
+    for (i, wt) in state.worktrees.iter().enumerate().skip(state.worktree_scroll).take(visible_height) {
+        let marker = if wt.is_current { "* " } else { "  " };
+        let branch = match (&wt.branch, wt.bare) {
+            (_, true) => "(bare)".to_string(),
+            (Some(b), _) => format!("[{}]", b),
+            (None, _) => "(detached)".to_string(),
+        };
+        let mut flags = Vec::new();
+        if wt.is_main {
+            flags.push("main");
+        }
+        if wt.locked {
+            flags.push("locked");
+        }
+        if wt.prunable {
+            flags.push("prunable");
+        }
+        let flags = if flags.is_empty() { String::new() } else { format!(" ({})", flags.join(", ")) };
+        let path = wt.path.display().to_string();
 
-    // Handle input mode
-    if state.input_mode.is_some() {
-        handle_input_mode(state, code);
-        return;
+        if i == state.worktree_selected {
+            let text = format!("{}{} {} {}{}", marker, path, branch, wt.head, flags);
+            lines.push(Line::from(Span::styled(
+                pad_to_display_width(&text, max_width),
+                Style::default().fg(colors.selected_text).bg(colors.selected_bg),
+            )));
+        } else {
+            let path_style = if wt.is_current {
+                Style::default().fg(colors.branch_current).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(colors.branch_normal)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(marker, path_style),
+                Span::styled(path, path_style),
+                Span::styled(" ", Style::default()),
+                Span::styled(branch, Style::default().fg(colors.log_author)),
+                Span::styled(" ", Style::default()),
+                Span::styled(&wt.head, Style::default().fg(colors.log_hash)),
+                Span::styled(flags, Style::default().fg(colors.log_date)),
+            ]));
+        }
     }
 
-    // Handle commit message input
-    if state.commit_input_active && state.current_tab == GitTab::Commit {
-        handle_commit_input(state, code, modifiers);
-        return;
-    }
+    frame.render_widget(Paragraph::new(lines), area);
 
-    // Handle diff detail scrolling in log tab
-    if state.log_detail.is_some() && state.current_tab == GitTab::Log {
-        handle_log_detail_input(state, code);
-        return;
+    // Scrollbar
+    if state.worktrees.len() > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(state.worktrees.len())
+            .position(state.worktree_scroll);
+        let scrollbar_area = Rect::new(area.x + area.width.saturating_sub(1), area.y, 1, area.height);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            &mut scrollbar_state,
+        );
     }
+}
 
-    // Handle diff detail scrolling in commit tab
-    if state.log_detail.is_some() && state.current_tab == GitTab::Commit {
-        handle_status_diff_input(state, code);
+fn draw_footer(
+    frame: &mut Frame,
+    state: &GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    // Show message if present
+    if let Some(ref msg) = state.message {
+        let raw = format!(" {} ", msg);
+        let display = truncate_to_display_width(&raw, area.width as usize);
+        let message = Paragraph::new(Span::styled(
+            display,
+            Style::default().fg(colors.footer_text).add_modifier(Modifier::BOLD),
+        ));
+        frame.render_widget(message, area);
         return;
     }
 
-    // Common keys
-    match code {
-        KeyCode::Char('1') => {
-            state.current_tab = GitTab::Commit;
-            state.refresh_status();
-            return;
+    let shortcuts: Vec<(&str, &str)> = match state.current_tab {
+        GitTab::Commit => {
+            if state.commit_input_active {
+                vec![
+                    ("Enter", "commit "),
+                    ("Tab", "files "),
+                    ("Esc", "cancel"),
+                ]
+            } else if let Some(ref view) = state.stage_view {
+                let mut keys = vec![
+                    ("\u{2191}\u{2193}", "line "),
+                    ("n/p", "hunk "),
+                    ("Spc", "select "),
+                    ("h", "unk "),
+                ];
+                if view.staged {
+                    keys.push(("u", "nstage "));
+                } else {
+                    keys.push(("s", "tage "));
+                    keys.push(("d", "iscard "));
+                }
+                keys.push(("Tab", if view.staged { "unstaged " } else { "staged " }));
+                keys.push(("Esc", "close"));
+                keys
+            } else if state.log_detail.is_some() {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("PgUp/Dn", "scroll "),
+                    ("Esc", "close"),
+                ]
+            } else if let Some(op) = state.operation {
+                let mut keys = vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("Enter", "diff "),
+                    ("Spc", "resolved "),
+                    ("o", "urs "),
+                    ("t", "heirs "),
+                    ("c", "ontinue "),
+                ];
+                if op != GitOperation::Merge {
+                    keys.push(("s", "kip "));
+                }
+                keys.push(("A", "bort "));
+                keys.push(("Esc", "back"));
+                keys
+            } else {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("Spc", "stage "),
+                    ("Enter", "diff "),
+                    ("^a", "ll "),
+                    ("Tab", "msg "),
+                    ("a", "mend "),
+                    ("\u{2190}\u{2192}", "tab "),
+                    ("Esc", "back"),
+                ]
+            }
+        }
+        GitTab::Log => {
+            if state.log_detail.is_some() {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("\u{2190}\u{2192}", "commit "),
+                    ("PgUp/Dn", "scroll "),
+                    ("Esc", "close"),
+                ]
+            } else {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("Enter", "detail "),
+                    ("r", "estore "),
+                    ("c", "herry-pick "),
+                    ("v", "revert "),
+                    ("t/T", "ag(annotated) "),
+                    ("f", "ilter "),
+                    ("F", "clear "),
+                    ("a", "ll refs "),
+                    ("\u{2190}\u{2192}", "tab "),
+                    ("Esc", "back"),
+                ]
+            }
+        }
+        GitTab::Branch => vec![
+            ("\u{2191}\u{2193}", "nav "),
+            ("Enter", "checkout "),
+            ("c", "heckout "),
+            ("n", "ew "),
+            ("m", "erge "),
+            ("r", "ebase "),
+            ("x", "del "),
+            ("f", "etch "),
+            ("p/P", "ull(rebase) "),
+            ("u/U", "push(force) "),
+            ("\u{2190}\u{2192}", "tab "),
+            ("Esc", "back"),
+        ],
+        GitTab::Stash => {
+            if state.log_detail.is_some() {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("\u{2190}\u{2192}", "stash "),
+                    ("PgUp/Dn", "scroll "),
+                    ("Esc", "close"),
+                ]
+            } else {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("Enter", "diff "),
+                    ("n", "ew "),
+                    ("u", "ntracked "),
+                    ("S", "taged "),
+                    ("a", "pply "),
+                    ("p", "op "),
+                    ("x", "drop "),
+                    ("b", "ranch "),
+                    ("\u{2190}\u{2192}", "tab "),
+                    ("Esc", "back"),
+                ]
+            }
+        }
+        GitTab::Tags => {
+            if state.log_detail.is_some() {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("\u{2190}\u{2192}", "tag "),
+                    ("PgUp/Dn", "scroll "),
+                    ("Esc", "close"),
+                ]
+            } else {
+                vec![
+                    ("\u{2191}\u{2193}", "nav "),
+                    ("Enter", "show "),
+                    ("n", "ew "),
+                    ("a", "nnotated "),
+                    ("c", "heckout "),
+                    ("x", "del "),
+                    ("\u{2190}\u{2192}", "tab "),
+                    ("Esc", "back"),
+                ]
+            }
+        }
+        GitTab::Worktrees => vec![
+            ("\u{2191}\u{2193}", "nav "),
+            ("Enter", "open in panel "),
+            ("n", "ew "),
+            ("x", "remove "),
+            ("X", "force remove "),
+            ("\u{2190}\u{2192}", "tab "),
+            ("Esc", "back"),
+        ],
+    };
+
+    let mut spans = Vec::new();
+    for (key, rest) in &shortcuts {
+        spans.push(Span::styled(*key, Style::default().fg(colors.footer_key)));
+        spans.push(Span::styled(":", Style::default().fg(colors.footer_text)));
+        spans.push(Span::styled(*rest, Style::default().fg(colors.footer_text)));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_input_dialog(
+    frame: &mut Frame,
+    state: &GitScreenState,
+    area: Rect,
+    colors: &super::theme::GitScreenColors,
+) {
+    let title = match &state.input_mode {
+        Some(InputMode::BranchCreate) => " New Branch Name ",
+        Some(InputMode::CommitAmend) => " Amend Message ",
+        Some(InputMode::StashPush(StashMode::Tracked)) => " Stash Message ",
+        Some(InputMode::StashPush(StashMode::IncludeUntracked)) => " Stash Message (with untracked) ",
+        Some(InputMode::StashPush(StashMode::StagedOnly)) => " Stash Message (staged only) ",
+        Some(InputMode::StashBranch(_)) => " Branch From Stash ",
+        Some(InputMode::LogFilter) => " Filter (author: msg: since: until: path: file:) ",
+        Some(InputMode::TagCreate { annotated: false, .. }) => " New Tag Name ",
+        Some(InputMode::TagCreate { annotated: true, .. }) => " New Annotated Tag Name ",
+        Some(InputMode::TagMessage { .. }) => " Tag Message ",
+        Some(InputMode::WorktreeAdd) => " New Worktree (path [branch]) ",
+        None => return,
+    };
+
+    let width = if matches!(state.input_mode, Some(InputMode::LogFilter) | Some(InputMode::WorktreeAdd)) { 70u16 } else { 50u16 };
+    let width = width.min(area.width.saturating_sub(4));
+    let height = 3u16;
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width, height);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.tab_active))
+        .title(title)
+        .style(Style::default().bg(colors.bg));
+
+    let dialog_inner_width = width.saturating_sub(2) as usize; // border 2칸 제외
+    let display_buf = truncate_to_display_width(&state.input_buffer, dialog_inner_width);
+    let input = Paragraph::new(Span::styled(
+        display_buf,
+        Style::default().fg(colors.commit_input_text),
+    ))
+    .block(block);
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_widget(input, dialog_area);
+
+    // Cursor
+    let cursor_x = dialog_area.x + 1 + UnicodeWidthStr::width(state.input_buffer.as_str()) as u16;
+    let cursor_y = dialog_area.y + 1;
+    if cursor_x < dialog_area.x + dialog_area.width - 1 {
+        frame.set_cursor_position((cursor_x, cursor_y));
+    }
+}
+
+fn draw_confirm_dialog(
+    frame: &mut Frame,
+    state: &GitScreenState,
+    area: Rect,
+    theme: &super::theme::Theme,
+) {
+    let (msg, title) = match &state.confirm_action {
+        Some(ConfirmAction::BranchDelete(name)) => (format!("Delete branch '{}'?", name), " Delete "),
+        Some(ConfirmAction::RestoreToCommit(hash)) => (format!("Restore files to {}?", hash), " Restore "),
+        Some(ConfirmAction::DiscardLines(count)) => (format!("Discard {} changed line(s)?", count), " Discard "),
+        Some(ConfirmAction::StashApply(name)) => (format!("Apply {}?", name), " Apply Stash "),
+        Some(ConfirmAction::StashPop(name)) => (format!("Apply and drop {}?", name), " Pop Stash "),
+        Some(ConfirmAction::StashDrop(name)) => (format!("Drop {}?", name), " Drop Stash "),
+        Some(ConfirmAction::Merge(branch)) => (format!("Merge '{}' into '{}'?", branch, state.branch_name), " Merge "),
+        Some(ConfirmAction::Rebase(branch)) => (format!("Rebase '{}' onto '{}'?", state.branch_name, branch), " Rebase "),
+        Some(ConfirmAction::CherryPick(hash)) => (format!("Cherry-pick {} onto '{}'?", hash, state.branch_name), " Cherry-pick "),
+        Some(ConfirmAction::Revert(hash)) => (format!("Revert {}?", hash), " Revert "),
+        Some(ConfirmAction::Push(RemoteCommand::Push { set_upstream, force_with_lease })) => {
+            let target = match set_upstream {
+                Some((remote, branch)) => format!("{}/{}", remote, branch),
+                None => "its upstream".to_string(),
+            };
+            if *force_with_lease {
+                (format!("Force push '{}' to {} (with lease)?", state.branch_name, target), " Force Push ")
+            } else {
+                (format!("Push '{}' and set upstream to {}?", state.branch_name, target), " Push ")
+            }
+        }
+        Some(ConfirmAction::Push(_)) => return,
+        Some(ConfirmAction::AbortOperation(op)) => (format!("Abort the {} and discard its changes?", op.label().to_lowercase()), " Abort "),
+        Some(ConfirmAction::TagDelete(name)) => (format!("Delete tag '{}'?", name), " Delete Tag "),
+        Some(ConfirmAction::WorktreeRemove { path, force }) => {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if *force {
+                (format!("Remove worktree '{}' and discard its changes?", name), " Force Remove ")
+            } else {
+                (format!("Remove worktree '{}'?", name), " Remove Worktree ")
+            }
+        }
+        None => return,
+    };
+    let cd = &theme.confirm_dialog;
+
+    let width = 50u16.min(area.width.saturating_sub(4));
+    let height = 6u16;
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width, height);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(cd.border))
+        .title(title)
+        .title_style(Style::default().fg(cd.title).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(cd.bg));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(Clear, dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    // Message
+    let message_area = Rect::new(inner.x + 1, inner.y + 1, inner.width - 2, 1);
+    frame.render_widget(
+        Paragraph::new(msg)
+            .style(Style::default().fg(cd.message_text))
+            .alignment(ratatui::layout::Alignment::Center),
+        message_area,
+    );
+
+    // Buttons
+    let selected_style = Style::default()
+        .fg(cd.button_selected_text)
+        .bg(cd.button_selected_bg);
+    let normal_style = Style::default().fg(cd.button_text);
+
+    let yes_style = if state.confirm_selected_button == 0 { selected_style } else { normal_style };
+    let no_style = if state.confirm_selected_button == 1 { selected_style } else { normal_style };
+
+    let buttons = Line::from(vec![
+        Span::styled("  ", Style::default()),
+        Span::styled(" Yes ", yes_style),
+        Span::styled("    ", Style::default()),
+        Span::styled(" No ", no_style),
+        Span::styled("  ", Style::default()),
+    ]);
+    let button_area = Rect::new(inner.x + 1, inner.y + inner.height - 2, inner.width - 2, 1);
+    frame.render_widget(
+        Paragraph::new(buttons).alignment(ratatui::layout::Alignment::Center),
+        button_area,
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// 입력 처리
+// ═══════════════════════════════════════════════════════════════════════════════
+
+pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    if app.git_screen_state.is_none() {
+        return;
+    }
+
+    // A remote operation keeps the screen until it ends; Esc then hides its output
+    if let Some(ref mut state) = app.git_screen_state {
+        if let Some(ref op) = state.remote_op {
+            if op.is_running() {
+                state.show_msg(&format!("{} in progress...", op.command.label()));
+                return;
+            }
+            if code == KeyCode::Esc && state.confirm_action.is_none() && state.input_mode.is_none() {
+                state.remote_op = None;
+                return;
+            }
+        }
+    }
+
+    // Check if we need to close - handle before borrowing state
+    {
+        let state = app.git_screen_state.as_ref().unwrap();
+        let should_close = code == KeyCode::Esc
+            && state.confirm_action.is_none()
+            && state.input_mode.is_none()
+            && !state.commit_input_active
+            && state.log_detail.is_none();
+
+        if should_close {
+            // Return to the file viewer when opened from its blame mode
+            app.current_screen = match app.previous_screen.take() {
+                Some(Screen::FileViewer) => Screen::FileViewer,
+                _ => Screen::FilePanel,
+            };
+            app.git_screen_state = None;
+            app.refresh_panels();
+            return;
+        }
+    }
+
+    let state = app.git_screen_state.as_mut().unwrap();
+
+    // Handle confirm dialog first
+    if state.confirm_action.is_some() {
+        handle_confirm_input(state, code);
+        return;
+    }
+
+    // Handle input mode
+    if state.input_mode.is_some() {
+        handle_input_mode(state, code);
+        return;
+    }
+
+    // Handle commit message input
+    if state.commit_input_active && state.current_tab == GitTab::Commit {
+        handle_commit_input(state, code, modifiers);
+        return;
+    }
+
+    // Handle diff detail scrolling in log tab
+    if state.log_detail.is_some() && state.current_tab == GitTab::Log {
+        handle_log_detail_input(state, code);
+        return;
+    }
+
+    // Handle stash diff scrolling in stash tab
+    if state.log_detail.is_some() && state.current_tab == GitTab::Stash {
+        handle_stash_detail_input(state, code);
+        return;
+    }
+
+    // Handle tag detail scrolling in tags tab
+    if state.log_detail.is_some() && state.current_tab == GitTab::Tags {
+        handle_tag_detail_input(state, code);
+        return;
+    }
+
+    // Handle diff detail scrolling in commit tab
+    if state.log_detail.is_some() && state.current_tab == GitTab::Commit {
+        handle_status_diff_input(state, code);
+        return;
+    }
+
+    // Common keys
+    match code {
+        KeyCode::Char('1') => {
+            state.current_tab = GitTab::Commit;
+            state.refresh_status();
+            return;
+        }
+        KeyCode::Char('2') => {
+            state.current_tab = GitTab::Log;
+            return;
+        }
+        KeyCode::Char('3') => {
+            state.current_tab = GitTab::Branch;
+            return;
+        }
+        KeyCode::Char('4') => {
+            state.current_tab = GitTab::Stash;
+            return;
+        }
+        KeyCode::Char('5') => {
+            state.current_tab = GitTab::Tags;
+            return;
+        }
+        KeyCode::Char('6') => {
+            state.current_tab = GitTab::Worktrees;
+            return;
+        }
+        KeyCode::Left => {
+            state.current_tab = match state.current_tab {
+                GitTab::Commit => GitTab::Worktrees,
+                GitTab::Log => GitTab::Commit,
+                GitTab::Branch => GitTab::Log,
+                GitTab::Stash => GitTab::Branch,
+                GitTab::Tags => GitTab::Stash,
+                GitTab::Worktrees => GitTab::Tags,
+            };
+            if matches!(state.current_tab, GitTab::Commit) {
+                state.refresh_status();
+            }
+            return;
+        }
+        KeyCode::Right => {
+            state.current_tab = match state.current_tab {
+                GitTab::Commit => GitTab::Log,
+                GitTab::Log => GitTab::Branch,
+                GitTab::Branch => GitTab::Stash,
+                GitTab::Stash => GitTab::Tags,
+                GitTab::Tags => GitTab::Worktrees,
+                GitTab::Worktrees => GitTab::Commit,
+            };
+            if matches!(state.current_tab, GitTab::Commit) {
+                state.refresh_status();
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    // Tab-specific keys
+    match state.current_tab {
+        GitTab::Commit => match state.operation {
+            Some(op) => handle_conflict_input(state, code, op),
+            None => handle_commit_tab_input(state, code, modifiers),
+        },
+        GitTab::Log => handle_log_input(state, code),
+        GitTab::Branch => handle_branch_input(state, code),
+        GitTab::Stash => handle_stash_input(state, code),
+        GitTab::Tags => handle_tags_input(state, code),
+        GitTab::Worktrees => handle_worktrees_input(state, code),
+    }
+
+    // Opening a worktree leaves the git screen for a new panel
+    if let Some(path) = app.git_screen_state.as_mut().and_then(|s| s.open_worktree.take()) {
+        app.current_screen = Screen::FilePanel;
+        app.previous_screen = None;
+        app.git_screen_state = None;
+        app.open_worktree_panel(&path);
+    }
+}
+
+fn handle_status_diff_input(state: &mut GitScreenState, code: KeyCode) {
+    if state.stage_view.is_some() {
+        handle_stage_view_input(state, code);
+        return;
+    }
+    match code {
+        KeyCode::Esc => {
+            state.log_detail = None;
+            state.log_detail_scroll = 0;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.log_detail_scroll = state.log_detail_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.log_detail_scroll += 1;
+        }
+        KeyCode::PageUp => {
+            state.log_detail_scroll = state.log_detail_scroll.saturating_sub(20);
+        }
+        KeyCode::PageDown => {
+            state.log_detail_scroll += 20;
+        }
+        KeyCode::Home => {
+            state.log_detail_scroll = 0;
+        }
+        KeyCode::End => {
+            if let Some(ref detail) = state.log_detail {
+                let total = detail.lines().count();
+                state.log_detail_scroll = total;
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_stage_view_input(state: &mut GitScreenState, code: KeyCode) {
+    let view = match state.stage_view.as_mut() {
+        Some(v) => v,
+        None => return,
+    };
+    match code {
+        KeyCode::Esc => {
+            state.close_stage_view();
+        }
+        KeyCode::Up | KeyCode::Char('k') => view.move_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => view.move_cursor(1),
+        KeyCode::PageUp => view.move_cursor(-20),
+        KeyCode::PageDown => view.move_cursor(20),
+        KeyCode::Home => view.cursor = 0,
+        KeyCode::End => view.move_cursor(isize::MAX / 2),
+        KeyCode::Char('n') => view.jump_hunk(true),
+        KeyCode::Char('p') => view.jump_hunk(false),
+        KeyCode::Char(' ') => {
+            view.toggle_line();
+            view.move_cursor(1);
+        }
+        KeyCode::Char('h') => view.toggle_hunk(),
+        KeyCode::Tab => state.switch_stage_side(),
+        KeyCode::Char('s') => state.apply_stage_action(StageAction::Stage),
+        KeyCode::Char('u') => state.apply_stage_action(StageAction::Unstage),
+        KeyCode::Char('d') => {
+            if view.staged {
+                state.show_msg("Unstage before discarding");
+                return;
+            }
+            let count = view.target_lines().len();
+            if count > 0 {
+                state.confirm_action = Some(ConfirmAction::DiscardLines(count));
+                state.confirm_selected_button = 1;
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_commit_tab_input(state: &mut GitScreenState, code: KeyCode, modifiers: KeyModifiers) {
+    let len = state.status_files.len();
+    if len == 0 && code != KeyCode::Tab {
+        return;
+    }
+
+    // Ctrl+A: stage/unstage all
+    if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('a') {
+        let has_unstaged = state.status_files.iter().any(|f| !f.staged);
+        for entry in &state.status_files {
+            if has_unstaged && !entry.staged {
+                let _ = stage_file(&state.repo_path, &entry.path);
+            } else if !has_unstaged && entry.staged {
+                let _ = unstage_file(&state.repo_path, &entry.path);
+            }
+        }
+        state.refresh_status();
+        return;
+    }
+
+    match code {
+        KeyCode::Up => {
+            state.commit_selected = state.commit_selected.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            if len > 0 && state.commit_selected + 1 < len {
+                state.commit_selected += 1;
+            }
+        }
+        KeyCode::Home => {
+            state.commit_selected = 0;
+        }
+        KeyCode::End => {
+            if len > 0 {
+                state.commit_selected = len.saturating_sub(1);
+            }
+        }
+        KeyCode::PageUp => {
+            state.commit_selected = state.commit_selected.saturating_sub(10);
+        }
+        KeyCode::PageDown => {
+            if len > 0 {
+                state.commit_selected = (state.commit_selected + 10).min(len.saturating_sub(1));
+            }
+        }
+        KeyCode::Char(' ') => {
+            // Stage/unstage toggle
+            if let Some(entry) = state.status_files.get(state.commit_selected) {
+                let path = entry.path.clone();
+                if entry.staged {
+                    let _ = unstage_file(&state.repo_path, &path);
+                } else {
+                    let _ = stage_file(&state.repo_path, &path);
+                }
+                state.refresh_status();
+            }
+        }
+        KeyCode::Enter => {
+            // Show diff for selected file (with hunk / line staging)
+            if let Some(entry) = state.status_files.get(state.commit_selected) {
+                let (path, staged) = (entry.path.clone(), entry.staged);
+                if !state.open_stage_view(&path, staged) {
+                    let full_path = state.repo_path.join(&path);
+                    if let Ok(content) = std::fs::read_to_string(&full_path) {
+                        state.log_detail = Some(content);
+                    } else {
+                        state.show_msg("Cannot display file");
+                    }
+                    state.log_detail_scroll = 0;
+                }
+            }
+        }
+        KeyCode::Tab => {
+            state.commit_input_active = true;
+        }
+        KeyCode::Char('a') => {
+            // Amend mode
+            state.input_mode = Some(InputMode::CommitAmend);
+            // Pre-fill with last commit message
+            let output = git_cmd(&state.repo_path)
+                .args(["log", "-1", "--format=%s"])
+                .output();
+            if let Ok(o) = output {
+                if o.status.success() {
+                    state.input_buffer = String::from_utf8_lossy(&o.stdout).trim().to_string();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_conflict_input(state: &mut GitScreenState, code: KeyCode, op: GitOperation) {
+    let len = state.status_files.len();
+    let selected = state.status_files.get(state.commit_selected).cloned();
+
+    match code {
+        KeyCode::Up => {
+            state.commit_selected = state.commit_selected.saturating_sub(1);
+        }
+        KeyCode::Down if state.commit_selected + 1 < len => {
+            state.commit_selected += 1;
+        }
+        KeyCode::Home => {
+            state.commit_selected = 0;
+        }
+        KeyCode::End => {
+            state.commit_selected = len.saturating_sub(1);
+        }
+        KeyCode::PageUp => {
+            state.commit_selected = state.commit_selected.saturating_sub(10);
+        }
+        KeyCode::PageDown => {
+            state.commit_selected = (state.commit_selected + 10).min(len.saturating_sub(1));
+        }
+        KeyCode::Enter => {
+            // Combined diff with conflict markers, or the plain diff once resolved
+            if let Some(entry) = selected {
+                let mut diff = get_file_diff(&state.repo_path, &entry.path, false);
+                if diff.is_empty() {
+                    diff = get_file_diff(&state.repo_path, &entry.path, true);
+                }
+                if diff.is_empty() {
+                    state.show_msg("No diff");
+                } else {
+                    state.log_detail = Some(diff);
+                    state.log_detail_scroll = 0;
+                }
+            }
+        }
+        KeyCode::Char(' ') => {
+            // Mark the file (edited by hand) as resolved
+            if let Some(entry) = selected {
+                match stage_file(&state.repo_path, &entry.path) {
+                    Ok(()) => state.show_msg(&format!("Resolved {}", entry.path)),
+                    Err(e) => state.show_msg(e.lines().next().unwrap_or("Failed")),
+                }
+                state.refresh_status();
+            }
+        }
+        KeyCode::Char('o') | KeyCode::Char('t') => {
+            if let Some(entry) = selected {
+                let side = if code == KeyCode::Char('o') { "--ours" } else { "--theirs" };
+                match resolve_with(&state.repo_path, &entry.path, side) {
+                    Ok(()) => state.show_msg(&format!("Resolved {} with {}", entry.path, &side[2..])),
+                    Err(e) => state.show_msg(e.lines().next().unwrap_or("Failed")),
+                }
+                state.refresh_status();
+            }
+        }
+        KeyCode::Char('c') => {
+            if state.status_files.iter().any(is_conflicted) {
+                state.show_msg("Resolve all conflicts first");
+                return;
+            }
+            let result = operation_step(&state.repo_path, op, "--continue");
+            finish_operation(state, result, &format!("{} completed", op.label()));
+        }
+        KeyCode::Char('s') => {
+            let result = operation_step(&state.repo_path, op, "--skip");
+            finish_operation(state, result, &format!("{} completed", op.label()));
+        }
+        KeyCode::Char('A') => {
+            state.confirm_action = Some(ConfirmAction::AbortOperation(op));
+            state.confirm_selected_button = 1;
+        }
+        _ => {}
+    }
+}
+
+fn handle_commit_input(state: &mut GitScreenState, code: KeyCode, _modifiers: KeyModifiers) {
+    match code {
+        KeyCode::Tab | KeyCode::Esc => {
+            state.commit_input_active = false;
+        }
+        KeyCode::Enter => {
+            if state.commit_message.trim().is_empty() {
+                state.show_msg("Commit message is empty");
+                return;
+            }
+            match do_commit(&state.repo_path, &state.commit_message) {
+                Ok(msg) => {
+                    let short_msg = msg.lines().next().unwrap_or("Committed").to_string();
+                    state.show_msg(&short_msg);
+                    state.commit_message.clear();
+                    state.commit_input_active = false;
+                    state.refresh_all();
+                }
+                Err(e) => {
+                    let short_err = e.lines().next().unwrap_or("Commit failed").to_string();
+                    state.show_msg(&short_err);
+                }
+            }
+        }
+        KeyCode::Char(c) => {
+            state.commit_message.push(c);
+        }
+        KeyCode::Backspace => {
+            state.commit_message.pop();
+        }
+        _ => {}
+    }
+}
+
+fn handle_log_input(state: &mut GitScreenState, code: KeyCode) {
+    // Filter keys also work when nothing matches
+    match code {
+        KeyCode::Char('f') => {
+            state.input_mode = Some(InputMode::LogFilter);
+            state.input_buffer = state.log_filter.to_query();
+            return;
+        }
+        KeyCode::Char('F') => {
+            state.log_filter = LogFilter {
+                all_refs: state.log_filter.all_refs,
+                ..Default::default()
+            };
+            state.log_selected = 0;
+            state.reload_log();
+            return;
+        }
+        KeyCode::Char('a') => {
+            state.log_filter.all_refs = !state.log_filter.all_refs;
+            state.reload_log();
+            state.show_msg(if state.log_filter.all_refs { "Showing all refs" } else { "Showing current branch" });
+            return;
+        }
+        _ => {}
+    }
+
+    let len = state.log_entries.len();
+    if len == 0 {
+        return;
+    }
+
+    match code {
+        KeyCode::Up => {
+            state.log_selected = state.log_selected.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            if state.log_selected + 1 < len {
+                state.log_selected += 1;
+            }
+        }
+        KeyCode::Home => {
+            state.log_selected = 0;
+        }
+        KeyCode::End => {
+            state.log_selected = len.saturating_sub(1);
+        }
+        KeyCode::PageUp => {
+            state.log_selected = state.log_selected.saturating_sub(10);
         }
-        KeyCode::Char('2') => {
-            state.current_tab = GitTab::Log;
-            return;
+        KeyCode::PageDown => {
+            state.log_selected = (state.log_selected + 10).min(len.saturating_sub(1));
         }
-        KeyCode::Char('3') => {
-            state.current_tab = GitTab::Branch;
-            return;
+        KeyCode::Enter => {
+            // Show commit diff
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                let diff = get_commit_diff(&state.repo_path, &entry.hash);
+                state.log_detail = Some(diff);
+                state.log_detail_scroll = 0;
+            }
         }
-        KeyCode::Left => {
-            state.current_tab = match state.current_tab {
-                GitTab::Commit => GitTab::Branch,
-                GitTab::Log => GitTab::Commit,
-                GitTab::Branch => GitTab::Log,
-            };
-            if matches!(state.current_tab, GitTab::Commit) {
-                state.refresh_status();
+        KeyCode::Char('r') => {
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                state.confirm_action = Some(ConfirmAction::RestoreToCommit(entry.hash.clone()));
+                state.confirm_selected_button = 1;
             }
-            return;
         }
-        KeyCode::Right => {
-            state.current_tab = match state.current_tab {
-                GitTab::Commit => GitTab::Log,
-                GitTab::Log => GitTab::Branch,
-                GitTab::Branch => GitTab::Commit,
-            };
-            if matches!(state.current_tab, GitTab::Commit) {
-                state.refresh_status();
+        KeyCode::Char('c') | KeyCode::Char('v') => {
+            if state.operation.is_some() {
+                state.show_msg("Finish or abort the current operation first");
+                return;
+            }
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                let hash = entry.hash.clone();
+                state.confirm_action = Some(if code == KeyCode::Char('c') {
+                    ConfirmAction::CherryPick(hash)
+                } else {
+                    ConfirmAction::Revert(hash)
+                });
+                state.confirm_selected_button = 1;
+            }
+        }
+        KeyCode::Char('t') | KeyCode::Char('T') => {
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                state.input_mode = Some(InputMode::TagCreate {
+                    target: Some(entry.hash.clone()),
+                    annotated: code == KeyCode::Char('T'),
+                });
+                state.input_buffer.clear();
             }
-            return;
         }
         _ => {}
     }
-
-    // Tab-specific keys
-    match state.current_tab {
-        GitTab::Commit => handle_commit_tab_input(state, code, modifiers),
-        GitTab::Log => handle_log_input(state, code),
-        GitTab::Branch => handle_branch_input(state, code),
-    }
 }
 
-fn handle_status_diff_input(state: &mut GitScreenState, code: KeyCode) {
+fn handle_log_detail_input(state: &mut GitScreenState, code: KeyCode) {
     match code {
         KeyCode::Esc => {
             state.log_detail = None;
@@ -1365,184 +3678,297 @@ fn handle_status_diff_input(state: &mut GitScreenState, code: KeyCode) {
                 state.log_detail_scroll = total;
             }
         }
+        // Allow selecting different commits while detail is open
+        KeyCode::Left => {
+            state.log_selected = state.log_selected.saturating_sub(1);
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                let diff = get_commit_diff(&state.repo_path, &entry.hash);
+                state.log_detail = Some(diff);
+                state.log_detail_scroll = 0;
+            }
+        }
+        KeyCode::Right => {
+            if state.log_selected + 1 < state.log_entries.len() {
+                state.log_selected += 1;
+            }
+            if let Some(entry) = state.log_entries.get(state.log_selected) {
+                let diff = get_commit_diff(&state.repo_path, &entry.hash);
+                state.log_detail = Some(diff);
+                state.log_detail_scroll = 0;
+            }
+        }
         _ => {}
     }
 }
 
-fn handle_commit_tab_input(state: &mut GitScreenState, code: KeyCode, modifiers: KeyModifiers) {
-    let len = state.status_files.len();
-    if len == 0 && code != KeyCode::Tab {
-        return;
-    }
-
-    // Ctrl+A: stage/unstage all
-    if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('a') {
-        let has_unstaged = state.status_files.iter().any(|f| !f.staged);
-        for entry in &state.status_files {
-            if has_unstaged && !entry.staged {
-                let _ = stage_file(&state.repo_path, &entry.path);
-            } else if !has_unstaged && entry.staged {
-                let _ = unstage_file(&state.repo_path, &entry.path);
-            }
-        }
-        state.refresh_status();
+fn handle_branch_input(state: &mut GitScreenState, code: KeyCode) {
+    let len = state.branches.len();
+    if len == 0 && !matches!(code, KeyCode::Char('n') | KeyCode::Char('f')) {
         return;
     }
 
     match code {
         KeyCode::Up => {
-            state.commit_selected = state.commit_selected.saturating_sub(1);
+            state.branch_selected = state.branch_selected.saturating_sub(1);
         }
         KeyCode::Down => {
-            if len > 0 && state.commit_selected + 1 < len {
-                state.commit_selected += 1;
+            if len > 0 && state.branch_selected + 1 < len {
+                state.branch_selected += 1;
             }
         }
         KeyCode::Home => {
-            state.commit_selected = 0;
+            state.branch_selected = 0;
         }
         KeyCode::End => {
             if len > 0 {
-                state.commit_selected = len.saturating_sub(1);
+                state.branch_selected = len.saturating_sub(1);
             }
         }
         KeyCode::PageUp => {
-            state.commit_selected = state.commit_selected.saturating_sub(10);
+            state.branch_selected = state.branch_selected.saturating_sub(10);
         }
         KeyCode::PageDown => {
             if len > 0 {
-                state.commit_selected = (state.commit_selected + 10).min(len.saturating_sub(1));
+                state.branch_selected = (state.branch_selected + 10).min(len.saturating_sub(1));
             }
         }
-        KeyCode::Char(' ') => {
-            // Stage/unstage toggle
-            if let Some(entry) = state.status_files.get(state.commit_selected) {
-                let path = entry.path.clone();
-                if entry.staged {
-                    let _ = unstage_file(&state.repo_path, &path);
-                } else {
-                    let _ = stage_file(&state.repo_path, &path);
+        KeyCode::Enter | KeyCode::Char('c') => {
+            // Checkout branch
+            if let Some(branch) = state.branches.get(state.branch_selected) {
+                if branch.is_current {
+                    state.show_msg("Already on this branch");
+                    return;
                 }
-                state.refresh_status();
-            }
-        }
-        KeyCode::Enter => {
-            // Show diff for selected file
-            if let Some(entry) = state.status_files.get(state.commit_selected) {
-                let diff = get_file_diff(&state.repo_path, &entry.path, entry.staged);
-                if diff.is_empty() {
-                    let full_path = state.repo_path.join(&entry.path);
-                    if let Ok(content) = std::fs::read_to_string(&full_path) {
-                        state.log_detail = Some(content);
-                    } else {
-                        state.show_msg("Cannot display file");
+                let name = branch.name.clone();
+                match checkout_branch(&state.repo_path, &name) {
+                    Ok(()) => {
+                        state.show_msg(&format!("Switched to {}", name));
+                        state.refresh_all();
+                    }
+                    Err(e) => {
+                        let short_err = e.lines().next().unwrap_or("Checkout failed").to_string();
+                        state.show_msg(&short_err);
                     }
-                } else {
-                    state.log_detail = Some(diff);
                 }
-                state.log_detail_scroll = 0;
             }
         }
-        KeyCode::Tab => {
-            state.commit_input_active = true;
+        KeyCode::Char('n') => {
+            // New branch
+            state.input_mode = Some(InputMode::BranchCreate);
+            state.input_buffer.clear();
         }
-        KeyCode::Char('a') => {
-            // Amend mode
-            state.input_mode = Some(InputMode::CommitAmend);
-            // Pre-fill with last commit message
-            let output = git_cmd(&state.repo_path)
-                .args(["log", "-1", "--format=%s"])
-                .output();
-            if let Ok(o) = output {
-                if o.status.success() {
-                    state.input_buffer = String::from_utf8_lossy(&o.stdout).trim().to_string();
+        KeyCode::Char('f') => state.start_remote(RemoteCommand::Fetch),
+        KeyCode::Char('p') => state.start_remote(RemoteCommand::Pull { rebase: false }),
+        KeyCode::Char('P') => state.start_remote(RemoteCommand::Pull { rebase: true }),
+        KeyCode::Char('u') => state.request_push(false),
+        KeyCode::Char('U') => state.request_push(true),
+        KeyCode::Char('m') | KeyCode::Char('r') => {
+            // Merge the selected branch into / rebase the current branch onto it
+            if state.operation.is_some() {
+                state.show_msg("Finish or abort the current operation first");
+                return;
+            }
+            if let Some(branch) = state.branches.get(state.branch_selected) {
+                if branch.is_current {
+                    state.show_msg("Select another branch");
+                    return;
+                }
+                let name = branch.name.clone();
+                state.confirm_action = Some(if code == KeyCode::Char('m') {
+                    ConfirmAction::Merge(name)
+                } else {
+                    ConfirmAction::Rebase(name)
+                });
+                state.confirm_selected_button = 1;
+            }
+        }
+        KeyCode::Char('x') => {
+            // Delete branch (force)
+            if let Some(branch) = state.branches.get(state.branch_selected) {
+                if branch.is_current {
+                    state.show_msg("Cannot delete current branch");
+                    return;
+                }
+                if branch.is_remote {
+                    state.show_msg("Cannot delete remote branch");
+                    return;
                 }
+                state.confirm_action = Some(ConfirmAction::BranchDelete(branch.name.clone()));
+                state.confirm_selected_button = 1;
             }
         }
         _ => {}
     }
 }
 
-fn handle_commit_input(state: &mut GitScreenState, code: KeyCode, _modifiers: KeyModifiers) {
+fn handle_stash_input(state: &mut GitScreenState, code: KeyCode) {
+    let len = state.stashes.len();
+
+    // Creating a stash works with an empty list too
+    let new_mode = match code {
+        KeyCode::Char('n') => Some(StashMode::Tracked),
+        KeyCode::Char('u') => Some(StashMode::IncludeUntracked),
+        KeyCode::Char('S') => Some(StashMode::StagedOnly),
+        _ => None,
+    };
+    if let Some(mode) = new_mode {
+        state.input_mode = Some(InputMode::StashPush(mode));
+        state.input_buffer.clear();
+        return;
+    }
+
+    let name = match state.stashes.get(state.stash_selected) {
+        Some(entry) => entry.name.clone(),
+        None => return,
+    };
+
     match code {
-        KeyCode::Tab | KeyCode::Esc => {
-            state.commit_input_active = false;
+        KeyCode::Up => {
+            state.stash_selected = state.stash_selected.saturating_sub(1);
+        }
+        KeyCode::Down if state.stash_selected + 1 < len => {
+            state.stash_selected += 1;
+        }
+        KeyCode::Home => {
+            state.stash_selected = 0;
+        }
+        KeyCode::End => {
+            state.stash_selected = len.saturating_sub(1);
+        }
+        KeyCode::PageUp => {
+            state.stash_selected = state.stash_selected.saturating_sub(10);
+        }
+        KeyCode::PageDown => {
+            state.stash_selected = (state.stash_selected + 10).min(len.saturating_sub(1));
         }
         KeyCode::Enter => {
-            if state.commit_message.trim().is_empty() {
-                state.show_msg("Commit message is empty");
-                return;
-            }
-            match do_commit(&state.repo_path, &state.commit_message) {
-                Ok(msg) => {
-                    let short_msg = msg.lines().next().unwrap_or("Committed").to_string();
-                    state.show_msg(&short_msg);
-                    state.commit_message.clear();
-                    state.commit_input_active = false;
-                    state.refresh_all();
-                }
-                Err(e) => {
-                    let short_err = e.lines().next().unwrap_or("Commit failed").to_string();
-                    state.show_msg(&short_err);
-                }
-            }
+            state.log_detail = Some(get_stash_diff(&state.repo_path, &name));
+            state.log_detail_scroll = 0;
         }
-        KeyCode::Char(c) => {
-            state.commit_message.push(c);
+        KeyCode::Char('a') => {
+            state.confirm_action = Some(ConfirmAction::StashApply(name));
+            state.confirm_selected_button = 1;
+        }
+        KeyCode::Char('p') => {
+            state.confirm_action = Some(ConfirmAction::StashPop(name));
+            state.confirm_selected_button = 1;
+        }
+        KeyCode::Char('x') => {
+            state.confirm_action = Some(ConfirmAction::StashDrop(name));
+            state.confirm_selected_button = 1;
+        }
+        KeyCode::Char('b') => {
+            state.input_mode = Some(InputMode::StashBranch(name));
+            state.input_buffer.clear();
+        }
+        _ => {}
+    }
+}
+
+fn handle_stash_detail_input(state: &mut GitScreenState, code: KeyCode) {
+    match code {
+        KeyCode::Esc => {
+            state.log_detail = None;
+            state.log_detail_scroll = 0;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.log_detail_scroll = state.log_detail_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.log_detail_scroll += 1;
+        }
+        KeyCode::PageUp => {
+            state.log_detail_scroll = state.log_detail_scroll.saturating_sub(20);
+        }
+        KeyCode::PageDown => {
+            state.log_detail_scroll += 20;
+        }
+        KeyCode::Home => {
+            state.log_detail_scroll = 0;
+        }
+        KeyCode::End => {
+            if let Some(ref detail) = state.log_detail {
+                state.log_detail_scroll = detail.lines().count();
+            }
         }
-        KeyCode::Backspace => {
-            state.commit_message.pop();
+        // Allow selecting different stashes while the diff is open
+        KeyCode::Left | KeyCode::Right => {
+            if code == KeyCode::Left {
+                state.stash_selected = state.stash_selected.saturating_sub(1);
+            } else if state.stash_selected + 1 < state.stashes.len() {
+                state.stash_selected += 1;
+            }
+            if let Some(entry) = state.stashes.get(state.stash_selected) {
+                state.log_detail = Some(get_stash_diff(&state.repo_path, &entry.name));
+                state.log_detail_scroll = 0;
+            }
         }
         _ => {}
     }
 }
 
-fn handle_log_input(state: &mut GitScreenState, code: KeyCode) {
-    let len = state.log_entries.len();
-    if len == 0 {
+fn handle_tags_input(state: &mut GitScreenState, code: KeyCode) {
+    let len = state.tags.len();
+
+    // Creating a tag (at HEAD) works with an empty list too
+    if let KeyCode::Char('n') | KeyCode::Char('a') = code {
+        state.input_mode = Some(InputMode::TagCreate {
+            target: None,
+            annotated: code == KeyCode::Char('a'),
+        });
+        state.input_buffer.clear();
         return;
     }
 
+    let name = match state.tags.get(state.tag_selected) {
+        Some(entry) => entry.name.clone(),
+        None => return,
+    };
+
     match code {
         KeyCode::Up => {
-            state.log_selected = state.log_selected.saturating_sub(1);
+            state.tag_selected = state.tag_selected.saturating_sub(1);
         }
-        KeyCode::Down => {
-            if state.log_selected + 1 < len {
-                state.log_selected += 1;
-            }
+        KeyCode::Down if state.tag_selected + 1 < len => {
+            state.tag_selected += 1;
         }
         KeyCode::Home => {
-            state.log_selected = 0;
+            state.tag_selected = 0;
         }
         KeyCode::End => {
-            state.log_selected = len.saturating_sub(1);
+            state.tag_selected = len.saturating_sub(1);
         }
         KeyCode::PageUp => {
-            state.log_selected = state.log_selected.saturating_sub(10);
+            state.tag_selected = state.tag_selected.saturating_sub(10);
         }
         KeyCode::PageDown => {
-            state.log_selected = (state.log_selected + 10).min(len.saturating_sub(1));
+            state.tag_selected = (state.tag_selected + 10).min(len.saturating_sub(1));
         }
         KeyCode::Enter => {
-            // Show commit diff
-            if let Some(entry) = state.log_entries.get(state.log_selected) {
-                let diff = get_commit_diff(&state.repo_path, &entry.hash);
-                state.log_detail = Some(diff);
-                state.log_detail_scroll = 0;
-            }
+            state.log_detail = Some(get_tag_detail(&state.repo_path, &name));
+            state.log_detail_scroll = 0;
         }
-        KeyCode::Char('r') => {
-            if let Some(entry) = state.log_entries.get(state.log_selected) {
-                state.confirm_action = Some(ConfirmAction::RestoreToCommit(entry.hash.clone()));
-                state.confirm_selected_button = 1;
+        KeyCode::Char('c') => {
+            match checkout_tag(&state.repo_path, &name) {
+                Ok(()) => {
+                    state.show_msg(&format!("Checked out {} (detached HEAD)", name));
+                    state.refresh_all();
+                }
+                Err(e) => {
+                    let short_err = e.lines().next().unwrap_or("Checkout failed").to_string();
+                    state.show_msg(&short_err);
+                }
             }
         }
+        KeyCode::Char('x') => {
+            state.confirm_action = Some(ConfirmAction::TagDelete(name));
+            state.confirm_selected_button = 1;
+        }
         _ => {}
     }
 }
 
-fn handle_log_detail_input(state: &mut GitScreenState, code: KeyCode) {
+fn handle_tag_detail_input(state: &mut GitScreenState, code: KeyCode) {
     match code {
         KeyCode::Esc => {
             state.log_detail = None;
@@ -1565,26 +3991,18 @@ fn handle_log_detail_input(state: &mut GitScreenState, code: KeyCode) {
         }
         KeyCode::End => {
             if let Some(ref detail) = state.log_detail {
-                let total = detail.lines().count();
-                state.log_detail_scroll = total;
-            }
-        }
-        // Allow selecting different commits while detail is open
-        KeyCode::Left => {
-            state.log_selected = state.log_selected.saturating_sub(1);
-            if let Some(entry) = state.log_entries.get(state.log_selected) {
-                let diff = get_commit_diff(&state.repo_path, &entry.hash);
-                state.log_detail = Some(diff);
-                state.log_detail_scroll = 0;
+                state.log_detail_scroll = detail.lines().count();
             }
         }
-        KeyCode::Right => {
-            if state.log_selected + 1 < state.log_entries.len() {
-                state.log_selected += 1;
+        // Allow selecting different tags while one is open
+        KeyCode::Left | KeyCode::Right => {
+            if code == KeyCode::Left {
+                state.tag_selected = state.tag_selected.saturating_sub(1);
+            } else if state.tag_selected + 1 < state.tags.len() {
+                state.tag_selected += 1;
             }
-            if let Some(entry) = state.log_entries.get(state.log_selected) {
-                let diff = get_commit_diff(&state.repo_path, &entry.hash);
-                state.log_detail = Some(diff);
+            if let Some(entry) = state.tags.get(state.tag_selected) {
+                state.log_detail = Some(get_tag_detail(&state.repo_path, &entry.name));
                 state.log_detail_scroll = 0;
             }
         }
@@ -1592,78 +4010,108 @@ fn handle_log_detail_input(state: &mut GitScreenState, code: KeyCode) {
     }
 }
 
-fn handle_branch_input(state: &mut GitScreenState, code: KeyCode) {
-    let len = state.branches.len();
-    if len == 0 && code != KeyCode::Char('n') {
+fn handle_worktrees_input(state: &mut GitScreenState, code: KeyCode) {
+    let len = state.worktrees.len();
+
+    if code == KeyCode::Char('n') {
+        state.input_mode = Some(InputMode::WorktreeAdd);
+        state.input_buffer.clear();
         return;
     }
 
+    let wt = match state.worktrees.get(state.worktree_selected) {
+        Some(wt) => wt.clone(),
+        None => return,
+    };
+
     match code {
         KeyCode::Up => {
-            state.branch_selected = state.branch_selected.saturating_sub(1);
+            state.worktree_selected = state.worktree_selected.saturating_sub(1);
         }
-        KeyCode::Down => {
-            if len > 0 && state.branch_selected + 1 < len {
-                state.branch_selected += 1;
-            }
+        KeyCode::Down if state.worktree_selected + 1 < len => {
+            state.worktree_selected += 1;
         }
         KeyCode::Home => {
-            state.branch_selected = 0;
+            state.worktree_selected = 0;
         }
         KeyCode::End => {
-            if len > 0 {
-                state.branch_selected = len.saturating_sub(1);
-            }
-        }
-        KeyCode::PageUp => {
-            state.branch_selected = state.branch_selected.saturating_sub(10);
+            state.worktree_selected = len.saturating_sub(1);
         }
-        KeyCode::PageDown => {
-            if len > 0 {
-                state.branch_selected = (state.branch_selected + 10).min(len.saturating_sub(1));
+        KeyCode::Enter => {
+            if wt.bare || !wt.path.is_dir() {
+                state.show_msg("Worktree directory is not available");
+            } else {
+                state.open_worktree = Some(wt.path);
             }
         }
-        KeyCode::Enter | KeyCode::Char('c') => {
-            // Checkout branch
-            if let Some(branch) = state.branches.get(state.branch_selected) {
-                if branch.is_current {
-                    state.show_msg("Already on this branch");
-                    return;
-                }
-                let name = branch.name.clone();
-                match checkout_branch(&state.repo_path, &name) {
-                    Ok(()) => {
-                        state.show_msg(&format!("Switched to {}", name));
-                        state.refresh_all();
-                    }
-                    Err(e) => {
-                        let short_err = e.lines().next().unwrap_or("Checkout failed").to_string();
-                        state.show_msg(&short_err);
-                    }
-                }
+        KeyCode::Char('x') | KeyCode::Char('X') => {
+            if wt.is_main {
+                state.show_msg("The main worktree cannot be removed");
+            } else if wt.is_current {
+                state.show_msg("Cannot remove the worktree this screen was opened in");
+            } else {
+                state.confirm_action = Some(ConfirmAction::WorktreeRemove {
+                    path: wt.path,
+                    force: code == KeyCode::Char('X'),
+                });
+                state.confirm_selected_button = 1;
             }
         }
-        KeyCode::Char('n') => {
-            // New branch
-            state.input_mode = Some(InputMode::BranchCreate);
-            state.input_buffer.clear();
+        _ => {}
+    }
+}
+
+/// Show the outcome of a stash operation and reload everything it may have touched
+fn finish_stash_op(state: &mut GitScreenState, result: Result<(), String>, done: &str) {
+    match result {
+        Ok(()) => {
+            state.show_msg(done);
         }
-        KeyCode::Char('x') => {
-            // Delete branch (force)
-            if let Some(branch) = state.branches.get(state.branch_selected) {
-                if branch.is_current {
-                    state.show_msg("Cannot delete current branch");
-                    return;
-                }
-                if branch.is_remote {
-                    state.show_msg("Cannot delete remote branch");
-                    return;
-                }
-                state.confirm_action = Some(ConfirmAction::BranchDelete(branch.name.clone()));
-                state.confirm_selected_button = 1;
+        Err(e) => {
+            let short_err = e.lines().next().unwrap_or("Stash failed").to_string();
+            state.show_msg(&short_err);
+        }
+    }
+    // Even a failed apply can leave conflicted files behind
+    state.refresh_all();
+}
+
+fn finish_tag_create(state: &mut GitScreenState, result: Result<(), String>, name: &str) {
+    match result {
+        Ok(()) => {
+            state.show_msg(&format!("Created tag {}", name));
+            state.refresh_all();
+            state.tag_selected = state.tags.iter().position(|t| t.name == name).unwrap_or(0);
+        }
+        Err(e) => {
+            let short_err = e.lines().next().unwrap_or("Tag failed").to_string();
+            state.show_msg(&short_err);
+        }
+    }
+}
+
+/// Show the outcome of a merge / rebase / cherry-pick / revert step. When it
+/// stopped on conflicts, switch to the commit tab which then lists them.
+fn finish_operation(state: &mut GitScreenState, result: Result<(), String>, done: &str) {
+    state.refresh_all();
+    match (result, state.operation) {
+        (Ok(()), None) => state.show_msg(done),
+        (_, Some(op)) => {
+            let conflicts = state.status_files.iter().filter(|f| is_conflicted(f)).count();
+            state.current_tab = GitTab::Commit;
+            state.log_detail = None;
+            state.stage_view = None;
+            state.commit_selected = state.status_files.iter().position(is_conflicted).unwrap_or(0);
+            if conflicts > 0 {
+                state.show_msg(&format!("{} stopped: {} conflicted file(s)", op.label(), conflicts));
+            } else {
+                state.show_msg(&format!("{} stopped: resolve, then c to continue", op.label()));
             }
         }
-        _ => {}
+        (Err(e), None) => {
+            let short_err = e.lines().next().unwrap_or("Operation failed").to_string();
+            state.show_msg(&short_err);
+        }
     }
 }
 
@@ -1675,7 +4123,10 @@ fn handle_input_mode(state: &mut GitScreenState, code: KeyCode) {
         }
         KeyCode::Enter => {
             let input = state.input_buffer.clone();
-            if input.trim().is_empty() {
+            // A stash message is optional; git picks a default one.
+            // An empty log filter clears the filter.
+            let optional = matches!(state.input_mode, Some(InputMode::StashPush(_)) | Some(InputMode::LogFilter));
+            if input.trim().is_empty() && !optional {
                 state.input_mode = None;
                 state.input_buffer.clear();
                 return;
@@ -1707,6 +4158,68 @@ fn handle_input_mode(state: &mut GitScreenState, code: KeyCode) {
                         }
                     }
                 }
+                Some(InputMode::StashPush(mode)) => {
+                    match stash_push(&state.repo_path, input.trim(), mode) {
+                        Ok(msg) => {
+                            let short_msg = msg.lines().next().unwrap_or("Stashed").to_string();
+                            state.show_msg(&short_msg);
+                            state.stash_selected = 0;
+                            state.refresh_all();
+                        }
+                        Err(e) => {
+                            let short_err = e.lines().next().unwrap_or("Stash failed").to_string();
+                            state.show_msg(&short_err);
+                        }
+                    }
+                }
+                Some(InputMode::StashBranch(name)) => {
+                    let result = stash_branch(&state.repo_path, input.trim(), &name);
+                    finish_stash_op(state, result, &format!("Created branch {} from {}", input.trim(), name));
+                }
+                Some(InputMode::LogFilter) => match LogFilter::parse(&input) {
+                    Ok(filter) => {
+                        state.log_filter = LogFilter {
+                            all_refs: state.log_filter.all_refs,
+                            ..filter
+                        };
+                        state.log_selected = 0;
+                        state.reload_log();
+                    }
+                    Err(e) => state.show_msg(&e),
+                },
+                Some(InputMode::TagCreate { target, annotated: true }) => {
+                    // The message is asked for next
+                    state.input_mode = Some(InputMode::TagMessage {
+                        name: input.trim().to_string(),
+                        target,
+                    });
+                }
+                Some(InputMode::TagCreate { target, annotated: false }) => {
+                    let result = create_tag(&state.repo_path, input.trim(), target.as_deref(), None);
+                    finish_tag_create(state, result, input.trim());
+                }
+                Some(InputMode::TagMessage { name, target }) => {
+                    let result = create_tag(&state.repo_path, &name, target.as_deref(), Some(input.trim()));
+                    finish_tag_create(state, result, &name);
+                }
+                Some(InputMode::WorktreeAdd) => {
+                    // A relative path is taken relative to the screen's directory
+                    let result = split_query(input.trim()).and_then(|parts| match parts.as_slice() {
+                        [dir] => add_worktree(&state.repo_path, dir, None).map(|_| dir.clone()),
+                        [dir, branch] => add_worktree(&state.repo_path, dir, Some(branch)).map(|_| dir.clone()),
+                        _ => Err("Expected: <path> [<branch>]".to_string()),
+                    });
+                    match result {
+                        Ok(dir) => {
+                            state.show_msg(&format!("Added worktree {}", dir));
+                            state.refresh_all();
+                        }
+                        Err(e) => {
+                            let short_err = e.lines().next().unwrap_or("Failed").to_string();
+                            state.show_msg(&short_err);
+                        }
+                    }
+                }
                 None => {}
             }
             state.input_buffer.clear();
@@ -1748,6 +4261,68 @@ fn execute_confirm_action(state: &mut GitScreenState) {
                     }
                 }
             }
+            ConfirmAction::DiscardLines(_) => {
+                state.apply_stage_action(StageAction::Discard);
+            }
+            ConfirmAction::StashApply(name) => {
+                let result = stash_cmd(&state.repo_path, "apply", &[], &name);
+                finish_stash_op(state, result, &format!("Applied {}", name));
+            }
+            ConfirmAction::StashPop(name) => {
+                let result = stash_cmd(&state.repo_path, "pop", &[], &name);
+                finish_stash_op(state, result, &format!("Popped {}", name));
+            }
+            ConfirmAction::StashDrop(name) => {
+                let result = stash_cmd(&state.repo_path, "drop", &[], &name);
+                finish_stash_op(state, result, &format!("Dropped {}", name));
+            }
+            ConfirmAction::Merge(branch) => {
+                let result = merge_branch(&state.repo_path, &branch);
+                finish_operation(state, result, &format!("Merged {}", branch));
+            }
+            ConfirmAction::Rebase(branch) => {
+                let result = rebase_onto(&state.repo_path, &branch);
+                finish_operation(state, result, &format!("Rebased onto {}", branch));
+            }
+            ConfirmAction::CherryPick(hash) => {
+                let result = cherry_pick_commit(&state.repo_path, &hash);
+                finish_operation(state, result, &format!("Cherry-picked {}", hash));
+            }
+            ConfirmAction::Revert(hash) => {
+                let result = revert_commit(&state.repo_path, &hash);
+                finish_operation(state, result, &format!("Reverted {}", hash));
+            }
+            ConfirmAction::Push(command) => {
+                state.start_remote(command);
+            }
+            ConfirmAction::AbortOperation(op) => {
+                let result = operation_step(&state.repo_path, op, "--abort");
+                finish_operation(state, result, &format!("{} aborted", op.label()));
+            }
+            ConfirmAction::TagDelete(name) => {
+                match delete_tag(&state.repo_path, &name) {
+                    Ok(()) => {
+                        state.show_msg(&format!("Deleted tag {}", name));
+                        state.refresh_all();
+                    }
+                    Err(e) => {
+                        let short_err = e.lines().next().unwrap_or("Delete failed").to_string();
+                        state.show_msg(&short_err);
+                    }
+                }
+            }
+            ConfirmAction::WorktreeRemove { path, force } => {
+                match remove_worktree(&state.repo_path, &path, force) {
+                    Ok(()) => {
+                        state.show_msg(&format!("Removed worktree {}", path.display()));
+                        state.refresh_all();
+                    }
+                    Err(e) => {
+                        let short_err = e.lines().next().unwrap_or("Remove failed").to_string();
+                        state.show_msg(&short_err);
+                    }
+                }
+            }
         }
     }
 }
@@ -1781,3 +4356,557 @@ pub fn handle_paste(state: &mut GitScreenState, text: &str) {
         state.input_buffer.push_str(text);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run_git(dir: &Path, args: &[&str]) -> String {
+        let output = git_cmd(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    /// Repo with one committed file and two separate unstaged hunks
+    fn setup_repo() -> tempfile::TempDir {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        run_git(dir, &["init", "-q"]);
+        let original: String = (1..=20).map(|i| format!("line{}\n", i)).collect();
+        fs::write(dir.join("f.txt"), &original).unwrap();
+        run_git(dir, &["add", "f.txt"]);
+        run_git(dir, &["commit", "-q", "-m", "init"]);
+        let modified = original.replace("line2\n", "TWO\n").replace("line18\n", "EIGHTEEN\n");
+        fs::write(dir.join("f.txt"), modified).unwrap();
+        tmp
+    }
+
+    fn first_hunk_lines(patch: &FilePatch) -> BTreeSet<usize> {
+        patch.hunk_changes(0).into_iter().collect()
+    }
+
+    #[test]
+    fn test_stage_and_unstage_single_hunk() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+
+        let patch = FilePatch::parse(&get_file_diff(dir, "f.txt", false));
+        assert_eq!(patch.hunks.len(), 2);
+        apply_lines(dir, &patch, &first_hunk_lines(&patch), false, StageAction::Stage).unwrap();
+
+        let cached = get_file_diff(dir, "f.txt", true);
+        assert!(cached.contains("+TWO"));
+        assert!(!cached.contains("+EIGHTEEN"));
+        let unstaged = get_file_diff(dir, "f.txt", false);
+        assert!(unstaged.contains("+EIGHTEEN"));
+        assert!(!unstaged.contains("+TWO"));
+
+        let staged_patch = FilePatch::parse(&cached);
+        let all: BTreeSet<usize> = staged_patch.all_changes().into_iter().collect();
+        apply_lines(dir, &staged_patch, &all, true, StageAction::Unstage).unwrap();
+        assert!(get_file_diff(dir, "f.txt", true).is_empty());
+    }
+
+    #[test]
+    fn test_stage_single_line() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+
+        let patch = FilePatch::parse(&get_file_diff(dir, "f.txt", false));
+        // Only the added line of the first hunk; its removal stays unstaged
+        let added: BTreeSet<usize> = patch
+            .hunk_changes(0)
+            .into_iter()
+            .filter(|&i| patch.lines[i].kind == PatchLineKind::Added)
+            .collect();
+        apply_lines(dir, &patch, &added, false, StageAction::Stage).unwrap();
+
+        let cached = get_file_diff(dir, "f.txt", true);
+        assert!(cached.contains("+TWO"));
+        assert!(!cached.contains("-line2"));
+    }
+
+    #[test]
+    fn test_discard_hunk() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+
+        let patch = FilePatch::parse(&get_file_diff(dir, "f.txt", false));
+        apply_lines(dir, &patch, &first_hunk_lines(&patch), false, StageAction::Discard).unwrap();
+
+        let content = fs::read_to_string(dir.join("f.txt")).unwrap();
+        assert!(content.contains("line2\n"));
+        assert!(content.contains("EIGHTEEN\n"));
+        assert!(get_file_diff(dir, "f.txt", true).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+
+        let patch = FilePatch::parse(&get_file_diff(dir, "f.txt", false));
+        let lines: BTreeSet<usize> = patch.all_changes().into_iter().collect();
+        assert!(apply_lines(dir, &patch, &lines, false, StageAction::Unstage).is_err());
+        assert!(apply_lines(dir, &patch, &lines, true, StageAction::Discard).is_err());
+        assert!(apply_lines(dir, &patch, &BTreeSet::new(), false, StageAction::Stage).is_err());
+    }
+
+    #[test]
+    fn test_parse_stash_list() {
+        let list = parse_stash_list("stash@{0}|2 minutes ago|On main: wip|x\nbad line\nstash@{1}|1 day ago|WIP on main: abc fix\n");
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "stash@{0}");
+        assert_eq!(list[0].date, "2 minutes ago");
+        assert_eq!(list[0].message, "On main: wip|x");
+        assert_eq!(list[1].message, "WIP on main: abc fix");
+    }
+
+    #[test]
+    fn test_stash_name_validation() {
+        assert!(is_valid_stash_name("stash@{0}"));
+        assert!(is_valid_stash_name("stash@{12}"));
+        assert!(!is_valid_stash_name("stash@{}"));
+        assert!(!is_valid_stash_name("stash@{-1}"));
+        assert!(!is_valid_stash_name("--all"));
+        assert!(stash_cmd(Path::new("."), "drop", &[], "--all").is_err());
+    }
+
+    #[test]
+    fn test_stash_push_apply_pop_drop() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+        fs::write(dir.join("new.txt"), "untracked\n").unwrap();
+
+        stash_push(dir, "first", StashMode::Tracked).unwrap();
+        assert!(get_file_diff(dir, "f.txt", false).is_empty());
+        assert!(dir.join("new.txt").exists());
+
+        stash_push(dir, "", StashMode::IncludeUntracked).unwrap();
+        assert!(!dir.join("new.txt").exists());
+        // Nothing left to stash
+        assert!(stash_push(dir, "empty", StashMode::Tracked).is_err());
+
+        let stashes = get_stashes(dir);
+        assert_eq!(stashes.len(), 2);
+        assert!(stashes[1].message.ends_with("first"));
+        assert!(get_stash_diff(dir, "stash@{0}").contains("new.txt"));
+        assert!(get_stash_diff(dir, "stash@{1}").contains("+TWO"));
+
+        stash_cmd(dir, "pop", &[], "stash@{0}").unwrap();
+        assert!(dir.join("new.txt").exists());
+        stash_cmd(dir, "apply", &[], "stash@{0}").unwrap();
+        assert!(get_file_diff(dir, "f.txt", false).contains("+EIGHTEEN"));
+        stash_cmd(dir, "drop", &[], "stash@{0}").unwrap();
+        assert!(get_stashes(dir).is_empty());
+    }
+
+    #[test]
+    fn test_stash_staged_only_and_branch() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+        let patch = FilePatch::parse(&get_file_diff(dir, "f.txt", false));
+        apply_lines(dir, &patch, &first_hunk_lines(&patch), false, StageAction::Stage).unwrap();
+
+        stash_push(dir, "staged", StashMode::StagedOnly).unwrap();
+        assert!(get_file_diff(dir, "f.txt", true).is_empty());
+        let unstaged = get_file_diff(dir, "f.txt", false);
+        assert!(unstaged.contains("+EIGHTEEN"));
+        assert!(!unstaged.contains("+TWO"));
+
+        run_git(dir, &["checkout", "--", "f.txt"]);
+        assert!(stash_branch(dir, "-x", "stash@{0}").is_err());
+        stash_branch(dir, "from-stash", "stash@{0}").unwrap();
+        assert_eq!(get_current_branch(dir), "from-stash");
+        assert!(get_stashes(dir).is_empty());
+    }
+
+    /// Repo where "main" and "topic" change the same line of f.txt
+    fn setup_diverged_repo() -> tempfile::TempDir {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        run_git(dir, &["init", "-q", "-b", "main"]);
+        fs::write(dir.join("f.txt"), "base\n").unwrap();
+        fs::write(dir.join("other.txt"), "x\n").unwrap();
+        run_git(dir, &["add", "."]);
+        run_git(dir, &["commit", "-q", "-m", "base"]);
+        run_git(dir, &["checkout", "-q", "-b", "topic"]);
+        fs::write(dir.join("f.txt"), "topic\n").unwrap();
+        run_git(dir, &["commit", "-q", "-am", "topic change"]);
+        run_git(dir, &["checkout", "-q", "main"]);
+        fs::write(dir.join("f.txt"), "main\n").unwrap();
+        run_git(dir, &["commit", "-q", "-am", "main change"]);
+        tmp
+    }
+
+    fn conflicted_paths(dir: &Path) -> Vec<String> {
+        get_status(dir).into_iter().filter(is_conflicted).map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn test_merge_conflict_resolve_and_continue() {
+        let tmp = setup_diverged_repo();
+        let dir = tmp.path();
+        assert_eq!(get_operation(dir), None);
+
+        assert!(merge_branch(dir, "topic").is_err());
+        assert_eq!(get_operation(dir), Some(GitOperation::Merge));
+        assert_eq!(conflicted_paths(dir), vec!["f.txt"]);
+        assert!(operation_step(dir, GitOperation::Merge, "--skip").is_err());
+
+        resolve_with(dir, "f.txt", "--theirs").unwrap();
+        assert!(conflicted_paths(dir).is_empty());
+        operation_step(dir, GitOperation::Merge, "--continue").unwrap();
+
+        assert_eq!(get_operation(dir), None);
+        assert_eq!(fs::read_to_string(dir.join("f.txt")).unwrap(), "topic\n");
+        let parents = run_git(dir, &["rev-list", "--parents", "-n", "1", "HEAD"]);
+        assert_eq!(parents.split_whitespace().count(), 3);
+    }
+
+    #[test]
+    fn test_delete_modify_conflict_resolves_with_either_side() {
+        for (side, kept) in [("--ours", false), ("--theirs", true)] {
+            let tmp = setup_diverged_repo();
+            let dir = tmp.path();
+            // main deletes f.txt, topic modified it
+            run_git(dir, &["rm", "-q", "f.txt"]);
+            run_git(dir, &["commit", "-q", "-m", "delete f"]);
+
+            assert!(merge_branch(dir, "topic").is_err());
+            assert_eq!(conflicted_paths(dir), vec!["f.txt"]);
+
+            resolve_with(dir, "f.txt", side).unwrap();
+            assert!(conflicted_paths(dir).is_empty(), "{}", side);
+            operation_step(dir, GitOperation::Merge, "--continue").unwrap();
+            assert_eq!(dir.join("f.txt").exists(), kept, "{}", side);
+            assert!(run_git(dir, &["status", "--porcelain"]).is_empty(), "{}", side);
+        }
+    }
+
+    #[test]
+    fn test_rebase_conflict_abort() {
+        let tmp = setup_diverged_repo();
+        let dir = tmp.path();
+        let head = run_git(dir, &["rev-parse", "HEAD"]);
+
+        assert!(rebase_onto(dir, "-i").is_err());
+        assert!(rebase_onto(dir, "topic").is_err());
+        assert_eq!(get_operation(dir), Some(GitOperation::Rebase));
+        assert_eq!(conflicted_paths(dir), vec!["f.txt"]);
+
+        operation_step(dir, GitOperation::Rebase, "--abort").unwrap();
+        assert_eq!(get_operation(dir), None);
+        assert_eq!(get_current_branch(dir), "main");
+        assert_eq!(run_git(dir, &["rev-parse", "HEAD"]), head);
+    }
+
+    #[test]
+    fn test_cherry_pick_and_revert() {
+        let tmp = setup_diverged_repo();
+        let dir = tmp.path();
+        run_git(dir, &["checkout", "-q", "topic"]);
+        fs::write(dir.join("other.txt"), "y\n").unwrap();
+        run_git(dir, &["commit", "-q", "-am", "other change"]);
+        let hash = run_git(dir, &["rev-parse", "--short", "HEAD"]).trim().to_string();
+        run_git(dir, &["checkout", "-q", "main"]);
+
+        assert!(cherry_pick_commit(dir, "--abort").is_err());
+        cherry_pick_commit(dir, &hash).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("other.txt")).unwrap(), "y\n");
+        assert_eq!(get_log(dir, 1)[0].message, "other change");
+
+        let picked = get_log(dir, 1)[0].hash.clone();
+        revert_commit(dir, &picked).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("other.txt")).unwrap(), "x\n");
+        assert!(get_log(dir, 1)[0].message.starts_with("Revert"));
+        assert_eq!(get_operation(dir), None);
+    }
+
+    #[test]
+    fn test_cherry_pick_conflict_skip() {
+        let tmp = setup_diverged_repo();
+        let dir = tmp.path();
+        let hash = run_git(dir, &["rev-parse", "--short", "topic"]).trim().to_string();
+        let head = run_git(dir, &["rev-parse", "HEAD"]);
+
+        assert!(cherry_pick_commit(dir, &hash).is_err());
+        assert_eq!(get_operation(dir), Some(GitOperation::CherryPick));
+        assert!(operation_step(dir, GitOperation::CherryPick, "--continue").is_err());
+
+        operation_step(dir, GitOperation::CherryPick, "--skip").unwrap();
+        assert_eq!(get_operation(dir), None);
+        assert_eq!(run_git(dir, &["rev-parse", "HEAD"]), head);
+        assert_eq!(fs::read_to_string(dir.join("f.txt")).unwrap(), "main\n");
+    }
+
+    #[test]
+    fn test_log_filter_parse() {
+        let f = LogFilter::parse(r#"author:kim fix crash since:"2 weeks ago" file:src/a.rs"#).unwrap();
+        assert_eq!(f.author.as_deref(), Some("kim"));
+        assert_eq!(f.message.as_deref(), Some("fix crash"));
+        assert_eq!(f.since.as_deref(), Some("2 weeks ago"));
+        assert_eq!(f.path.as_deref(), Some("src/a.rs"));
+        assert!(f.follow);
+        assert!(f.is_active());
+        assert_eq!(LogFilter::parse(&f.to_query()).unwrap(), f);
+
+        // Unknown keys are message text
+        let f = LogFilter::parse("msg:^feat scope:ui").unwrap();
+        assert_eq!(f.message.as_deref(), Some("^feat scope:ui"));
+        assert!(!f.follow);
+
+        assert!(!LogFilter::parse("  ").unwrap().is_active());
+        assert!(LogFilter::parse("author:").is_err());
+        assert!(LogFilter::parse(r#"msg:"open"#).is_err());
+    }
+
+    fn entry(hash: &str, parents: &[&str]) -> GitLogEntry {
+        GitLogEntry {
+            hash: hash.to_string(),
+            message: String::new(),
+            author: String::new(),
+            date: String::new(),
+            refs: String::new(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            graph: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_graph() {
+        // m merges b into a; c is the common base; r is an unrelated root
+        let mut entries = vec![
+            entry("m", &["a", "b"]),
+            entry("b", &["c"]),
+            entry("a", &["c"]),
+            entry("c", &[]),
+            entry("r", &[]),
+        ];
+        build_graph(&mut entries);
+        let graph: Vec<&str> = entries.iter().map(|e| e.graph.as_str()).collect();
+        assert_eq!(graph, vec!["*\\", "|*", "*|", "*/", "*"]);
+    }
+
+    #[test]
+    fn test_filtered_log_and_file_history() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        run_git(dir, &["init", "-q"]);
+        fs::write(dir.join("a.txt"), "one\n").unwrap();
+        fs::write(dir.join("other.txt"), "x\n").unwrap();
+        run_git(dir, &["add", "."]);
+        run_git(dir, &["commit", "-q", "-m", "add a"]);
+        run_git(dir, &["mv", "a.txt", "b.txt"]);
+        run_git(dir, &["commit", "-q", "-m", "rename to b"]);
+        fs::write(dir.join("b.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.join("other.txt"), "y\n").unwrap();
+        run_git(dir, &["commit", "-q", "-am", "fix: change both"]);
+        run_git(dir, &["tag", "v1"]);
+
+        let all = get_log(dir, 200);
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|e| e.graph == "*"));
+        assert!(all[0].refs.contains("tag: v1"));
+
+        let history = get_log_filtered(dir, 200, &LogFilter::parse("file:b.txt").unwrap()).unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().all(|e| e.graph.is_empty()));
+        let plain = get_log_filtered(dir, 200, &LogFilter::parse("path:b.txt").unwrap()).unwrap();
+        assert_eq!(plain.len(), 2);
+
+        let by_message = get_log_filtered(dir, 200, &LogFilter::parse("^FIX").unwrap()).unwrap();
+        assert_eq!(by_message.len(), 1);
+        let by_author = get_log_filtered(dir, 200, &LogFilter::parse("author:nobody").unwrap()).unwrap();
+        assert!(by_author.is_empty());
+        assert!(get_log_filtered(dir, 200, &LogFilter::parse("msg:(").unwrap()).is_err());
+
+        fs::create_dir(dir.join("sub")).unwrap();
+        assert_eq!(repo_relative_path(&dir.join("sub"), "x.txt").as_deref(), Some("sub/x.txt"));
+    }
+
+    #[test]
+    fn test_parse_track() {
+        assert_eq!(parse_track("ahead 2, behind 3"), (2, 3, false));
+        assert_eq!(parse_track("behind 1"), (0, 1, false));
+        assert_eq!(parse_track("gone"), (0, 0, true));
+        assert_eq!(parse_track(""), (0, 0, false));
+    }
+
+    /// Run a remote operation to completion
+    fn run_remote(dir: &Path, command: RemoteCommand) -> Result<(), String> {
+        let mut op = RemoteOp::start(git_commit_cmd(dir), command);
+        let started = std::time::Instant::now();
+        while !op.poll() {
+            assert!(started.elapsed() < std::time::Duration::from_secs(60), "remote operation hung");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        op.result.unwrap()
+    }
+
+    fn current_branch_entry(dir: &Path) -> GitBranchEntry {
+        get_branches(dir).into_iter().find(|b| b.is_current).unwrap()
+    }
+
+    /// Bare "origin" plus a clone "a" that pushed one commit to it
+    fn setup_remote() -> tempfile::TempDir {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        run_git(root, &["init", "-q", "--bare", "-b", "main", "origin.git"]);
+        run_git(root, &["init", "-q", "-b", "main", "a"]);
+        let a = root.join("a");
+        fs::write(a.join("f.txt"), "one\n").unwrap();
+        run_git(&a, &["add", "f.txt"]);
+        run_git(&a, &["commit", "-q", "-m", "one"]);
+        run_git(&a, &["remote", "add", "origin", root.join("origin.git").to_str().unwrap()]);
+        tmp
+    }
+
+    #[test]
+    fn test_push_fetch_pull_with_tracking() {
+        let tmp = setup_remote();
+        let root = tmp.path();
+        let a = root.join("a");
+
+        assert_eq!(default_remote(&a).as_deref(), Some("origin"));
+        assert!(current_branch_entry(&a).upstream.is_none());
+        let push = RemoteCommand::Push {
+            set_upstream: Some(("origin".to_string(), "main".to_string())),
+            force_with_lease: false,
+        };
+        run_remote(&a, push).unwrap();
+        assert_eq!(current_branch_entry(&a).upstream.as_deref(), Some("origin/main"));
+
+        run_git(root, &["clone", "-q", "origin.git", "b"]);
+        let b = root.join("b");
+
+        fs::write(a.join("f.txt"), "one\ntwo\n").unwrap();
+        run_git(&a, &["commit", "-q", "-am", "two"]);
+        assert_eq!(current_branch_entry(&a).ahead, 1);
+        run_remote(&a, RemoteCommand::Push { set_upstream: None, force_with_lease: false }).unwrap();
+        assert_eq!(current_branch_entry(&a).ahead, 0);
+
+        run_remote(&b, RemoteCommand::Fetch).unwrap();
+        let tracked = current_branch_entry(&b);
+        assert_eq!((tracked.ahead, tracked.behind), (0, 1));
+        run_remote(&b, RemoteCommand::Pull { rebase: false }).unwrap();
+        assert_eq!(current_branch_entry(&b).behind, 0);
+        assert_eq!(fs::read_to_string(b.join("f.txt")).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_rejected_push_and_force_with_lease() {
+        let tmp = setup_remote();
+        let root = tmp.path();
+        let a = root.join("a");
+        let push = RemoteCommand::Push {
+            set_upstream: Some(("origin".to_string(), "main".to_string())),
+            force_with_lease: false,
+        };
+        run_remote(&a, push).unwrap();
+
+        // Rewrite the pushed commit
+        fs::write(a.join("f.txt"), "uno\n").unwrap();
+        run_git(&a, &["commit", "-q", "-a", "--amend", "-m", "uno"]);
+        let tracked = current_branch_entry(&a);
+        assert_eq!((tracked.ahead, tracked.behind), (1, 1));
+
+        let err = run_remote(&a, RemoteCommand::Push { set_upstream: None, force_with_lease: false }).unwrap_err();
+        assert!(err.starts_with("Rejected"), "{}", err);
+        run_remote(&a, RemoteCommand::Push { set_upstream: None, force_with_lease: true }).unwrap();
+        let tracked = current_branch_entry(&a);
+        assert_eq!((tracked.ahead, tracked.behind), (0, 0));
+
+        let err = run_remote(&root.join("origin.git"), RemoteCommand::Pull { rebase: false }).unwrap_err();
+        assert!(!err.is_empty());
+    }
+
+    #[test]
+    fn test_create_checkout_and_delete_tags() {
+        let tmp = setup_repo();
+        let dir = tmp.path();
+        let first = run_git(dir, &["rev-parse", "--short", "HEAD"]).trim().to_string();
+        run_git(dir, &["commit", "-q", "-a", "-m", "second"]);
+
+        create_tag(dir, "v1.0", Some(&first), None).unwrap();
+        create_tag(dir, "v2.0", None, Some("Release 2.0")).unwrap();
+        assert!(create_tag(dir, "bad name", None, None).is_err());
+        assert!(create_tag(dir, "v1.0", None, None).is_err());
+
+        let tags = get_tags(dir);
+        let v1 = tags.iter().find(|t| t.name == "v1.0").unwrap();
+        assert!(!v1.annotated);
+        assert_eq!(v1.commit, first);
+        assert_eq!(v1.subject, "init");
+        let v2 = tags.iter().find(|t| t.name == "v2.0").unwrap();
+        assert!(v2.annotated);
+        assert_eq!(v2.subject, "Release 2.0");
+        // The peeled commit, not the tag object
+        assert_eq!(v2.commit, run_git(dir, &["rev-parse", "--short", "HEAD"]).trim());
+        assert!(get_tag_detail(dir, "v2.0").contains("Release 2.0"));
+
+        checkout_tag(dir, "v1.0").unwrap();
+        assert_eq!(run_git(dir, &["rev-parse", "--short", "HEAD"]).trim(), first);
+        assert!(run_git(dir, &["status", "--porcelain=v2", "--branch"]).contains("branch.head (detached)"));
+
+        delete_tag(dir, "v1.0").unwrap();
+        assert!(get_tags(dir).iter().all(|t| t.name != "v1.0"));
+        assert!(delete_tag(dir, "v1.0").is_err());
+    }
+
+    #[test]
+    fn test_parse_worktrees() {
+        let sample = "worktree /repo\nHEAD 1234567890abcdef\nbranch refs/heads/main\n\n\
+worktree /repo-feature\nHEAD abcdef1234567890\nbranch refs/heads/feature/x\nlocked reason\n\n\
+worktree /tmp/gone\nHEAD 1111111222222\ndetached\nprunable gitdir file points to non-existent location\n\n";
+        let worktrees = parse_worktrees(sample);
+        assert_eq!(worktrees.len(), 3);
+        assert!(worktrees[0].is_main);
+        assert_eq!(worktrees[0].head, "1234567");
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].branch.as_deref(), Some("feature/x"));
+        assert!(worktrees[1].locked && !worktrees[1].is_main);
+        assert_eq!(worktrees[2].branch, None);
+        assert!(worktrees[2].prunable);
+    }
+
+    #[test]
+    fn test_add_and_remove_worktrees() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("repo");
+        fs::create_dir(&dir).unwrap();
+        run_git(&dir, &["init", "-q"]);
+        fs::write(dir.join("f.txt"), "x\n").unwrap();
+        run_git(&dir, &["add", "f.txt"]);
+        run_git(&dir, &["commit", "-q", "-m", "init"]);
+        run_git(&dir, &["branch", "existing"]);
+
+        // New branch, relative to the repository directory
+        add_worktree(&dir, "../wt-new", Some("feature")).unwrap();
+        add_worktree(&dir, "../wt-existing", Some("existing")).unwrap();
+        assert!(add_worktree(&dir, "--force", None).is_err());
+        assert!(add_worktree(&dir, "../wt-bad", Some("bad name")).is_err());
+
+        let worktrees = get_worktrees(&dir);
+        assert_eq!(worktrees.len(), 3);
+        assert!(worktrees[0].is_main && worktrees[0].is_current);
+        let new = worktrees.iter().find(|w| w.branch.as_deref() == Some("feature")).unwrap();
+        assert!(new.path.join("f.txt").exists());
+        assert!(!new.is_current);
+        assert!(worktrees.iter().any(|w| w.branch.as_deref() == Some("existing")));
+        // Seen from inside the added worktree, that one is current
+        assert!(get_worktrees(&new.path).iter().any(|w| w.is_current && w.branch.as_deref() == Some("feature")));
+
+        // A dirty worktree needs force
+        fs::write(new.path.join("f.txt"), "changed\n").unwrap();
+        assert!(remove_worktree(&dir, &new.path, false).is_err());
+        remove_worktree(&dir, &new.path, true).unwrap();
+        assert!(!new.path.exists());
+        assert_eq!(get_worktrees(&dir).len(), 2);
+    }
+}
@@ -0,0 +1,293 @@
+//! Git status decorations for local file panels.
+//!
+//! `collect` runs `git status --porcelain=v2` for the panel's directory and
+//! folds the result into one status per panel entry: files get their own
+//! status, directories the most significant status found below them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use super::git_screen::git_cmd_public as git_cmd;
+
+/// Status of a panel entry, ordered by significance for directory rollups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GitFileStatus {
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl GitFileStatus {
+    pub fn marker(self) -> &'static str {
+        match self {
+            GitFileStatus::Ignored => "!",
+            GitFileStatus::Untracked => "?",
+            GitFileStatus::Staged => "+",
+            GitFileStatus::Modified => "M",
+            GitFileStatus::Conflicted => "U",
+        }
+    }
+}
+
+/// Marker for a directory that contains changes
+pub const DIR_ROLLUP_MARKER: &str = "\u{2022}";
+
+#[derive(Debug, Clone, Default)]
+pub struct GitPanelStatus {
+    /// Directory the status was collected for
+    pub dir: PathBuf,
+    pub branch: String,
+    pub ahead: u32,
+    pub behind: u32,
+    /// Status by entry name in `dir`
+    pub entries: HashMap<String, GitFileStatus>,
+    /// Entries whose status comes from something below them (directories)
+    pub rollups: HashMap<String, GitFileStatus>,
+}
+
+impl GitPanelStatus {
+    /// Own status of an entry, or its rollup status if it is a directory with changes
+    pub fn status_of(&self, name: &str) -> Option<(GitFileStatus, bool)> {
+        if let Some(&s) = self.entries.get(name) {
+            return Some((s, false));
+        }
+        self.rollups.get(name).map(|&s| (s, true))
+    }
+
+    /// "main ↑1 ↓2"
+    pub fn branch_label(&self) -> String {
+        let mut label = self.branch.clone();
+        if self.ahead > 0 {
+            label.push_str(&format!(" \u{2191}{}", self.ahead));
+        }
+        if self.behind > 0 {
+            label.push_str(&format!(" \u{2193}{}", self.behind));
+        }
+        label
+    }
+
+    /// Parse `git status --porcelain=v2 --branch -z` output.
+    /// `prefix` is the panel directory relative to the repo root ("" for the root).
+    pub fn parse(output: &str, dir: &Path, prefix: &str) -> Self {
+        let mut status = GitPanelStatus {
+            dir: dir.to_path_buf(),
+            ..Default::default()
+        };
+        let mut head_oid = String::new();
+
+        let mut records = output.split('\0');
+        while let Some(record) = records.next() {
+            let (kind, rest) = record.split_once(' ').unwrap_or((record, ""));
+            let (file_status, path) = match kind {
+                "#" => {
+                    if let Some(head) = rest.strip_prefix("branch.head ") {
+                        status.branch = head.to_string();
+                    } else if let Some(oid) = rest.strip_prefix("branch.oid ") {
+                        head_oid = oid.to_string();
+                    } else if let Some(ab) = rest.strip_prefix("branch.ab ") {
+                        for part in ab.split(' ') {
+                            if let Some(n) = part.strip_prefix('+') {
+                                status.ahead = n.parse().unwrap_or(0);
+                            } else if let Some(n) = part.strip_prefix('-') {
+                                status.behind = n.parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                "1" | "2" => {
+                    // "<XY> <sub> <mH> <mI> <mW> <hH> <hI> [<score>] <path>"
+                    let fields = if kind == "1" { 8 } else { 9 };
+                    let parts: Vec<&str> = rest.splitn(fields, ' ').collect();
+                    if kind == "2" {
+                        // Rename / copy source path follows as its own record
+                        records.next();
+                    }
+                    let xy = parts.first().copied().unwrap_or("..");
+                    let path = match parts.get(fields - 1) {
+                        Some(p) => *p,
+                        None => continue,
+                    };
+                    let worktree_dirty = xy.as_bytes().get(1).is_some_and(|&c| c != b'.');
+                    let s = if worktree_dirty { GitFileStatus::Modified } else { GitFileStatus::Staged };
+                    (s, path)
+                }
+                "u" => match rest.splitn(10, ' ').nth(9) {
+                    Some(path) => (GitFileStatus::Conflicted, path),
+                    None => continue,
+                },
+                "?" => (GitFileStatus::Untracked, rest),
+                "!" => (GitFileStatus::Ignored, rest),
+                _ => continue,
+            };
+            status.add(prefix, path, file_status);
+        }
+
+        if status.branch == "(detached)" && head_oid.len() >= 7 {
+            status.branch = format!("({})", &head_oid[..7]);
+        }
+        status
+    }
+
+    fn add(&mut self, prefix: &str, path: &str, file_status: GitFileStatus) {
+        let rel = if prefix.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(prefix).and_then(|p| p.strip_prefix('/')) {
+                Some(rel) => rel,
+                None => return,
+            }
+        };
+        // Untracked / ignored directories are reported as "dir/"
+        let rel = rel.trim_end_matches('/');
+        if rel.is_empty() {
+            return;
+        }
+
+        match rel.split_once('/') {
+            None => {
+                let entry = self.entries.entry(rel.to_string()).or_insert(file_status);
+                *entry = (*entry).max(file_status);
+            }
+            Some((dir, _)) => {
+                // Ignored files below a directory don't make it interesting
+                if file_status == GitFileStatus::Ignored {
+                    return;
+                }
+                let entry = self.rollups.entry(dir.to_string()).or_insert(file_status);
+                *entry = (*entry).max(file_status);
+            }
+        }
+    }
+}
+
+/// Collect the status of `dir`; None when it is not inside a git work tree
+pub fn collect(dir: &Path) -> Option<GitPanelStatus> {
+    let prefix = git_cmd(dir)
+        .args(["rev-parse", "--show-prefix"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().trim_end_matches('/').to_string())?;
+
+    // Porcelain paths are root-relative even when run from a subdirectory;
+    // the "." pathspec limits the output to `dir`
+    let output = git_cmd(dir)
+        .args([
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--ignored=matching",
+            "--untracked-files=normal",
+            "--",
+            ".",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+
+    Some(GitPanelStatus::parse(&String::from_utf8_lossy(&output.stdout), dir, &prefix))
+}
+
+/// Collect the status on a background thread
+pub fn spawn_collect(dir: PathBuf) -> Receiver<Option<GitPanelStatus>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(collect(&dir));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SAMPLE: &str = "# branch.oid 1234567890abcdef\0\
+# branch.head main\0\
+# branch.upstream origin/main\0\
+# branch.ab +2 -1\0\
+1 .M N... 100644 100644 100644 aaa aaa src/main.rs\0\
+1 M. N... 100644 100644 100644 aaa bbb src/ui/app.rs\0\
+1 A. N... 000000 100644 100644 000 ccc README.md\0\
+2 R. N... 100644 100644 100644 aaa aaa R100 src/new name.rs\0src/old.rs\0\
+u UU N... 100644 100644 100644 100644 a b c src/conflict.rs\0\
+? src/scratch/\0\
+! target/\0\
+! src/ui/cache.tmp\0";
+
+    #[test]
+    fn test_parse_root() {
+        let status = GitPanelStatus::parse(SAMPLE, Path::new("/repo"), "");
+        assert_eq!(status.branch, "main");
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!(status.branch_label(), "main \u{2191}2 \u{2193}1");
+        assert_eq!(status.status_of("README.md"), Some((GitFileStatus::Staged, false)));
+        assert_eq!(status.status_of("target"), Some((GitFileStatus::Ignored, false)));
+        // src holds a conflict, which outranks everything else below it
+        assert_eq!(status.status_of("src"), Some((GitFileStatus::Conflicted, true)));
+        assert_eq!(status.status_of("Cargo.toml"), None);
+    }
+
+    #[test]
+    fn test_parse_subdirectory() {
+        let status = GitPanelStatus::parse(SAMPLE, Path::new("/repo/src"), "src");
+        assert_eq!(status.status_of("main.rs"), Some((GitFileStatus::Modified, false)));
+        assert_eq!(status.status_of("new name.rs"), Some((GitFileStatus::Staged, false)));
+        assert_eq!(status.status_of("old.rs"), None);
+        assert_eq!(status.status_of("conflict.rs"), Some((GitFileStatus::Conflicted, false)));
+        assert_eq!(status.status_of("scratch"), Some((GitFileStatus::Untracked, false)));
+        // Only an ignored file below: no rollup; the staged app.rs gives one
+        assert_eq!(status.status_of("ui"), Some((GitFileStatus::Staged, true)));
+        assert_eq!(status.status_of("README.md"), None);
+    }
+
+    #[test]
+    fn test_detached_head() {
+        let status = GitPanelStatus::parse("# branch.oid 1234567890abcdef\0# branch.head (detached)\0", Path::new("/r"), "");
+        assert_eq!(status.branch, "(1234567)");
+    }
+
+    #[test]
+    fn test_collect() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        assert!(collect(dir).is_none());
+
+        let git = |args: &[&str]| {
+            let ok = git_cmd(dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success();
+            assert!(ok, "git {:?} failed", args);
+        };
+        git(&["init", "-q"]);
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/a.txt"), "a").unwrap();
+        fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        fs::write(dir.join("sub/a.txt"), "changed").unwrap();
+        fs::write(dir.join("new.txt"), "n").unwrap();
+        fs::write(dir.join("debug.log"), "x").unwrap();
+
+        let status = collect(dir).unwrap();
+        assert!(!status.branch.is_empty());
+        assert_eq!(status.status_of("sub"), Some((GitFileStatus::Modified, true)));
+        assert_eq!(status.status_of("new.txt"), Some((GitFileStatus::Untracked, false)));
+        assert_eq!(status.status_of("debug.log"), Some((GitFileStatus::Ignored, false)));
+        assert_eq!(status.status_of(".gitignore"), None);
+
+        let sub = collect(&dir.join("sub")).unwrap();
+        assert_eq!(sub.status_of("a.txt"), Some((GitFileStatus::Modified, false)));
+        assert!(sub.status_of("new.txt").is_none());
+    }
+}
@@ -251,6 +251,12 @@ fn build_help_content(theme: &Theme, kb: &Keybindings) -> Vec<Line<'static>> {
     lines.push(pk(PanelAction::Delete, "Delete file(s)"));
     lines.push(pk(PanelAction::EncryptAll, "Encrypt all files (AES-256)"));
     lines.push(pk(PanelAction::DecryptAll, "Decrypt .cokacenc files"));
+    lines.push(pk(PanelAction::RekeyAll, "Re-key .cokacenc files"));
+    lines.push(pk(PanelAction::VerifyEncrypted, "Verify .cokacenc files"));
+    lines.push(pk(PanelAction::ListEncrypted, "List .cokacenc contents"));
+    lines.push(pk(PanelAction::ToggleVault, "Vault view (extract with Decrypt)"));
+    lines.push(pk(PanelAction::RemoveDuplicates, "Find/remove duplicate files"));
+    lines.push(pk(PanelAction::FindSimilarImages, "Find similar images"));
     lines.push(pk(PanelAction::Search, "Find/search files"));
     #[cfg(target_os = "macos")]
     {
@@ -438,6 +444,11 @@ fn build_help_content(theme: &Theme, kb: &Keybindings) -> Vec<Line<'static>> {
     lines.push(dsk(DiffScreenAction::CollapseDir, "Collapse directory"));
     lines.push(dsk(DiffScreenAction::ExpandAll, "Expand all"));
     lines.push(dsk(DiffScreenAction::CollapseAll, "Collapse all"));
+    lines.push(dsk(DiffScreenAction::EditExcludes, "Edit exclude patterns (Tab: presets)"));
+    lines.push(dsk(DiffScreenAction::ToggleGitignore, "Toggle .gitignore/.ignore handling"));
+    lines.push(dsk(DiffScreenAction::SyncToRight, "Copy selected/current to right (remote)"));
+    lines.push(dsk(DiffScreenAction::SyncToLeft, "Copy selected/current to left (remote)"));
+    lines.push(dsk(DiffScreenAction::Export, "Export patch / JSON / CSV report"));
     lines.push(dsk(DiffScreenAction::Close, "Return to file panel"));
     lines.push(Line::from(""));
 
@@ -462,6 +473,8 @@ fn build_help_content(theme: &Theme, kb: &Keybindings) -> Vec<Line<'static>> {
     lines.push(dfk(DiffFileViewAction::GoEnd, "Go to end"));
     lines.push(dfk(DiffFileViewAction::NextChange, "Jump to next change"));
     lines.push(dfk(DiffFileViewAction::PrevChange, "Jump to previous change"));
+    lines.push(dfk(DiffFileViewAction::ToggleHex, "Toggle hex view (binary files open in hex)"));
+    lines.push(dfk(DiffFileViewAction::Export, "Export as unified patch"));
     lines.push(dfk(DiffFileViewAction::Close, "Return to diff screen"));
     lines.push(Line::from(""));
 
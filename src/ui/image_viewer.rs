@@ -85,8 +85,26 @@ impl ImageViewerState {
     pub fn new(path: &Path) -> Self {
         // Scan for image files in the same directory
         let (image_list, current_index) = Self::scan_images_in_directory(path);
+        Self::with_list(path, image_list, current_index)
+    }
 
-        let mut state = Self {
+    /// Page through `image_list` instead of the directory of `path`
+    pub fn with_list(path: &Path, image_list: Vec<std::path::PathBuf>, current_index: usize) -> Self {
+        let mut state = Self::unloaded(path, image_list, current_index);
+        // Start async image loading
+        state.start_loading(path);
+        state
+    }
+
+    /// Show an image decoded from memory (e.g. a decrypted file); `path` only names it
+    pub fn from_bytes(path: &Path, bytes: Vec<u8>) -> Self {
+        let mut state = Self::unloaded(path, Vec::new(), 0);
+        state.spawn_loader(move || image::load_from_memory(&bytes));
+        state
+    }
+
+    fn unloaded(path: &Path, image_list: Vec<std::path::PathBuf>, current_index: usize) -> Self {
+        Self {
             path: path.to_path_buf(),
             image: None,
             error: None,
@@ -99,15 +117,19 @@ impl ImageViewerState {
             receiver: None,
             inline_protocol: None,
             use_inline: false,
-        };
-
-        // Start async image loading
-        state.start_loading(path);
-        state
+        }
     }
 
     /// Start async loading of an image
     fn start_loading(&mut self, path: &Path) {
+        let path = path.to_path_buf();
+        self.spawn_loader(move || image::open(&path));
+    }
+
+    fn spawn_loader<F>(&mut self, load: F)
+    where
+        F: FnOnce() -> image::ImageResult<DynamicImage> + Send + 'static,
+    {
         self.is_loading = true;
         self.image = None;
         self.error = None;
@@ -115,9 +137,8 @@ impl ImageViewerState {
         let (tx, rx): (Sender<ImageLoadResult>, Receiver<ImageLoadResult>) = mpsc::channel();
         self.receiver = Some(rx);
 
-        let path = path.to_path_buf();
         thread::spawn(move || {
-            let result = match image::open(&path) {
+            let result = match load() {
                 Ok(img) => ImageLoadResult {
                     image: Some(img),
                     error: None,
@@ -357,9 +378,14 @@ pub fn draw(frame: &mut Frame, app: &mut App, area: Rect, theme: &Theme) {
     // Clear area
     frame.render_widget(ratatui::widgets::Clear, viewer_area);
 
-    let filename = state.path.file_name()
+    let mut filename = state.path.file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "Image".to_string());
+    // Previewing a group of similar images: mark the one that is kept
+    let dedup_preview = app.previous_screen == Some(Screen::DedupScreen);
+    if dedup_preview && app.dedup_screen_state.as_ref().is_some_and(|d| d.is_kept(&state.path)) {
+        filename.push_str(" [KEEP]");
+    }
 
     let position_info = state.get_position_info();
     let use_inline = state.use_inline;
@@ -484,6 +510,10 @@ pub fn draw(frame: &mut Frame, app: &mut App, area: Rect, theme: &Theme) {
             (kb.image_viewer_first_key(ImageViewerAction::Close).to_string(), "close"),
         ]
     };
+    let mut shortcuts = shortcuts;
+    if dedup_preview {
+        shortcuts.insert(2, (kb.image_viewer_first_key(ImageViewerAction::ToggleSelect).to_string(), "keep "));
+    }
     let mut help_spans = Vec::new();
     for (key, label) in &shortcuts {
         help_spans.push(Span::styled(key.as_str(), fk));
@@ -589,6 +619,11 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
 
     if let Some(action) = app.keybindings.image_viewer_action(code, modifiers) {
         match action {
+            ImageViewerAction::Close if app.previous_screen == Some(Screen::DedupScreen) => {
+                app.previous_screen = None;
+                app.current_screen = Screen::DedupScreen;
+                app.image_viewer_state = None;
+            }
             ImageViewerAction::Close => {
                 let last_image_name = state.path.file_name()
                     .map(|n| n.to_string_lossy().to_string());
@@ -642,6 +677,13 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
             ImageViewerAction::NextImage => {
                 state.navigate_next();
             }
+            ImageViewerAction::ToggleSelect if app.previous_screen == Some(Screen::DedupScreen) => {
+                if let Some(ref mut dedup) = app.dedup_screen_state {
+                    dedup.keep_path(&state.path);
+                }
+            }
+            // Files of a similar-images group are deleted from its review
+            ImageViewerAction::Delete if app.previous_screen == Some(Screen::DedupScreen) => {}
             ImageViewerAction::ToggleSelect => {
                 let filename = state.path.file_name().map(|n| n.to_string_lossy().to_string());
                 state.navigate_next();
@@ -667,6 +709,7 @@ pub fn handle_input(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
                     selected_button: 1,
                     selection: None,
                     use_md5: false,
+                    compress: false,
                 });
             }
         }
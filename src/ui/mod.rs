@@ -17,5 +17,13 @@ pub mod search_result;
 pub mod help;
 pub mod diff_screen;
 pub mod diff_file_view;
+pub mod diff_export;
+pub mod diff_exclude;
+pub mod diff_hex;
+pub mod diff_side;
+pub mod git_blame;
+pub mod git_patch;
+pub mod git_remote;
 pub mod git_screen;
+pub mod git_status;
 pub mod dedup_screen;
@@ -7,16 +7,23 @@ use ratatui::{
 };
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use super::{app::{PanelState, SortBy, SortOrder}, theme::Theme};
+use super::{app::{PanelState, SortBy, SortOrder}, git_status::{self, GitFileStatus}, theme::Theme};
 use crate::utils::format::{format_size, truncate_to_display_width, pad_to_display_width};
 
 pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bool, is_bookmarked: bool, diff_selected: bool, theme: &Theme) {
-    let inner_width = area.width.saturating_sub(2) as usize;
+    let full_inner_width = area.width.saturating_sub(2) as usize;
+
+    // Git branch shown at the right of the title; dropped when the panel is too narrow
+    let branch_title = panel.git_status.as_ref()
+        .map(|s| format!(" {} ", s.branch_label()))
+        .filter(|t| t.width() < full_inner_width / 2);
+    let inner_width = full_inner_width - branch_title.as_ref().map_or(0, |t| t.width() + 1);
 
     // Build path display (truncate if too long, using display width)
     let path_str = panel.display_path();
     let bookmark_marker = if is_bookmarked { "✻" } else { "" };
-    let prefix = bookmark_marker.to_string();
+    let vault_marker = if panel.vault.is_some() { "[vault] " } else { "" };
+    let prefix = format!("{}{}", bookmark_marker, vault_marker);
     let path_display_width = path_str.width();
     let display_path = if inner_width > 4 && path_display_width + prefix.width() > inner_width.saturating_sub(4) {
         // Calculate how many characters to show from the end (by display width)
@@ -37,7 +44,7 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
         format!("{}{}", prefix, path_str)
     };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .title(format!(" {} ", display_path))
         .title_style(if panel.is_remote() && is_active {
             Style::default()
@@ -62,6 +69,11 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
                 theme.panel.border
             }
         ));
+    if let Some(branch_title) = branch_title {
+        block = block.title_top(
+            Line::from(Span::styled(branch_title, Style::default().fg(theme.panel.git_branch))).right_aligned(),
+        );
+    }
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
@@ -84,7 +96,7 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
         .max()
         .unwrap_or(0);
 
-    let (name_col, type_col, size_col, date_col) = if inner.width > min_columns {
+    let (mut name_col, type_col, size_col, date_col) = if inner.width > min_columns {
         let available_for_name = (inner.width - min_columns) as usize;
 
         // Check if we can show Type column:
@@ -104,8 +116,15 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
         (name_width, 0_usize, 0_usize, 0_usize)
     };
 
+    // Git status column, taken from the name column
+    let git_col = if panel.git_status.is_some() && name_col >= 8 { 2 } else { 0 };
+    name_col -= git_col;
+
     // Header row
-    let header = create_header_line(panel, name_col, type_col, size_col, date_col, is_active, theme);
+    let mut header = create_header_line(panel, name_col, type_col, size_col, date_col, is_active, theme);
+    if git_col > 0 {
+        header.spans.insert(0, Span::raw(" ".repeat(git_col)));
+    }
     let header_bg = if is_active {
         theme.panel.header_bg_active
     } else {
@@ -155,7 +174,7 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
         let is_marked = panel.selected_files.contains(&file.name);
         let show_cursor = is_cursor && is_active;
 
-        let line = create_file_line(
+        let mut line = create_file_line(
             file,
             show_cursor,
             is_marked,
@@ -166,16 +185,21 @@ pub fn draw(frame: &mut Frame, panel: &mut PanelState, area: Rect, is_active: bo
             theme,
         );
 
+        let cursor_bg = if is_marked {
+            theme.panel.marked_text
+        } else if file.is_symlink {
+            theme.panel.symlink_text
+        } else if file.is_directory {
+            theme.panel.directory_text
+        } else {
+            theme.panel.file_text
+        };
+
+        if git_col > 0 {
+            line.spans.insert(0, create_git_status_span(panel, file, show_cursor, cursor_bg, theme));
+        }
+
         let paragraph = if show_cursor {
-            let cursor_bg = if is_marked {
-                theme.panel.marked_text
-            } else if file.is_symlink {
-                theme.panel.symlink_text
-            } else if file.is_directory {
-                theme.panel.directory_text
-            } else {
-                theme.panel.file_text
-            };
             Paragraph::new(line).style(Style::default().bg(cursor_bg))
         } else {
             Paragraph::new(line)
@@ -333,6 +357,31 @@ fn create_header_line(panel: &PanelState, name_width: usize, type_width: usize,
     ])
 }
 
+/// Two-column git status marker: own status, or a rollup dot for directories with changes
+fn create_git_status_span(panel: &PanelState, file: &super::app::FileItem, is_cursor: bool, cursor_bg: Color, theme: &Theme) -> Span<'static> {
+    let status = match panel.git_status.as_ref() {
+        Some(s) if file.name != ".." => s.status_of(&file.name),
+        _ => None,
+    };
+    let Some((status, rollup)) = status else {
+        return Span::raw("  ");
+    };
+
+    let marker = if rollup { git_status::DIR_ROLLUP_MARKER } else { status.marker() };
+    let color = match status {
+        GitFileStatus::Modified => theme.panel.git_modified,
+        GitFileStatus::Staged => theme.panel.git_staged,
+        GitFileStatus::Untracked => theme.panel.git_untracked,
+        GitFileStatus::Ignored => theme.panel.git_ignored,
+        GitFileStatus::Conflicted => theme.panel.git_conflicted,
+    };
+    let style = if is_cursor {
+        Style::default().fg(theme.panel.selected_text).bg(cursor_bg)
+    } else {
+        Style::default().fg(color)
+    };
+    Span::styled(format!(" {}", marker), style)
+}
 
 fn create_file_line(
     file: &super::app::FileItem,
@@ -85,6 +85,12 @@ pub struct PanelColors {
     pub size_text: Color,
     pub date_text: Color,
     pub remote_indicator: Color,    // [SSH] 인디케이터 색상
+    pub git_modified: Color,        // git: 작업 트리에서 수정됨
+    pub git_staged: Color,          // git: 인덱스에 스테이징됨
+    pub git_untracked: Color,       // git: 추적되지 않는 파일
+    pub git_ignored: Color,         // git: .gitignore로 무시됨
+    pub git_conflicted: Color,      // git: 병합 충돌
+    pub git_branch: Color,          // 패널 제목의 브랜치/ahead/behind 표시
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -322,6 +328,10 @@ pub struct ViewerColors {
     pub hex_bytes: Color,
     pub hex_ascii: Color,
     pub wrap_indicator: Color,
+    pub blame_recent: Color,     // blame 거터: 최근 커밋
+    pub blame_medium: Color,     // blame 거터: 수개월 전 커밋
+    pub blame_old: Color,        // blame 거터: 오래된 커밋
+    pub blame_cursor_bg: Color,  // blame 모드 커서 줄 배경
     pub footer_key: Color,
     pub footer_text: Color,
 }
@@ -659,6 +669,9 @@ pub struct DedupScreenColors {
     pub log_error: Color,
     pub footer_key: Color,
     pub footer_text: Color,
+    pub selected_bg: Color,     // 검토 목록 커서 배경
+    pub selected_text: Color,   // 검토 목록 커서 텍스트
+    pub keep_text: Color,       // 남길 파일 (KEEP) 표시
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -785,6 +798,12 @@ impl Theme {
             size_text: Color::Indexed(251),
             date_text: Color::Indexed(251),
             remote_indicator: Color::Indexed(67),
+            git_modified: Color::Indexed(166),
+            git_staged: Color::Indexed(28),
+            git_untracked: Color::Indexed(136),
+            git_ignored: Color::Indexed(248),
+            git_conflicted: Color::Indexed(161),
+            git_branch: Color::Indexed(91),
         };
 
         // 앱 헤더
@@ -980,6 +999,10 @@ impl Theme {
             hex_bytes: Color::Indexed(243),
             hex_ascii: Color::Indexed(238),
             wrap_indicator: Color::Indexed(248),
+            blame_recent: Color::Indexed(25),
+            blame_medium: Color::Indexed(67),
+            blame_old: Color::Indexed(248),
+            blame_cursor_bg: Color::Indexed(253),
             footer_key: Color::Indexed(74),
             footer_text: Color::Indexed(251),
         };
@@ -1273,6 +1296,9 @@ impl Theme {
             log_error: Color::Indexed(124),
             footer_key: Color::Indexed(74),
             footer_text: Color::Indexed(251),
+            selected_bg: Color::Indexed(153),
+            selected_text: Color::Indexed(16),
+            keep_text: Color::Indexed(34),
         };
 
         Self {
@@ -1349,6 +1375,12 @@ impl Theme {
             size_text: Color::Indexed(245),
             date_text: Color::Indexed(245),
             remote_indicator: Color::Indexed(117),
+            git_modified: Color::Indexed(214),
+            git_staged: Color::Indexed(114),
+            git_untracked: Color::Indexed(180),
+            git_ignored: Color::Indexed(240),
+            git_conflicted: Color::Indexed(204),
+            git_branch: Color::Indexed(183),
         };
 
         let header = HeaderColors {
@@ -1521,6 +1553,10 @@ impl Theme {
             hex_bytes: Color::Indexed(252),
             hex_ascii: Color::Indexed(255),
             wrap_indicator: Color::Indexed(240),
+            blame_recent: Color::Indexed(117),
+            blame_medium: Color::Indexed(74),
+            blame_old: Color::Indexed(242),
+            blame_cursor_bg: Color::Indexed(237),
             footer_key: Color::Indexed(117),
             footer_text: Color::Indexed(245),
         };
@@ -1768,6 +1804,9 @@ impl Theme {
             log_error: Color::Indexed(209),
             footer_key: Color::Indexed(117),
             footer_text: Color::Indexed(245),
+            selected_bg: Color::Indexed(239),
+            selected_text: Color::Indexed(255),
+            keep_text: Color::Indexed(114),
         };
 
         Self {
@@ -1840,6 +1879,12 @@ impl Theme {
             size_text: Color::Indexed(102),
             date_text: Color::Indexed(102),
             remote_indicator: Color::Indexed(108),
+            git_modified: Color::Indexed(180),
+            git_staged: Color::Indexed(108),
+            git_untracked: Color::Indexed(144),
+            git_ignored: Color::Indexed(239),
+            git_conflicted: Color::Indexed(174),
+            git_branch: Color::Indexed(146),
         };
 
         let header = HeaderColors {
@@ -2008,6 +2053,10 @@ impl Theme {
             hex_bytes: Color::Indexed(188),
             hex_ascii: Color::Indexed(195),
             wrap_indicator: Color::Indexed(239),
+            blame_recent: Color::Indexed(110),
+            blame_medium: Color::Indexed(146),
+            blame_old: Color::Indexed(240),
+            blame_cursor_bg: Color::Indexed(236),
             footer_key: Color::Indexed(146),
             footer_text: Color::Indexed(102),
         };
@@ -2255,6 +2304,9 @@ impl Theme {
             log_error: Color::Indexed(167),
             footer_key: Color::Indexed(146),
             footer_text: Color::Indexed(102),
+            selected_bg: Color::Indexed(238),
+            selected_text: Color::Indexed(231),
+            keep_text: Color::Indexed(108),
         };
 
         Self {
@@ -2462,7 +2514,19 @@ impl Theme {
     "__date_text__": "수정일 컬럼의 텍스트 색상. bg 위에 표시됨. size_text와 유사한 시각적 우선순위. 보조 정보로서 파일명보다 덜 강조됨. palette.fg_dim 참조",
     "date_text": {},
     "__remote_indicator__": "원격 패널 [SSH] 인디케이터 색상. 패널 헤더에서 원격 연결 상태를 나타내는 텍스트 색상",
-    "remote_indicator": {}
+    "remote_indicator": {},
+    "__git_modified__": "git 저장소 안의 로컬 패널에서 작업 트리가 수정된 파일의 상태 표시(M) 색상. 파일명 왼쪽 상태 칸에 bg 위로 표시됨. 하위에 변경이 있는 폴더의 표시(•)에도 사용",
+    "git_modified": {},
+    "__git_staged__": "인덱스에 스테이징되었고 작업 트리는 깨끗한 파일의 상태 표시(+) 색상. git_modified와 구분되어야 함",
+    "git_staged": {},
+    "__git_untracked__": "git이 추적하지 않는 새 파일의 상태 표시(?) 색상",
+    "git_untracked": {},
+    "__git_ignored__": ".gitignore로 무시되는 파일/폴더의 상태 표시(!) 색상. 다른 git 상태보다 낮은 강조",
+    "git_ignored": {},
+    "__git_conflicted__": "병합 충돌 중인 파일의 상태 표시(U) 색상. 가장 눈에 띄어야 함",
+    "git_conflicted": {},
+    "__git_branch__": "패널 제목 오른쪽의 브랜치 이름과 ahead/behind(↑↓) 표시 색상. border 위에 표시됨",
+    "git_branch": {}
   }},
 
   "__header__": "=== 앱 헤더: 화면 최상단의 앱 제목 및 브랜딩 영역. 항상 표시되는 고정 요소 ===",
@@ -2775,6 +2839,14 @@ impl Theme {
     "hex_ascii": {},
     "__wrap_indicator__": "줄 바꿈 표시자(↩ 또는 유사 기호). 긴 줄이 화면 너비를 초과하여 래핑될 때 표시. line_number 영역 또는 줄 끝에 표시. text보다 낮은 시각적 강조",
     "wrap_indicator": {},
+    "__blame_recent__": "blame 모드 좌측 거터(커밋 해시, 작성자, 날짜)에서 최근(한 달 이내) 변경된 줄의 색상. bg 위에 표시됨. 가장 눈에 띄어야 함",
+    "blame_recent": {},
+    "__blame_medium__": "blame 거터에서 한 달~1년 전에 변경된 줄의 색상. blame_recent와 blame_old 사이의 강조",
+    "blame_medium": {},
+    "__blame_old__": "blame 거터에서 1년 이상 지난 변경의 색상. line_number와 비슷하게 낮은 강조",
+    "blame_old": {},
+    "__blame_cursor_bg__": "blame 모드에서 현재 선택된 줄의 배경색. 커밋으로 이동할 줄을 표시. text와 blame 색상이 이 위에서 읽혀야 함",
+    "blame_cursor_bg": {},
     "__footer_key__": "하단 도움말의 단축키 텍스트. 사용 가능한 키 안내. editor.footer_key와 유사",
     "footer_key": {},
     "__footer_text__": "하단 도움말의 설명 텍스트. footer_key의 기능 설명",
@@ -3223,7 +3295,7 @@ impl Theme {
     "border": {},
     "__title__": "제목 텍스트",
     "title": {},
-    "__phase_text__": "현재 단계 텍스트 (Scanning/Hashing/Deleting/Complete)",
+    "__phase_text__": "현재 단계 텍스트 (Scanning/Hashing/Review/Applying/Complete)",
     "phase_text": {},
     "__stats_text__": "통계 레이블 텍스트",
     "stats_text": {},
@@ -3244,7 +3316,13 @@ impl Theme {
     "__footer_key__": "기능 바 단축키",
     "footer_key": {},
     "__footer_text__": "기능 바 설명",
-    "footer_text": {}
+    "footer_text": {},
+    "__selected_bg__": "검토 목록 커서 배경",
+    "selected_bg": {},
+    "__selected_text__": "검토 목록 커서 텍스트",
+    "selected_text": {},
+    "__keep_text__": "남길 파일 (KEEP) 표시",
+    "keep_text": {}
   }}
 }}"#,
             // name
@@ -3263,6 +3341,8 @@ impl Theme {
             ci(self.panel.selected_bg), ci(self.panel.selected_text), ci(self.panel.marked_text),
             ci(self.panel.size_text), ci(self.panel.date_text),
             ci(self.panel.remote_indicator),
+            ci(self.panel.git_modified), ci(self.panel.git_staged), ci(self.panel.git_untracked),
+            ci(self.panel.git_ignored), ci(self.panel.git_conflicted), ci(self.panel.git_branch),
             // header
             ci(self.header.bg), ci(self.header.text), ci(self.header.title),
             // status_bar
@@ -3333,6 +3413,8 @@ impl Theme {
             ci(self.viewer.search_match_other_bg), ci(self.viewer.search_match_other_fg),
             ci(self.viewer.search_info), ci(self.viewer.hex_offset), ci(self.viewer.hex_bytes),
             ci(self.viewer.hex_ascii), ci(self.viewer.wrap_indicator),
+            ci(self.viewer.blame_recent), ci(self.viewer.blame_medium),
+            ci(self.viewer.blame_old), ci(self.viewer.blame_cursor_bg),
             ci(self.viewer.footer_key), ci(self.viewer.footer_text),
             // process_manager
             ci(self.process_manager.bg), ci(self.process_manager.border), ci(self.process_manager.header_text),
@@ -3439,6 +3521,8 @@ impl Theme {
             ci(self.dedup_screen.progress_text), ci(self.dedup_screen.log_text), ci(self.dedup_screen.log_text_alt),
             ci(self.dedup_screen.log_deleted), ci(self.dedup_screen.log_error),
             ci(self.dedup_screen.footer_key), ci(self.dedup_screen.footer_text),
+            ci(self.dedup_screen.selected_bg), ci(self.dedup_screen.selected_text),
+            ci(self.dedup_screen.keep_text),
         )
     }
 }
@@ -134,6 +134,18 @@ pub struct PanelColorsJson {
     pub date_text: u8,
     #[serde(default = "default_67")]
     pub remote_indicator: u8,
+    #[serde(default = "default_166")]
+    pub git_modified: u8,
+    #[serde(default = "default_28")]
+    pub git_staged: u8,
+    #[serde(default = "default_136")]
+    pub git_untracked: u8,
+    #[serde(default = "default_248")]
+    pub git_ignored: u8,
+    #[serde(default = "default_161")]
+    pub git_conflicted: u8,
+    #[serde(default = "default_91")]
+    pub git_branch: u8,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -462,6 +474,14 @@ pub struct ViewerColorsJson {
     pub hex_ascii: u8,
     #[serde(default = "default_248")]
     pub wrap_indicator: u8,
+    #[serde(default = "default_25")]
+    pub blame_recent: u8,
+    #[serde(default = "default_67")]
+    pub blame_medium: u8,
+    #[serde(default = "default_248")]
+    pub blame_old: u8,
+    #[serde(default = "default_253")]
+    pub blame_cursor_bg: u8,
     #[serde(default = "default_74")]
     pub footer_key: u8,
     #[serde(default = "default_251")]
@@ -948,6 +968,12 @@ pub struct DedupScreenColorsJson {
     pub footer_key: u8,
     #[serde(default = "default_102")]
     pub footer_text: u8,
+    #[serde(default = "default_238")]
+    pub selected_bg: u8,
+    #[serde(default = "default_231")]
+    pub selected_text: u8,
+    #[serde(default = "default_108")]
+    pub keep_text: u8,
 }
 
 impl Default for DedupScreenColorsJson {
@@ -957,6 +983,7 @@ impl Default for DedupScreenColorsJson {
             stats_text: 188, progress_bar_fill: 108, progress_bar_empty: 236,
             progress_text: 188, log_text: 188, log_text_alt: 144,
             log_deleted: 174, log_error: 167, footer_key: 146, footer_text: 102,
+            selected_bg: 238, selected_text: 231, keep_text: 108,
         }
     }
 }
@@ -1125,6 +1152,12 @@ pub fn theme_from_json(json: &ThemeJson) -> Theme {
         size_text: idx(json.panel.size_text),
         date_text: idx(json.panel.date_text),
         remote_indicator: idx(json.panel.remote_indicator),
+        git_modified: idx(json.panel.git_modified),
+        git_staged: idx(json.panel.git_staged),
+        git_untracked: idx(json.panel.git_untracked),
+        git_ignored: idx(json.panel.git_ignored),
+        git_conflicted: idx(json.panel.git_conflicted),
+        git_branch: idx(json.panel.git_branch),
     };
 
     let header = HeaderColors {
@@ -1291,6 +1324,10 @@ pub fn theme_from_json(json: &ThemeJson) -> Theme {
         hex_bytes: idx(json.viewer.hex_bytes),
         hex_ascii: idx(json.viewer.hex_ascii),
         wrap_indicator: idx(json.viewer.wrap_indicator),
+        blame_recent: idx(json.viewer.blame_recent),
+        blame_medium: idx(json.viewer.blame_medium),
+        blame_old: idx(json.viewer.blame_old),
+        blame_cursor_bg: idx(json.viewer.blame_cursor_bg),
         footer_key: idx(json.viewer.footer_key),
         footer_text: idx(json.viewer.footer_text),
     };
@@ -1538,6 +1575,9 @@ pub fn theme_from_json(json: &ThemeJson) -> Theme {
         log_error: idx(json.dedup_screen.log_error),
         footer_key: idx(json.dedup_screen.footer_key),
         footer_text: idx(json.dedup_screen.footer_text),
+        selected_bg: idx(json.dedup_screen.selected_bg),
+        selected_text: idx(json.dedup_screen.selected_text),
+        keep_text: idx(json.dedup_screen.keep_text),
     };
 
     Theme {